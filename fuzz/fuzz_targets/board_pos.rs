@@ -0,0 +1,13 @@
+#![no_main]
+
+use alvinw_chess::pos::BoardPos;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|s: &str| {
+    if let Ok(pos) = s.parse::<BoardPos>() {
+        // Case and surrounding whitespace are accepted, but positions are written
+        // in lowercase.
+        assert_eq!(pos.to_string(), s.trim().to_ascii_lowercase());
+        assert_eq!(pos.to_string().parse::<BoardPos>().ok(), Some(pos));
+    }
+});
@@ -0,0 +1,22 @@
+#![no_main]
+
+use alvinw_chess::{board::Board, game::Game};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|fen: &str| {
+    let _ = Board::from_fen_placement_data(fen);
+
+    if let Ok(mut game) = Game::from_fen(fen) {
+        // Anything that was parsed must be exported to a FEN string that parses
+        // to the same position.
+        let exported = game.to_fen();
+        let reparsed = Game::from_fen(&exported).expect("Exported FEN is valid.");
+        assert_eq!(reparsed.to_fen(), exported);
+
+        // Generating moves for a parsed position must not panic either.
+        for (pos, _) in game.board().pieces().collect::<Vec<_>>() {
+            let _ = game.get_legal_moves(&pos);
+        }
+        let _ = game.get_state();
+    }
+});
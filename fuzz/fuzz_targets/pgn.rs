@@ -0,0 +1,25 @@
+#![no_main]
+
+use alvinw_chess::pgn::{PgnGame, PgnReader, PgnWriter};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|pgn: &str| {
+    // Reading several games, with and without recovering from errors, must not
+    // panic.
+    let _ = PgnReader::new(pgn.as_bytes()).recover(true).count();
+
+    if let Ok(game) = PgnGame::parse(pgn) {
+        // Anything that was parsed must be written to PGN that parses to the same
+        // moves.
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_game(&game).expect("Writing to a Vec does not fail.");
+        let written = String::from_utf8(writer.into_inner()).expect("PGN is written as UTF-8.");
+        let reparsed = PgnGame::parse(&written).expect("Written PGN is valid.");
+        assert_eq!(
+            reparsed.moves().iter().map(|m| m.get()).collect::<Vec<_>>(),
+            game.moves().iter().map(|m| m.get()).collect::<Vec<_>>(),
+        );
+
+        let _ = game.final_position().get_state();
+    }
+});
@@ -0,0 +1,133 @@
+//! Precomputed attack tables for the pieces that jump to their squares instead of
+//! sliding, the knights, kings and pawns.
+//!
+//! The tables are indexed by `rank * 8 + file` and are generated at compile time.
+//! They are the single definition of how these pieces move that the rest of the
+//! crate uses. The offsets and the directions of the sliding pieces are also used
+//! to describe how pieces move, see `MovementPattern`.
+
+use crate::{board::Color, pos::BoardPos, square_set::SquareSet};
+
+/// The `(file, rank)` offsets that a knight jumps by.
+pub const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (-1,  2), (1,   2),
+    (2,   1), (2,  -1),
+    (-1, -2), (1,  -2),
+    (-2,  1), (-2, -1),
+];
+
+/// The `(file, rank)` offsets that a king moves by, not counting castling.
+pub const KING_OFFSETS: [(i8, i8); 8] = [
+    (-1,  1), (0,  1), (1,  1),
+    (-1,  0), /******/ (1,  0),
+    (-1, -1), (0, -1), (1, -1),
+];
+
+/// The `(file, rank)` directions that a rook slides in.
+pub const ROOK_DIRECTIONS: [(i8, i8); 4] = [
+              (0,  1),
+    (-1,  0), /******/ (1,  0),
+              (0, -1),
+];
+
+/// The `(file, rank)` directions that a bishop slides in.
+pub const BISHOP_DIRECTIONS: [(i8, i8); 4] = [
+    (-1,  1), (1,  1),
+    (-1, -1), (1, -1),
+];
+
+/// The `(file, rank)` directions that a queen slides in.
+pub const QUEEN_DIRECTIONS: [(i8, i8); 8] = KING_OFFSETS;
+
+const WHITE_PAWN_OFFSETS: [(i8, i8); 2] = [(-1, 1), (1, 1)];
+const BLACK_PAWN_OFFSETS: [(i8, i8); 2] = [(-1, -1), (1, -1)];
+
+/// The squares attacked by a knight on each square.
+pub const KNIGHT_ATTACKS: [SquareSet; 64] = leaper_table(&KNIGHT_OFFSETS);
+
+/// The squares attacked by a king on each square. Castling is not included.
+pub const KING_ATTACKS: [SquareSet; 64] = leaper_table(&KING_OFFSETS);
+
+/// The squares attacked diagonally by a pawn on each square, indexed by the
+/// index of the pawn's color (see `Color::index`) and then by the square.
+pub const PAWN_ATTACKS: [[SquareSet; 64]; 2] = [
+    leaper_table(&WHITE_PAWN_OFFSETS),
+    leaper_table(&BLACK_PAWN_OFFSETS),
+];
+
+/// Get the squares attacked by a knight on the position.
+pub fn knight_attacks(pos: &BoardPos) -> SquareSet {
+    KNIGHT_ATTACKS[index(pos)]
+}
+
+/// Get the squares attacked by a king on the position.
+pub fn king_attacks(pos: &BoardPos) -> SquareSet {
+    KING_ATTACKS[index(pos)]
+}
+
+/// Get the squares that a pawn of the specified color on the position can capture
+/// on. Forward moves and en passant are not included.
+pub fn pawn_attacks(pos: &BoardPos, color: &Color) -> SquareSet {
+    PAWN_ATTACKS[color.index()][index(pos)]
+}
+
+fn index(pos: &BoardPos) -> usize {
+    (pos.rank() * 8 + pos.file()) as usize
+}
+
+/// Build the table of attacked squares for a piece that jumps by the offsets.
+const fn leaper_table(offsets: &[(i8, i8)]) -> [SquareSet; 64] {
+    let mut table = [SquareSet::EMPTY; 64];
+    let mut square = 0;
+    while square < 64 {
+        let file = (square % 8) as i8;
+        let rank = (square / 8) as i8;
+        let mut bits = 0_u64;
+        let mut i = 0;
+        while i < offsets.len() {
+            let (delta_file, delta_rank) = offsets[i];
+            let target_file = file + delta_file;
+            let target_rank = rank + delta_rank;
+            if target_file >= 0 && target_file <= 7 && target_rank >= 0 && target_rank <= 7 {
+                bits |= 1 << (target_rank * 8 + target_file);
+            }
+            i += 1;
+        }
+        table[square] = SquareSet::from_bits(bits);
+        square += 1;
+    }
+    table
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compute the attacks the way the move generation used to, by offsetting the
+    /// position.
+    fn offset_attacks(pos: &BoardPos, offsets: &[(i8, i8)]) -> SquareSet {
+        offsets.iter()
+            .filter_map(|(delta_file, delta_rank)| pos.offset(*delta_file, *delta_rank))
+            .collect()
+    }
+
+    #[test]
+    fn tables_match_offsets() {
+        for file in 0..8 {
+            for rank in 0..8 {
+                let pos = BoardPos::new(file, rank);
+                assert_eq!(knight_attacks(&pos), offset_attacks(&pos, &KNIGHT_OFFSETS), "{pos}");
+                assert_eq!(king_attacks(&pos), offset_attacks(&pos, &KING_OFFSETS), "{pos}");
+                assert_eq!(pawn_attacks(&pos, &Color::White), offset_attacks(&pos, &WHITE_PAWN_OFFSETS), "{pos}");
+                assert_eq!(pawn_attacks(&pos, &Color::Black), offset_attacks(&pos, &BLACK_PAWN_OFFSETS), "{pos}");
+            }
+        }
+    }
+
+    #[test]
+    fn corner_knight() {
+        let attacks: Vec<BoardPos> = knight_attacks(&"a1".parse().unwrap()).iter().collect();
+        assert_eq!(attacks, ["c2".parse().unwrap(), "b3".parse().unwrap()]);
+    }
+}
@@ -1,7 +1,10 @@
+use std::fmt;
+
 use crate::{pos::BoardPos, piece::PieceType, game::FenParseError};
 
 const BOARD_SIZE: usize = 8;
 
+#[derive(Clone)]
 pub struct Board {
     data: [[Option<Tile>; BOARD_SIZE]; BOARD_SIZE],
 }
@@ -27,7 +30,7 @@ impl Board {
     pub fn remove_tile(&mut self, pos: &BoardPos) -> Option<Tile> {
         let existing = self.data[pos.rank() as usize][pos.file() as usize];
         self.data[pos.rank() as usize][pos.file() as usize] = None;
-        return existing;
+        existing
     }
 
     /// If the `tile` parameter is `Some`, the tile is set, otherwise, the tile at
@@ -36,11 +39,86 @@ impl Board {
         self.data[pos.rank() as usize][pos.file() as usize] = tile;
     }
 
+    /// Get an iterator over all occupied squares and their tiles.
+    /// 
+    /// The squares are ordered by rank and then by file, starting with `a1`, `b1`
+    /// and ending with `g8`, `h8`.
+    pub fn pieces(&self) -> impl Iterator<Item = (BoardPos, Tile)> + '_ {
+        (0..8_u8)
+            .flat_map(|rank| (0..8_u8).map(move |file| BoardPos::new(file, rank)))
+            .filter_map(|pos| self.get_tile(&pos).map(|tile| (pos, tile)))
+    }
+
+    /// Get an iterator over the ranks of the board, starting with rank 8 and ending
+    /// with rank 1, which is the order they are written in FEN and usually drawn in.
+    ///
+    /// Each rank is an iterator over its eight squares and their tiles, starting
+    /// with file a.
+    pub fn ranks(&self) -> impl Iterator<Item = impl Iterator<Item = (BoardPos, Option<Tile>)> + '_> + '_ {
+        self.rows(&Color::White)
+    }
+
+    /// Get an iterator over the rows of the board as it is drawn from the
+    /// perspective of the team, from the top row to the bottom row, see
+    /// `BoardPos::from_grid`.
+    ///
+    /// Each row is an iterator over its eight squares and their tiles, from left
+    /// to right.
+    pub fn rows(&self, perspective: &Color) -> impl Iterator<Item = impl Iterator<Item = (BoardPos, Option<Tile>)> + '_> + '_ {
+        let perspective = *perspective;
+        (0..8_u8).map(move |row| {
+            (0..8_u8).map(move |col| {
+                let pos = BoardPos::from_grid(col, row, &perspective).expect("The grid is 8 by 8.");
+                let tile = self.get_tile(&pos);
+                (pos, tile)
+            })
+        })
+    }
+
+    /// Get an iterator over the files of the board, starting with file a and
+    /// ending with file h.
+    ///
+    /// Each file is an iterator over its eight squares and their tiles, starting
+    /// with rank 1.
+    pub fn files(&self) -> impl Iterator<Item = impl Iterator<Item = (BoardPos, Option<Tile>)> + '_> + '_ {
+        (0..8_u8).map(move |file| {
+            (0..8_u8).map(move |rank| {
+                let pos = BoardPos::new(file, rank);
+                let tile = self.get_tile(&pos);
+                (pos, tile)
+            })
+        })
+    }
+
+    /// Draw the board as text, which is the same as its `Display` implementation.
+    ///
+    /// Rank 8 is at the top, with the rank numbers on the left and the files
+    /// underneath. White pieces are uppercase, black pieces are lowercase and
+    /// empty squares are dots. The starting position is drawn as follows:
+    ///
+    /// ```text
+    /// 8 r n b q k b n r
+    /// 7 p p p p p p p p
+    /// 6 . . . . . . . .
+    /// 5 . . . . . . . .
+    /// 4 . . . . . . . .
+    /// 3 . . . . . . . .
+    /// 2 P P P P P P P P
+    /// 1 R N B Q K B N R
+    ///   a b c d e f g h
+    /// ```
+    ///
+    /// There is no trailing newline, and the output does not change between
+    /// versions, so it can be compared in tests.
+    pub fn to_ascii(&self) -> String {
+        self.to_string()
+    }
+
     /// Create a `Board` instance from FEN placement data.
     /// 
     /// Note that the string should not be the entire FEN string, but should only be
     /// the first part of the FEN data, the part known as the "placement data".
-    pub fn from_fen_placement_data(fen: &str) -> Result<Self, FenParseError> {
+    pub fn from_fen_placement_data(fen: &str) -> Result<Self, FenParseError<'_>> {
         let mut board = Board::empty();
 
         let mut file = 0;
@@ -52,6 +130,9 @@ impl Board {
                 }
                 file += skip as u8;
             } else if char == '/' {
+                if rank == 0 {
+                    return Err(FenParseError::TooManyRanks);
+                }
                 file = 0;
                 rank -= 1;
             } else {
@@ -82,10 +163,9 @@ impl Board {
     /// part of the FEN data, the part known as the "placement data".
     pub fn to_fen_placement_data(&self) -> String {
         let mut str = String::new();
-        for rank in (0..8_u8).rev() {
+        for rank in self.ranks() {
             let mut empty_count = 0;
-            for file in 0_u8..8 {
-                let tile = self.get_tile(&BoardPos::new(file, rank));
+            for (_, tile) in rank {
                 match tile {
                     None => {
                         empty_count += 1;
@@ -113,6 +193,71 @@ impl Board {
     }
 }
 
+/// Create a board with the tiles placed on their positions.
+///
+/// If a position appears more than once, the last tile placed there is kept.
+impl FromIterator<(BoardPos, Tile)> for Board {
+    fn from_iter<T: IntoIterator<Item = (BoardPos, Tile)>>(iter: T) -> Board {
+        let mut board = Board::empty();
+        board.extend(iter);
+        board
+    }
+}
+
+/// Place the tiles on their positions, replacing any tiles already there.
+impl Extend<(BoardPos, Tile)> for Board {
+    fn extend<T: IntoIterator<Item = (BoardPos, Tile)>>(&mut self, iter: T) {
+        for (pos, tile) in iter {
+            self.set_tile(&pos, tile);
+        }
+    }
+}
+
+/// Iterate over the occupied squares in the same order as `Board::pieces`.
+impl IntoIterator for Board {
+    type Item = (BoardPos, Tile);
+    type IntoIter = std::vec::IntoIter<(BoardPos, Tile)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pieces().collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl IntoIterator for &Board {
+    type Item = (BoardPos, Tile);
+    type IntoIter = std::vec::IntoIter<(BoardPos, Tile)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pieces().collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl fmt::Display for Board {
+    // See `Board::to_ascii` for an example.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, rank) in self.ranks().enumerate() {
+            write!(f, "{}", 8 - index)?;
+            for (_, tile) in rank {
+                let char = match tile {
+                    Some(tile) if tile.color() == Color::White => tile.piece().char().to_ascii_uppercase(),
+                    Some(tile) => tile.piece().char(),
+                    None => '.',
+                };
+                write!(f, " {char}")?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "  a b c d e f g h")
+    }
+}
+
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The placement data is much more readable than the raw tile array.
+        write!(f, "Board({})", self.to_fen_placement_data())
+    }
+}
+
 /// A tile on the chess board, for example a black king or a white knight.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Tile {
@@ -127,9 +272,33 @@ impl Tile {
 
     pub fn piece(&self) -> PieceType { self.piece }
     pub fn color(&self) -> Color { self.color }
+
+    /// Pack the tile into a single byte.
+    ///
+    /// The lowest three bits are the index of the piece (see `PieceType::index`)
+    /// and the fourth bit is the index of the color (see `Color::index`). The
+    /// remaining bits are zero. A white king is therefore `0` and a black pawn is
+    /// `13`.
+    pub fn to_u8(&self) -> u8 {
+        (self.color.index() << 3 | self.piece.index()) as u8
+    }
+
+    /// Unpack a tile from a byte created by `to_u8`.
+    ///
+    /// Returns `None` if the byte is not a valid tile.
+    pub fn try_from_u8(byte: u8) -> Option<Tile> {
+        if byte >> 4 != 0 {
+            return None;
+        }
+        let piece = PieceType::from_index((byte & 0b111) as usize)?;
+        let color = if byte >> 3 == 0 { Color::White } else { Color::Black };
+        Some(Tile::new(piece, color))
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Color {
     White,
     Black,
@@ -142,6 +311,23 @@ impl Color {
             Self::Black => Self::White,
         }
     }
+
+    /// Get a stable index for this color, `0` for white and `1` for black.
+    pub fn index(&self) -> usize {
+        match self {
+            Self::White => 0,
+            Self::Black => 1,
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::White => write!(f, "White"),
+            Self::Black => write!(f, "Black"),
+        }
+    }
 }
 
 
@@ -164,7 +350,7 @@ mod tests {
         let pos = "b2".parse().unwrap();
         let tile1 = Tile { piece: PieceType::King, color: Color::White };
 
-        board.set_tile(&pos, tile1.clone());
+        board.set_tile(&pos, tile1);
 
         let tile2 = board.get_tile(&pos);
 
@@ -178,4 +364,140 @@ mod tests {
         
         assert_eq!(FEN_PLACEMENT_DATA, board.to_fen_placement_data());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn ascii_board() {
+        let board = Board::from_fen_placement_data("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R").unwrap();
+
+        assert_eq!(board.to_ascii(), [
+            "8 r . . . k . . r",
+            "7 p . p p q p b .",
+            "6 b n . . p n p .",
+            "5 . . . P N . . .",
+            "4 . p . . P . . .",
+            "3 . . N . . Q . p",
+            "2 P P P B B P P P",
+            "1 R . . . K . . R",
+            "  a b c d e f g h",
+        ].join("\n"));
+        assert_eq!(format!("{board}"), board.to_ascii());
+        assert!(Board::empty().to_ascii().starts_with("8 . . . . . . . .\n7 "));
+    }
+
+    #[test]
+    fn pieces_order() {
+        let board = Board::from_fen_placement_data("4k3/8/8/8/8/8/8/R3K2R").unwrap();
+        let positions: Vec<String> = board.pieces()
+            .map(|(pos, _tile)| pos.to_string())
+            .collect();
+
+        assert_eq!(positions, ["a1", "e1", "h1", "e8"]);
+    }
+
+    #[test]
+    fn ranks_and_files_order() {
+        let board = Board::from_fen_placement_data("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").unwrap();
+
+        let ranks: Vec<Vec<String>> = board.ranks()
+            .map(|rank| rank.map(|(pos, _tile)| pos.to_string()).collect())
+            .collect();
+        assert_eq!(ranks.len(), 8);
+        assert_eq!(ranks[0], ["a8", "b8", "c8", "d8", "e8", "f8", "g8", "h8"]);
+        assert_eq!(ranks[7][0], "a1");
+
+        let files: Vec<Vec<String>> = board.files()
+            .map(|file| file.map(|(pos, _tile)| pos.to_string()).collect())
+            .collect();
+        assert_eq!(files[0], ["a1", "a2", "a3", "a4", "a5", "a6", "a7", "a8"]);
+        assert_eq!(files[7][7], "h8");
+
+        let e_file: Vec<Option<Tile>> = board.files().nth(4).unwrap().map(|(_pos, tile)| tile).collect();
+        assert_eq!(e_file[0], Some(Tile::new(PieceType::King, Color::White)));
+        assert_eq!(e_file[7], Some(Tile::new(PieceType::King, Color::Black)));
+    }
+
+    #[test]
+    fn placement_data_from_ranks() {
+        let board = Board::from_fen_placement_data("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R").unwrap();
+
+        let placement: Vec<String> = board.ranks()
+            .map(|rank| {
+                let mut str = String::new();
+                let mut empty_count = 0;
+                for (_pos, tile) in rank {
+                    match tile {
+                        None => empty_count += 1,
+                        Some(tile) => {
+                            if empty_count > 0 {
+                                str.push_str(&empty_count.to_string());
+                                empty_count = 0;
+                            }
+                            let char = tile.piece().char();
+                            str.push(if tile.color() == Color::White { char.to_ascii_uppercase() } else { char });
+                        }
+                    }
+                }
+                if empty_count > 0 {
+                    str.push_str(&empty_count.to_string());
+                }
+                str
+            })
+            .collect();
+
+        assert_eq!(placement.join("/"), board.to_fen_placement_data());
+    }
+
+    #[test]
+    fn tile_byte_round_trip() {
+        for index in 0..6 {
+            let piece = PieceType::from_index(index).unwrap();
+            for color in [Color::White, Color::Black] {
+                let tile = Tile::new(piece, color);
+                assert_eq!(Tile::try_from_u8(tile.to_u8()), Some(tile));
+            }
+        }
+        assert_eq!(Tile::new(PieceType::King, Color::White).to_u8(), 0);
+        assert_eq!(Tile::new(PieceType::Pawn, Color::Black).to_u8(), 13);
+    }
+
+    #[test]
+    fn invalid_tile_bytes() {
+        let valid = (0..=u8::MAX).filter(|byte| Tile::try_from_u8(*byte).is_some()).count();
+        assert_eq!(valid, 12);
+        for byte in [6, 7, 14, 15, 16, 0x80, 0xff] {
+            assert!(Tile::try_from_u8(byte).is_none(), "{byte}");
+        }
+    }
+
+    #[test]
+    fn collect_board() {
+        let board: Board = [
+            ("e1", PieceType::King, Color::White),
+            ("a1", PieceType::Rook, Color::White),
+            ("h1", PieceType::Rook, Color::White),
+            ("e8", PieceType::King, Color::Black),
+            // Last write wins.
+            ("h1", PieceType::Rook, Color::White),
+        ]
+            .into_iter()
+            .map(|(pos, piece, color)| (pos.parse().unwrap(), Tile::new(piece, color)))
+            .collect();
+
+        assert_eq!(board.to_fen_placement_data(), "4k3/8/8/8/8/8/8/R3K2R");
+
+        let round_trip: Board = board.into_iter().collect();
+        assert_eq!(round_trip.to_fen_placement_data(), "4k3/8/8/8/8/8/8/R3K2R");
+    }
+
+    #[test]
+    fn extend_board() {
+        let mut board = Board::from_fen_placement_data("4k3/8/8/8/8/8/8/4K3").unwrap();
+        board.extend([
+            ("e2".parse().unwrap(), Tile::new(PieceType::Pawn, Color::White)),
+            ("e8".parse().unwrap(), Tile::new(PieceType::Queen, Color::Black)),
+        ]);
+
+        assert_eq!(board.to_fen_placement_data(), "4q3/8/8/8/8/8/4P3/4K3");
+        assert_eq!((&board).into_iter().count(), 3);
+    }
+}
@@ -0,0 +1,193 @@
+//! An error type covering every error of the crate.
+
+use std::{fmt, io};
+
+use crate::{
+    game::{AdjudicateError, EditError, FenParseError, GetMovesetError, HumanMoveError, InferError, MovePieceError, ParseMoveError, PremoveError, SanError},
+    pgn::{PgnError, PgnTagError},
+    pos::ParseBoardPosError,
+    puzzle::PuzzleError,
+    time_control::ParseTimeControlError,
+};
+
+#[cfg(feature = "serde")]
+use crate::game::SavedGameError;
+
+/// Any error returned by the crate.
+///
+/// Every error type of the crate converts into this type, so functions that use
+/// several parts of the crate can return `Result` and use `?` everywhere. The
+/// specific error types are still returned by each function for callers that
+/// want to handle the errors in detail.
+///
+/// ```
+/// use alvinw_chess::{game::{Game, Moved, PromotionPiece}, pos::BoardPos};
+///
+/// fn promote_to_queen(fen: &str, from: &str, to: &str) -> alvinw_chess::Result<Game> {
+///     let mut game = Game::from_fen(fen)?;
+///     let from: BoardPos = from.parse()?;
+///     let to: BoardPos = to.parse()?;
+///     // Fails if there is no piece of the team to move on the square.
+///     game.get_legal_moves(&from)?;
+///     if let Moved::NeedsPromotion(pending) = game.play_move(&from, &to)? {
+///         pending.promote(PromotionPiece::Queen);
+///     }
+///     Ok(game)
+/// }
+///
+/// let game = promote_to_queen("7k/P7/8/8/8/8/8/4K3 w - - 0 1", "a7", "a8").unwrap();
+/// assert!(game.to_fen().starts_with("Q6k/"));
+///
+/// let err = promote_to_queen("7k/P7/8/8/8/8/8/4K3 w - - 0 1", "a7", "b8").unwrap_err();
+/// assert!(matches!(err, alvinw_chess::Error::MovePiece(_)));
+/// let err = promote_to_queen("7k/P7/8/8/8/8/8/4K3 w - - 0 1", "b7", "b8").unwrap_err();
+/// assert!(matches!(err, alvinw_chess::Error::GetMoveset(_)));
+/// assert!(promote_to_queen("7k/P7/8/8/8/8/8/4K3 w - - 0 1", "a9", "a8").is_err());
+/// ```
+#[derive(Debug)]
+pub enum Error {
+    /// A FEN string is invalid, see `FenParseError`. The error message is
+    /// included, since `FenParseError` borrows from the FEN string.
+    Fen(String),
+    BoardPos(ParseBoardPosError),
+    ParseMove(ParseMoveError),
+    MovePiece(MovePieceError),
+    GetMoveset(GetMovesetError),
+    San(SanError),
+    HumanMove(HumanMoveError),
+    Adjudicate(AdjudicateError),
+    Infer(InferError),
+    Premove(PremoveError),
+    Edit(EditError),
+    Pgn(PgnError),
+    PgnTag(PgnTagError),
+    Puzzle(PuzzleError),
+    TimeControl(ParseTimeControlError),
+    #[cfg(feature = "serde")]
+    SavedGame(SavedGameError),
+    Io(io::Error),
+}
+
+/// A result with the crate's `Error` type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Fen(err) => write!(f, "Invalid FEN: {err}"),
+            Self::BoardPos(err) => err.fmt(f),
+            Self::ParseMove(err) => err.fmt(f),
+            Self::MovePiece(err) => err.fmt(f),
+            Self::GetMoveset(err) => err.fmt(f),
+            Self::San(err) => err.fmt(f),
+            Self::HumanMove(err) => err.fmt(f),
+            Self::Adjudicate(err) => err.fmt(f),
+            Self::Infer(err) => err.fmt(f),
+            Self::Premove(err) => err.fmt(f),
+            Self::Edit(err) => err.fmt(f),
+            Self::Pgn(err) => err.fmt(f),
+            Self::PgnTag(err) => err.fmt(f),
+            Self::Puzzle(err) => err.fmt(f),
+            Self::TimeControl(err) => err.fmt(f),
+            #[cfg(feature = "serde")]
+            Self::SavedGame(err) => err.fmt(f),
+            Self::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Fen(_) => None,
+            Self::BoardPos(err) => Some(err),
+            Self::ParseMove(err) => Some(err),
+            Self::MovePiece(err) => Some(err),
+            Self::GetMoveset(err) => Some(err),
+            Self::San(err) => Some(err),
+            Self::HumanMove(err) => Some(err),
+            Self::Adjudicate(err) => Some(err),
+            Self::Infer(err) => Some(err),
+            Self::Premove(err) => Some(err),
+            Self::Edit(err) => Some(err),
+            Self::Pgn(err) => Some(err),
+            Self::PgnTag(err) => Some(err),
+            Self::Puzzle(err) => Some(err),
+            Self::TimeControl(err) => Some(err),
+            #[cfg(feature = "serde")]
+            Self::SavedGame(err) => Some(err),
+            Self::Io(err) => Some(err),
+        }
+    }
+}
+
+impl From<FenParseError<'_>> for Error {
+    fn from(err: FenParseError<'_>) -> Self {
+        Self::Fen(err.to_string())
+    }
+}
+
+macro_rules! impl_from {
+    ($($variant:ident($err:ty)),* $(,)?) => {
+        $(
+            impl From<$err> for Error {
+                fn from(err: $err) -> Self {
+                    Self::$variant(err)
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(
+    BoardPos(ParseBoardPosError),
+    ParseMove(ParseMoveError),
+    MovePiece(MovePieceError),
+    GetMoveset(GetMovesetError),
+    San(SanError),
+    HumanMove(HumanMoveError),
+    Adjudicate(AdjudicateError),
+    Infer(InferError),
+    Premove(PremoveError),
+    Edit(EditError),
+    Pgn(PgnError),
+    PgnTag(PgnTagError),
+    Puzzle(PuzzleError),
+    TimeControl(ParseTimeControlError),
+    Io(io::Error),
+);
+
+#[cfg(feature = "serde")]
+impl_from!(SavedGame(SavedGameError));
+
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use crate::game::{Game, Move};
+
+    use super::*;
+
+    fn play(fen: &str, moves: &[&str]) -> Result<Game> {
+        let mut game = Game::from_fen(fen)?;
+        for m in moves {
+            let m: Move = m.parse()?;
+            game.make_move(&m)?;
+        }
+        Ok(game)
+    }
+
+    #[test]
+    fn question_mark_converts() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert!(play(fen, &["e2e4", "e7e5"]).is_ok());
+        assert!(matches!(play("8/8 x", &[]), Err(Error::Fen(_))));
+        assert!(matches!(play(fen, &["e2"]), Err(Error::ParseMove(_))));
+        assert!(matches!(play(fen, &["e2e5"]), Err(Error::MovePiece(MovePieceError::InvalidMove))));
+
+        let err = play(fen, &["e7e5"]).unwrap_err();
+        assert_eq!(err.to_string(), MovePieceError::NotCurrentTurn.to_string());
+        assert!(err.source().is_some());
+    }
+}
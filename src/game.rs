@@ -1,16 +1,138 @@
+use std::{collections::BTreeMap, sync::Arc};
+
 use crate::{board::{Board, Color, Tile}, pos::BoardPos, piece::PieceType};
 
 mod fen;
 pub use fen::FenParseError;
 
+mod fen_validation;
+pub use fen_validation::{normalize_fen, validate_fen, validate_fen_lenient, FenField, FenIssue, Severity};
+
 mod movement;
-pub use movement::{MovePieceError, GetMovesetError};
+pub use movement::{Move, MovePieceError, GetMovesetError};
+
+mod moved;
+pub use moved::{MoveEffects, MoveOutcome, Moved, PromotionPending, PromotionPiece};
+
+mod move_list;
+pub use move_list::{MoveBuffer, MoveList, MAX_MOVES};
+
+mod coordinates;
+pub use coordinates::{CastlingNotation, ParseMoveError};
+
+mod san;
+pub use san::{PieceLetters, SanError};
+
+mod human;
+pub use human::HumanMoveError;
+
+mod description;
+pub use description::DescriptionError;
+
+mod infer;
+pub use infer::{infer_move, InferError};
+
+mod diff;
+pub use diff::{diff_positions, PositionChange, PositionDiff};
+
+mod record;
+pub use record::{validate_game_record, GameRecordReport, RecordError, RecordErrorKind};
+
+mod undo;
+use undo::UndoRecord;
+
+mod annotation;
+pub use annotation::Annotation;
+
+mod pgn;
 
 mod check;
 
+mod kingless;
+
+mod checks;
+
+mod escapes;
+pub use escapes::CheckEscapes;
+
+mod threats;
+
+mod explain;
+pub use explain::SquareReport;
+
+mod blunder;
+
+mod fog;
+
+mod edit;
+pub use edit::{BoardEditor, EditError};
+
+mod premove;
+pub use premove::PremoveError;
+
+mod path;
+pub use path::MovePath;
+
+mod move_delta;
+pub use move_delta::{LegalMoveDelta, LegalMoveMap};
+
+mod describe;
+
+mod render;
+
+mod report;
+
+mod material;
+pub use material::ImbalanceReport;
+
+mod endgame;
+pub use endgame::EndgameClass;
+
+mod zobrist;
+pub use zobrist::repetition_keys_equal;
+
+mod mirror;
+
+mod repetition;
+
+mod move_cache;
+use move_cache::MoveCache;
+
+mod perft;
+
+mod rules;
+pub use rules::{Rules, StandardRules};
+
+mod variant;
+pub use variant::{RacingKingsRules, Variant};
+
+mod outcome;
+pub use outcome::{AdjudicateError, Outcome};
+
+mod mate;
+
+mod perpetual;
+
+mod training;
+pub use training::{TrainingFormat, TrainingOptions, TrainingSample, POSITION_BYTES};
+
+#[cfg(feature = "serde")]
+mod saved;
+#[cfg(feature = "serde")]
+pub use saved::{SavedAdjudication, SavedGame, SavedGameError, SAVE_FORMAT_VERSION};
+
+#[cfg(feature = "testing")]
+mod invariants;
+
+#[cfg(feature = "random")]
+mod random;
+#[cfg(feature = "random")]
+pub use random::RandomPositionConstraints;
+
 /// The FEN for the starting position of the game.
 const STARTING_POSITION_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
+#[derive(Clone, Debug)]
 pub struct Game {
     board: Board,
     current_turn: Color,
@@ -20,6 +142,26 @@ pub struct Game {
     promotion_required: Option<BoardPos>,
     halfmove_clock: u32,
     fullmove_number: u32,
+    /// The FEN of the position before the first move in `history`.
+    starting_fen: String,
+    history: Vec<Move>,
+    /// What is needed to undo each move in `history`, see `undo`.
+    undo_stack: Vec<UndoRecord>,
+    /// The repetition keys of the positions after each move in `history`, and of
+    /// the starting position first. See `repetition_plies`.
+    position_keys: Vec<u64>,
+    /// The ply of the last irreversible move, see `last_irreversible_ply`.
+    last_irreversible_ply: usize,
+    /// The rules of the variant that the game is played by.
+    rules: Arc<dyn Rules>,
+    outcome: Option<Outcome>,
+    /// The reason given when the game was adjudicated.
+    adjudication_note: Option<String>,
+    /// The annotations of the moves in `history`, by ply.
+    annotations: BTreeMap<usize, Vec<Annotation>>,
+    /// Bumped whenever the position changes, see `MoveCache`.
+    generation: u64,
+    move_cache: MoveCache,
 }
 
 impl Game {
@@ -28,22 +170,117 @@ impl Game {
         Self::from_fen(STARTING_POSITION_FEN).expect("Hardcoded FEN is valid.")
     }
 
+    /// Return the game to the starting position of its rules.
+    ///
+    /// See `reset_to_fen` for what is kept.
+    pub fn reset(&mut self) {
+        let rules = Arc::clone(&self.rules);
+        self.reset_to_fen(rules.starting_fen()).expect("The starting FEN of the rules is valid.");
+    }
+
+    /// Return the game to the position described by the FEN string.
+    ///
+    /// Everything about the position is replaced, including any pending
+    /// promotion, and the move history is cleared. The rules are kept. This
+    /// method must be kept in sync when state is added to `Game`.
+    ///
+    /// # Errors
+    /// If the FEN string is invalid the error is returned and the game is left
+    /// unchanged.
+    pub fn reset_to_fen<'a>(&mut self, fen: &'a str) -> Result<(), FenParseError<'a>> {
+        let game = Game::from_fen_with_rules(fen, Arc::clone(&self.rules))?;
+        self.board = game.board;
+        self.current_turn = game.current_turn;
+        self.white_castling = game.white_castling;
+        self.black_castling = game.black_castling;
+        self.en_passant_target = game.en_passant_target;
+        self.promotion_required = game.promotion_required;
+        self.halfmove_clock = game.halfmove_clock;
+        self.fullmove_number = game.fullmove_number;
+        self.starting_fen = game.starting_fen;
+        self.history = game.history;
+        self.undo_stack = game.undo_stack;
+        self.position_keys = game.position_keys;
+        self.last_irreversible_ply = game.last_irreversible_ply;
+        self.outcome = game.outcome;
+        self.adjudication_note = game.adjudication_note;
+        self.annotations = game.annotations;
+        self.invalidate_move_cache();
+        Ok(())
+    }
+
     /// Get the underlying `Board` instance for this game.
     /// 
     /// It is not recomended that users of this library use this method, but it
     /// exists if low-level access and modification to the board is required.
-    pub fn board(&mut self) -> &mut Board { &mut self.board }
+    ///
+    /// The cached legal moves are cleared, since the board may be changed.
+    pub fn board(&mut self) -> &mut Board {
+        self.invalidate_move_cache();
+        &mut self.board
+    }
+
+    /// Get the moves made since the game was created or last reset, oldest first.
+    ///
+    /// Promotions are included in the moves once the piece has been promoted.
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /// Get the FEN of the position the game started from, which is the position
+    /// before the first move in `history`.
+    pub fn starting_fen(&self) -> &str {
+        &self.starting_fen
+    }
 
     /// Get the `Color` of the team that is next to make a move.
     pub fn current_turn(&self) -> Color {
         self.current_turn
     }
 
+    /// Get the number of the current full move. It starts at 1 and is increased
+    /// after each move by black.
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// Get the number of half-moves since the last capture or pawn move, which
+    /// is used for the fifty-move rule.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// Get the castling availability of the specified team.
+    /// 
+    /// Note that this only tells whether the team has the right to castle on each
+    /// side. Whether castling is possible right now also depends on the pieces
+    /// between the king and the rook and whether the squares are attacked.
+    pub fn castling_availability(&self, color: &Color) -> CastlingAvailability {
+        match color {
+            Color::White => self.white_castling,
+            Color::Black => self.black_castling,
+        }
+    }
+
+    /// Get the square that a pawn may move to in order to capture en passant, or
+    /// `None` if en passant is not possible.
+    pub fn en_passant_target(&self) -> Option<BoardPos> {
+        self.en_passant_target.clone()
+    }
+
     /// Get the tile at the specified position, or `None` if the square is empty.
     pub fn get_tile(&self, pos: &BoardPos) -> Option<Tile> {
         self.board.get_tile(pos)
     }
 
+    /// Get the total number of pieces on the board, including both kings.
+    ///
+    /// Endgame tablebases usually only cover positions up to a certain number of
+    /// pieces, so this tells whether probing one is worthwhile.
+    pub fn piece_count_total(&self) -> usize {
+        self.board.pieces().count()
+    }
+
     /// Get the current game state. This method must be called after each move.
     /// 
     /// See the `GameState` enum for the possible values.
@@ -55,6 +292,17 @@ impl Game {
             return GameState::PromotionRequired(pos.clone());
         }
 
+        match self.outcome {
+            Some(Outcome::VariantEnd { winner }) => return GameState::VariantEnd(winner),
+            Some(Outcome::Adjudicated { winner }) => return GameState::Adjudicated(winner),
+            _ => {},
+        }
+
+        // See `is_kingless`.
+        if self.is_kingless() {
+            return GameState::Normal;
+        }
+
         if self.is_check(&Color::White) {
             if self.is_checkmate(&Color::White) {
                 return GameState::Checkmate(Color::White);
@@ -71,7 +319,16 @@ impl Game {
             }
         }
 
-        return GameState::Normal;
+        // En passant and castling are included in the legal moves.
+        if self.legal_moves().is_empty() {
+            return GameState::Stalemate;
+        }
+
+        if self.is_insufficient_material() {
+            return GameState::InsufficientMaterial;
+        }
+
+        GameState::Normal
     }
 
     /// Promote a pawn.
@@ -81,6 +338,9 @@ impl Game {
     /// 
     /// Pawns and kings are not valid piece types to this method.
     /// 
+    /// The outcome of the game is decided once the pawn has been promoted, so the
+    /// game is never over while a promotion is pending.
+    ///
     /// ## Panics
     /// This method will panic if the piece type is a pawn or king. This method will
     /// also panic if it is called when there is no piece to be promoted
@@ -99,12 +359,27 @@ impl Game {
 
         let new_tile = Tile::new(piece_type, pawn.color());
         self.board.set_tile(pos, new_tile);
+        self.invalidate_move_cache();
+
+        if let Some(last) = self.history.last_mut() {
+            *last = Move::new(last.from().clone(), last.to().clone(), Some(piece_type));
+        }
 
         self.promotion_required = None;
+        self.record_position_key();
+        self.update_outcome();
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-struct CastlingAvailability {
+/// Which sides a team has the right to castle on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CastlingAvailability {
     pub kingside: bool,
     pub queenside: bool,
 }
@@ -116,12 +391,25 @@ pub enum GameState {
     /// The king is under threat. The color represents the color of the team that
     /// is in check.
     Check(Color),
-    /// The game is won. The color represents the team that has won.
+    /// The game is over by checkmate. The color represents the team that is
+    /// checkmated and has lost, see `Outcome::Checkmate` for the winner.
     Checkmate(Color),
+    /// The team whose turn it is has no legal moves but is not in check, so the
+    /// game is a draw.
+    Stalemate,
+    /// Neither team has enough material to checkmate, see
+    /// `is_insufficient_material`, so the game can only end in a draw. Moves can
+    /// still be made.
+    InsufficientMaterial,
     /// The player is required to choose which piece to promote a pawn to at the
     /// specified location.
     PromotionRequired(BoardPos),
-    // TODO draw?
+    /// The game was ended by a rule of its variant, see `Rules::outcome`. The
+    /// color is the team that won, or `None` for a draw.
+    VariantEnd(Option<Color>),
+    /// The game was ended by an arbiter, see `adjudicate`. The color is the team
+    /// that won, or `None` for a draw.
+    Adjudicated(Option<Color>),
 }
 
 
@@ -149,4 +437,58 @@ mod tests {
         game.move_piece(&"e8".parse().unwrap(), &"e7".parse().unwrap()).unwrap();
         assert_eq!(game.get_state(), GameState::Normal);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn reset_game() {
+        let mut game = Game::from_fen("4k3/2P5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.move_piece(&"c7".parse().unwrap(), &"c8".parse().unwrap()).unwrap();
+
+        game.reset();
+        assert_eq!(game.to_fen(), STARTING_POSITION_FEN);
+        assert_eq!(game.get_state(), GameState::Normal);
+
+        let fen = "4k3/8/8/8/8/8/8/R3K2R b KQ - 3 20";
+        game.reset_to_fen(fen).unwrap();
+        assert_eq!(game.to_fen(), fen);
+
+        // Invalid FEN strings leave the game as it was.
+        assert!(game.reset_to_fen("not a fen").is_err());
+        assert_eq!(game.to_fen(), fen);
+    }
+
+    #[test]
+    fn stalemate() {
+        let mut game = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(game.get_state(), GameState::Stalemate);
+        assert_eq!(game.outcome(), Some(Outcome::Stalemate));
+
+        // Only white would be stalemated, but it is black's turn.
+        let mut game = Game::from_fen("8/8/8/8/8/6k1/5q2/7K b - - 0 1").unwrap();
+        assert_eq!(game.get_state(), GameState::Normal);
+
+        // Capturing en passant is the only legal move.
+        let mut game = Game::from_fen("7k/5Q2/6K1/8/3pP3/3P4/8/8 b - e3 0 1").unwrap();
+        assert_eq!(game.get_state(), GameState::Normal);
+        let mut game = Game::from_fen("7k/5Q2/6K1/8/3pP3/3P4/8/8 b - - 0 1").unwrap();
+        assert_eq!(game.get_state(), GameState::Stalemate);
+    }
+
+    #[test]
+    fn history() {
+        let fen = "4k3/2P5/8/8/8/8/8/4K3 w - - 0 1";
+        let mut game = Game::from_fen(fen).unwrap();
+        game.move_piece(&"c7".parse().unwrap(), &"c8".parse().unwrap()).unwrap();
+        game.promote(PieceType::Knight);
+        game.move_piece(&"e8".parse().unwrap(), &"f7".parse().unwrap()).unwrap();
+
+        assert_eq!(game.starting_fen(), fen);
+        assert_eq!(game.history(), [
+            Move::new("c7".parse().unwrap(), "c8".parse().unwrap(), Some(PieceType::Knight)),
+            Move::new("e8".parse().unwrap(), "f7".parse().unwrap(), None),
+        ]);
+
+        game.reset();
+        assert_eq!(game.starting_fen(), STARTING_POSITION_FEN);
+        assert!(game.history().is_empty());
+    }
+}
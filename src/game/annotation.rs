@@ -0,0 +1,245 @@
+use std::time::Duration;
+
+use crate::{board::Color, pos::BoardPos};
+
+use super::Game;
+
+/// A note about a move, for example from a commentator or an engine.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Annotation {
+    /// A text comment.
+    Comment(String),
+    /// A Numeric Annotation Glyph, for example `2` for a mistake (`?`).
+    Nag(u8),
+    /// An evaluation of the position after the move in centipawns, from the
+    /// perspective of white.
+    Eval(i32),
+    /// The time left on the clock of the player who made the move.
+    Clock(Duration),
+    /// The time the player spent on the move.
+    TimeSpent(Duration),
+    /// An arrow drawn on the board after the move, for example to show a plan.
+    /// The color is a letter like in Lichess, where `G` is green, `R` red, `Y`
+    /// yellow and `B` blue.
+    Arrow { from: BoardPos, to: BoardPos, color: char },
+    /// A square highlighted on the board after the move. The color is a letter,
+    /// see `Arrow`.
+    Highlight { square: BoardPos, color: char },
+}
+
+impl Game {
+
+    /// Add an annotation to a move in the history. The ply is the number of the
+    /// move in `history`, starting at 1 for the first move.
+    ///
+    /// Annotations are exported by `PgnGame::from_game`.
+    ///
+    /// ## Panics
+    /// This method will panic if the ply is 0 or larger than the number of moves
+    /// in the history.
+    pub fn annotate(&mut self, ply: usize, annotation: Annotation) {
+        assert!(ply >= 1 && ply <= self.history.len(), "There is no move with ply {ply}.");
+        self.annotations.entry(ply).or_default().push(annotation);
+    }
+
+    /// Get the annotations of a move in the history, in the order they were added.
+    /// See `annotate`.
+    pub fn annotations(&self, ply: usize) -> &[Annotation] {
+        self.annotations.get(&ply).map_or(&[], Vec::as_slice)
+    }
+
+    /// Record how long a move in the history took, for example when importing a
+    /// game. The time spent and the time left on the clock after the move are
+    /// stored as `TimeSpent` and `Clock` annotations, replacing any that the move
+    /// already has. `None` removes the annotation.
+    ///
+    /// ## Panics
+    /// This method will panic if the ply is 0 or larger than the number of moves
+    /// in the history, see `annotate`.
+    pub fn set_move_time(&mut self, ply: usize, time_spent: Option<Duration>, clock_after: Option<Duration>) {
+        assert!(ply >= 1 && ply <= self.history.len(), "There is no move with ply {ply}.");
+        let annotations = self.annotations.entry(ply).or_default();
+        annotations.retain(|annotation| !matches!(annotation, Annotation::TimeSpent(_) | Annotation::Clock(_)));
+        annotations.extend(time_spent.map(Annotation::TimeSpent));
+        annotations.extend(clock_after.map(Annotation::Clock));
+        if annotations.is_empty() {
+            self.annotations.remove(&ply);
+        }
+    }
+
+    /// Get the time spent on a move in the history, see `set_move_time`.
+    pub fn time_spent(&self, ply: usize) -> Option<Duration> {
+        self.annotations(ply).iter().rev().find_map(|annotation| match annotation {
+            Annotation::TimeSpent(time) => Some(*time),
+            _ => None,
+        })
+    }
+
+    /// Get the time left on the clock of the player after a move in the history,
+    /// see `set_move_time`.
+    pub fn clock_after(&self, ply: usize) -> Option<Duration> {
+        self.annotations(ply).iter().rev().find_map(|annotation| match annotation {
+            Annotation::Clock(time) => Some(*time),
+            _ => None,
+        })
+    }
+
+    /// Draw an arrow on the board after a move in the history, for an analysis
+    /// board. The arrow is stored as an `Arrow` annotation, so it is saved with
+    /// the game and exported to PGN as a `[%cal Ge2e4]` command.
+    ///
+    /// The color tag is a letter like `G` for green, see `Annotation::Arrow`.
+    ///
+    /// ## Panics
+    /// This method will panic if the ply is 0 or larger than the number of moves
+    /// in the history, see `annotate`, or if the color tag is not an ASCII letter.
+    pub fn add_arrow(&mut self, ply: usize, from: BoardPos, to: BoardPos, color_tag: char) {
+        assert!(color_tag.is_ascii_alphabetic(), "Invalid color tag: {color_tag}");
+        self.annotate(ply, Annotation::Arrow { from, to, color: color_tag });
+    }
+
+    /// Highlight a square on the board after a move in the history, like
+    /// `add_arrow`. The highlight is exported to PGN as a `[%csl Ge4]` command.
+    ///
+    /// ## Panics
+    /// This method will panic if the ply is 0 or larger than the number of moves
+    /// in the history, see `annotate`, or if the color tag is not an ASCII letter.
+    pub fn add_highlight(&mut self, ply: usize, square: BoardPos, color_tag: char) {
+        assert!(color_tag.is_ascii_alphabetic(), "Invalid color tag: {color_tag}");
+        self.annotate(ply, Annotation::Highlight { square, color: color_tag });
+    }
+
+    /// Get the arrows of a move in the history as (from, to, color tag), in the
+    /// order they were added. See `add_arrow`.
+    pub fn arrows(&self, ply: usize) -> Vec<(BoardPos, BoardPos, char)> {
+        self.annotations(ply).iter().filter_map(|annotation| match annotation {
+            Annotation::Arrow { from, to, color } => Some((from.clone(), to.clone(), *color)),
+            _ => None,
+        }).collect()
+    }
+
+    /// Get the highlighted squares of a move in the history as (square, color
+    /// tag), in the order they were added. See `add_highlight`.
+    pub fn highlights(&self, ply: usize) -> Vec<(BoardPos, char)> {
+        self.annotations(ply).iter().filter_map(|annotation| match annotation {
+            Annotation::Highlight { square, color } => Some((square.clone(), *color)),
+            _ => None,
+        }).collect()
+    }
+
+    /// Get the total time that the team spent on its moves in the history.
+    /// Moves without a recorded time count as no time.
+    pub fn total_time_spent(&self, color: &Color) -> Duration {
+        // The last move was made by the team that is not to move.
+        let last = self.history.len();
+        (1..=last)
+            .filter(|ply| {
+                let mover = if (last - ply).is_multiple_of(2) { self.current_turn.opposite() } else { self.current_turn };
+                mover == *color
+            })
+            .filter_map(|ply| self.time_spent(ply))
+            .sum()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::pgn::{PgnGame, PgnWriter, Score};
+
+    use super::*;
+
+    #[test]
+    fn annotations_are_exported() {
+        let mut game = Game::new();
+        for san in ["e4", "e5", "Qh5", "Nc6"] {
+            let m = game.parse_san(san).unwrap();
+            game.make_move(&m).unwrap();
+        }
+        game.annotate(3, Annotation::Comment("Too early".to_string()));
+        game.annotate(3, Annotation::Nag(6));
+        game.annotate(3, Annotation::Eval(-35));
+        game.annotate(4, Annotation::Clock(Duration::from_secs(95)));
+        assert_eq!(game.annotations(3).len(), 3);
+        assert!(game.annotations(2).is_empty());
+
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_played_game(&game).unwrap();
+        let pgn = PgnGame::parse(&String::from_utf8(writer.into_inner()).unwrap()).unwrap();
+        assert_eq!(pgn.moves()[2].san(), "Qh5");
+        assert_eq!(pgn.moves()[2].comments(), ["Too early"]);
+        assert_eq!(pgn.moves()[2].eval(), Some(Score::Centipawns(-35)));
+        assert_eq!(pgn.moves()[2].nags(), [6]);
+        assert!(pgn.moves()[3].comments().is_empty());
+        assert_eq!(pgn.moves()[3].clock(), Some(Duration::from_secs(95)));
+    }
+
+    #[test]
+    fn move_times() {
+        let mut game = Game::new();
+        for san in ["e4", "e5", "Nf3"] {
+            let m = game.parse_san(san).unwrap();
+            game.make_move(&m).unwrap();
+        }
+        game.set_move_time(1, Some(Duration::from_secs(2)), Some(Duration::from_secs(178)));
+        game.set_move_time(2, Some(Duration::from_millis(4500)), Some(Duration::from_millis(175_500)));
+        game.set_move_time(3, Some(Duration::from_secs(10)), Some(Duration::from_secs(168)));
+        // Setting the time again replaces it.
+        game.annotate(3, Annotation::Nag(1));
+        game.set_move_time(3, Some(Duration::from_secs(12)), Some(Duration::from_secs(166)));
+
+        assert_eq!(game.time_spent(1), Some(Duration::from_secs(2)));
+        assert_eq!(game.time_spent(2), Some(Duration::from_millis(4500)));
+        assert_eq!(game.time_spent(3), Some(Duration::from_secs(12)));
+        assert_eq!(game.clock_after(3), Some(Duration::from_secs(166)));
+        assert_eq!(game.annotations(3).len(), 3);
+        assert_eq!(game.total_time_spent(&Color::White), Duration::from_secs(14));
+        assert_eq!(game.total_time_spent(&Color::Black), Duration::from_millis(4500));
+
+        game.set_move_time(2, None, None);
+        assert_eq!(game.time_spent(2), None);
+        assert!(game.annotations(2).is_empty());
+
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_played_game(&game).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(written.contains("1. e4 {[%clk 0:02:58] [%emt 0:00:02]} 1... e5 2. Nf3"), "{written}");
+        let pgn = PgnGame::parse(&written).unwrap();
+        assert_eq!(pgn.moves()[2].time_spent(), Some(Duration::from_secs(12)));
+    }
+
+    #[test]
+    fn arrows_round_trip() {
+        let mut game = Game::new();
+        for san in ["e4", "e5", "Nf3"] {
+            let m = game.parse_san(san).unwrap();
+            game.make_move(&m).unwrap();
+        }
+        let pos = |square: &str| square.parse::<BoardPos>().unwrap();
+        game.add_arrow(2, pos("g1"), pos("f3"), 'G');
+        game.add_arrow(2, pos("d8"), pos("h4"), 'R');
+        game.add_highlight(2, pos("e5"), 'Y');
+        game.annotate(2, Annotation::Comment("Attack the pawn".to_string()));
+        game.add_highlight(3, pos("f3"), 'B');
+
+        assert_eq!(game.arrows(2), [(pos("g1"), pos("f3"), 'G'), (pos("d8"), pos("h4"), 'R')]);
+        assert_eq!(game.highlights(2), [(pos("e5"), 'Y')]);
+        assert!(game.arrows(1).is_empty());
+
+        let written = game.to_pgn(&Default::default());
+        let words = written.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert!(words.contains("1. e4 e5 {[%csl Ye5] [%cal Gg1f3,Rd8h4]} {Attack the pawn} 2. Nf3 {[%csl Bf3]}"), "{written}");
+        let pgn = PgnGame::parse(&written).unwrap();
+        assert_eq!(pgn.moves()[1].arrows(), game.arrows(2));
+        assert_eq!(pgn.moves()[1].highlights(), game.highlights(2));
+        assert_eq!(pgn.moves()[1].comments(), ["Attack the pawn"]);
+        assert_eq!(pgn.moves()[2].highlights(), game.highlights(3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn annotate_missing_move() {
+        Game::new().annotate(1, Annotation::Nag(1));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{board::Color, pos::BoardPos, piece::PieceType};
+
+use super::{Game, Move, Outcome};
+
+/// The value of a king in an exchange. It is larger than all other pieces
+/// together, so that a king only captures when nothing can recapture.
+const KING_EXCHANGE_VALUE: i32 = 10_000;
+
+impl Game {
+
+    /// Get the legal moves of the team to move that lose at least `threshold`
+    /// centipawns of material, for a blunder check in casual play. Material is
+    /// counted with `PieceType::value`.
+    ///
+    /// A move loses material if the opponent can answer it by capturing more than
+    /// the move itself captured. The captures and recaptures that follow on the
+    /// same square are counted, which is known as static exchange evaluation.
+    /// This finds moves onto a defended square as well as moves that leave
+    /// another piece undefended. Material that could already be captured before
+    /// the move is not counted against it.
+    ///
+    /// Only a single ply is looked at: a move that checkmates is never losing and
+    /// the material a move captures counts in its favor, but forks, discovered
+    /// attacks and other deeper tactics are not seen, and pins are ignored in the
+    /// exchanges.
+    ///
+    /// The moves are in the order of `legal_moves`.
+    pub fn losing_moves(&mut self, threshold: i32) -> Vec<Move> {
+        let color = self.current_turn;
+        let already_lost = self.exchange_loss(&color);
+
+        self.legal_moves()
+            .into_iter()
+            .filter(|m| {
+                let mut game = self.clone();
+                let gained = game.material_captured_by(m);
+                game.make_move(m).expect("The move is legal.");
+                if matches!(game.outcome, Some(Outcome::Checkmate { .. })) {
+                    return false;
+                }
+                game.exchange_loss(&color) - gained - already_lost >= threshold
+            })
+            .collect()
+    }
+
+    /// Get the material that a legal move captures, including what a promotion
+    /// gains.
+    fn material_captured_by(&self, m: &Move) -> i32 {
+        let moving = self.board.get_tile(m.from()).expect("The move is legal.");
+        let captured = match self.board.get_tile(m.to()) {
+            Some(tile) => tile.piece().value(),
+            // A pawn moving diagonally to an empty square captures en passant.
+            None if moving.piece() == PieceType::Pawn && m.from().file() != m.to().file() => PieceType::Pawn.value(),
+            None => 0,
+        };
+        let promotion = m.promotion().map_or(0, |piece| piece.value() - PieceType::Pawn.value());
+        captured + promotion
+    }
+
+    /// Get the most material that the opponent can win by capturing one of the
+    /// pieces of the color, see `exchange`.
+    fn exchange_loss(&mut self, color: &Color) -> i32 {
+        let targets: Vec<BoardPos> = self.board.pieces()
+            .filter(|(_, tile)| tile.color() == *color && tile.piece() != PieceType::King)
+            .map(|(pos, _)| pos)
+            .collect();
+        targets.iter()
+            .map(|pos| self.exchange(pos, &color.opposite()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Get the material that the color wins by starting to capture on the
+    /// position, with both teams always capturing with their least valuable
+    /// piece and stopping when capturing would lose material.
+    pub(super) fn exchange(&mut self, pos: &BoardPos, color: &Color) -> i32 {
+        let board = self.board.clone();
+
+        let mut gains = Vec::new();
+        let mut target = self.board.get_tile(pos).map_or(0, |tile| exchange_value(tile.piece()));
+        let mut side = *color;
+        loop {
+            let attacker = self.attackers(pos, &side)
+                .into_iter()
+                .min_by_key(|attacker| self.board.get_tile(attacker).map_or(0, |tile| exchange_value(tile.piece())));
+            let Some(attacker) = attacker else { break };
+
+            gains.push(target);
+            let tile = self.board.remove_tile(&attacker).expect("The attacker exists.");
+            self.board.set_tile(pos, tile);
+            target = exchange_value(tile.piece());
+            side = side.opposite();
+        }
+
+        // The position is restored, so the cached moves are still valid.
+        self.board = board;
+
+        // Each capture is only made if it gains material after the recaptures.
+        gains.iter().rev().fold(0, |score, gain| (gain - score).max(0))
+    }
+}
+
+fn exchange_value(piece: PieceType) -> i32 {
+    match piece {
+        PieceType::King => KING_EXCHANGE_VALUE,
+        piece => piece.value(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn losing(fen: &str, threshold: i32) -> Vec<String> {
+        let mut game = Game::from_fen(fen).unwrap();
+        let mut moves: Vec<String> = game.losing_moves(threshold).iter().map(Move::to_string).collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn queen_takes_defended_pawn() {
+        let moves = losing("4k3/8/3p4/4p3/8/8/7Q/4K3 w - - 0 1", 200);
+        assert!(moves.contains(&"h2e5".to_string()));
+        assert!(!moves.contains(&"h2h5".to_string()));
+        assert!(!moves.contains(&"e1d1".to_string()));
+    }
+
+    #[test]
+    fn sound_capture() {
+        let moves = losing("4k3/8/8/4p3/8/8/7Q/4K3 w - - 0 1", 100);
+        assert!(!moves.contains(&"h2e5".to_string()));
+        // The pawn would take the queen.
+        assert!(moves.contains(&"h2f4".to_string()));
+
+        // The knight takes the rook for free.
+        let moves = losing("4k3/8/8/3r4/8/2N5/1P6/4K3 w - - 0 1", 100);
+        assert!(!moves.contains(&"c3d5".to_string()));
+    }
+
+    #[test]
+    fn abandoned_piece() {
+        // The pawn defends the knight from the bishop.
+        let moves = losing("4k3/8/8/b7/8/2N5/1P6/4K3 w - - 0 1", 200);
+        assert!(moves.contains(&"b2b3".to_string()));
+        assert!(!moves.contains(&"b2b4".to_string()));
+    }
+
+    #[test]
+    fn checkmate_is_not_losing() {
+        // The rook leaves the knight to the bishop, but it is checkmate.
+        let moves = losing("6k1/5ppp/8/8/N7/8/8/R2b2K1 w - - 0 1", 200);
+        assert!(!moves.contains(&"a1a8".to_string()));
+        assert!(moves.contains(&"a1c1".to_string()));
+    }
+}
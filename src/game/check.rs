@@ -1,4 +1,4 @@
-use crate::{board::Color, pos::BoardPos, piece::PieceType};
+use crate::{attacks, board::Color, pos::BoardPos, piece::PieceType};
 
 use super::Game;
 
@@ -14,7 +14,7 @@ impl Game {
 
         let enemy_color = color.opposite();
 
-        return self.is_attacked_by(&king_pos, &enemy_color);
+        self.is_attacked_by(&king_pos, &enemy_color)
     }
 
     pub(super) fn is_attacked_by(&self, pos: &BoardPos, color: &Color) -> bool {
@@ -30,6 +30,25 @@ impl Game {
                     // Only enemy pieces can attack.
                     continue;
                 }
+
+                // Knights, kings and pawns can be looked up directly since their
+                // attacks do not depend on other pieces. Pawns attack the squares
+                // diagonally in front of them even when they are empty, unlike their
+                // moves.
+                let leaper_attacks = match tile.piece() {
+                    PieceType::Knight => Some(attacks::knight_attacks(&enemy_pos)),
+                    PieceType::King => Some(attacks::king_attacks(&enemy_pos)),
+                    PieceType::Pawn => Some(attacks::pawn_attacks(&enemy_pos, &tile.color())),
+                    _ => None,
+                };
+                if let Some(leaper_attacks) = leaper_attacks {
+                    let blocked = self.board.get_tile(pos).is_some_and(|target| target.color() == *color);
+                    if leaper_attacks.contains(pos) && !blocked {
+                        return true;
+                    }
+                    continue;
+                }
+
                 let enemy_moves = self.get_pseudo_legal_moves(&enemy_pos, false);
 
                 if enemy_moves.contains(pos) {
@@ -38,13 +57,13 @@ impl Game {
             }
         }
 
-        return false;
+        false
     }
 
     /// Get the position of the king of the specified color.
     /// 
     /// Returns `None` if there is no king.
-    fn get_king_pos(&self, color: &Color) -> Option<BoardPos> {
+    pub(super) fn get_king_pos(&self, color: &Color) -> Option<BoardPos> {
         for file in 0..8 {
             for rank in 0..8 {
                 let pos = BoardPos::new(file, rank);
@@ -56,13 +75,17 @@ impl Game {
                 }
             }
         }
-        return None;
+        None
     }
 
     pub(super) fn is_checkmate(&mut self, color: &Color) -> bool {
         if !self.is_check(color) {
             return false;
         }
+        if *color == self.current_turn {
+            // The legal moves of the team to move are usually already cached.
+            return !self.has_legal_moves();
+        }
         for file in 0..8 {
             for rank in 0..8 {
                 let pos = BoardPos::new(file, rank);
@@ -98,7 +121,7 @@ impl Game {
         }
         // None of the possible moves that were attempted resulted in it no longer being
         // check, so there is nothing the team can do. It is checkmate.
-        return true;
+        true
     }
 }
 
@@ -0,0 +1,175 @@
+use crate::{attacks, board::Color, piece::PieceType, pos::BoardPos};
+
+use super::{Game, Move};
+
+impl Game {
+
+    /// Whether the legal move would put the opponent in check.
+    ///
+    /// The move is made on a copy of the game. Use `checking_moves` to find all
+    /// checks in a position.
+    ///
+    /// ## Panics
+    /// This method will panic if the move is not legal.
+    pub fn gives_check(&self, m: &Move) -> bool {
+        let mut after = self.clone();
+        after.make_move(m).expect("The move must be legal.");
+        let opponent = self.current_turn.opposite();
+        after.is_check(&opponent)
+    }
+
+    /// Get every legal move that puts the opponent in check, in the same order as
+    /// `legal_moves`.
+    ///
+    /// Instead of trying every move, the squares from which each piece would
+    /// attack the enemy king are looked up, and the pieces standing between the
+    /// king and one of the team's sliding pieces are found to detect discovered
+    /// checks. Only castling and en passant, which move more than one piece, are
+    /// tried out with `gives_check`.
+    pub fn checking_moves(&mut self) -> Vec<Move> {
+        let color = self.current_turn;
+        let king = match self.get_king_pos(&color.opposite()) {
+            Some(king) => king,
+            None => return Vec::new(),
+        };
+        let blockers = self.discovered_check_blockers(&king, &color);
+
+        self.legal_moves()
+            .into_iter()
+            .filter(|m| {
+                let tile = self.board.get_tile(m.from()).expect("Legal moves have a piece.");
+                let is_castling = tile.piece() == PieceType::King && m.from().file().abs_diff(m.to().file()) == 2;
+                let is_en_passant = tile.piece() == PieceType::Pawn
+                    && m.from().file() != m.to().file()
+                    && self.board.get_tile(m.to()).is_none();
+                if is_castling || is_en_passant {
+                    return self.gives_check(m);
+                }
+
+                let piece = m.promotion().unwrap_or(tile.piece());
+                let direct = self.attacks_after_move(piece, &color, m, &king);
+                let discovered = blockers.iter().any(|(blocker, direction)| {
+                    blocker == m.from() && !is_on_ray(&king, *direction, m.to())
+                });
+                direct || discovered
+            })
+            .collect()
+    }
+
+    /// Find the pieces of the color that are the only piece between the king and
+    /// a sliding piece of the same color, together with the direction from the
+    /// king to the piece. Moving such a piece off the line gives a discovered
+    /// check.
+    fn discovered_check_blockers(&self, king: &BoardPos, color: &Color) -> Vec<(BoardPos, (i8, i8))> {
+        let mut blockers = Vec::new();
+        for &direction in &attacks::QUEEN_DIRECTIONS {
+            let mut pieces = Vec::new();
+            let mut pos = king.offset(direction.0, direction.1);
+            while let Some(current) = pos {
+                if let Some(tile) = self.board.get_tile(&current) {
+                    pieces.push((current.clone(), tile));
+                    if pieces.len() == 2 {
+                        break;
+                    }
+                }
+                pos = current.offset(direction.0, direction.1);
+            }
+
+            if let [(blocker, first), (_, second)] = pieces.as_slice() {
+                if first.color() == *color
+                    && second.color() == *color
+                    && second.piece().movement().slides.contains(&direction) {
+                    blockers.push((blocker.clone(), direction));
+                }
+            }
+        }
+        blockers
+    }
+
+    /// Whether a piece of the type would attack the king after the move, with the
+    /// square it moved from empty.
+    fn attacks_after_move(&self, piece: PieceType, color: &Color, m: &Move, king: &BoardPos) -> bool {
+        let to = m.to();
+        match piece {
+            PieceType::Pawn => attacks::pawn_attacks(to, color).contains(king),
+            PieceType::Knight => attacks::knight_attacks(to).contains(king),
+            PieceType::King => false,
+            PieceType::Queen | PieceType::Rook | PieceType::Bishop => {
+                let direction = match to.direction_to(king) {
+                    Some(direction) => direction,
+                    None => return false,
+                };
+                piece.movement().slides.contains(&direction)
+                    && to.squares_between(king).expect("The squares are on a line.")
+                        .iter()
+                        .all(|pos| pos == m.from() || self.board.get_tile(pos).is_none())
+            },
+        }
+    }
+}
+
+/// Whether the position is on the ray going from the start in the direction.
+fn is_on_ray(start: &BoardPos, direction: (i8, i8), pos: &BoardPos) -> bool {
+    start.direction_to(pos) == Some(direction)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matches_gives_check(game: &mut Game) {
+        let expected: Vec<Move> = game.legal_moves()
+            .into_iter()
+            .filter(|m| game.gives_check(m))
+            .collect();
+        assert_eq!(game.checking_moves(), expected, "{}", game.to_fen());
+    }
+
+    #[test]
+    fn matches_gives_check() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            // Discovered checks by a knight, a pawn and a king, and double checks.
+            "4k3/8/8/8/4N3/8/4R3/B3K3 w - - 0 1",
+            "7k/8/8/8/3P4/8/1B6/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/4K3/4R2R w - - 0 1",
+            "k7/8/8/8/8/8/8/K6R w - - 0 1",
+            "4r3/4k3/8/8/8/8/8/4K3 b - - 0 1",
+            "7k/8/8/4N3/8/8/8/Q3K3 w - - 0 1",
+            // Promotions, also with discovered check.
+            "2r1k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+            "7k/P7/8/8/8/8/8/R3K3 w - - 0 1",
+            "8/RP5k/8/8/8/8/8/4K3 w - - 0 1",
+            // En passant with a discovered check along the rank and diagonal.
+            "8/8/8/RPp4k/8/8/8/4K3 w - c6 0 1",
+            "8/8/8/1R1pP2k/8/8/8/4K3 w - d6 0 1",
+            "7k/8/8/3pP3/8/8/8/B3K3 w - d6 0 1",
+            // Castling with check from the rook.
+            "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+            "3k4/8/8/8/8/8/8/R3K3 w Q - 0 1",
+        ];
+        for fen in fens {
+            let mut game = Game::from_fen(fen).unwrap();
+            assert_matches_gives_check(&mut game);
+
+            // Also check every position after one move from the position.
+            for m in game.legal_moves() {
+                let mut after = game.clone();
+                after.make_move(&m).unwrap();
+                assert_matches_gives_check(&mut after);
+            }
+        }
+    }
+
+    #[test]
+    fn checks_in_position() {
+        let mut game = Game::from_fen("4k3/8/8/8/4N3/8/4R3/B3K3 w - - 0 1").unwrap();
+        let checks: Vec<String> = game.checking_moves().iter().map(Move::to_string).collect();
+        // Every knight move uncovers the rook, and Nd6 and Nf6 are double checks.
+        assert_eq!(checks.len(), 8);
+        assert!(checks.contains(&"e4d6".to_string()));
+        assert!(!checks.contains(&"e2e3".to_string()));
+    }
+}
@@ -0,0 +1,213 @@
+use std::{fmt, str::FromStr};
+
+use crate::{piece::PieceType, pos::BoardPos};
+
+use super::{Game, Move, MovePieceError};
+
+/// The error returned when a move is not in coordinate notation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParseMoveError;
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The move is not in coordinate notation, like e2e4 or e7e8q.")
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+/// Parses a move in coordinate notation as used by UCI, like `e2e4` or `e7e8q`
+/// for a promotion.
+///
+/// Castling is written as the king moving two squares, like `e1g1`. The king
+/// moving onto its own rook, like `e1h1`, is also accepted by `Game::move_piece`
+/// and `Game::make_move`.
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let str = str.trim();
+        if !str.is_ascii() || !(4..=5).contains(&str.len()) {
+            return Err(ParseMoveError);
+        }
+        let from: BoardPos = str[0..2].parse().map_err(|_| ParseMoveError)?;
+        let to: BoardPos = str[2..4].parse().map_err(|_| ParseMoveError)?;
+        let promotion = match str[4..].chars().next() {
+            None => None,
+            Some(char) => Some(PieceType::from_char(char.to_ascii_lowercase()).map_err(|_| ParseMoveError)?),
+        };
+        Ok(Move::new(from, to, promotion))
+    }
+}
+
+/// Formats the move in coordinate notation, see `FromStr`.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from(), self.to())?;
+        if let Some(promotion) = self.promotion() {
+            write!(f, "{}", promotion.char())?;
+        }
+        Ok(())
+    }
+}
+
+impl Move {
+    /// Write the move in coordinate notation as used by UCI, like `e2e4` or
+    /// `e7e8q`. This is the same as `to_string`, and the inverse of `parse`.
+    ///
+    /// Castling is written as the king moving two squares. Use
+    /// `Game::format_coordinate_move` for the notation of `UCI_Chess960`.
+    pub fn to_uci(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// How castling is written in coordinate notation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CastlingNotation {
+    /// The king moves two squares towards the rook, like `e1g1`. This is the
+    /// notation of standard UCI.
+    KingTwoSquares,
+    /// The king moves onto its own rook, like `e1h1`. This is the notation of
+    /// `UCI_Chess960`.
+    KingTakesRook,
+}
+
+impl Game {
+
+    /// Make a move written in coordinate notation as used by UCI, like `e2e4`,
+    /// `e1g1` or `e7e8q`.
+    ///
+    /// A promotion is made together with the move, so the game is never left
+    /// waiting for `promote`. Castling is written as the king moving two squares,
+    /// and the king moving onto its own rook is also accepted.
+    ///
+    /// # Errors
+    /// `InvalidMove` is returned if the string is not in coordinate notation, or
+    /// if the promotion piece is missing from a promotion. Otherwise the errors
+    /// are the same as for `make_move`, and the game is not changed.
+    pub fn move_uci(&mut self, uci: &str) -> Result<(), MovePieceError> {
+        let m: Move = uci.parse().map_err(|_| MovePieceError::InvalidMove)?;
+        self.make_move(&m)
+    }
+
+    /// Write a legal move in coordinate notation, with castling written in the
+    /// chosen notation.
+    pub fn format_coordinate_move(&self, m: &Move, castling: CastlingNotation) -> String {
+        let rook = match castling {
+            CastlingNotation::KingTwoSquares => None,
+            CastlingNotation::KingTakesRook => self.castling_rook(m),
+        };
+        match rook {
+            Some(rook) => Move::new(m.from().clone(), rook, None).to_string(),
+            None => m.to_string(),
+        }
+    }
+
+    /// Get the square the king castles to if the move is the king of the team
+    /// whose turn it is moving onto its own castling rook, like `e1h1`.
+    ///
+    /// Whether castling is legal is not checked.
+    pub(super) fn castling_by_rook(&self, from: &BoardPos, to: &BoardPos) -> Option<BoardPos> {
+        let color = self.current_turn;
+        let king = self.board.get_tile(from)?;
+        let rook = self.board.get_tile(to)?;
+        if king.piece() != PieceType::King || rook.piece() != PieceType::Rook
+            || king.color() != color || rook.color() != color
+            || from.rank() != to.rank() {
+            return None;
+        }
+        let dir = if to.file() > from.file() { 1 } else { -1 };
+        let target = from.offset(2 * dir, 0)?;
+        // The rook must be the first piece beyond the square the king castles to.
+        (self.find_rook(&target, &color, dir).as_ref() == Some(to)).then_some(target)
+    }
+
+    /// Get the square of the rook that the king castles with, if the move is
+    /// castling.
+    fn castling_rook(&self, m: &Move) -> Option<BoardPos> {
+        let king = self.board.get_tile(m.from())?;
+        if king.piece() != PieceType::King || m.from().file().abs_diff(m.to().file()) != 2 {
+            return None;
+        }
+        let dir = if m.to().file() > m.from().file() { 1 } else { -1 };
+        self.find_rook(m.to(), &king.color(), dir)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::game::GameState;
+
+    use super::*;
+
+    #[test]
+    fn move_uci() {
+        let mut game = Game::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let fen = game.to_fen();
+        for uci in ["e5d6x", "b7a8", "b7a8k", "e5e7", "e2e4", "a8a7"] {
+            assert!(game.move_uci(uci).is_err(), "{uci}");
+        }
+        assert!(matches!(game.move_uci("b7a8"), Err(MovePieceError::InvalidMove)));
+        assert!(matches!(game.move_uci("e2e4"), Err(MovePieceError::NoTile)));
+        assert_eq!(game.to_fen(), fen);
+
+        game.move_uci("e5d6").unwrap();
+        game.move_uci("e8g8").unwrap();
+        game.move_uci("b7a8q").unwrap();
+        assert_eq!(game.get_state(), GameState::Normal);
+        game.move_uci("f8a8").unwrap();
+        game.move_uci("e1h1").unwrap();
+        assert!(game.to_fen().starts_with("r5k1/8/3P4/8/8/8/8/R4RK1 b - -"));
+
+        let uci: Vec<String> = game.history().iter().map(Move::to_uci).collect();
+        assert_eq!(uci, ["e5d6", "e8g8", "b7a8q", "f8a8", "e1g1"]);
+        let mut replay = Game::from_fen(&fen).unwrap();
+        for m in &uci {
+            replay.move_uci(m).unwrap();
+        }
+        assert_eq!(replay.to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn parse_and_format() {
+        let m: Move = "e7e8q".parse().unwrap();
+        assert_eq!(m, Move::new("e7".parse().unwrap(), "e8".parse().unwrap(), Some(PieceType::Queen)));
+        assert_eq!(m.to_string(), "e7e8q");
+        assert_eq!(" E2e4 ".parse::<Move>().unwrap().to_string(), "e2e4");
+
+        for str in ["", "e2", "e2e9", "e2e4x", "e2e4qq", "e2–e4"] {
+            assert_eq!(str.parse::<Move>(), Err(ParseMoveError), "{str}");
+        }
+    }
+
+    #[test]
+    fn king_takes_rook_castling() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let mut game = Game::from_fen(fen).unwrap();
+        game.make_move(&"e1h1".parse().unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+        assert_eq!(game.history()[0].to_string(), "e1g1");
+
+        game.move_piece(&"e8".parse().unwrap(), &"a8".parse().unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
+
+        let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1").unwrap();
+        assert!(matches!(game.make_move(&"e1h1".parse().unwrap()), Err(MovePieceError::InvalidMove)));
+        assert!(matches!(game.move_piece(&"e1".parse().unwrap(), &"a1".parse().unwrap()), Ok(effects) if effects.queenside_castle));
+    }
+
+    #[test]
+    fn format_castling() {
+        let game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let kingside = Move::new("e1".parse().unwrap(), "g1".parse().unwrap(), None);
+        let queenside = Move::new("e1".parse().unwrap(), "c1".parse().unwrap(), None);
+        let rook_move = Move::new("h1".parse().unwrap(), "h8".parse().unwrap(), None);
+
+        assert_eq!(game.format_coordinate_move(&kingside, CastlingNotation::KingTwoSquares), "e1g1");
+        assert_eq!(game.format_coordinate_move(&kingside, CastlingNotation::KingTakesRook), "e1h1");
+        assert_eq!(game.format_coordinate_move(&queenside, CastlingNotation::KingTakesRook), "e1a1");
+        assert_eq!(game.format_coordinate_move(&rook_move, CastlingNotation::KingTakesRook), "h1h8");
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{board::Color, piece::PieceType};
+
+use super::Game;
+
+/// The order in which the pieces of a team are described.
+const PIECE_ORDER: [PieceType; 6] = [
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Pawn,
+];
+
+impl Game {
+
+    /// Describe the position in plain English, for example to be read aloud by a
+    /// screen reader.
+    /// 
+    /// The pieces of each team are grouped by type, from the king down to the
+    /// pawns, followed by whose turn it is, castling availability and en passant.
+    /// For example:
+    /// 
+    /// `White: king on e1, rooks on a1 and h1. Black: king on e8. White to move; White may castle both sides.`
+    /// 
+    /// The output is deterministic, the squares of each piece type are listed in
+    /// the order `a1`, `b1`, ..., `h8`.
+    pub fn describe_position(&self) -> String {
+        let mut str = String::new();
+        str.push_str(&self.describe_team(&Color::White));
+        str.push_str(". ");
+        str.push_str(&self.describe_team(&Color::Black));
+        str.push_str(". ");
+
+        let mut clauses = vec![format!("{} to move", self.current_turn)];
+        for color in [Color::White, Color::Black] {
+            let castling = self.castling_availability(&color);
+            let sides = match (castling.kingside, castling.queenside) {
+                (true, true) => "both sides",
+                (true, false) => "kingside",
+                (false, true) => "queenside",
+                (false, false) => continue,
+            };
+            clauses.push(format!("{color} may castle {sides}"));
+        }
+        if let Some(en_passant_target) = self.en_passant_target() {
+            clauses.push(format!("en passant available on {en_passant_target}"));
+        }
+        str.push_str(&clauses.join("; "));
+        str.push('.');
+        str
+    }
+
+    /// Describe the pieces of a team, for example `White: king on e1, rooks on a1 and h1`.
+    fn describe_team(&self, color: &Color) -> String {
+        let mut groups = Vec::new();
+        for piece in PIECE_ORDER {
+            let squares: Vec<String> = self.board.pieces()
+                .filter(|(_pos, tile)| tile.color() == *color && tile.piece() == piece)
+                .map(|(pos, _tile)| pos.to_string())
+                .collect();
+            match squares.len() {
+                0 => {},
+                1 => groups.push(format!("{} on {}", piece.name(), squares[0])),
+                _ => groups.push(format!("{}s on {}", piece.name(), join_list(&squares))),
+            }
+        }
+        if groups.is_empty() {
+            return format!("{color}: no pieces");
+        }
+        format!("{color}: {}", groups.join(", "))
+    }
+}
+
+/// Join the items into an English list, for example `a1, b1 and c1`.
+fn join_list(items: &[String]) -> String {
+    match items.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_starting_position() {
+        let game = Game::new();
+
+        assert_eq!(game.describe_position(),
+            "White: king on e1, queen on d1, rooks on a1 and h1, bishops on c1 and f1, \
+            knights on b1 and g1, pawns on a2, b2, c2, d2, e2, f2, g2 and h2. \
+            Black: king on e8, queen on d8, rooks on a8 and h8, bishops on c8 and f8, \
+            knights on b8 and g8, pawns on a7, b7, c7, d7, e7, f7, g7 and h7. \
+            White to move; White may castle both sides; Black may castle both sides."
+        );
+    }
+
+    #[test]
+    fn describe_endgame() {
+        let game = Game::from_fen("4k3/8/8/3pP3/8/8/8/R3K3 w Q d6 0 1").unwrap();
+
+        assert_eq!(game.describe_position(),
+            "White: king on e1, rook on a1, pawn on e5. \
+            Black: king on e8, pawn on d5. \
+            White to move; White may castle queenside; en passant available on d6."
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use std::fmt;
+
+use crate::{board::Color, piece::PieceType};
+
+use super::{Game, GameState, Move};
+
+/// Errors returned by `Game::resolve_description`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DescriptionError {
+    /// A word of the description is not understood, or the description says
+    /// nothing about the move. The word is included, or empty if there is none.
+    Unreadable(String),
+    /// No legal move matches the description.
+    NoMatch,
+    /// More than one legal move matches the description, so the caller can ask
+    /// which one was meant. The matching moves are included.
+    Ambiguous(Vec<Move>),
+}
+
+impl fmt::Display for DescriptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unreadable(word) if word.is_empty() => write!(f, "That does not describe a move."),
+            Self::Unreadable(word) => write!(f, "The word '{word}' is not understood."),
+            Self::NoMatch => write!(f, "No legal move matches the description."),
+            Self::Ambiguous(moves) => {
+                let moves: Vec<String> = moves.iter().map(Move::to_string).collect();
+                write!(f, "The description matches several moves: {}.", moves.join(", "))
+            },
+        }
+    }
+}
+
+impl std::error::Error for DescriptionError {}
+
+/// What a description says about a move.
+#[derive(Default, Debug)]
+struct Description {
+    piece: Option<PieceType>,
+    capture: bool,
+    captured: Option<PieceType>,
+    file: Option<u8>,
+    rank: Option<u8>,
+    promotion: Option<PieceType>,
+    check: bool,
+    mate: bool,
+    castling: bool,
+    /// `Some(true)` for kingside and `Some(false)` for queenside castling.
+    castling_side: Option<bool>,
+}
+
+/// Words that carry no meaning for the move.
+const FILLER_WORDS: [&str; 13] = ["to", "on", "the", "an", "square", "rank", "file", "move", "moves", "goes", "go", "and", "please"];
+
+impl Game {
+
+    /// Find the legal move described in loose spoken English, for example from a
+    /// voice interface or a screen reader. Examples are `knight takes on d5`,
+    /// `rook to the seventh`, `queen check`, `pawn takes knight` and `castle
+    /// short`.
+    ///
+    /// A description may name the piece, `takes` or `captures` with or without
+    /// the piece that is captured, a destination square, file like `d` or rank
+    /// like `seventh`, the promotion piece after `promotes to`, and `check` or
+    /// `mate`. Ranks given by ordinals are counted from the side of the team to
+    /// move, so `the seventh` is the second rank for black, while squares like
+    /// `d5` are always absolute. Castling is written with `castle`, `castles` or
+    /// `castling` and optionally `kingside`, `short`, `queenside` or `long`.
+    ///
+    /// The game is not changed, use `make_move` to make the move.
+    ///
+    /// # Errors
+    /// `Unreadable` is returned for unknown words or an empty description,
+    /// `NoMatch` if no legal move matches and `Ambiguous` with the matching moves
+    /// if several do.
+    pub fn resolve_description(&mut self, text: &str) -> Result<Move, DescriptionError> {
+        let description = parse_description(text, &self.current_turn)?;
+
+        let moves: Vec<Move> = self.legal_moves().into_iter()
+            .filter(|m| self.matches_description(m, &description))
+            .collect();
+        let mut moves = match description.mate {
+            true => moves.into_iter().filter(|m| self.gives_mate(m)).collect(),
+            false => moves,
+        };
+
+        match moves.len() {
+            0 => Err(DescriptionError::NoMatch),
+            1 => Ok(moves.remove(0)),
+            _ => Err(DescriptionError::Ambiguous(moves)),
+        }
+    }
+
+    /// Check whether a legal move matches everything a description says, except
+    /// for checkmate.
+    fn matches_description(&self, m: &Move, description: &Description) -> bool {
+        let tile = self.board.get_tile(m.from()).expect("Legal moves have a piece.");
+        let is_castling = tile.piece() == PieceType::King && m.from().file().abs_diff(m.to().file()) == 2;
+        if description.castling || is_castling {
+            return description.castling
+                && is_castling
+                && description.castling_side.is_none_or(|kingside| kingside == (m.to().file() > m.from().file()));
+        }
+
+        let captured = match self.board.get_tile(m.to()) {
+            Some(target) => Some(target.piece()),
+            None if tile.piece() == PieceType::Pawn && m.from().file() != m.to().file() => Some(PieceType::Pawn),
+            None => None,
+        };
+
+        description.piece.is_none_or(|piece| piece == tile.piece())
+            && (!description.capture || captured.is_some())
+            && description.captured.is_none_or(|piece| Some(piece) == captured)
+            && description.file.is_none_or(|file| file == m.to().file())
+            && description.rank.is_none_or(|rank| rank == m.to().rank())
+            && description.promotion.is_none_or(|piece| Some(piece) == m.promotion())
+            && (!description.check || self.gives_check(m))
+    }
+
+    /// Check whether a legal move checkmates the opponent.
+    fn gives_mate(&self, m: &Move) -> bool {
+        let mut game = self.clone();
+        game.make_move(m).expect("The move is legal.");
+        matches!(game.get_state(), GameState::Checkmate(_))
+    }
+}
+
+/// Read a description of a move by the team, see `Game::resolve_description`.
+fn parse_description(text: &str, color: &Color) -> Result<Description, DescriptionError> {
+    let text = text.to_lowercase()
+        .replace(['-', ',', '.', '!', '?'], " ")
+        .replace("king side", "kingside")
+        .replace("queen side", "queenside");
+    let words: Vec<&str> = text.split_whitespace().collect();
+
+    let mut description = Description::default();
+    let mut said_anything = false;
+    for (i, &word) in words.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| words[i]);
+        said_anything = true;
+
+        if let Some(piece) = piece_word(word) {
+            if matches!(previous, Some("takes" | "take" | "captures" | "capture" | "x")) {
+                description.captured = Some(piece);
+            } else if matches!(previous, Some("promotes" | "promote" | "promoting" | "equals"))
+                || (matches!(previous, Some("to")) && words.get(i.wrapping_sub(2)).is_some_and(|word| word.starts_with("promot"))) {
+                description.promotion = Some(piece);
+            } else if description.piece.is_none() {
+                description.piece = Some(piece);
+            } else {
+                return Err(DescriptionError::Unreadable(word.to_string()));
+            }
+            continue;
+        }
+
+        match word {
+            "takes" | "take" | "captures" | "capture" | "x" => description.capture = true,
+            "promotes" | "promote" | "promoting" | "equals" => {},
+            "castle" | "castles" | "castling" => description.castling = true,
+            "kingside" | "short" => {
+                description.castling = true;
+                description.castling_side = Some(true);
+            },
+            "queenside" | "long" => {
+                description.castling = true;
+                description.castling_side = Some(false);
+            },
+            "check" => description.check = true,
+            "mate" | "checkmate" => description.mate = true,
+            // An `a` is a file at the end or before `file`, and otherwise an article.
+            "a" if i + 1 < words.len() && words[i + 1] != "file" => said_anything = i > 0 || words.len() > 1,
+            word if FILLER_WORDS.contains(&word) => {},
+            word => {
+                if let Ok(pos) = word.parse::<crate::pos::BoardPos>() {
+                    description.file = Some(pos.file());
+                    description.rank = Some(pos.rank());
+                } else if let Some(file) = file_word(word) {
+                    description.file = Some(file);
+                } else if let Some(rank) = ordinal_word(word) {
+                    description.rank = Some(match color {
+                        Color::White => rank,
+                        Color::Black => 7 - rank,
+                    });
+                } else {
+                    return Err(DescriptionError::Unreadable(word.to_string()));
+                }
+            },
+        }
+    }
+
+    if !said_anything {
+        return Err(DescriptionError::Unreadable(String::new()));
+    }
+    Ok(description)
+}
+
+fn piece_word(word: &str) -> Option<PieceType> {
+    match word {
+        "king" => Some(PieceType::King),
+        "queen" => Some(PieceType::Queen),
+        "rook" => Some(PieceType::Rook),
+        "bishop" => Some(PieceType::Bishop),
+        "knight" => Some(PieceType::Knight),
+        "pawn" => Some(PieceType::Pawn),
+        _ => None,
+    }
+}
+
+/// Read a file like `d`, starting at 0 for the a-file.
+fn file_word(word: &str) -> Option<u8> {
+    match word.as_bytes() {
+        [file @ b'a'..=b'h'] => Some(file - b'a'),
+        _ => None,
+    }
+}
+
+/// Read an ordinal like `seventh` or `7th`, starting at 0 for `first`.
+fn ordinal_word(word: &str) -> Option<u8> {
+    const ORDINALS: [[&str; 2]; 8] = [
+        ["first", "1st"], ["second", "2nd"], ["third", "3rd"], ["fourth", "4th"],
+        ["fifth", "5th"], ["sixth", "6th"], ["seventh", "7th"], ["eighth", "8th"],
+    ];
+    ORDINALS.iter().position(|names| names.contains(&word)).map(|rank| rank as u8)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(fen: &str, text: &str) -> Result<String, DescriptionError> {
+        Game::from_fen(fen).unwrap().resolve_description(text).map(|m| m.to_string())
+    }
+
+    const SCANDINAVIAN: &str = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2";
+
+    #[test]
+    fn unambiguous() {
+        assert_eq!(resolve(SCANDINAVIAN, "pawn takes on d5").unwrap(), "e4d5");
+        assert_eq!(resolve(SCANDINAVIAN, "Pawn takes pawn!").unwrap(), "e4d5");
+        assert_eq!(resolve(SCANDINAVIAN, "knight to f3").unwrap(), "g1f3");
+        assert_eq!(resolve(SCANDINAVIAN, "bishop to the b file").unwrap(), "f1b5");
+        assert_eq!(resolve(SCANDINAVIAN, "queen h5").unwrap(), "d1h5");
+        assert_eq!(resolve(SCANDINAVIAN, "bishop check").unwrap(), "f1b5");
+
+        // Ranks are counted from the team to move.
+        assert_eq!(resolve("r3k3/8/8/8/8/8/8/4K2R w - - 0 1", "rook to the seventh").unwrap(), "h1h7");
+        assert_eq!(resolve("r3k3/8/8/8/8/8/8/4K2R b - - 0 1", "rook to the seventh").unwrap(), "a8a2");
+
+        assert_eq!(resolve("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "castles short").unwrap(), "e8g8");
+        assert_eq!(resolve("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "castle queen-side").unwrap(), "e1c1");
+        assert_eq!(resolve("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "pawn a8 promotes to knight").unwrap(), "a7a8n");
+        assert_eq!(resolve("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "rook mate").unwrap(), "a1a8");
+    }
+
+    #[test]
+    fn ambiguous() {
+        let fen = "r1bqkbnr/ppp2ppp/2np4/4p3/4P3/P2P1N2/1PP2PPP/RNBQKB1R w KQkq - 0 5";
+        let err = resolve(fen, "knight to d2").unwrap_err();
+        assert_eq!(err, DescriptionError::Ambiguous(vec!["b1d2".parse().unwrap(), "f3d2".parse().unwrap()]));
+        assert_eq!(err.to_string(), "The description matches several moves: b1d2, f3d2.");
+        assert!(matches!(resolve("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "castle"), Err(DescriptionError::Ambiguous(moves)) if moves.len() == 2));
+    }
+
+    #[test]
+    fn impossible() {
+        assert_eq!(resolve(SCANDINAVIAN, "queen takes on h7"), Err(DescriptionError::NoMatch));
+        assert_eq!(resolve(SCANDINAVIAN, "castle"), Err(DescriptionError::NoMatch));
+        assert_eq!(resolve(SCANDINAVIAN, "knight jumps to f3"), Err(DescriptionError::Unreadable("jumps".to_string())));
+        assert_eq!(resolve(SCANDINAVIAN, "  "), Err(DescriptionError::Unreadable(String::new())));
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+
+use crate::{board::{Color, Tile}, pos::BoardPos};
+
+use super::Game;
+
+/// One difference between two positions, see `diff_positions`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PositionChange {
+    /// The piece stands on another square.
+    Moved { tile: Tile, from: BoardPos, to: BoardPos },
+    /// The piece is no longer on the board, for example because it was captured.
+    Removed { tile: Tile, pos: BoardPos },
+    /// The piece is new on the board, for example after a promotion.
+    Added { tile: Tile, pos: BoardPos },
+    /// The other team is to move.
+    Turn(Color),
+    /// The team may now castle on the side, or may no longer if `available` is
+    /// false.
+    Castling { color: Color, kingside: bool, available: bool },
+    /// The en passant target changed to the square, or was removed.
+    EnPassant(Option<BoardPos>),
+}
+
+impl fmt::Display for PositionChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Moved { tile, from, to } => write!(f, "{} {} moved {from}→{to}", tile.color(), tile.piece().name()),
+            Self::Removed { tile, pos } => write!(f, "{} lost a {} on {pos}", tile.color(), tile.piece().name()),
+            Self::Added { tile, pos } => write!(f, "{} gained a {} on {pos}", tile.color(), tile.piece().name()),
+            Self::Turn(color) => write!(f, "{color} to move"),
+            Self::Castling { color, kingside, available } => {
+                let side = if *kingside { "kingside" } else { "queenside" };
+                let may = if *available { "may now" } else { "may no longer" };
+                write!(f, "{color} {may} castle {side}")
+            },
+            Self::EnPassant(Some(pos)) => write!(f, "En passant is possible on {pos}"),
+            Self::EnPassant(None) => write!(f, "En passant is no longer possible"),
+        }
+    }
+}
+
+/// What differs between two positions, see `diff_positions`.
+///
+/// The `Display` implementation joins the changes with semicolons, like
+/// `White rook moved a1→d1; Black lost a pawn on e5`.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PositionDiff {
+    /// The moved pieces first, then the removed and the added pieces, each by
+    /// square, followed by the changes to the turn, castling and en passant.
+    pub changes: Vec<PositionChange>,
+}
+
+impl PositionDiff {
+    /// Whether the positions are the same.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for PositionDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let changes: Vec<String> = self.changes.iter().map(ToString::to_string).collect();
+        write!(f, "{}", changes.join("; "))
+    }
+}
+
+/// Describe how position `b` differs from position `a` in chess terms, for
+/// example to show a learner what changed after a takeback or an engine line.
+///
+/// A piece that is removed from one square and added on another is reported as
+/// moved if it is the only piece of its type and color that was removed and the
+/// only one that was added. Otherwise the removals and additions are reported
+/// separately, so a promotion is a lost pawn and a gained queen. The move
+/// counters are not compared.
+pub fn diff_positions(a: &Game, b: &Game) -> PositionDiff {
+    let mut removed: Vec<(BoardPos, Tile)> = Vec::new();
+    let mut added: Vec<(BoardPos, Tile)> = Vec::new();
+    for (pos, before) in a.board.pieces() {
+        if b.board.get_tile(&pos) != Some(before) {
+            removed.push((pos, before));
+        }
+    }
+    for (pos, after) in b.board.pieces() {
+        if a.board.get_tile(&pos) != Some(after) {
+            added.push((pos, after));
+        }
+    }
+
+    let mut moved = Vec::new();
+    for (from, tile) in &removed {
+        let count = |list: &[(BoardPos, Tile)]| list.iter().filter(|(_, other)| other == tile).count();
+        if count(&removed) == 1 && count(&added) == 1 {
+            let (to, _) = added.iter().find(|(_, other)| other == tile).expect("The piece was added.");
+            moved.push(PositionChange::Moved { tile: *tile, from: from.clone(), to: to.clone() });
+        }
+    }
+    let is_moved = |tile: &Tile| moved.iter().any(|change| matches!(change, PositionChange::Moved { tile: other, .. } if other == tile));
+
+    let removed: Vec<PositionChange> = removed.into_iter()
+        .filter(|(_, tile)| !is_moved(tile))
+        .map(|(pos, tile)| PositionChange::Removed { tile, pos })
+        .collect();
+    let added: Vec<PositionChange> = added.into_iter()
+        .filter(|(_, tile)| !is_moved(tile))
+        .map(|(pos, tile)| PositionChange::Added { tile, pos })
+        .collect();
+    let mut changes = moved;
+    changes.extend(removed);
+    changes.extend(added);
+
+    if a.current_turn != b.current_turn {
+        changes.push(PositionChange::Turn(b.current_turn));
+    }
+    for color in [Color::White, Color::Black] {
+        let before = a.castling_availability(&color);
+        let after = b.castling_availability(&color);
+        if before.kingside != after.kingside {
+            changes.push(PositionChange::Castling { color, kingside: true, available: after.kingside });
+        }
+        if before.queenside != after.queenside {
+            changes.push(PositionChange::Castling { color, kingside: false, available: after.queenside });
+        }
+    }
+    if a.en_passant_target != b.en_passant_target {
+        changes.push(PositionChange::EnPassant(b.en_passant_target.clone()));
+    }
+
+    PositionDiff { changes }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(a: &str, b: &str) -> String {
+        diff_positions(&Game::from_fen(a).unwrap(), &Game::from_fen(b).unwrap()).to_string()
+    }
+
+    #[test]
+    fn castling_diff() {
+        let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+        let before = game.clone();
+        game.make_move(&"e8c8".parse().unwrap()).unwrap();
+        assert_eq!(diff_positions(&before, &game).to_string(),
+            "Black rook moved a8→d8; Black king moved e8→c8; White to move; \
+            Black may no longer castle kingside; Black may no longer castle queenside");
+        assert!(diff_positions(&game, &game).is_empty());
+    }
+
+    #[test]
+    fn capture_diff() {
+        assert_eq!(diff("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", "rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2"),
+            "White pawn moved e4→d5; Black lost a pawn on d5; Black to move");
+
+        // A promotion with capture, and two rooks that can not be told apart.
+        assert_eq!(diff("1n2k3/P7/8/8/8/8/8/R3K2R w - - 0 1", "1Q2k3/8/8/8/8/8/8/3RKR2 w - - 0 1"),
+            "White lost a rook on a1; White lost a rook on h1; White lost a pawn on a7; Black lost a knight on b8; \
+            White gained a rook on d1; White gained a rook on f1; White gained a queen on b8");
+
+        let mut game = Game::from_fen("rnbqkbnr/ppp1pppp/8/4P3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2").unwrap();
+        let before = game.clone();
+        game.make_move(&"f7f5".parse().unwrap()).unwrap();
+        assert_eq!(diff_positions(&before, &game).to_string(), "Black pawn moved f7→f5; White to move; En passant is possible on f6");
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+
+use crate::{board::{Board, Color, Tile}, pos::BoardPos, piece::PieceType};
+
+use super::{fen::is_possible_en_passant_target, validate_fen, CastlingAvailability, FenIssue, Game, Severity};
+
+/// Why an edit made by `Game::edit` was rolled back.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EditError {
+    /// The errors that `validate_fen` found in the edited position.
+    pub issues: Vec<FenIssue>,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.issues.first() {
+            Some(issue) => write!(f, "The edited position is illegal: {}", issue.message),
+            None => write!(f, "The edited position is illegal."),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// Changes the tiles of a board without any validation, see `Game::edit`.
+pub struct BoardEditor<'a> {
+    board: &'a mut Board,
+}
+
+impl BoardEditor<'_> {
+    /// Get the tile at the position, including the changes made so far.
+    pub fn get(&self, pos: &BoardPos) -> Option<Tile> {
+        self.board.get_tile(pos)
+    }
+
+    /// Put a tile on the position, replacing any tile that is there.
+    pub fn set(&mut self, pos: &BoardPos, tile: Tile) {
+        self.board.set_tile(pos, tile);
+    }
+
+    /// Remove the tile at the position, returning it.
+    pub fn remove(&mut self, pos: &BoardPos) -> Option<Tile> {
+        self.board.remove_tile(pos)
+    }
+
+    /// Move the tile on `from` to `to`, replacing any tile there, without
+    /// checking that the move is legal. Nothing happens if `from` is empty.
+    pub fn move_piece_raw(&mut self, from: &BoardPos, to: &BoardPos) {
+        if let Some(tile) = self.board.remove_tile(from) {
+            self.board.set_tile(to, tile);
+        }
+    }
+}
+
+impl Game {
+
+    /// Edit the board as a single transaction, for position editors and analysis.
+    ///
+    /// The closure can change the tiles freely through the `BoardEditor`. When it
+    /// returns, castling rights whose king or rook has left its starting square
+    /// and an en passant target that is no longer possible are removed, and the
+    /// position is checked with `validate_fen`. The turn and clocks are kept.
+    ///
+    /// A legal edit starts the game over from the edited position, like
+    /// `reset_to_fen`, so the move history is cleared. Unlike changing the tiles
+    /// through `board`, nothing derived from the position is left stale.
+    ///
+    /// # Errors
+    /// If the edited position is illegal, for example because a king is missing
+    /// or the team that is not to move is in check, the edit is rolled back and
+    /// the errors are returned.
+    pub fn edit<F: FnOnce(&mut BoardEditor)>(&mut self, f: F) -> Result<(), EditError> {
+        let mut edited = self.clone();
+        f(&mut BoardEditor { board: &mut edited.board });
+
+        edited.white_castling = possible_castling(&edited.board, &Color::White, edited.white_castling);
+        edited.black_castling = possible_castling(&edited.board, &Color::Black, edited.black_castling);
+        edited.en_passant_target = edited.en_passant_target
+            .filter(|target| is_possible_en_passant_target(&edited.board, &edited.current_turn, target));
+        edited.promotion_required = None;
+
+        let fen = edited.to_fen();
+        let issues: Vec<FenIssue> = validate_fen(&fen)
+            .into_iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .collect();
+        if !issues.is_empty() {
+            return Err(EditError { issues });
+        }
+
+        self.reset_to_fen(&fen).expect("A FEN without errors can be read.");
+        Ok(())
+    }
+}
+
+/// Keep the castling rights of the team whose king and rook are still on their
+/// starting squares.
+fn possible_castling(board: &Board, color: &Color, castling: CastlingAvailability) -> CastlingAvailability {
+    let rank = match color {
+        Color::White => 0,
+        Color::Black => 7,
+    };
+    let has = |file: u8, piece: PieceType| board.get_tile(&BoardPos::new(file, rank)) == Some(Tile::new(piece, *color));
+    let king = has(4, PieceType::King);
+    CastlingAvailability {
+        kingside: castling.kingside && king && has(7, PieceType::Rook),
+        queenside: castling.queenside && king && has(0, PieceType::Rook),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(text: &str) -> BoardPos {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn valid_edit() {
+        let mut game = Game::from_fen("rnbqkbnr/pppp1ppp/8/8/3pP3/8/PPP2PPP/RNBQKBNR b KQkq e3 0 3").unwrap();
+        let hash = game.zobrist_hash();
+        game.edit(|editor| {
+            editor.remove(&pos("h1"));
+            editor.move_piece_raw(&pos("e4"), &pos("e5"));
+            editor.set(&pos("h3"), Tile::new(PieceType::Queen, Color::White));
+        }).unwrap();
+
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppp1ppp/8/4P3/3p4/7Q/PPP2PPP/RNBQKBN1 b Qkq - 0 3");
+        assert_ne!(game.zobrist_hash(), hash);
+        assert_eq!(game.zobrist_hash(), Game::from_fen(&game.to_fen()).unwrap().zobrist_hash());
+        assert!(game.legal_moves().iter().all(|m| m.to_string() != "d4e3"));
+        assert!(game.history().is_empty());
+    }
+
+    #[test]
+    fn illegal_edit_rolls_back() {
+        let mut game = Game::new();
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+        let fen = game.to_fen();
+        let err = game.edit(|editor| {
+            editor.remove(&pos("e1"));
+        }).unwrap_err();
+        assert!(!err.issues.is_empty());
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.history().len(), 1);
+
+        // White is not to move, so it can not be left in check.
+        let err = game.edit(|editor| editor.set(&pos("e2"), Tile::new(PieceType::Queen, Color::Black)));
+        assert!(err.is_err());
+        assert_eq!(game.to_fen(), fen);
+    }
+}
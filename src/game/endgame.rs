@@ -0,0 +1,263 @@
+use crate::{board::Color, pos::BoardPos, piece::PieceType};
+
+use super::{material::is_light_square, Game};
+
+/// A textbook endgame, see `Game::endgame_class`.
+///
+/// `strong` is the team with the extra material.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EndgameClass {
+    /// King and pawn against king. `unstoppable` is whether the pawn promotes
+    /// before the defending king can catch it, by the rule of the square. The
+    /// own king standing in front of the pawn is not considered.
+    Kpk { strong: Color, unstoppable: bool },
+    /// King and rook against king, always a win.
+    Krk { strong: Color },
+    /// King and queen against king, always a win.
+    Kqk { strong: Color },
+    /// King, bishop and knight against king, a win with the right technique.
+    Kbnk { strong: Color },
+    /// King, bishop and pawns against king. `wrong_bishop` is whether all pawns are
+    /// rook pawns on the same file and the bishop can not reach the promotion
+    /// square, which is a draw if the defending king gets to the corner.
+    Kbpk { strong: Color, wrong_bishop: bool },
+    /// King, rook and pawn against king and rook.
+    Krpkr { strong: Color },
+    /// Each team has a king, one bishop and any number of pawns, and the bishops
+    /// move on squares of different colors. These endings are often drawn even
+    /// with one or two extra pawns.
+    OppositeBishops,
+}
+
+impl Game {
+
+    /// Recognize the textbook endgame that the position is, from the pieces of
+    /// each team and where they stand, for example to pick exercises for a trainer
+    /// or to switch to a special evaluation.
+    ///
+    /// `None` is returned for positions that are not one of the endgames in
+    /// `EndgameClass`.
+    pub fn endgame_class(&self) -> Option<EndgameClass> {
+        let white = self.team_signature(&Color::White);
+        let black = self.team_signature(&Color::Black);
+
+        for (strong, own, other) in [(Color::White, &white, &black), (Color::Black, &black, &white)] {
+            let class = match (own.as_str(), other.as_str()) {
+                ("KP", "K") => EndgameClass::Kpk { strong, unstoppable: self.pawn_is_unstoppable(&strong) },
+                ("KR", "K") => EndgameClass::Krk { strong },
+                ("KQ", "K") => EndgameClass::Kqk { strong },
+                ("KBN", "K") => EndgameClass::Kbnk { strong },
+                ("KRP", "KR") => EndgameClass::Krpkr { strong },
+                (own, "K") if own.len() > 2 && is_bishop_and_pawns(own) => EndgameClass::Kbpk { strong, wrong_bishop: self.has_wrong_bishop(&strong) },
+                _ => continue,
+            };
+            return Some(class);
+        }
+
+        if is_bishop_and_pawns(&white) && is_bishop_and_pawns(&black) {
+            let white_bishop = self.find_piece(&Color::White, PieceType::Bishop)?;
+            let black_bishop = self.find_piece(&Color::Black, PieceType::Bishop)?;
+            if is_light_square(&white_bishop) != is_light_square(&black_bishop) {
+                return Some(EndgameClass::OppositeBishops);
+            }
+        }
+        None
+    }
+
+    /// Check the rule of the square for the only pawn of the team, see
+    /// `pawn_square_rule`.
+    fn pawn_is_unstoppable(&self, color: &Color) -> bool {
+        match (self.find_piece(color, PieceType::Pawn), self.get_king_pos(&color.opposite())) {
+            (Some(pawn), Some(king)) => !self.pawn_square_rule(&pawn, &king),
+            _ => false,
+        }
+    }
+
+    /// Check the rule of the square: whether a king on `defender_king` catches the
+    /// passed pawn on `pawn` before it promotes, counting the tempo of the team to
+    /// move. The king catches the pawn if it is no further from the promotion
+    /// square than the pawn, after the defending team moves first if it is its
+    /// turn. A pawn on its starting rank counts its two-square move.
+    ///
+    /// Only the distances count, so pieces in the way of the king or the pawn and
+    /// the attacking king are not considered.
+    ///
+    /// ## Panics
+    /// This method will panic if there is no pawn on `pawn`.
+    pub fn pawn_square_rule(&self, pawn: &BoardPos, defender_king: &BoardPos) -> bool {
+        let tile = self.board.get_tile(pawn).filter(|tile| tile.piece() == PieceType::Pawn);
+        let color = tile.unwrap_or_else(|| panic!("There is no pawn on {pawn}.")).color();
+        let (start_rank, last_rank) = match color {
+            Color::White => (1, 7),
+            Color::Black => (6, 0),
+        };
+        let mut pawn_moves = pawn.rank().abs_diff(last_rank);
+        if pawn.rank() == start_rank {
+            pawn_moves -= 1;
+        }
+
+        let promotion = BoardPos::new(pawn.file(), last_rank);
+        let tempo = u8::from(self.current_turn != color);
+        defender_king.distance(&promotion).saturating_sub(tempo) <= pawn_moves
+    }
+
+    /// Get the key squares of the pawn on `pawn` in king and pawn against king:
+    /// the attacking king wins if it stands on one of them, whoever is to move.
+    ///
+    /// For a pawn that has not passed the middle of the board, the key squares are
+    /// the three squares two ranks in front of it. On the fifth and sixth rank of
+    /// its team, the three squares one rank in front of it are also key squares,
+    /// and on the seventh rank they are the squares beside the pawn and beside the
+    /// promotion square. A rook pawn only has the two squares in front of the
+    /// promotion square on the next file, since the defending king holds the corner
+    /// otherwise. The squares are ordered by rank and then by file.
+    ///
+    /// ## Panics
+    /// This method will panic if there is no pawn on `pawn`.
+    pub fn key_squares(&self, pawn: &BoardPos) -> Vec<BoardPos> {
+        let tile = self.board.get_tile(pawn).filter(|tile| tile.piece() == PieceType::Pawn);
+        let color = tile.unwrap_or_else(|| panic!("There is no pawn on {pawn}.")).color();
+        // Ranks are counted from the side of the team, starting at 0.
+        let (relative_rank, forward) = match color {
+            Color::White => (pawn.rank(), 1),
+            Color::Black => (7 - pawn.rank(), -1),
+        };
+
+        let offsets: Vec<(i8, i8)> = match (pawn.file(), relative_rank) {
+            (_, 0 | 7) => Vec::new(),
+            (0, _) => vec![(1, 7 - relative_rank as i8), (1, 6 - relative_rank as i8)],
+            (7, _) => vec![(-1, 7 - relative_rank as i8), (-1, 6 - relative_rank as i8)],
+            (_, 1..=3) => vec![(-1, 2), (0, 2), (1, 2)],
+            (_, 4 | 5) => vec![(-1, 1), (0, 1), (1, 1), (-1, 2), (0, 2), (1, 2)],
+            _ => vec![(-1, 0), (1, 0), (-1, 1), (1, 1)],
+        };
+        let mut squares: Vec<BoardPos> = offsets.into_iter()
+            .filter_map(|(file, rank)| pawn.offset(file, rank * forward))
+            .collect();
+        squares.sort_by_key(|square| (square.rank(), square.file()));
+        squares.dedup();
+        squares
+    }
+
+    /// Check whether the pawns of the team are rook pawns on one file whose
+    /// promotion square is not the color of its bishop.
+    fn has_wrong_bishop(&self, color: &Color) -> bool {
+        let Some(bishop) = self.find_piece(color, PieceType::Bishop) else { return false };
+        let files: Vec<u8> = self.board.pieces()
+            .filter(|(_, tile)| tile.color() == *color && tile.piece() == PieceType::Pawn)
+            .map(|(pos, _)| pos.file())
+            .collect();
+        let Some(&file) = files.first() else { return false };
+        if !matches!(file, 0 | 7) || files.iter().any(|other| *other != file) {
+            return false;
+        }
+
+        let last_rank = match color {
+            Color::White => 7,
+            Color::Black => 0,
+        };
+        is_light_square(&BoardPos::new(file, last_rank)) != is_light_square(&bishop)
+    }
+
+    /// Find a piece of the type and color, if there is one.
+    fn find_piece(&self, color: &Color, piece: PieceType) -> Option<BoardPos> {
+        self.board.pieces()
+            .find(|(_, tile)| tile.color() == *color && tile.piece() == piece)
+            .map(|(pos, _)| pos)
+    }
+}
+
+/// Check whether a team signature is a king, one bishop and at least zero pawns,
+/// like `KB` or `KBPP`.
+fn is_bishop_and_pawns(signature: &str) -> bool {
+    signature.strip_prefix("KB").is_some_and(|pawns| pawns.chars().all(|char| char == 'P'))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class(fen: &str) -> Option<EndgameClass> {
+        Game::from_fen(fen).unwrap().endgame_class()
+    }
+
+    #[test]
+    fn king_and_pawn() {
+        // The black king is outside the square of the pawn.
+        assert_eq!(class("8/8/8/4P3/8/8/8/k3K3 b - - 0 1"), Some(EndgameClass::Kpk { strong: Color::White, unstoppable: true }));
+        assert_eq!(class("8/8/3k4/4P3/8/8/8/4K3 b - - 0 1"), Some(EndgameClass::Kpk { strong: Color::White, unstoppable: false }));
+        // The king reaches the square only if it moves first.
+        assert_eq!(class("8/8/8/8/8/8/2P4k/4K3 b - - 0 1"), Some(EndgameClass::Kpk { strong: Color::White, unstoppable: false }));
+        assert_eq!(class("8/8/8/8/8/8/2P4k/4K3 w - - 0 1"), Some(EndgameClass::Kpk { strong: Color::White, unstoppable: true }));
+        assert_eq!(class("4k3/8/8/8/8/1pK5/8/8 w - - 0 1"), Some(EndgameClass::Kpk { strong: Color::Black, unstoppable: false }));
+    }
+
+    #[test]
+    fn square_rule() {
+        let pos = |square: &str| square.parse::<BoardPos>().unwrap();
+
+        // The king on e7 is one step outside the square of the a5 pawn.
+        let game = Game::from_fen("8/4k3/8/P7/8/8/8/4K3 b - - 0 1").unwrap();
+        assert!(game.pawn_square_rule(&pos("a5"), &pos("e7")));
+        let game = Game::from_fen("8/4k3/8/P7/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!game.pawn_square_rule(&pos("a5"), &pos("e7")));
+
+        // A pawn on its starting rank can move two squares.
+        let game = Game::from_fen("8/8/8/6k1/8/8/1P6/4K3 w - - 0 1").unwrap();
+        assert!(game.pawn_square_rule(&pos("b2"), &pos("g5")));
+        assert!(!game.pawn_square_rule(&pos("b2"), &pos("h5")));
+        let game = Game::from_fen("8/8/8/6k1/8/8/1P6/4K3 b - - 0 1").unwrap();
+        assert!(game.pawn_square_rule(&pos("b2"), &pos("h5")));
+
+        let game = Game::from_fen("4k3/8/8/8/7p/3K4/8/8 w - - 0 1").unwrap();
+        assert!(game.pawn_square_rule(&pos("h4"), &pos("d3")));
+        let game = Game::from_fen("4k3/8/8/8/7p/3K4/8/8 b - - 0 1").unwrap();
+        assert!(!game.pawn_square_rule(&pos("h4"), &pos("d3")));
+    }
+
+    #[test]
+    fn key_squares() {
+        let key_squares = |fen: &str, pawn: &str| -> Vec<String> {
+            let game = Game::from_fen(fen).unwrap();
+            game.key_squares(&pawn.parse().unwrap()).iter().map(ToString::to_string).collect()
+        };
+
+        assert_eq!(key_squares("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "e2"), ["d4", "e4", "f4"]);
+        assert_eq!(key_squares("4k3/8/8/4P3/8/8/8/4K3 w - - 0 1", "e5"), ["d6", "e6", "f6", "d7", "e7", "f7"]);
+        assert_eq!(key_squares("4k3/8/4P3/8/8/8/8/4K3 w - - 0 1", "e6"), ["d7", "e7", "f7", "d8", "e8", "f8"]);
+        assert_eq!(key_squares("k7/8/8/8/P7/8/8/4K3 w - - 0 1", "a4"), ["b7", "b8"]);
+        assert_eq!(key_squares("4k3/8/3p4/8/8/8/8/4K3 b - - 0 1", "d6"), ["c4", "d4", "e4"]);
+        assert_eq!(key_squares("4k3/8/8/8/3p4/8/8/4K3 b - - 0 1", "d4"), ["c2", "d2", "e2", "c3", "d3", "e3"]);
+        assert_eq!(key_squares("4k3/8/8/8/8/8/7p/K7 b - - 0 1", "h2"), ["g1", "g2"]);
+    }
+
+    #[test]
+    fn wrong_bishop() {
+        // The light-squared bishop can not cover h8, so black holds the corner.
+        assert_eq!(class("7k/8/8/6KP/8/8/4B3/8 w - - 0 1"), Some(EndgameClass::Kbpk { strong: Color::White, wrong_bishop: true }));
+        assert_eq!(class("7k/8/8/6KP/8/8/5B2/8 w - - 0 1"), Some(EndgameClass::Kbpk { strong: Color::White, wrong_bishop: false }));
+        assert_eq!(class("7k/8/8/6KP/7P/8/4B3/8 w - - 0 1"), Some(EndgameClass::Kbpk { strong: Color::White, wrong_bishop: true }));
+        assert_eq!(class("7k/8/8/6KP/6P1/8/4B3/8 w - - 0 1"), Some(EndgameClass::Kbpk { strong: Color::White, wrong_bishop: false }));
+    }
+
+    #[test]
+    fn other_endgames() {
+        assert_eq!(class("8/8/4k3/8/8/8/8/R3K3 w - - 0 1"), Some(EndgameClass::Krk { strong: Color::White }));
+        assert_eq!(class("8/8/4k3/8/2q5/8/8/4K3 w - - 0 1"), Some(EndgameClass::Kqk { strong: Color::Black }));
+        assert_eq!(class("8/8/4k3/8/8/8/8/1N2KB2 w - - 0 1"), Some(EndgameClass::Kbnk { strong: Color::White }));
+        assert_eq!(class("8/r7/4k3/8/3P4/8/8/R3K3 w - - 0 1"), Some(EndgameClass::Krpkr { strong: Color::White }));
+        assert_eq!(class("8/5p2/4k3/1b6/3P4/8/5P2/2B1K3 w - - 0 1"), Some(EndgameClass::OppositeBishops));
+        // Bishops on the same color.
+        assert_eq!(class("8/5p2/4k3/2b5/3P4/8/5P2/2B1K3 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn unclassified() {
+        assert_eq!(Game::new().endgame_class(), None);
+        assert_eq!(class("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4"), None);
+        assert_eq!(class("8/8/4k3/8/8/8/8/1NN1K3 w - - 0 1"), None);
+        assert_eq!(class("8/8/4k3/8/8/8/8/4KB2 w - - 0 1"), None);
+    }
+}
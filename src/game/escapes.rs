@@ -0,0 +1,109 @@
+use crate::{piece::PieceType, pos::BoardPos};
+
+use super::{Game, Move};
+
+/// The legal replies to a check, grouped by how they meet the check.
+///
+/// Every legal move is in exactly one of the groups. A king capturing the
+/// checking piece is a king move. In double check only king moves are legal, so
+/// the other groups are empty.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CheckEscapes {
+    /// The positions of the pieces giving check.
+    pub checkers: Vec<BoardPos>,
+    /// Moves of the king out of check.
+    pub king_moves: Vec<Move>,
+    /// Captures of the checking piece by a piece other than the king, including
+    /// en passant captures.
+    pub captures: Vec<Move>,
+    /// Moves of a piece in between the checking piece and the king.
+    pub blocks: Vec<Move>,
+}
+
+impl Game {
+
+    /// Get the legal replies to the check against the team whose turn it is,
+    /// grouped by whether they move the king, capture the checking piece or block
+    /// the check.
+    ///
+    /// Returns `None` if the team whose turn it is is not in check.
+    pub fn check_escapes(&mut self) -> Option<CheckEscapes> {
+        let color = self.current_turn;
+        if !self.is_check(&color) {
+            return None;
+        }
+        let king_pos = self.get_king_pos(&color)?;
+        let checkers = self.attackers(&king_pos, &color.opposite());
+
+        let mut escapes = CheckEscapes {
+            checkers,
+            king_moves: Vec::new(),
+            captures: Vec::new(),
+            blocks: Vec::new(),
+        };
+
+        for m in self.legal_moves() {
+            if *m.from() == king_pos {
+                escapes.king_moves.push(m);
+                continue;
+            }
+            let is_en_passant = self.en_passant_target.as_ref() == Some(m.to())
+                && self.board.get_tile(m.from()).is_some_and(|tile| tile.piece() == PieceType::Pawn);
+            let captured = if is_en_passant {
+                BoardPos::new(m.to().file(), m.from().rank())
+            } else {
+                m.to().clone()
+            };
+            if escapes.checkers.contains(&captured) {
+                escapes.captures.push(m);
+            } else {
+                escapes.blocks.push(m);
+            }
+        }
+
+        Some(escapes)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_moves(moves: &[Move]) -> Vec<String> {
+        let mut moves: Vec<String> = moves.iter().map(|m| format!("{}{}", m.from(), m.to())).collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn back_rank_check() {
+        let mut game = Game::from_fen("6k1/R7/8/8/8/2B5/5PP1/r5K1 w - - 0 1").unwrap();
+        let escapes = game.check_escapes().unwrap();
+
+        assert_eq!(escapes.checkers, ["a1".parse().unwrap()]);
+        assert_eq!(format_moves(&escapes.king_moves), ["g1h2"]);
+        assert_eq!(format_moves(&escapes.captures), ["a7a1", "c3a1"]);
+        assert_eq!(format_moves(&escapes.blocks), ["c3e1"]);
+    }
+
+    #[test]
+    fn double_check() {
+        let mut game = Game::from_fen("3qk3/8/3N4/1B6/8/8/8/4K3 b - - 0 1").unwrap();
+        let escapes = game.check_escapes().unwrap();
+
+        assert_eq!(escapes.checkers.len(), 2);
+        assert_eq!(format_moves(&escapes.king_moves), ["e8e7", "e8f8"]);
+        assert!(escapes.captures.is_empty());
+        assert!(escapes.blocks.is_empty());
+    }
+
+    #[test]
+    fn en_passant_capture_of_checker() {
+        let mut game = Game::from_fen("8/8/8/3k4/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let escapes = game.check_escapes().unwrap();
+
+        assert_eq!(format_moves(&escapes.captures), ["d4e3"]);
+        assert!(Game::new().check_escapes().is_none());
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+
+use crate::{board::{Color, Tile}, piece::PieceType, pos::BoardPos};
+
+use super::Game;
+
+/// Everything about one square that matters for its safety, see
+/// `Game::explain_square`.
+///
+/// The report is made from the point of view of `color`, so attackers are enemy
+/// pieces and defenders are pieces of `color`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SquareReport {
+    pub pos: BoardPos,
+    /// The team whose square it is, which is the color of the piece on it, or
+    /// the team to move if the square is empty.
+    pub color: Color,
+    /// The piece on the square.
+    pub piece: Option<Tile>,
+    /// The enemy pieces that attack the square, see `Game::attackers`.
+    pub attackers: Vec<(BoardPos, PieceType)>,
+    /// The pieces of `color` that defend the square, see `Game::defenders`.
+    pub defenders: Vec<(BoardPos, PieceType)>,
+    /// The material that the opponent wins by capturing the piece on the square
+    /// and trading on it for as long as that gains material, in centipawns. This
+    /// is 0 for an empty square or a piece that can not be won.
+    pub exchange: i32,
+    /// The enemy sliding piece that would pin a piece of `color` standing on the
+    /// square to its king.
+    pub pinned_by: Option<(BoardPos, PieceType)>,
+    /// The enemy sliding piece that the square lies between and the king of
+    /// `color`, on a line that the piece moves along. Other pieces may stand
+    /// between them, unlike for `pinned_by`.
+    pub on_king_ray: Option<(BoardPos, PieceType)>,
+}
+
+impl Game {
+
+    /// Explain why a square is safe or weak, combining the attackers and
+    /// defenders of the square, the outcome of trading on it, and whether it lies
+    /// on a line between an enemy sliding piece and the king.
+    ///
+    /// The report is made for the team of the piece on the square, or for the
+    /// team to move if the square is empty. Its `Display` is a short text in
+    /// plain English, for example for a tooltip.
+    pub fn explain_square(&mut self, pos: &BoardPos) -> SquareReport {
+        let piece = self.board.get_tile(pos);
+        let color = piece.map_or(self.current_turn, |tile| tile.color());
+        let enemy = color.opposite();
+
+        let with_pieces = |game: &Game, positions: Vec<BoardPos>| -> Vec<(BoardPos, PieceType)> {
+            positions.into_iter()
+                .map(|pos| {
+                    let piece = game.board.get_tile(&pos).expect("Attackers are pieces.").piece();
+                    (pos, piece)
+                })
+                .collect()
+        };
+        let attackers = self.attackers(pos, &enemy);
+        let attackers = with_pieces(self, attackers);
+        let defenders = self.defenders(pos, &color, false);
+        let defenders = with_pieces(self, defenders);
+        let exchange = if piece.is_some() { self.exchange(pos, &enemy) } else { 0 };
+        let king_ray = self.king_ray(pos, &color);
+        let pinned_by = king_ray.clone().filter(|(_, pins)| *pins).map(|(slider, _)| slider);
+        let on_king_ray = king_ray.map(|(slider, _)| slider);
+
+        SquareReport { pos: pos.clone(), color, piece, attackers, defenders, exchange, pinned_by, on_king_ray }
+    }
+
+    /// Find the first enemy sliding piece beyond the position on the line from the
+    /// king of the color through the position, and whether it would pin a piece
+    /// standing on the position.
+    fn king_ray(&self, pos: &BoardPos, color: &Color) -> Option<((BoardPos, PieceType), bool)> {
+        let king = self.get_king_pos(color)?;
+        let direction = king.direction_to(pos)?;
+        let towards_king = (-direction.0, -direction.1);
+
+        let mut blocked = false;
+        let mut beyond = false;
+        let mut current = king.offset(direction.0, direction.1);
+        while let Some(square) = current {
+            if square == *pos {
+                beyond = true;
+            } else if let Some(tile) = self.board.get_tile(&square) {
+                if beyond && tile.color() != *color && tile.piece().movement().slides.contains(&towards_king) {
+                    return Some(((square, tile.piece()), !blocked));
+                }
+                blocked = true;
+            }
+            current = square.offset(direction.0, direction.1);
+        }
+        None
+    }
+}
+
+impl fmt::Display for SquareReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.piece {
+            Some(tile) => write!(f, "{}, {} {}.", self.pos, tile.color().to_string().to_lowercase(), tile.piece().name())?,
+            None => write!(f, "{}, empty.", self.pos)?,
+        }
+        match self.attackers.as_slice() {
+            [] => write!(f, " Not attacked.")?,
+            attackers => write!(f, " Attacked by {}.", list(attackers))?,
+        }
+        match self.defenders.as_slice() {
+            [] => write!(f, " Not defended.")?,
+            defenders => write!(f, " Defended by {}.", list(defenders))?,
+        }
+        if self.exchange > 0 {
+            write!(f, " {} wins {} centipawns by capturing.", self.color.opposite(), self.exchange)?;
+        }
+        let here = if self.piece.is_some() { "Pinned" } else { "A piece here would be pinned" };
+        match (&self.pinned_by, &self.on_king_ray) {
+            (Some((pos, piece)), _) => write!(f, " {here} to the king by the {} on {pos}.", piece.name())?,
+            (None, Some((pos, piece))) => write!(f, " On the line from the {} on {pos} to the king.", piece.name())?,
+            (None, None) => {},
+        }
+        Ok(())
+    }
+}
+
+/// List pieces like `the knight on f3 and the queen on d1`.
+fn list(pieces: &[(BoardPos, PieceType)]) -> String {
+    let names: Vec<String> = pieces.iter().map(|(pos, piece)| format!("the {} on {pos}", piece.name())).collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {last}", rest.join(", ")),
+        _ => names.join(""),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn explain(fen: &str, pos: &str) -> SquareReport {
+        Game::from_fen(fen).unwrap().explain_square(&pos.parse().unwrap())
+    }
+
+    #[test]
+    fn contested_center() {
+        let report = explain("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", "e5");
+        assert_eq!(report.to_string(), "e5, black pawn. Attacked by the knight on f3. Defended by the knight on c6.");
+
+        // Once the knight on c6 is gone, White wins the pawn.
+        let report = explain("r1bqkbnr/pppp1ppp/8/4p3/3PP3/5N2/PPP2PPP/RNBQKB1R b KQkq - 0 3", "e5");
+        assert_eq!(report.exchange, 100);
+        assert_eq!(report.to_string(),
+            "e5, black pawn. Attacked by the pawn on d4 and the knight on f3. Not defended. White wins 100 centipawns by capturing.");
+    }
+
+    #[test]
+    fn back_rank() {
+        let fen = "6k1/5ppp/8/8/8/8/5PPP/3r2K1 w - - 0 1";
+        let report = explain(fen, "e1");
+        assert_eq!(report.pinned_by, Some(("d1".parse().unwrap(), PieceType::Rook)));
+        assert_eq!(report.to_string(),
+            "e1, empty. Attacked by the rook on d1. Not defended. A piece here would be pinned to the king by the rook on d1.");
+
+        let fen = "6k1/5ppp/8/8/8/8/5PPP/3r1BK1 w - - 0 1";
+        assert_eq!(explain(fen, "f1").to_string(),
+            "f1, white bishop. Attacked by the rook on d1. Defended by the king on g1. Pinned to the king by the rook on d1.");
+        let report = explain(fen, "e1");
+        assert_eq!(report.pinned_by, None);
+        assert_eq!(report.on_king_ray, Some(("d1".parse().unwrap(), PieceType::Rook)));
+        assert_eq!(report.to_string(),
+            "e1, empty. Attacked by the rook on d1. Not defended. On the line from the rook on d1 to the king.");
+    }
+}
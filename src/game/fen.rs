@@ -1,22 +1,61 @@
-use crate::{board::{Board, Color}, pos::ParseBoardPosError};
+use std::{collections::BTreeMap, fmt, sync::Arc};
 
-use super::{Game, CastlingAvailability};
+use crate::{board::{Board, Color, Tile}, pos::{BoardPos, ParseBoardPosError}, piece::PieceType};
+
+use super::{Game, CastlingAvailability, MoveCache, StandardRules};
 
 #[derive(Debug)]
 pub enum FenParseError<'a> {
     LargeSkip,
+    TooManyRanks,
     OutsideBoard(u8, u8),
     InvalidPiece(char),
     TooShort,
     InvalidTurn(&'a str),
     InvalidEnPassantTarget(ParseBoardPosError),
+    ImpossibleEnPassantTarget(BoardPos),
     InvalidClockInteger,
+    /// The rules of the game rejected the fields after the standard fields, see
+    /// `Rules::read_fen_extension`.
+    InvalidExtension(String),
+    /// The team does not have exactly one king, see `Game::from_fen_validated`.
+    KingCount(Color, usize),
+    /// A pawn stands on the first or the last rank, see `Game::from_fen_validated`.
+    PawnOnBackRank(BoardPos),
+    /// The team has more than 16 pieces, see `Game::from_fen_validated`.
+    TooManyPieces(Color, usize),
+    /// The team that is not to move is in check, see `Game::from_fen_validated`.
+    OpponentInCheck(Color),
+}
+
+impl fmt::Display for FenParseError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::LargeSkip => write!(f, "The placement data skips past the end of a rank."),
+            Self::TooManyRanks => write!(f, "The placement data has more than 8 ranks."),
+            Self::OutsideBoard(file, rank) => write!(f, "The placement data places a piece outside of the board (file {file}, rank {rank})."),
+            Self::InvalidPiece(char) => write!(f, "'{char}' is not a valid piece."),
+            Self::TooShort => write!(f, "The FEN string is missing fields."),
+            Self::InvalidTurn(turn) => write!(f, "'{turn}' is not a valid turn, expected 'w' or 'b'."),
+            Self::InvalidEnPassantTarget(err) => write!(f, "Invalid en passant target: {err}"),
+            Self::ImpossibleEnPassantTarget(pos) => write!(f, "There is no pawn that could have just passed {pos}."),
+            Self::InvalidClockInteger => write!(f, "The halfmove clock and fullmove number must be integers."),
+            Self::InvalidExtension(reason) => write!(f, "Invalid extension: {reason}"),
+            Self::KingCount(color, count) => write!(f, "{color} has {count} kings, but must have exactly one."),
+            Self::PawnOnBackRank(pos) => write!(f, "There is a pawn on {pos}, but pawns can not stand on the first or last rank."),
+            Self::TooManyPieces(color, count) => write!(f, "{color} has {count} pieces, but can have at most 16."),
+            Self::OpponentInCheck(color) => write!(f, "{color} is in check, but it is not their turn."),
+        }
+    }
 }
 
+impl std::error::Error for FenParseError<'_> {}
+
 impl Game {
 
     /// Create a new `Game` instance from a FEN (Forsyth–Edwards Notation) string.
-    pub fn from_fen(fen: &str) -> Result<Self, FenParseError> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+    pub fn from_fen(fen: &str) -> Result<Self, FenParseError<'_>> {
         let mut iter = fen.split_whitespace();
         
         let placement_data = iter.next().ok_or(FenParseError::TooShort)?;
@@ -45,10 +84,14 @@ impl Game {
         let en_passant_target = if en_passant == "-" {
             None
         } else {
-            Some(
-                en_passant.parse()
-                    .map_err(|err| FenParseError::InvalidEnPassantTarget(err))?
-            )
+            let en_passant_target: BoardPos = en_passant.parse()
+                .map_err(FenParseError::InvalidEnPassantTarget)?;
+            // Making moves assumes that the pawn that passed the target is there, so
+            // an impossible target must be rejected here.
+            if !is_possible_en_passant_target(&board, &current_turn, &en_passant_target) {
+                return Err(FenParseError::ImpossibleEnPassantTarget(en_passant_target));
+            }
+            Some(en_passant_target)
         };
 
         let halfmove_clock = iter.next()
@@ -60,7 +103,7 @@ impl Game {
             .parse()
             .ok().ok_or(FenParseError::InvalidClockInteger)?;
 
-        Ok(Self {
+        let mut game = Self {
             board,
             current_turn,
             white_castling,
@@ -68,8 +111,60 @@ impl Game {
             en_passant_target,
             promotion_required: None,
             halfmove_clock,
-            fullmove_number
-        })
+            fullmove_number,
+            starting_fen: String::new(),
+            history: Vec::new(),
+            undo_stack: Vec::new(),
+            position_keys: Vec::new(),
+            last_irreversible_ply: 0,
+            rules: Arc::new(StandardRules),
+            outcome: None,
+            adjudication_note: None,
+            annotations: BTreeMap::new(),
+            generation: 0,
+            move_cache: MoveCache::default(),
+        };
+        game.starting_fen = game.to_fen();
+        game.record_position_key();
+        game.update_outcome();
+        Ok(game)
+    }
+
+    /// Create a new `Game` instance from a FEN string like `from_fen`, but reject
+    /// positions that can not occur in a game of chess.
+    ///
+    /// `from_fen` accepts positions without kings for analysis, see `is_kingless`,
+    /// and does not check whether the position makes sense. Use this method for
+    /// FEN strings from users, where such positions are mistakes.
+    ///
+    /// # Errors
+    /// In addition to the errors of `from_fen`, `KingCount` is returned if a team
+    /// does not have exactly one king, `PawnOnBackRank` for a pawn on the first or
+    /// last rank, `TooManyPieces` if a team has more than 16 pieces and
+    /// `OpponentInCheck` if the team that is not to move is in check.
+    pub fn from_fen_validated(fen: &str) -> Result<Self, FenParseError<'_>> {
+        let game = Self::from_fen(fen)?;
+
+        for color in [Color::White, Color::Black] {
+            let pieces: Vec<(BoardPos, Tile)> = game.board.pieces()
+                .filter(|(_, tile)| tile.color() == color)
+                .collect();
+            let kings = pieces.iter().filter(|(_, tile)| tile.piece() == PieceType::King).count();
+            if kings != 1 {
+                return Err(FenParseError::KingCount(color, kings));
+            }
+            if pieces.len() > 16 {
+                return Err(FenParseError::TooManyPieces(color, pieces.len()));
+            }
+        }
+        if let Some((pos, _)) = game.board.pieces().find(|(pos, tile)| tile.piece() == PieceType::Pawn && matches!(pos.rank(), 0 | 7)) {
+            return Err(FenParseError::PawnOnBackRank(pos));
+        }
+        let waiting = game.current_turn.opposite();
+        if game.is_check(&waiting) {
+            return Err(FenParseError::OpponentInCheck(waiting));
+        }
+        Ok(game)
     }
 
     /// Export the current state of the game to a FEN (Forsyth–Edwards Notation)
@@ -99,11 +194,28 @@ impl Game {
         str.push_str(&self.halfmove_clock.to_string());
         str.push(' ');
         str.push_str(&self.fullmove_number.to_string());
+        if let Some(extension) = self.rules.fen_extension(self) {
+            str.push(' ');
+            str.push_str(&extension);
+        }
         str
     }
 
 }
 
+/// Check that an enemy pawn could just have moved two squares past the en passant
+/// target, which must be on the third rank of the enemy team.
+pub(super) fn is_possible_en_passant_target(board: &Board, current_turn: &Color, target: &BoardPos) -> bool {
+    let enemy_color = current_turn.opposite();
+    let (target_rank, pawn_rank) = match enemy_color {
+        Color::White => (2, 3),
+        Color::Black => (5, 4),
+    };
+    target.rank() == target_rank
+        && board.get_tile(target).is_none()
+        && board.get_tile(&BoardPos::new(target.file(), pawn_rank)) == Some(Tile::new(PieceType::Pawn, enemy_color))
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -114,4 +226,95 @@ mod tests {
         // Ensure FEN parsing of starting position doesn't panic
         Game::new();
     }
+
+    #[test]
+    fn hostile_fen_strings() {
+        // Inputs that used to panic while parsing or when making moves afterwards.
+        let inputs = [
+            "8/8/8/8/8/8/8/8/8 w - - 0 1",
+            "////////// w - - 0 1",
+            "4k3/8/8/4P3/8/8/8/4K3 w - e6 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - e4 0 1",
+            "4k3/8/8/8/8/8/8/4K3 b - z9 0 1",
+        ];
+        for fen in inputs {
+            assert!(Game::from_fen(fen).is_err(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn clocks_do_not_overflow() {
+        let max = u32::MAX;
+        let mut game = Game::from_fen(&format!("4k3/8/8/8/8/8/8/4K3 b - - {max} {max}")).unwrap();
+        game.move_piece(&"e8".parse().unwrap(), &"d8".parse().unwrap()).unwrap();
+
+        assert_eq!(game.to_fen(), format!("3k4/8/8/8/8/8/8/4K3 w - - {max} {max}"));
+    }
+
+    #[test]
+    fn clocks_round_trip() {
+        let fens = [
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "8/5k2/8/8/8/8/2K5/8 b - - 37 61",
+            "4k3/8/8/8/8/8/8/R3K3 w Q - 99 150",
+        ];
+        for fen in fens {
+            assert_eq!(Game::from_fen(fen).unwrap().to_fen(), fen);
+        }
+        for fen in ["4k3/8/8/8/8/8/8/4K3 w - - x 1", "4k3/8/8/8/8/8/8/4K3 w - - 0 -1"] {
+            assert!(matches!(Game::from_fen(fen), Err(FenParseError::InvalidClockInteger)), "{fen}");
+        }
+    }
+
+    #[test]
+    fn halfmove_clock_resets() {
+        let mut game = Game::from_fen("4k3/4p3/8/8/8/8/8/R3K3 w Q - 12 40").unwrap();
+        game.make_move(&"a1a2".parse().unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "4k3/4p3/8/8/8/8/R7/4K3 b - - 13 40");
+        game.make_move(&"e7e5".parse().unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/4p3/8/8/R7/4K3 w - e6 0 41");
+        game.make_move(&"a2a7".parse().unwrap()).unwrap();
+        game.make_move(&"e8d8".parse().unwrap()).unwrap();
+        assert_eq!(game.halfmove_clock(), 2);
+    }
+
+    #[test]
+    fn validated_positions() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            // The team to move may be in check.
+            "4k3/8/8/8/8/8/8/4K2r w - - 0 1",
+        ];
+        for fen in fens {
+            assert_eq!(Game::from_fen_validated(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn validated_rejections() {
+        let err = Game::from_fen_validated("4k3/8/8/8/8/8/8/4R3 w - - 0 1").unwrap_err();
+        assert!(matches!(err, FenParseError::KingCount(Color::White, 0)));
+        assert_eq!(err.to_string(), "White has 0 kings, but must have exactly one.");
+        assert!(matches!(Game::from_fen_validated("4k3/8/8/8/8/8/8/K3K3 w - - 0 1"), Err(FenParseError::KingCount(Color::White, 2))));
+        assert!(matches!(Game::from_fen_validated("4kk2/8/8/8/8/8/8/4K3 w - - 0 1"), Err(FenParseError::KingCount(Color::Black, 2))));
+
+        let err = Game::from_fen_validated("4k3/8/8/8/8/8/8/P3K3 w - - 0 1").unwrap_err();
+        assert!(matches!(&err, FenParseError::PawnOnBackRank(pos) if pos.to_string() == "a1"));
+        assert!(matches!(Game::from_fen_validated("1p2k3/8/8/8/8/8/8/4K3 w - - 0 1"), Err(FenParseError::PawnOnBackRank(_))));
+
+        let crowded = "rnbqkbnr/pppppppp/8/8/8/N7/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert!(matches!(Game::from_fen_validated(crowded), Err(FenParseError::TooManyPieces(Color::White, 17))));
+
+        assert!(matches!(Game::from_fen_validated("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"), Err(FenParseError::OpponentInCheck(Color::Black))));
+
+        // The same positions are accepted without validation.
+        assert!(Game::from_fen("4k3/8/8/8/8/8/8/4R3 w - - 0 1").is_ok());
+    }
+
+    #[test]
+    fn en_passant_target_round_trip() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(Game::from_fen(fen).unwrap().to_fen(), fen);
+    }
 }
\ No newline at end of file
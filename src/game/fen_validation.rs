@@ -0,0 +1,352 @@
+use crate::{board::{Board, Color, Tile}, piece::PieceType, pos::BoardPos};
+
+use super::{fen::is_possible_en_passant_target, Game};
+
+/// A field of a FEN string.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum FenField {
+    Placement,
+    Turn,
+    Castling,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveNumber,
+}
+
+impl FenField {
+    const ALL: [FenField; 6] = [
+        FenField::Placement,
+        FenField::Turn,
+        FenField::Castling,
+        FenField::EnPassant,
+        FenField::HalfmoveClock,
+        FenField::FullmoveNumber,
+    ];
+
+    /// The value used when the field is missing, if the field can be left out.
+    fn default_value(&self) -> Option<&'static str> {
+        match self {
+            FenField::Placement | FenField::Turn => None,
+            FenField::Castling | FenField::EnPassant => Some("-"),
+            FenField::HalfmoveClock => Some("0"),
+            FenField::FullmoveNumber => Some("1"),
+        }
+    }
+}
+
+/// How serious a problem in a FEN string is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    /// The FEN string does not describe a legal position and can not be fixed
+    /// automatically.
+    Error,
+    /// The FEN string can be fixed by replacing the field with the suggested
+    /// value, see `normalize_fen`.
+    Warning,
+}
+
+/// A problem found by `validate_fen`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FenIssue {
+    pub field: FenField,
+    pub severity: Severity,
+    pub message: String,
+    /// The value that fixes the field, for warnings. If a field has several
+    /// warnings, they all suggest the same value.
+    pub suggestion: Option<String>,
+}
+
+impl FenIssue {
+    fn error(field: FenField, message: String) -> FenIssue {
+        FenIssue { field, severity: Severity::Error, message, suggestion: None }
+    }
+
+    fn warning(field: FenField, message: String, suggestion: &str) -> FenIssue {
+        FenIssue { field, severity: Severity::Warning, message, suggestion: Some(suggestion.to_string()) }
+    }
+}
+
+/// Find every problem in a FEN string, instead of stopping at the first one like
+/// `Game::from_fen`.
+///
+/// The placement data must have 8 ranks of 8 squares, one king of each color and
+/// no pawns on the first or last rank, and the team that is not to move must not
+/// be in check. These problems are errors. Missing fields after the turn, castling
+/// rights without the king and rook on their starting squares, impossible en
+/// passant targets and invalid or inconsistent clocks are warnings with a
+/// suggested fix.
+///
+/// An empty list is returned if there are no problems.
+pub fn validate_fen(fen: &str) -> Vec<FenIssue> {
+    validate(fen, false)
+}
+
+/// Find every problem in a FEN string like `validate_fen`, but report a team
+/// without a king as a warning instead of an error, for analysis positions. See
+/// `Game::is_kingless`.
+///
+/// The warning suggests keeping the placement data as it is.
+pub fn validate_fen_lenient(fen: &str) -> Vec<FenIssue> {
+    validate(fen, true)
+}
+
+fn validate(fen: &str, lenient: bool) -> Vec<FenIssue> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let mut issues = Vec::new();
+
+    let board = match fields.first() {
+        Some(placement) => Some(validate_placement(placement, lenient, &mut issues)),
+        None => {
+            issues.push(FenIssue::error(FenField::Placement, "The placement data is missing.".to_string()));
+            None
+        },
+    };
+
+    let turn = match fields.get(1) {
+        Some(&"w") => Some(Color::White),
+        Some(&"b") => Some(Color::Black),
+        Some(turn) => {
+            issues.push(FenIssue::error(FenField::Turn, format!("'{turn}' is not a valid turn, expected 'w' or 'b'.")));
+            None
+        },
+        None => {
+            issues.push(FenIssue::error(FenField::Turn, "The turn is missing.".to_string()));
+            None
+        },
+    };
+
+    for field in &FenField::ALL[2..] {
+        if fields.len() <= *field as usize {
+            let default = field.default_value().expect("Only the first two fields are required.");
+            issues.push(FenIssue::warning(*field, format!("The {field:?} field is missing."), default));
+        }
+    }
+
+    if let Some(castling) = fields.get(2) {
+        validate_castling(castling, board.as_ref(), &mut issues);
+    }
+
+    let mut has_en_passant_target = false;
+    if let Some(&en_passant) = fields.get(3) {
+        if en_passant != "-" {
+            match en_passant.parse::<BoardPos>() {
+                Err(err) => issues.push(FenIssue::warning(FenField::EnPassant, format!("Invalid en passant target: {err}"), "-")),
+                Ok(target) => match (&board, &turn) {
+                    (Some(board), Some(turn)) if !is_possible_en_passant_target(board, turn, &target) => {
+                        let message = format!("There is no pawn that could have just passed {target}.");
+                        issues.push(FenIssue::warning(FenField::EnPassant, message, "-"));
+                    },
+                    _ => has_en_passant_target = true,
+                },
+            }
+        }
+    }
+
+    if let Some(halfmove_clock) = fields.get(4) {
+        match halfmove_clock.parse::<u32>() {
+            Err(_) => issues.push(FenIssue::warning(FenField::HalfmoveClock, format!("'{halfmove_clock}' is not a valid halfmove clock."), "0")),
+            Ok(clock) if clock > 0 && has_en_passant_target => {
+                let message = "The halfmove clock must be 0 after a pawn has moved two squares.".to_string();
+                issues.push(FenIssue::warning(FenField::HalfmoveClock, message, "0"));
+            },
+            Ok(_) => {},
+        }
+    }
+
+    if let Some(fullmove_number) = fields.get(5) {
+        match fullmove_number.parse::<u32>() {
+            Ok(number) if number > 0 => {},
+            _ => issues.push(FenIssue::warning(FenField::FullmoveNumber, format!("'{fullmove_number}' is not a valid fullmove number."), "1")),
+        }
+    }
+    if fields.len() > FenField::ALL.len() {
+        let message = format!("There are {} fields, only the first {} are used.", fields.len(), FenField::ALL.len());
+        let fullmove_number = issues.iter()
+            .find(|issue| issue.field == FenField::FullmoveNumber)
+            .and_then(|issue| issue.suggestion.clone())
+            .unwrap_or_else(|| fields[5].to_string());
+        issues.push(FenIssue::warning(FenField::FullmoveNumber, message, &fullmove_number));
+    }
+
+    // Checks that need the whole position are only done once everything else is
+    // known to be usable.
+    if !issues.iter().any(|issue| issue.severity == Severity::Error) {
+        let game = Game::from_fen(&apply_fixes(&fields, &issues)).expect("All problems have been fixed.");
+        let waiting = game.current_turn.opposite();
+        if game.is_check(&waiting) {
+            issues.push(FenIssue::error(FenField::Placement, format!("{waiting} is in check, but it is not their turn.")));
+        }
+    }
+
+    issues.sort_by_key(|issue| issue.field);
+    issues
+}
+
+/// Fix the warnings found by `validate_fen` and return the FEN string in the
+/// standard form, with exactly six fields separated by single spaces.
+///
+/// # Errors
+/// If the FEN string has problems that can not be fixed, the errors are returned.
+pub fn normalize_fen(fen: &str) -> Result<String, Vec<FenIssue>> {
+    let issues = validate_fen(fen);
+    let errors: Vec<FenIssue> = issues.iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .cloned()
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    Ok(apply_fixes(&fields, &issues))
+}
+
+/// Replace the fields that have a suggested fix, including missing fields.
+fn apply_fixes(fields: &[&str], issues: &[FenIssue]) -> String {
+    let fixed: Vec<&str> = FenField::ALL.iter()
+        .map(|field| {
+            issues.iter()
+                .find(|issue| issue.field == *field && issue.suggestion.is_some())
+                .and_then(|issue| issue.suggestion.as_deref())
+                .or_else(|| fields.get(*field as usize).copied())
+                .unwrap_or_default()
+        })
+        .collect();
+    fixed.join(" ")
+}
+
+/// Check the placement data and return the pieces that could be placed, so that
+/// the other fields can still be checked.
+fn validate_placement(placement: &str, lenient: bool, issues: &mut Vec<FenIssue>) -> Board {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        issues.push(FenIssue::error(FenField::Placement, format!("The placement data has {} ranks instead of 8.", ranks.len())));
+    }
+
+    let mut board = Board::empty();
+    for (index, rank_data) in ranks.iter().enumerate().take(8) {
+        let rank = 7 - index as u8;
+        let mut file = 0u32;
+        for char in rank_data.chars() {
+            if let Some(skip) = char.to_digit(10).filter(|skip| (1..=8).contains(skip)) {
+                file += skip;
+                continue;
+            }
+            let piece = match PieceType::from_char(char.to_ascii_lowercase()) {
+                Ok(piece) => piece,
+                Err(_) => {
+                    issues.push(FenIssue::error(FenField::Placement, format!("'{char}' is not a valid piece.")));
+                    continue;
+                },
+            };
+            let color = if char.is_ascii_lowercase() { Color::Black } else { Color::White };
+            if file < 8 {
+                board.set_tile(&BoardPos::new(file as u8, rank), Tile::new(piece, color));
+            }
+            file += 1;
+        }
+        if file != 8 {
+            issues.push(FenIssue::error(FenField::Placement, format!("Rank {} has {file} squares instead of 8.", rank + 1)));
+        }
+    }
+
+    for color in [Color::White, Color::Black] {
+        let kings = board.pieces()
+            .filter(|(_, tile)| *tile == Tile::new(PieceType::King, color))
+            .count();
+        if kings == 0 && lenient {
+            issues.push(FenIssue::warning(FenField::Placement, format!("{color} has no king."), placement));
+        } else if kings != 1 {
+            issues.push(FenIssue::error(FenField::Placement, format!("{color} has {kings} kings instead of 1.")));
+        }
+    }
+    for (pos, tile) in board.pieces() {
+        if tile.piece() == PieceType::Pawn && (pos.rank() == 0 || pos.rank() == 7) {
+            issues.push(FenIssue::error(FenField::Placement, format!("There is a pawn on {pos}.")));
+        }
+    }
+
+    board
+}
+
+/// Check that the castling rights are written correctly and that the king and
+/// rook of each right are on their starting squares.
+fn validate_castling(castling: &str, board: Option<&Board>, issues: &mut Vec<FenIssue>) {
+    let rights = [('K', "e1", "h1"), ('Q', "e1", "a1"), ('k', "e8", "h8"), ('q', "e8", "a8")];
+    let is_possible = |right: char| {
+        let Some(board) = board else { return true };
+        let (_, king, rook) = rights.iter().find(|(char, _, _)| *char == right).expect("The right exists.");
+        let color = if right.is_ascii_uppercase() { Color::White } else { Color::Black };
+        board.get_tile(&king.parse().unwrap()) == Some(Tile::new(PieceType::King, color))
+            && board.get_tile(&rook.parse().unwrap()) == Some(Tile::new(PieceType::Rook, color))
+    };
+
+    let normalized: String = rights.iter()
+        .map(|(right, _, _)| *right)
+        .filter(|right| castling.contains(*right) && is_possible(*right))
+        .collect();
+    let normalized = if normalized.is_empty() { "-".to_string() } else { normalized };
+
+    let mut found = false;
+    for char in castling.chars() {
+        if char == '-' && castling.len() == 1 {
+            continue;
+        }
+        if !rights.iter().any(|(right, _, _)| *right == char) {
+            issues.push(FenIssue::warning(FenField::Castling, format!("'{char}' is not a valid castling right."), &normalized));
+            found = true;
+        } else if !is_possible(char) {
+            let message = format!("Castling right '{char}' needs the king and rook on their starting squares.");
+            issues.push(FenIssue::warning(FenField::Castling, message, &normalized));
+            found = true;
+        }
+    }
+    if !found && castling != normalized {
+        issues.push(FenIssue::warning(FenField::Castling, format!("The castling rights '{castling}' should be written as '{normalized}'."), &normalized));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_every_problem() {
+        let issues = validate_fen("rnbqkbnr/pppppppp/8/8/8/7/PPPPPPPP/RNBQKBNR w KQkq e3 0 0");
+        let found: Vec<(FenField, Severity)> = issues.iter().map(|issue| (issue.field, issue.severity)).collect();
+
+        assert_eq!(found, [
+            (FenField::Placement, Severity::Error),
+            (FenField::EnPassant, Severity::Warning),
+            (FenField::FullmoveNumber, Severity::Warning),
+        ], "{issues:?}");
+        assert_eq!(issues[0].message, "Rank 3 has 7 squares instead of 8.");
+    }
+
+    #[test]
+    fn normalize() {
+        assert_eq!(normalize_fen("4k3/8/8/8/8/8/8/4K3 w KQkq e6 5"), Ok("4k3/8/8/8/8/8/8/4K3 w - - 5 1".to_string()));
+        assert_eq!(normalize_fen("r3k3/8/8/8/8/8/8/4K2R   b qKx -  0 1"), Ok("r3k3/8/8/8/8/8/8/4K2R b Kq - 0 1".to_string()));
+        assert_eq!(normalize_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 3 9"), Ok("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 9".to_string()));
+
+        assert_eq!(normalize_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 x 7"), Ok("4k3/8/8/8/8/8/8/4K3 b - - 0 1".to_string()));
+
+        let fen = Game::new().to_fen();
+        assert_eq!(validate_fen(&fen), []);
+        assert_eq!(normalize_fen(&fen), Ok(fen));
+    }
+
+    #[test]
+    fn errors_can_not_be_normalized() {
+        let errors = normalize_fen("P3k3/8/8/8/8/8/8/8 x").unwrap_err();
+        let messages: Vec<&str> = errors.iter().map(|issue| issue.message.as_str()).collect();
+        assert_eq!(messages, [
+            "White has 0 kings instead of 1.",
+            "There is a pawn on a8.",
+            "'x' is not a valid turn, expected 'w' or 'b'.",
+        ]);
+
+        let errors = normalize_fen("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1").unwrap_err();
+        assert_eq!(errors[0].message, "Black is in check, but it is not their turn.");
+    }
+}
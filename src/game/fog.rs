@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+
+use crate::{board::{Board, Color}, pos::BoardPos};
+
+use super::Game;
+
+impl Game {
+
+    /// Get the squares that the specified team can see in fog-of-war chess, also
+    /// known as dark chess.
+    ///
+    /// A team sees the squares of its own pieces and every square that one of its
+    /// pieces can legally move to. Enemy pieces on those squares are visible,
+    /// every other square is hidden by the fog.
+    ///
+    /// If `color` is not the team whose turn it is, its moves are computed as if
+    /// it was its turn, ignoring the en passant target since it can only be used
+    /// by the team to move.
+    pub fn visible_squares(&mut self, color: &Color) -> HashSet<BoardPos> {
+        // En passant is only possible for the team whose turn it is.
+        let en_passant_target = self.en_passant_target.take();
+        if *color == self.current_turn {
+            self.en_passant_target = en_passant_target.clone();
+        }
+
+        let own: Vec<BoardPos> = self.board.pieces()
+            .filter(|(_, tile)| tile.color() == *color)
+            .map(|(pos, _)| pos)
+            .collect();
+        let mut visible = HashSet::new();
+        for pos in own {
+            visible.extend(self.get_legal_moves_unchecked(&pos));
+            visible.insert(pos);
+        }
+
+        self.en_passant_target = en_passant_target;
+
+        visible
+    }
+
+    /// Get the board as seen by the specified team in fog-of-war chess, with the
+    /// enemy pieces that it can not see removed. See `visible_squares`.
+    pub fn fogged_board(&mut self, color: &Color) -> Board {
+        let visible = self.visible_squares(color);
+        self.board.pieces()
+            .filter(|(pos, tile)| tile.color() == *color || visible.contains(pos))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pawn_hides_rook() {
+        let mut game = Game::from_fen("4k3/8/4r3/8/4P3/8/8/K7 w - - 0 1").unwrap();
+        let visible = game.visible_squares(&Color::White);
+        assert!(visible.contains(&"e4".parse().unwrap()));
+        assert!(visible.contains(&"e5".parse().unwrap()));
+        assert!(!visible.contains(&"e6".parse().unwrap()));
+        assert!(!visible.contains(&"d5".parse().unwrap()));
+
+        let fogged = game.fogged_board(&Color::White);
+        assert_eq!(fogged.to_fen_placement_data(), "8/8/8/8/4P3/8/8/K7");
+
+        // Black sees the pawn, since the rook can capture it.
+        let fogged = game.fogged_board(&Color::Black);
+        assert_eq!(fogged.to_fen_placement_data(), "4k3/8/4r3/8/4P3/8/8/8");
+    }
+
+    #[test]
+    fn captures_reveal_pieces() {
+        let mut game = Game::from_fen("4k3/8/4r3/3P4/8/8/8/K7 w - - 0 1").unwrap();
+        assert!(game.visible_squares(&Color::White).contains(&"e6".parse().unwrap()));
+        assert_eq!(game.fogged_board(&Color::White).to_fen_placement_data(), "8/8/4r3/3P4/8/8/8/K7");
+    }
+}
@@ -0,0 +1,212 @@
+use std::fmt;
+
+use crate::pos::BoardPos;
+
+use super::{Game, Move, SanError};
+
+/// Errors returned by `Game::move_human`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum HumanMoveError {
+    /// The text could not be understood as a move.
+    Unreadable,
+    /// The text is a move, but not a legal move in this position. If exactly one
+    /// legal move goes to the same square, it is suggested in Standard Algebraic
+    /// Notation.
+    Illegal { suggestion: Option<String> },
+    /// More than one legal move matches the text. The matching moves are included.
+    Ambiguous(Vec<Move>),
+}
+
+impl fmt::Display for HumanMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unreadable => write!(f, "That is not a move."),
+            Self::Illegal { suggestion: Some(suggestion) } => write!(f, "That move is not legal. Did you mean {suggestion}?"),
+            Self::Illegal { suggestion: None } => write!(f, "That move is not legal."),
+            Self::Ambiguous(moves) => {
+                let moves: Vec<String> = moves.iter().map(Move::to_string).collect();
+                write!(f, "The move is ambiguous, it could be any of {}.", moves.join(", "))
+            },
+        }
+    }
+}
+
+impl std::error::Error for HumanMoveError {}
+
+impl Game {
+
+    /// Make a move typed by a person, and return the move that was made.
+    ///
+    /// The text is read leniently. Coordinate notation is tried first, with or
+    /// without separators and capture markers and in any case, like `e2-e4`,
+    /// `E2E4` or `e2xe4`. Otherwise the text is read as Standard Algebraic
+    /// Notation, like `Nf3`, `Ng1-f3`, `O-O`, `0-0` or `e8Q`.
+    ///
+    /// # Errors
+    /// `Unreadable` is returned if the text is not a move at all, `Illegal` if
+    /// it is a move that can not be made in this position and `Ambiguous` if it
+    /// matches several legal moves. The game is not changed on errors.
+    pub fn move_human(&mut self, text: &str) -> Result<Move, HumanMoveError> {
+        let text = text.trim().trim_end_matches(['+', '#', '!', '?']);
+
+        let coordinates: String = text.chars()
+            .filter(|char| !matches!(char, '-' | 'x' | 'X' | ':' | '=' | ' '))
+            .flat_map(char::to_lowercase)
+            .collect();
+        if let Ok(m) = coordinates.parse::<Move>() {
+            return match self.make_move(&m) {
+                Ok(()) => Ok(self.history.last().cloned().unwrap_or(m)),
+                Err(_) => Err(self.illegal(m.to(), Some(m.from()))),
+            };
+        }
+
+        let san = normalize_san(text);
+        match self.parse_san(&san) {
+            Ok(m) => {
+                self.make_move(&m).expect("Parsed moves are legal.");
+                Ok(m)
+            },
+            Err(SanError::InvalidSyntax) => Err(HumanMoveError::Unreadable),
+            Err(SanError::IllegalMove) => {
+                let suggestion = match last_square(&san) {
+                    Some(to) => self.illegal(&to, None),
+                    None => HumanMoveError::Illegal { suggestion: None },
+                };
+                Err(suggestion)
+            },
+            Err(SanError::Ambiguous(moves)) => Err(HumanMoveError::Ambiguous(moves)),
+        }
+    }
+
+    /// Build the error for an illegal move, see `suggest_for_illegal`.
+    fn illegal(&mut self, to: &BoardPos, from: Option<&BoardPos>) -> HumanMoveError {
+        HumanMoveError::Illegal { suggestion: self.suggest_for_illegal(to, from) }
+    }
+
+    /// Suggest a legal move in Standard Algebraic Notation for an illegal move:
+    /// the only legal move to the same square, or else the only legal move from
+    /// the same square.
+    pub(super) fn suggest_for_illegal(&mut self, to: &BoardPos, from: Option<&BoardPos>) -> Option<String> {
+        let legal_moves = self.legal_moves();
+        let mut candidates: Vec<&Move> = legal_moves.iter().filter(|m| m.to() == to).collect();
+        if candidates.is_empty() {
+            if let Some(from) = from {
+                candidates = legal_moves.iter().filter(|m| m.from() == from).collect();
+            }
+        }
+
+        match candidates.as_slice() {
+            [m] => Some(self.move_to_san(m)),
+            _ => None,
+        }
+    }
+}
+
+/// Rewrite loosely written Standard Algebraic Notation so that `parse_san`
+/// accepts it: separators are removed, castling may use zeros and any case,
+/// files are lowercase and piece letters uppercase.
+fn normalize_san(text: &str) -> String {
+    let chars: Vec<char> = text.chars().filter(|char| !matches!(char, '-' | ':' | ' ')).collect();
+
+    let castling: String = chars.iter().map(|char| char.to_ascii_uppercase()).collect();
+    match castling.as_str() {
+        "OO" | "00" => return "O-O".to_string(),
+        "OOO" | "000" => return "O-O-O".to_string(),
+        _ => {},
+    }
+
+    let mut san = String::new();
+    for (i, &char) in chars.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1);
+        let char = if next.is_some_and(char::is_ascii_digit) && char.is_ascii_alphabetic() {
+            // A file, like the `E` in `E4`.
+            char.to_ascii_lowercase()
+        } else if i == 0 && matches!(char, 'k' | 'q' | 'r' | 'n') {
+            // A piece. A lowercase `b` is a file, not a bishop.
+            char.to_ascii_uppercase()
+        } else if next.is_none() && previous.is_some_and(|char| char.is_ascii_digit() || char == '=') {
+            // A promotion, like the `q` in `e8q`.
+            char.to_ascii_uppercase()
+        } else if char == 'X' {
+            'x'
+        } else {
+            char
+        };
+        san.push(char);
+    }
+    san
+}
+
+/// Find the last square in the text, which is the destination of a move in
+/// Standard Algebraic Notation.
+pub(super) fn last_square(san: &str) -> Option<BoardPos> {
+    (0..san.len().saturating_sub(1)).rev().find_map(|i| san.get(i..i + 2)?.parse().ok())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(game: &mut Game, moves: &[&str]) {
+        for text in moves {
+            game.move_human(text).unwrap();
+        }
+    }
+
+    #[test]
+    fn coordinate_shapes() {
+        let mut game = Game::new();
+        assert_eq!(game.move_human("e2-e4").unwrap().to_string(), "e2e4");
+        assert_eq!(game.move_human("E7E5").unwrap().to_string(), "e7e5");
+        assert_eq!(game.move_human("d2 d4").unwrap().to_string(), "d2d4");
+        assert_eq!(game.move_human("e5xd4").unwrap().to_string(), "e5d4");
+        assert_eq!(game.history().len(), 4);
+    }
+
+    #[test]
+    fn san_shapes() {
+        let mut game = Game::new();
+        assert_eq!(game.move_human("Ng1-f3").unwrap().to_string(), "g1f3");
+        assert_eq!(game.move_human("nf6").unwrap().to_string(), "g8f6");
+        assert_eq!(game.move_human("E4").unwrap().to_string(), "e2e4");
+        moves(&mut game, &["Nxe4", "Bc4", "Nc5"]);
+        assert_eq!(game.move_human("0-0").unwrap().to_string(), "e1g1");
+    }
+
+    #[test]
+    fn promotion() {
+        let mut game = Game::from_fen("8/P6k/8/8/8/8/1p6/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.move_human("a8Q").unwrap().to_string(), "a7a8q");
+        assert_eq!(game.move_human("b1=n").unwrap().to_string(), "b2b1n");
+        assert_eq!(game.move_human("a8-c7").unwrap_err(), HumanMoveError::Illegal { suggestion: None });
+    }
+
+    #[test]
+    fn castling_shapes() {
+        let mut game = Game::from_fen("r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1").unwrap();
+        game.move_human("o-o").unwrap();
+        game.move_human("O-O-O").unwrap();
+        assert_eq!(game.to_fen(), "2kr4/8/8/8/8/8/8/5RK1 w - - 2 2");
+    }
+
+    #[test]
+    fn errors() {
+        let mut game = Game::new();
+        moves(&mut game, &["e4", "e5"]);
+        assert_eq!(game.move_human("hello").unwrap_err(), HumanMoveError::Unreadable);
+        assert_eq!(game.move_human("").unwrap_err(), HumanMoveError::Unreadable);
+
+        let err = game.move_human("Bh5").unwrap_err();
+        assert_eq!(err, HumanMoveError::Illegal { suggestion: Some("Qh5".to_string()) });
+        assert_eq!(err.to_string(), "That move is not legal. Did you mean Qh5?");
+        assert_eq!(game.move_human("d1-h6").unwrap_err(), HumanMoveError::Illegal { suggestion: None });
+        assert_eq!(game.move_human("Nc3-d5").unwrap_err(), HumanMoveError::Illegal { suggestion: None });
+        assert_eq!(game.move_human("g1-g3").unwrap_err(), HumanMoveError::Illegal { suggestion: Some("g3".to_string()) });
+
+        moves(&mut game, &["Nf3", "Nc6", "d3", "d6"]);
+        assert!(matches!(game.move_human("Nd2"), Err(HumanMoveError::Ambiguous(moves)) if moves.len() == 2));
+        assert_eq!(game.history().len(), 6);
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+
+use crate::{board::Board, pos::BoardPos};
+
+use super::{Game, Move};
+
+/// Errors returned by `infer_move`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum InferError {
+    /// No legal move leads to the board.
+    NoLegalMove,
+    /// More than one legal move leads to the board. This can not happen in
+    /// standard chess. The matching moves are included.
+    Ambiguous(Vec<Move>),
+}
+
+impl fmt::Display for InferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoLegalMove => write!(f, "No legal move leads to the board."),
+            Self::Ambiguous(moves) => {
+                let moves: Vec<String> = moves.iter().map(Move::to_string).collect();
+                write!(f, "Several moves lead to the board: {}.", moves.join(", "))
+            },
+        }
+    }
+}
+
+impl std::error::Error for InferError {}
+
+/// Find the legal move that changes the board of the game into the board, for
+/// example to follow a game on an electronic board that only reports where the
+/// pieces are.
+///
+/// A normal move changes two squares, en passant three and castling four. A
+/// promotion is recognized by the new piece on the last rank, and the move
+/// includes the promotion piece.
+///
+/// # Errors
+/// `NoLegalMove` is returned if no single legal move leads to the board, for
+/// example if the board is corrupted or several moves were made, and
+/// `Ambiguous` if several moves do.
+pub fn infer_move(before: &Game, after: &Board) -> Result<Move, InferError> {
+    let changed: Vec<BoardPos> = squares()
+        .filter(|pos| before.board.get_tile(pos) != after.get_tile(pos))
+        .collect();
+    if changed.is_empty() || changed.len() > 4 {
+        return Err(InferError::NoLegalMove);
+    }
+
+    let mut game = before.clone();
+    let mut matches: Vec<Move> = game.legal_moves()
+        .into_iter()
+        .filter(|m| changed.contains(m.from()))
+        .filter(|m| {
+            let mut game = game.clone();
+            game.make_move(m).is_ok() && squares().all(|pos| game.board.get_tile(&pos) == after.get_tile(&pos))
+        })
+        .collect();
+
+    match matches.len() {
+        0 => Err(InferError::NoLegalMove),
+        1 => Ok(matches.remove(0)),
+        _ => Err(InferError::Ambiguous(matches)),
+    }
+}
+
+fn squares() -> impl Iterator<Item = BoardPos> {
+    (0..8).flat_map(|rank| (0..8).map(move |file| BoardPos::new(file, rank)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the board after making the move in the game.
+    fn board_after(game: &Game, m: &str) -> Board {
+        let mut game = game.clone();
+        game.make_move(&m.parse().unwrap()).unwrap();
+        game.board
+    }
+
+    fn assert_inferred(fen: &str, m: &str) {
+        let game = Game::from_fen(fen).unwrap();
+        let after = board_after(&game, m);
+        assert_eq!(infer_move(&game, &after).unwrap().to_string(), m, "{fen}");
+    }
+
+    #[test]
+    fn normal_moves() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_inferred(fen, "e2e4");
+        assert_inferred(fen, "g1f3");
+        assert_inferred("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", "e4d5");
+    }
+
+    #[test]
+    fn special_moves() {
+        assert_inferred("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1");
+        assert_inferred("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8");
+        assert_inferred("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6");
+        assert_inferred("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q");
+        assert_inferred("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8n");
+    }
+
+    #[test]
+    fn corrupted_boards() {
+        let game = Game::new();
+        assert_eq!(infer_move(&game, &game.board), Err(InferError::NoLegalMove));
+
+        // Two moves were made at once.
+        let mut after = board_after(&game, "e2e4");
+        after.set_tile(&"d4".parse().unwrap(), after.get_tile(&"d2".parse().unwrap()).unwrap());
+        after.remove_tile(&"d2".parse().unwrap());
+        assert_eq!(infer_move(&game, &after), Err(InferError::NoLegalMove));
+
+        // A piece disappeared.
+        let mut after = board_after(&game, "e2e4");
+        after.remove_tile(&"a8".parse().unwrap());
+        assert_eq!(infer_move(&game, &after), Err(InferError::NoLegalMove));
+
+        // An illegal move.
+        let mut after = Game::new().board;
+        after.set_tile(&"e5".parse().unwrap(), after.get_tile(&"e2".parse().unwrap()).unwrap());
+        after.remove_tile(&"e2".parse().unwrap());
+        assert_eq!(infer_move(&game, &after), Err(InferError::NoLegalMove));
+    }
+}
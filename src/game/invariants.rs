@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use super::{fen_validation::validate_fen, Game, GameState, Outcome, Severity};
+
+impl Game {
+
+    /// Check that the state of the game is consistent, see
+    /// `testing::play_random_game`. The problem found is returned as an error.
+    pub(crate) fn check_invariants(&mut self) -> Result<(), String> {
+        let fen = self.to_fen();
+        if let Some(issue) = validate_fen(&fen).into_iter().find(|issue| issue.severity == Severity::Error) {
+            return Err(format!("The FEN {fen} is invalid: {}", issue.message));
+        }
+
+        let mut fresh = Game::from_fen_with_rules(&fen, Arc::clone(&self.rules)).map_err(|err| format!("The FEN {fen} can not be read: {err}"))?;
+        if fresh.to_fen() != fen {
+            return Err(format!("The FEN {fen} was read back as {}", fresh.to_fen()));
+        }
+        if self.zobrist_hash() != fresh.zobrist_hash() {
+            return Err("The Zobrist hash differs from the hash of the FEN.".to_string());
+        }
+
+        // The cached moves and the recorded keys must match a fresh computation.
+        let mut moves = self.legal_moves();
+        let mut fresh_moves = fresh.legal_moves();
+        moves.sort_by_key(|m| m.to_string());
+        fresh_moves.sort_by_key(|m| m.to_string());
+        if moves != fresh_moves {
+            return Err("The legal moves differ from the legal moves of the FEN.".to_string());
+        }
+        if self.promotion_required.is_none() {
+            if self.position_keys.len() != self.history.len() + 1 {
+                return Err("A position key is missing for a move.".to_string());
+            }
+            if self.position_keys.last() != Some(&fresh.repetition_key()) {
+                return Err("The last position key is not the key of the position.".to_string());
+            }
+        }
+        if self.last_irreversible_ply > self.history.len() {
+            return Err("The last irreversible ply is after the last move.".to_string());
+        }
+
+        if self.is_check(&self.current_turn.opposite()) {
+            return Err("The team that is not to move is in check.".to_string());
+        }
+        let state = self.get_state();
+        let consistent = match self.outcome {
+            Some(Outcome::Checkmate { .. }) => matches!(state, GameState::Checkmate(_)),
+            Some(Outcome::Stalemate) => state == GameState::Stalemate,
+            Some(Outcome::Adjudicated { winner }) => state == GameState::Adjudicated(winner),
+            Some(Outcome::VariantEnd { .. }) => true,
+            None => self.promotion_required.is_some() || !moves.is_empty() || self.is_kingless(),
+        };
+        if !consistent {
+            return Err(format!("The outcome {:?} does not match the state {state:?}.", self.outcome));
+        }
+        Ok(())
+    }
+}
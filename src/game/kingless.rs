@@ -0,0 +1,93 @@
+use crate::board::Color;
+
+use super::Game;
+
+impl Game {
+
+    /// Whether a team has no king, which is allowed for analysis and for testing
+    /// how pieces move on an otherwise empty board.
+    ///
+    /// `from_fen` accepts positions without kings, and in such positions:
+    /// - a team without a king is never in check, so all moves that the pieces
+    ///   can make are legal for it, and it can never castle.
+    /// - `get_state` returns `Normal` instead of `Check`, `Checkmate` or
+    ///   `Stalemate`, and the game never ends by checkmate or stalemate, even if
+    ///   the team to move has no legal moves. Outcomes of the variant's rules
+    ///   and adjudications still apply.
+    /// - methods about a king, like `check_escapes`, `checking_moves` and
+    ///   `king_danger_squares`, return nothing for the missing king.
+    ///
+    /// `validate_fen` reports missing kings as errors, while
+    /// `validate_fen_lenient` reports them as warnings.
+    pub fn is_kingless(&self) -> bool {
+        self.get_king_pos(&Color::White).is_none() || self.get_king_pos(&Color::Black).is_none()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{validate_fen, validate_fen_lenient, GameState, Severity};
+
+    use super::*;
+
+    const ROOKS: &str = "r3k2r/8/8/8/8/8/8/R6R w KQkq - 0 1";
+
+    #[test]
+    fn kingless_state() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/4R3 w - - 0 1").unwrap();
+        assert!(game.is_kingless());
+        assert!(!Game::new().is_kingless());
+
+        // Capturing the black king is neither checkmate nor stalemate.
+        game.make_move(&"e1e8".parse().unwrap()).unwrap();
+        assert_eq!(game.get_state(), GameState::Normal);
+        assert_eq!(game.outcome(), None);
+
+        // Black has no legal moves, but it is not stalemate.
+        let mut game = Game::from_fen("8/8/8/8/8/8/p7/K7 b - - 0 1").unwrap();
+        assert!(game.legal_moves().is_empty());
+        assert_eq!(game.get_state(), GameState::Normal);
+        assert_eq!(game.outcome(), None);
+
+        // A team with a king can still be in check, but it is not reported.
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/4R3 b - - 0 1").unwrap();
+        assert_eq!(game.get_state(), GameState::Normal);
+        assert!(game.check_escapes().is_some());
+    }
+
+    #[test]
+    fn kingless_moves() {
+        // White has castling rights but no king to castle with.
+        let mut game = Game::from_fen(ROOKS).unwrap();
+        assert!(!game.legal_moves().iter().any(|m| m.from().to_string() == "e1"));
+        game.make_move(&"h1h2".parse().unwrap()).unwrap();
+
+        let moves: Vec<String> = game.legal_moves().iter().map(|m| m.to_string()).collect();
+        assert!(moves.contains(&"e8g8".to_string()));
+        assert!(moves.contains(&"e8c8".to_string()));
+        assert_eq!(game.check_escapes(), None);
+        assert!(game.king_danger_squares(&Color::White).is_empty());
+        assert!(game.checking_moves().is_empty());
+        assert_eq!(game.find_perpetual(4), None);
+
+        game.make_move(&"e8g8".parse().unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "r4rk1/8/8/8/8/8/7R/R7 w Q - 2 2");
+    }
+
+    #[test]
+    fn kingless_validation() {
+        let fen = "8/8/8/8/8/8/8/R6R w - - 0 1";
+        let strict = validate_fen(fen);
+        assert_eq!(strict.len(), 2);
+        assert!(strict.iter().all(|issue| issue.severity == Severity::Error));
+
+        let lenient = validate_fen_lenient(fen);
+        assert_eq!(lenient.len(), 2);
+        assert!(lenient.iter().all(|issue| issue.severity == Severity::Warning));
+
+        // Two kings are still an error.
+        let lenient = validate_fen_lenient("kk6/8/8/8/8/8/8/R6R w - - 0 1");
+        assert!(lenient.iter().any(|issue| issue.severity == Severity::Error));
+    }
+}
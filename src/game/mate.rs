@@ -0,0 +1,368 @@
+use std::sync::OnceLock;
+
+use crate::{attacks::KING_ATTACKS, piece::PieceType, pos::BoardPos};
+
+use super::{Game, Move};
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const QUEEN_DIRECTIONS: [(i8, i8); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
+/// Marks positions in a `MateTable` that are not lost for the lone king.
+const UNKNOWN: u8 = u8::MAX;
+
+/// The number of half-moves until checkmate for every position of an endgame with
+/// the lone king to move, indexed by `Endgame::index`.
+type MateTable = Vec<u8>;
+
+static ROOK_TABLE: OnceLock<MateTable> = OnceLock::new();
+static QUEEN_TABLE: OnceLock<MateTable> = OnceLock::new();
+
+/// A king and queen or king and rook against a lone king, with squares stored as
+/// `rank * 8 + file`.
+#[derive(Clone, Copy)]
+struct Endgame {
+    king: u8,
+    piece: u8,
+    queen: bool,
+    enemy_king: u8,
+}
+
+impl Game {
+
+    /// Get a move that makes progress towards checkmate in a king and queen or king
+    /// and rook against king endgame.
+    ///
+    /// Every move brings the checkmate closer no matter how the lone king replies,
+    /// so playing only these moves checkmates in at most 10 moves with a queen and
+    /// 16 moves with a rook. Stalemate and the capture of the queen or rook are
+    /// never allowed.
+    ///
+    /// Instead of the box-shrinking technique taught to players, the moves are
+    /// looked up in a distance-to-mate table of every position of the endgame,
+    /// since box-shrinking heuristics can cycle without careful tie-breaking. The
+    /// table is computed by working backwards from every checkmate the first time
+    /// an endgame is needed, which takes under a second in release builds, and the
+    /// table of 256 KiB is kept for later calls.
+    ///
+    /// Returns `None` unless the team whose turn it is has exactly a king and a
+    /// queen or a king and a rook and the opponent has only a king, or if the game
+    /// is over.
+    pub fn forced_mate_move(&self) -> Option<Move> {
+        if self.outcome.is_some() || self.promotion_required.is_some() {
+            return None;
+        }
+        let endgame = self.endgame()?;
+        let table = if endgame.queen {
+            QUEEN_TABLE.get_or_init(|| mate_table(true))
+        } else {
+            ROOK_TABLE.get_or_init(|| mate_table(false))
+        };
+
+        let (from, to) = endgame.moves()
+            .into_iter()
+            .filter(|&m| table[endgame.play(m).index()] != UNKNOWN)
+            .min_by_key(|&m| table[endgame.play(m).index()])?;
+        Some(Move::new(to_pos(from), to_pos(to), None))
+    }
+
+    /// Get the endgame if the position is a king and a queen or rook against a
+    /// lone king, with the stronger team to move.
+    fn endgame(&self) -> Option<Endgame> {
+        let color = self.current_turn;
+        let mut king = None;
+        let mut piece = None;
+        let mut enemy_king = None;
+        for (pos, tile) in self.board.pieces() {
+            let square = pos.rank() * 8 + pos.file();
+            match (tile.color() == color, tile.piece()) {
+                (true, PieceType::King) => king = Some(square),
+                (true, PieceType::Queen | PieceType::Rook) if piece.is_none() => {
+                    piece = Some((square, tile.piece() == PieceType::Queen));
+                },
+                (false, PieceType::King) => enemy_king = Some(square),
+                _ => return None,
+            }
+        }
+        let (piece, queen) = piece?;
+        Some(Endgame { king: king?, piece, queen, enemy_king: enemy_king? })
+    }
+}
+
+/// Compute the number of half-moves until checkmate for every position with the
+/// lone king to move, by working backwards from the checkmates.
+fn mate_table(queen: bool) -> MateTable {
+    let mut lone_king_to_move = vec![UNKNOWN; 64 * 64 * 64];
+    let mut stronger_to_move = vec![UNKNOWN; 64 * 64 * 64];
+    // The number of moves of the lone king that are not yet known to lose. Positions
+    // where the lone king can capture are never lost, so they start at `UNKNOWN`.
+    let mut remaining = vec![0u8; 64 * 64 * 64];
+
+    let mut frontier = Vec::new();
+    for index in 0..64 * 64 * 64 {
+        let endgame = Endgame::from_index(index, queen);
+        if !endgame.is_legal() {
+            continue;
+        }
+        let replies = endgame.defender_moves();
+        if replies == 0 {
+            if endgame.is_check() {
+                lone_king_to_move[index] = 0;
+                frontier.push(index);
+            }
+        } else if replies & (1 << endgame.piece) != 0 {
+            remaining[index] = UNKNOWN;
+        } else {
+            remaining[index] = replies.count_ones() as u8;
+        }
+    }
+
+    let mut plies = 0;
+    while !frontier.is_empty() {
+        let mut next = Vec::new();
+        for &index in &frontier {
+            let endgame = Endgame::from_index(index, queen);
+            if plies % 2 == 0 {
+                // The lone king is lost here, so every move leading here wins.
+                for previous in endgame.previous_moves() {
+                    let previous_index = previous.index();
+                    if stronger_to_move[previous_index] == UNKNOWN {
+                        stronger_to_move[previous_index] = plies + 1;
+                        next.push(previous_index);
+                    }
+                }
+            } else {
+                // The lone king only loses where all of its moves lead to a win.
+                for origin in squares(KING_ATTACKS[endgame.enemy_king as usize].bits()) {
+                    let previous = Endgame { enemy_king: origin, ..endgame };
+                    if !previous.is_legal() {
+                        continue;
+                    }
+                    let previous_index = previous.index();
+                    if lone_king_to_move[previous_index] != UNKNOWN || remaining[previous_index] == UNKNOWN {
+                        continue;
+                    }
+                    remaining[previous_index] -= 1;
+                    if remaining[previous_index] == 0 {
+                        lone_king_to_move[previous_index] = plies + 1;
+                        next.push(previous_index);
+                    }
+                }
+            }
+        }
+        frontier = next;
+        plies += 1;
+    }
+
+    lone_king_to_move
+}
+
+impl Endgame {
+    fn index(&self) -> usize {
+        (self.king as usize * 64 + self.piece as usize) * 64 + self.enemy_king as usize
+    }
+
+    fn from_index(index: usize, queen: bool) -> Endgame {
+        Endgame {
+            king: (index / 64 / 64) as u8,
+            piece: (index / 64 % 64) as u8,
+            queen,
+            enemy_king: (index % 64) as u8,
+        }
+    }
+
+    /// Whether the pieces are on different squares and the kings are not next to
+    /// each other.
+    fn is_legal(&self) -> bool {
+        self.king != self.piece
+            && self.king != self.enemy_king
+            && self.piece != self.enemy_king
+            && KING_ATTACKS[self.king as usize].bits() & (1 << self.enemy_king) == 0
+    }
+
+    /// The squares attacked by the queen or rook. The lone king does not block
+    /// the attacks since it can not hide behind itself.
+    fn piece_attacks(&self) -> u64 {
+        let mut attacks = 0;
+        for &direction in self.directions() {
+            let mut current = self.piece;
+            while let Some(next) = offset(current, direction) {
+                attacks |= 1 << next;
+                if next == self.king {
+                    break;
+                }
+                current = next;
+            }
+        }
+        attacks
+    }
+
+    fn directions(&self) -> &'static [(i8, i8)] {
+        if self.queen { &QUEEN_DIRECTIONS } else { &ROOK_DIRECTIONS }
+    }
+
+    fn is_check(&self) -> bool {
+        self.piece_attacks() & (1 << self.enemy_king) != 0
+    }
+
+    /// The squares the lone king can move to, including the square of the queen or
+    /// rook if it is not defended.
+    fn defender_moves(&self) -> u64 {
+        KING_ATTACKS[self.enemy_king as usize].bits()
+            & !KING_ATTACKS[self.king as usize].bits()
+            & !self.piece_attacks()
+    }
+
+    /// The legal moves of the stronger team as `(from, to)` squares.
+    fn moves(&self) -> Vec<(u8, u8)> {
+        let mut moves: Vec<(u8, u8)> = squares(KING_ATTACKS[self.king as usize].bits())
+            .filter(|&square| square != self.piece)
+            .filter(|&square| KING_ATTACKS[self.enemy_king as usize].bits() & (1 << square) == 0)
+            .map(|square| (self.king, square))
+            .collect();
+
+        // The queen or rook can not move through or onto either king.
+        for &direction in self.directions() {
+            let mut current = self.piece;
+            while let Some(next) = offset(current, direction) {
+                if next == self.king || next == self.enemy_king {
+                    break;
+                }
+                moves.push((self.piece, next));
+                current = next;
+            }
+        }
+        moves
+    }
+
+    fn play(&self, (from, to): (u8, u8)) -> Endgame {
+        let mut endgame = *self;
+        if from == self.king {
+            endgame.king = to;
+        } else {
+            endgame.piece = to;
+        }
+        endgame
+    }
+
+    /// The legal positions with the stronger team to move that lead to this
+    /// position with one move. The moves of the king, queen and rook are
+    /// reversible, so these are found by moving the pieces from here.
+    fn previous_moves(&self) -> Vec<Endgame> {
+        self.moves()
+            .into_iter()
+            .map(|m| self.play(m))
+            // The lone king can not be in check when it is not its turn.
+            .filter(|endgame| !endgame.is_check())
+            .collect()
+    }
+}
+
+fn offset(square: u8, (delta_file, delta_rank): (i8, i8)) -> Option<u8> {
+    let file = (square % 8) as i8 + delta_file;
+    let rank = (square / 8) as i8 + delta_rank;
+    if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+        return None;
+    }
+    Some((rank * 8 + file) as u8)
+}
+
+fn squares(bits: u64) -> impl Iterator<Item = u8> {
+    (0..64).filter(move |square| bits & (1 << square) != 0)
+}
+
+fn to_pos(square: u8) -> BoardPos {
+    BoardPos::new(square % 8, square / 8)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::{Board, Color, Tile}, game::Outcome};
+
+    /// Play the mating moves and the first legal reply of the lone king until the
+    /// game ends, returning the number of moves made by the stronger team.
+    fn play_to_mate(fen: &str) -> u32 {
+        let mut game = Game::from_fen(fen).unwrap();
+        let attacker = game.current_turn();
+
+        for moves in 1..=16 {
+            let m = game.forced_mate_move().unwrap_or_else(|| panic!("No move in {}", game.to_fen()));
+            game.make_move(&m).unwrap();
+            if let Some(outcome) = game.outcome() {
+                assert_eq!(outcome, Outcome::Checkmate { winner: attacker }, "{fen}");
+                return moves;
+            }
+            let reply = game.legal_moves().into_iter().next().unwrap();
+            game.make_move(&reply).unwrap();
+        }
+        panic!("No checkmate from {fen} within 16 moves, ended in {}", game.to_fen());
+    }
+
+    #[test]
+    fn mate_with_rook() {
+        for fen in [
+            "8/8/8/3k4/8/8/8/R3K3 w - - 0 1",
+            "7K/8/8/8/3k4/8/8/r7 b - - 0 1",
+            "k7/8/8/8/8/8/8/5RK1 w - - 0 1",
+        ] {
+            play_to_mate(fen);
+        }
+    }
+
+    #[test]
+    fn mate_with_queen() {
+        for fen in [
+            "8/8/8/3k4/8/8/8/Q3K3 w - - 0 1",
+            "8/8/3K4/8/8/4k3/8/7q b - - 0 1",
+        ] {
+            assert!(play_to_mate(fen) <= 10, "{fen}");
+        }
+    }
+
+    #[test]
+    fn mate_from_random_rook_positions() {
+        // A fixed sequence of pseudo-random squares so that the test is repeatable.
+        let mut seed: u64 = 1451;
+        let mut random_square = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            BoardPos::new((seed >> 33) as u8 % 8, (seed >> 45) as u8 % 8)
+        };
+
+        let mut tested = 0;
+        while tested < 10 {
+            let board: Board = [
+                (random_square(), Tile::new(PieceType::King, Color::White)),
+                (random_square(), Tile::new(PieceType::Rook, Color::White)),
+                (random_square(), Tile::new(PieceType::King, Color::Black)),
+            ].into_iter().collect();
+            let fen = format!("{} w - - 0 1", board.to_fen_placement_data());
+
+            // Skip positions where pieces share a square, the kings are next to each
+            // other or black is in check with white to move.
+            let game = Game::from_fen(&fen).unwrap();
+            if board.pieces().count() < 3 || game.is_check(&Color::Black) || game.forced_mate_move().is_none() {
+                continue;
+            }
+            play_to_mate(&fen);
+            tested += 1;
+        }
+    }
+
+    #[test]
+    fn never_stalemates() {
+        // Qb6 would be stalemate.
+        let mut game = Game::from_fen("k7/2K5/8/8/8/8/8/1Q6 w - - 0 1").unwrap();
+        let m = game.forced_mate_move().unwrap();
+        game.make_move(&m).unwrap();
+        assert_eq!(game.outcome(), Some(Outcome::Checkmate { winner: Color::White }));
+    }
+
+    #[test]
+    fn other_material() {
+        assert_eq!(Game::new().forced_mate_move(), None);
+        assert_eq!(Game::from_fen("8/8/8/3k4/8/8/8/B3K3 w - - 0 1").unwrap().forced_mate_move(), None);
+        assert_eq!(Game::from_fen("8/8/8/3k4/8/8/8/R3K3 b - - 0 1").unwrap().forced_mate_move(), None);
+        assert_eq!(Game::from_fen("8/8/8/3k4/8/8/8/RR2K3 w - - 0 1").unwrap().forced_mate_move(), None);
+    }
+}
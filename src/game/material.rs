@@ -0,0 +1,348 @@
+use std::fmt;
+
+use crate::{board::{Color, Tile}, pos::BoardPos, piece::PieceType};
+
+use super::Game;
+
+/// The pieces in the order they appear in a material signature, from the most
+/// valuable to the least valuable.
+const SIGNATURE_ORDER: [PieceType; 6] = [
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Pawn,
+];
+
+/// The material difference between the teams, see `Game::material_imbalance`.
+///
+/// Every difference is the white count minus the black count, so positive
+/// numbers favor white. The `Display` implementation writes a short badge like
+/// `+1 (B+P vs N)`, or `=` when the material is equal.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct ImbalanceReport {
+    /// The material balance in centipawns, the same as `Game::material_balance`.
+    pub balance: i32,
+    pub queens: i8,
+    pub rooks: i8,
+    pub bishops: i8,
+    pub knights: i8,
+    pub pawns: i8,
+    /// The number of times a team has won a rook for a knight or bishop, which is
+    /// called being up the exchange. Positive if white has the extra rooks and
+    /// black the extra minor pieces.
+    pub exchanges: i8,
+    /// Whether white has two bishops and black does not.
+    pub white_bishop_pair: bool,
+    /// Whether black has two bishops and white does not.
+    pub black_bishop_pair: bool,
+}
+
+impl ImbalanceReport {
+    /// Check whether the teams have the same pieces, in which case there is
+    /// nothing to report.
+    pub fn is_empty(&self) -> bool {
+        *self == ImbalanceReport::default()
+    }
+
+    /// Get the difference in the number of pieces of the type, white minus black.
+    /// Kings are never different.
+    pub fn difference(&self, piece: PieceType) -> i8 {
+        match piece {
+            PieceType::King => 0,
+            PieceType::Queen => self.queens,
+            PieceType::Rook => self.rooks,
+            PieceType::Bishop => self.bishops,
+            PieceType::Knight => self.knights,
+            PieceType::Pawn => self.pawns,
+        }
+    }
+}
+
+impl fmt::Display for ImbalanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "=");
+        }
+
+        // The values of the pieces are whole pawns.
+        let pawns = self.balance / PieceType::Pawn.value();
+        if pawns > 0 {
+            write!(f, "+{pawns}")?;
+        } else {
+            write!(f, "{pawns}")?;
+        }
+
+        let extra = |sign: i8| {
+            let letters: Vec<String> = SIGNATURE_ORDER.iter()
+                .flat_map(|piece| {
+                    let count = (self.difference(*piece) * sign).max(0) as usize;
+                    std::iter::repeat_n(piece.char().to_ascii_uppercase().to_string(), count)
+                })
+                .collect();
+            if letters.is_empty() { "-".to_string() } else { letters.join("+") }
+        };
+        write!(f, " ({} vs {})", extra(1), extra(-1))
+    }
+}
+
+impl Game {
+
+    /// Get the number of pieces of the specified type and color on the board.
+    pub fn piece_count(&self, color: &Color, piece: PieceType) -> u8 {
+        self.board.pieces()
+            .filter(|(_, tile)| tile.color() == *color && tile.piece() == piece)
+            .count() as u8
+    }
+
+    /// Check whether the team has enough material to ever checkmate the opponent
+    /// by any sequence of legal moves, even with the opponent's help.
+    ///
+    /// This is the check used when a player runs out of time: the game is drawn
+    /// instead of lost if the opponent can not possibly checkmate.
+    ///
+    /// A team with a pawn, rook or queen can always checkmate. A lone king can
+    /// never checkmate. For the minor pieces the following applies:
+    /// 1. two knights, or a knight together with a bishop, can checkmate. This
+    ///    includes king and two knights against a lone king, which can only be
+    ///    forced with the opponent's help but is still possible.
+    /// 2. bishops that all stand on squares of the same color can only checkmate
+    ///    if the opponent has a piece that could block its own king on the other
+    ///    color, meaning a pawn, knight, rook, queen or bishop on the other color.
+    ///    King and bishop against king and knight can therefore checkmate.
+    /// 3. a single knight can checkmate if the opponent has any piece apart from
+    ///    the king that could block its own king.
+    pub fn has_mating_material(&self, color: &Color) -> bool {
+        let own: Vec<(BoardPos, Tile)> = self.board.pieces()
+            .filter(|(_, tile)| tile.color() == *color && tile.piece() != PieceType::King)
+            .collect();
+        let opponent: Vec<(BoardPos, Tile)> = self.board.pieces()
+            .filter(|(_, tile)| tile.color() != *color && tile.piece() != PieceType::King)
+            .collect();
+
+        if own.iter().any(|(_, tile)| matches!(tile.piece(), PieceType::Pawn | PieceType::Rook | PieceType::Queen)) {
+            return true;
+        }
+
+        let knights = own.iter().filter(|(_, tile)| tile.piece() == PieceType::Knight).count();
+        let bishop_square_colors: Vec<bool> = own.iter()
+            .filter(|(_, tile)| tile.piece() == PieceType::Bishop)
+            .map(|(pos, _)| is_light_square(pos))
+            .collect();
+
+        match (knights, bishop_square_colors.first()) {
+            // A lone king.
+            (0, None) => false,
+            (1, None) => !opponent.is_empty(),
+            (0, Some(&light)) if bishop_square_colors.iter().all(|square| *square == light) => {
+                // The opponent must be able to block its king on the squares the
+                // bishops can not reach.
+                opponent.iter().any(|(pos, tile)| {
+                    tile.piece() != PieceType::Bishop || is_light_square(pos) != light
+                })
+            },
+            _ => true,
+        }
+    }
+
+    /// Whether neither team has enough material to checkmate, so the game can
+    /// only end in a draw. See `has_mating_material`.
+    ///
+    /// This is the case for king against king, king and bishop or king and knight
+    /// against king, and kings with bishops that all stand on squares of the same
+    /// color. Positions with a pawn, rook or queen are never insufficient.
+    pub fn is_insufficient_material(&self) -> bool {
+        !self.has_mating_material(&Color::White) && !self.has_mating_material(&Color::Black)
+    }
+
+    /// Get the material balance of the position in centipawns, counted with
+    /// `PieceType::value`. Positive numbers mean that white has more material.
+    pub fn material_balance(&self) -> i32 {
+        self.board.pieces()
+            .map(|(_, tile)| match tile.color() {
+                Color::White => tile.piece().value(),
+                Color::Black => -tile.piece().value(),
+            })
+            .sum()
+    }
+
+    /// Summarize how the material of the teams differs, for example to show a
+    /// badge next to the board. See `ImbalanceReport`.
+    pub fn material_imbalance(&self) -> ImbalanceReport {
+        let mut counts = [[0_i8; 6]; 2];
+        for (_, tile) in self.board.pieces() {
+            counts[tile.color().index()][tile.piece().index()] += 1;
+        }
+        let [white, black] = counts;
+        let difference = |piece: PieceType| white[piece.index()] - black[piece.index()];
+
+        let rooks = difference(PieceType::Rook);
+        let minors = difference(PieceType::Bishop) + difference(PieceType::Knight);
+        let exchanges = if rooks > 0 && minors < 0 {
+            rooks.min(-minors)
+        } else if rooks < 0 && minors > 0 {
+            -(-rooks).min(minors)
+        } else {
+            0
+        };
+
+        let bishops = PieceType::Bishop.index();
+        let balance = SIGNATURE_ORDER.iter()
+            .map(|piece| i32::from(difference(*piece)) * piece.value())
+            .sum();
+
+        ImbalanceReport {
+            balance,
+            queens: difference(PieceType::Queen),
+            rooks,
+            bishops: difference(PieceType::Bishop),
+            knights: difference(PieceType::Knight),
+            pawns: difference(PieceType::Pawn),
+            exchanges,
+            white_bishop_pair: white[bishops] >= 2 && black[bishops] < 2,
+            black_bishop_pair: black[bishops] >= 2 && white[bishops] < 2,
+        }
+    }
+
+    /// Get the material signature of the position, for example `KRPKR`.
+    ///
+    /// The signature lists the white pieces followed by the black pieces, each
+    /// starting with the king and sorted by value, using uppercase piece letters
+    /// for both teams. This is the conventional way of naming endgames and
+    /// endgame tablebase files.
+    pub fn material_signature(&self) -> String {
+        self.team_signature(&Color::White) + &self.team_signature(&Color::Black)
+    }
+
+    /// Get the part of the material signature for the pieces of one team, for
+    /// example `KRP`.
+    pub(super) fn team_signature(&self, color: &Color) -> String {
+        let mut signature = String::new();
+        for piece in SIGNATURE_ORDER {
+            let char = piece.char().to_ascii_uppercase();
+            for _ in 0..self.piece_count(color, piece) {
+                signature.push(char);
+            }
+        }
+        signature
+    }
+}
+
+/// Check whether the position is a light square, like `h1`.
+pub(super) fn is_light_square(pos: &BoardPos) -> bool {
+    (pos.file() + pos.rank()) % 2 == 1
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::game::GameState;
+
+    use super::*;
+
+    #[test]
+    fn starting_position_signature() {
+        let game = Game::new();
+
+        assert_eq!(game.piece_count(&Color::White, PieceType::Pawn), 8);
+        assert_eq!(game.piece_count(&Color::Black, PieceType::Queen), 1);
+        assert_eq!(game.material_signature(), "KQRRBBNNPPPPPPPPKQRRBBNNPPPPPPPP");
+    }
+
+    #[test]
+    fn endgame_signatures() {
+        assert_eq!(Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap().material_signature(), "KRK");
+        assert_eq!(Game::from_fen("4k3/3r4/8/2p5/8/1P6/P7/R3K3 w - - 0 1").unwrap().material_signature(), "KRPPKRP");
+    }
+
+    #[test]
+    fn mating_material() {
+        let white_can_mate = |fen| Game::from_fen(fen).unwrap().has_mating_material(&Color::White);
+
+        // Lone king.
+        assert!(!white_can_mate("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        // Pawn, rook and queen.
+        assert!(white_can_mate("4k3/8/8/8/8/8/P7/4K3 w - - 0 1"));
+        assert!(white_can_mate("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"));
+        assert!(white_can_mate("4k3/8/8/8/8/8/8/3QK3 w - - 0 1"));
+        // Single minor pieces against a lone king.
+        assert!(!white_can_mate("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!white_can_mate("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1"));
+        // Two knights and bishop with knight.
+        assert!(white_can_mate("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1"));
+        assert!(white_can_mate("4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1"));
+        // Bishops on the same color against a lone king, and on both colors.
+        assert!(!white_can_mate("4k3/8/8/8/8/B7/8/2B1K3 w - - 0 1"));
+        assert!(white_can_mate("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"));
+        // Helpmates with the opponent's pieces.
+        assert!(white_can_mate("4k1n1/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(white_can_mate("4k3/7p/8/8/8/8/8/1N2K3 w - - 0 1"));
+        // An opponent bishop can only block on its own square color.
+        assert!(!white_can_mate("4k3/8/8/8/8/8/8/b1B1K3 w - - 0 1"));
+        assert!(white_can_mate("4k3/8/8/8/8/8/8/1bB1K3 w - - 0 1"));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let insufficient = |fen| Game::from_fen(fen).unwrap().is_insufficient_material();
+
+        assert!(insufficient("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/1N2K3 b - - 0 1"));
+        // Both bishops are on dark squares.
+        assert!(insufficient("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!insufficient("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        // Bishops on both colors.
+        assert!(!insufficient("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/P7/4K3 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"));
+
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/2B1K3 b - - 0 1").unwrap();
+        assert_eq!(game.get_state(), GameState::InsufficientMaterial);
+    }
+
+    #[test]
+    fn equal_material() {
+        let game = Game::new();
+        assert_eq!(game.material_balance(), 0);
+        assert!(game.material_imbalance().is_empty());
+        assert_eq!(game.material_imbalance().to_string(), "=");
+    }
+
+    #[test]
+    fn exchange_up() {
+        let game = Game::from_fen("4k3/pp3n2/8/8/8/8/PP6/R3K3 w - - 0 1").unwrap();
+        let report = game.material_imbalance();
+        assert_eq!(report.exchanges, 1);
+        assert_eq!(report.rooks, 1);
+        assert_eq!(report.knights, -1);
+        assert_eq!(report.balance, 200);
+        assert_eq!(report.balance, game.material_balance());
+        assert_eq!(report.to_string(), "+2 (R vs N)");
+
+        let game = Game::from_fen("4k3/1p6/8/8/8/8/8/r2BK3 b - - 0 1").unwrap();
+        let report = game.material_imbalance();
+        assert_eq!(report.exchanges, -1);
+        assert_eq!(report.balance, -300);
+        assert_eq!(report.to_string(), "-3 (B vs R+P)");
+    }
+
+    #[test]
+    fn bishop_pair() {
+        let report = Game::from_fen("4k3/8/2n5/8/8/8/8/2B1KB2 w - - 0 1").unwrap().material_imbalance();
+        assert!(report.white_bishop_pair);
+        assert!(!report.black_bishop_pair);
+        assert_eq!(report.to_string(), "+3 (B+B vs N)");
+
+        // Both teams have the bishop pair.
+        let report = Game::from_fen("2b1kb2/8/8/8/8/8/8/2B1KB2 w - - 0 1").unwrap().material_imbalance();
+        assert!(!report.white_bishop_pair);
+        assert!(!report.black_bishop_pair);
+        assert!(report.is_empty());
+
+        // A bishop and a knight is not a pair.
+        let report = Game::from_fen("4k3/8/8/8/8/8/8/2B1KN2 w - - 0 1").unwrap().material_imbalance();
+        assert!(!report.white_bishop_pair);
+    }
+}
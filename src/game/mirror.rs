@@ -0,0 +1,125 @@
+use crate::{board::{Board, Tile}, pos::BoardPos};
+
+use super::{Game, Outcome};
+
+impl Game {
+
+    /// Get the same position with the colors of the pieces swapped and the board
+    /// mirrored vertically, so that a white pawn on `e2` becomes a black pawn on
+    /// `e7`.
+    ///
+    /// The turn, castling availability, en passant target, pending promotion and
+    /// outcome are swapped and mirrored as well, and the move counters are kept.
+    /// The game starts from the flipped position, without any history.
+    pub fn color_flipped(&self) -> Game {
+        let board: Board = self.board.pieces()
+            .map(|(pos, tile)| (mirror(&pos), Tile::new(tile.piece(), tile.color().opposite())))
+            .collect();
+
+        let mut game = self.clone();
+        game.board = board;
+        game.invalidate_move_cache();
+        game.current_turn = self.current_turn.opposite();
+        game.white_castling = self.black_castling;
+        game.black_castling = self.white_castling;
+        game.en_passant_target = self.en_passant_target.as_ref().map(mirror);
+        game.promotion_required = self.promotion_required.as_ref().map(mirror);
+        game.outcome = self.outcome.map(|outcome| match outcome {
+            Outcome::Checkmate { winner } => Outcome::Checkmate { winner: winner.opposite() },
+            Outcome::Stalemate => Outcome::Stalemate,
+            Outcome::Adjudicated { winner } => Outcome::Adjudicated { winner: winner.map(|winner| winner.opposite()) },
+            Outcome::VariantEnd { winner } => Outcome::VariantEnd { winner: winner.map(|winner| winner.opposite()) },
+        });
+        game.history.clear();
+        game.undo_stack.clear();
+        game.annotations.clear();
+        game.starting_fen = game.to_fen();
+        game.position_keys.clear();
+        game.last_irreversible_ply = 0;
+        game.record_position_key();
+        game
+    }
+}
+
+fn mirror(pos: &BoardPos) -> BoardPos {
+    BoardPos::new(pos.file(), 7 - pos.rank())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::game::Move;
+
+    use super::*;
+
+    #[test]
+    fn flip_position() {
+        let game = Game::from_fen("r3k3/8/8/3pP3/8/8/8/4K2R w Kq d6 3 20").unwrap();
+        let flipped = game.color_flipped();
+
+        assert_eq!(flipped.to_fen(), "4k2r/8/8/8/3Pp3/8/8/R3K3 b Qk d3 3 20");
+        assert_eq!(flipped.starting_fen(), flipped.to_fen());
+        assert_eq!(flipped.color_flipped().to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn flipped_game_has_nothing_to_undo() {
+        let mut game = Game::new();
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+        let mut flipped = game.color_flipped();
+
+        assert_eq!(flipped.undo(), None);
+        assert_eq!(flipped.to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1");
+        assert!(game.undo().is_some());
+    }
+
+    /// Get the legal moves of the game mirrored vertically, as strings in a sorted
+    /// order.
+    fn mirrored_moves(game: &mut Game) -> Vec<String> {
+        let mut moves: Vec<String> = game.legal_moves()
+            .iter()
+            .map(|m| Move::new(mirror(m.from()), mirror(m.to()), m.promotion()).to_string())
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    fn sorted_moves(game: &mut Game) -> Vec<String> {
+        let mut moves: Vec<String> = game.legal_moves().iter().map(Move::to_string).collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn flip_castling_availability() {
+        let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1").unwrap();
+        let mut flipped = game.color_flipped();
+
+        assert_eq!(flipped.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R b Qk - 0 1");
+        assert_eq!(sorted_moves(&mut flipped), mirrored_moves(&mut game));
+        assert!(sorted_moves(&mut flipped).contains(&"e8g8".to_string()));
+        assert!(!sorted_moves(&mut flipped).contains(&"e8c8".to_string()));
+    }
+
+    #[test]
+    fn flip_en_passant() {
+        let mut game = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let mut flipped = game.color_flipped();
+
+        assert_eq!(flipped.en_passant_target(), Some("d3".parse().unwrap()));
+        assert_eq!(sorted_moves(&mut flipped), mirrored_moves(&mut game));
+        flipped.make_move(&"e4d3".parse().unwrap()).unwrap();
+        assert_eq!(flipped.to_fen(), "4k3/8/8/8/8/3p4/8/4K3 w - - 0 2");
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn flipped_moves_are_mirrored(game: Game) {
+            let mut game = game;
+            let mut flipped = game.color_flipped();
+            proptest::prop_assert_eq!(sorted_moves(&mut flipped), mirrored_moves(&mut game));
+            proptest::prop_assert_eq!(flipped.color_flipped().to_fen(), game.to_fen());
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{per_square::PerSquare, pos::BoardPos, square_set::SquareSet};
+
+use super::Game;
+
+/// Legal moves that have been computed for a position, so that asking again in
+/// the same position is free.
+///
+/// The cache belongs to the position with the generation it was filled in. Every
+/// change to the position bumps the generation of the game, which makes the
+/// cached moves outdated.
+///
+/// The moves of each piece are stored by its square, so hovering over several
+/// pieces in a GUI only computes the moves of each piece once.
+#[derive(Clone, Default, Debug)]
+pub(super) struct MoveCache {
+    generation: u64,
+    movesets: PerSquare<Option<SquareSet>>,
+    has_legal_moves: Option<bool>,
+}
+
+impl Game {
+
+    /// Mark the cached legal moves as outdated. This must be called whenever the
+    /// position changes.
+    pub(super) fn invalidate_move_cache(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Get the cache for the current position, emptying it if it is outdated.
+    fn move_cache(&mut self) -> &mut MoveCache {
+        if self.move_cache.generation != self.generation {
+            self.move_cache = MoveCache { generation: self.generation, ..MoveCache::default() };
+        }
+        &mut self.move_cache
+    }
+
+    /// Compute the legal moves of every piece of the team to move, so that later
+    /// calls to `get_legal_moves` in the same position return right away.
+    ///
+    /// This is meant to be called right after a move, for example while waiting
+    /// for the player, so that a GUI never has to wait when the player hovers over
+    /// or clicks on a piece. Nothing is computed if the game is over.
+    pub fn warm_moves(&mut self) {
+        if self.outcome.is_some() {
+            return;
+        }
+        let color = self.current_turn;
+        let pieces: Vec<BoardPos> = self.board.pieces()
+            .filter(|(_, tile)| tile.color() == color)
+            .map(|(pos, _)| pos)
+            .collect();
+        let mut has_legal_moves = false;
+        for pos in pieces {
+            has_legal_moves |= !self.get_legal_moves_unchecked(&pos).is_empty();
+        }
+        self.cache_has_legal_moves(has_legal_moves);
+    }
+
+    /// Get the legal moves of the piece on the position from the cache, computing
+    /// them if they are not cached. See `get_legal_moves_unchecked`.
+    pub(super) fn legal_move_set(&mut self, pos: &BoardPos) -> SquareSet {
+        if let Some(moveset) = self.move_cache().movesets[pos] {
+            return moveset;
+        }
+        let moveset: SquareSet = self.compute_legal_moves(pos).into_iter().collect();
+        self.move_cache().movesets[pos] = Some(moveset);
+        moveset
+    }
+
+    /// Whether the legal moves of the piece on the position are cached.
+    #[cfg(test)]
+    fn is_cached(&mut self, pos: &BoardPos) -> bool {
+        self.move_cache().movesets[pos].is_some()
+    }
+
+    pub(super) fn cached_has_legal_moves(&mut self) -> Option<bool> {
+        self.move_cache().has_legal_moves
+    }
+
+    pub(super) fn cache_has_legal_moves(&mut self, has_legal_moves: bool) {
+        self.move_cache().has_legal_moves = Some(has_legal_moves);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::{Color, Tile}, piece::PieceType};
+
+    use super::*;
+
+    /// Check that the legal moves of every piece of the team to move are the same
+    /// as in a game that was just created from the position.
+    fn assert_same_as_fresh(game: &mut Game) {
+        let mut fresh = Game::from_fen(&game.to_fen()).unwrap();
+        let color = game.current_turn();
+        for (pos, tile) in game.board.pieces().collect::<Vec<_>>() {
+            if tile.color() == color {
+                let expected = fresh.get_legal_moves(&pos).unwrap();
+                assert_eq!(game.get_legal_moves(&pos).unwrap(), expected, "{pos} in {}", game.to_fen());
+                assert_eq!(game.get_legal_moves(&pos).unwrap(), expected, "{pos} in {}", game.to_fen());
+            }
+        }
+        assert_eq!(game.get_state(), fresh.get_state());
+    }
+
+    #[test]
+    fn cached_moves_equal_fresh_moves() {
+        let mut game = Game::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let moves = [("e5", "d6"), ("e8", "d7"), ("e1", "g1"), ("d7", "d6"), ("b7", "a8")];
+        for (from, to) in moves {
+            assert_same_as_fresh(&mut game);
+            game.move_piece(&from.parse().unwrap(), &to.parse().unwrap()).unwrap();
+        }
+        game.promote(PieceType::Queen);
+        assert_same_as_fresh(&mut game);
+    }
+
+    #[test]
+    fn board_changes_invalidate_the_cache() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let rook = "a1".parse().unwrap();
+        assert_eq!(game.get_legal_moves(&rook).unwrap().len(), 10);
+
+        game.board().set_tile(&"a4".parse().unwrap(), Tile::new(PieceType::Pawn, Color::White));
+        assert_eq!(game.get_legal_moves(&rook).unwrap().len(), 5);
+        assert_same_as_fresh(&mut game);
+
+        game.reset_to_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(game.get_legal_moves(&rook).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn warm_moves() {
+        let mut game = Game::new();
+        let knight = "g1".parse().unwrap();
+        assert!(!game.is_cached(&knight));
+        game.warm_moves();
+        assert!(game.is_cached(&knight));
+        assert!(!game.is_cached(&"g8".parse().unwrap()));
+        assert_eq!(game.get_legal_moves(&knight).unwrap(), Game::new().get_legal_moves(&knight).unwrap());
+        assert_same_as_fresh(&mut game);
+
+        // The next move, an undo and an edit all invalidate the cache.
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+        assert!(!game.is_cached(&"g8".parse().unwrap()));
+        game.warm_moves();
+        assert!(game.is_cached(&"g8".parse().unwrap()));
+        game.undo();
+        assert!(!game.is_cached(&"g8".parse().unwrap()) && !game.is_cached(&knight));
+        game.warm_moves();
+        game.edit(|editor| editor.set(&"f3".parse().unwrap(), Tile::new(PieceType::Pawn, Color::White))).unwrap();
+        assert!(!game.is_cached(&knight));
+        assert_same_as_fresh(&mut game);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{per_square::PerSquare, pos::BoardPos, square_set::SquareSet};
+
+use super::Game;
+
+/// The legal moves of the team to move as the squares that each piece can move
+/// to, see `Game::all_legal_moves`.
+///
+/// Promotions are included once, like the moves returned by `get_legal_moves`.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct LegalMoveMap(PerSquare<SquareSet>);
+
+impl LegalMoveMap {
+    /// Get the squares that the piece on `from` can move to.
+    pub fn destinations(&self, from: &BoardPos) -> SquareSet {
+        self.0[from]
+    }
+
+    /// Whether the piece on `from` can move to `to`.
+    pub fn contains(&self, from: &BoardPos, to: &BoardPos) -> bool {
+        self.0[from].contains(to)
+    }
+
+    /// Get the number of moves.
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|(_, destinations)| destinations.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|(_, destinations)| destinations.is_empty())
+    }
+
+    /// Get an iterator over the moves as (origin, destination) pairs.
+    ///
+    /// The moves are ordered by origin and then by destination, both by rank and
+    /// then by file like `SquareSet::iter`.
+    pub fn iter(&self) -> impl Iterator<Item = (BoardPos, BoardPos)> + '_ {
+        self.0.iter().flat_map(|(from, destinations)| destinations.iter().map(move |to| (from.clone(), to)))
+    }
+
+    /// Update the map with the changes in a delta, so that a map captured before
+    /// the delta was made becomes the map after it.
+    pub fn apply(&mut self, delta: &LegalMoveDelta) {
+        for (from, to) in &delta.removed {
+            self.0[from].remove(to);
+        }
+        for (from, to) in &delta.added {
+            self.0[from].insert(to);
+        }
+    }
+}
+
+/// The legal moves that were added and removed since a `LegalMoveMap` was
+/// captured, see `Game::legal_move_delta`.
+///
+/// The moves are (origin, destination) pairs in the same order as
+/// `LegalMoveMap::iter`.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegalMoveDelta {
+    pub added: Vec<(BoardPos, BoardPos)>,
+    pub removed: Vec<(BoardPos, BoardPos)>,
+}
+
+impl LegalMoveDelta {
+    /// Whether the legal moves are unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Game {
+
+    /// Get the legal moves of the team whose turn it is, by the square of the
+    /// piece. The map is empty if the game is over.
+    pub fn all_legal_moves(&mut self) -> LegalMoveMap {
+        let mut map = LegalMoveMap::default();
+        for m in self.legal_moves() {
+            map.0[m.from()].insert(m.to());
+        }
+        map
+    }
+
+    /// Get how the legal moves have changed since `previous` was captured with
+    /// `all_legal_moves`.
+    ///
+    /// This is meant for spectator views that show the legal moves, which only
+    /// need the delta and the move that was made after every ply instead of the
+    /// full map. `LegalMoveMap::apply` turns the previous map into the current one.
+    pub fn legal_move_delta(&mut self, previous: &LegalMoveMap) -> LegalMoveDelta {
+        let current = self.all_legal_moves();
+        let mut delta = LegalMoveDelta::default();
+        for ((from, before), (_, after)) in previous.0.iter().zip(current.0.iter()) {
+            delta.added.extend((*after & !*before).iter().map(|to| (from.clone(), to)));
+            delta.removed.extend((*before & !*after).iter().map(|to| (from.clone(), to)));
+        }
+        delta
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_after_e4() {
+        let mut game = Game::new();
+        let before = game.all_legal_moves();
+        assert_eq!(before.len(), 20);
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+
+        let delta = game.legal_move_delta(&before);
+        assert_eq!(delta.removed.len(), 20);
+        assert_eq!(delta.added.len(), 20);
+        assert!(delta.removed.contains(&("e2".parse().unwrap(), "e4".parse().unwrap())));
+        assert!(delta.added.iter().all(|(from, _)| from.rank() >= 6));
+
+        let mut map = before.clone();
+        map.apply(&delta);
+        assert_eq!(map, game.all_legal_moves());
+        assert!(game.legal_move_delta(&map).is_empty());
+    }
+
+    #[test]
+    fn delta_round_trips() {
+        let mut game = Game::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let mut map = game.all_legal_moves();
+        assert!(map.contains(&"e5".parse().unwrap(), &"d6".parse().unwrap()));
+        // The four promotions to a8 are one move.
+        assert!(map.destinations(&"b7".parse().unwrap()).contains(&"a8".parse().unwrap()));
+
+        for m in ["e5d6", "a8a7", "b7b8q", "e8f7", "e1g1"] {
+            game.make_move(&m.parse().unwrap()).unwrap();
+            let delta = game.legal_move_delta(&map);
+            map.apply(&delta);
+            assert_eq!(map, game.all_legal_moves());
+            assert_eq!(map.iter().count(), map.len());
+        }
+    }
+}
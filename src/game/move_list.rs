@@ -0,0 +1,233 @@
+use std::{ops::Deref, slice};
+
+use crate::{board::Color, piece::PieceType, pos::BoardPos};
+
+use super::{Game, GetMovesetError, Move};
+
+/// The most moves a `MoveList` can hold. No chess position has more than 218
+/// legal moves.
+pub const MAX_MOVES: usize = 256;
+
+/// A list that moves can be generated into, see `Game::legal_moves_into`.
+///
+/// This is implemented for `Vec<Move>` and for `MoveList`, which does not
+/// allocate.
+pub trait MoveBuffer {
+    /// Remove all moves from the list.
+    fn clear(&mut self);
+    /// Add a move to the end of the list.
+    fn push(&mut self, m: Move);
+}
+
+impl MoveBuffer for Vec<Move> {
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+
+    fn push(&mut self, m: Move) {
+        Vec::push(self, m);
+    }
+}
+
+/// A list of at most `MAX_MOVES` moves that is stored inline instead of on the
+/// heap, so that it can be created for every position of a search without
+/// allocating.
+#[derive(Clone, Debug)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> MoveList {
+        let empty = Move::new(BoardPos::new(0, 0), BoardPos::new(0, 0), None);
+        MoveList { moves: std::array::from_fn(|_| empty.clone()), len: 0 }
+    }
+
+    /// Get the moves as a slice.
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl MoveBuffer for MoveList {
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// ## Panics
+    /// This method will panic if the list already has `MAX_MOVES` moves.
+    fn push(&mut self, m: Move) {
+        assert!(self.len < MAX_MOVES, "A MoveList can not hold more than {MAX_MOVES} moves.");
+        self.moves[self.len] = m;
+        self.len += 1;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        self.as_slice()
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+impl Game {
+
+    /// Clear the buffer and fill it with the legal moves of the team whose turn it
+    /// is. This is the same as `legal_moves`, but the caller can reuse the buffer,
+    /// for example a `MoveList` that does not allocate.
+    pub fn legal_moves_into(&mut self, buf: &mut impl MoveBuffer) {
+        buf.clear();
+        let color = self.current_turn;
+        let positions: Vec<BoardPos> = self.board.pieces()
+            .filter(|(_, tile)| tile.color() == color)
+            .map(|(pos, _)| pos)
+            .collect();
+        for from in positions {
+            self.push_legal_moves(&from, &color, buf);
+        }
+    }
+
+    /// Get the legal moves of every piece of the type that belongs to the team
+    /// whose turn it is, in the same order as `legal_moves`.
+    ///
+    /// Only the moves of those pieces are generated, so this is cheaper than
+    /// filtering `legal_moves`.
+    pub fn legal_moves_of(&mut self, piece: PieceType) -> Vec<Move> {
+        let color = self.current_turn;
+        let positions: Vec<BoardPos> = self.board.pieces()
+            .filter(|(_, tile)| tile.color() == color && tile.piece() == piece)
+            .map(|(pos, _)| pos)
+            .collect();
+        let mut moves = Vec::new();
+        for from in positions {
+            self.push_legal_moves(&from, &color, &mut moves);
+        }
+        moves
+    }
+
+    /// Clear the buffer and fill it with the legal moves of the piece at the
+    /// position, with a move for each piece that a pawn can be promoted to.
+    ///
+    /// # Errors
+    /// The same errors as `get_legal_moves` are returned, and the buffer is left
+    /// empty.
+    pub fn legal_moves_from_into(&mut self, pos: &BoardPos, buf: &mut impl MoveBuffer) -> Result<(), GetMovesetError> {
+        buf.clear();
+        self.get_legal_moves(pos)?;
+        let color = self.current_turn;
+        self.push_legal_moves(pos, &color, buf);
+        Ok(())
+    }
+
+    fn push_legal_moves(&mut self, from: &BoardPos, color: &Color, buf: &mut impl MoveBuffer) {
+        let last_rank = if *color == Color::White { 7 } else { 0 };
+        let is_pawn = self.board.get_tile(from).is_some_and(|tile| tile.piece() == PieceType::Pawn);
+        for to in self.legal_move_set(from).iter() {
+            if is_pawn && to.rank() == last_rank {
+                for piece in [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight] {
+                    buf.push(Move::new(from.clone(), to.clone(), Some(piece)));
+                }
+            } else {
+                buf.push(Move::new(from.clone(), to, None));
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_match_legal_moves() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            // The most legal moves known in a position, 218.
+            "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1",
+        ];
+        let mut list = MoveList::new();
+        let mut vec = Vec::new();
+        for fen in fens {
+            let mut game = Game::from_fen(fen).unwrap();
+            for m in game.legal_moves() {
+                let mut after = game.clone();
+                after.make_move(&m).unwrap();
+                for mut position in [game.clone(), after] {
+                    let expected = position.legal_moves();
+                    position.legal_moves_into(&mut list);
+                    position.legal_moves_into(&mut vec);
+                    assert_eq!(list.as_slice(), expected.as_slice());
+                    assert_eq!(vec, expected);
+
+                    if position.is_game_over() {
+                        continue;
+                    }
+                    let mut per_piece = Vec::new();
+                    for (pos, tile) in position.board.pieces().collect::<Vec<_>>() {
+                        if tile.color() == position.current_turn() {
+                            position.legal_moves_from_into(&pos, &mut list).unwrap();
+                            per_piece.extend(list.iter().cloned());
+                        }
+                    }
+                    assert_eq!(per_piece, expected);
+                }
+            }
+        }
+        let mut game = Game::from_fen(fens[3]).unwrap();
+        game.legal_moves_into(&mut list);
+        assert_eq!(list.len(), 218);
+    }
+
+    #[test]
+    fn moves_of_piece_type() {
+        let mut game = Game::new();
+        let knight_moves: Vec<String> = game.legal_moves_of(PieceType::Knight).iter().map(Move::to_string).collect();
+        assert_eq!(knight_moves.len(), 4);
+        assert!(knight_moves.contains(&"g1f3".to_string()));
+        assert!(game.legal_moves_of(PieceType::Bishop).is_empty());
+        assert_eq!(game.legal_moves_of(PieceType::Pawn).len(), 16);
+
+        // The moves of all piece types together are the legal moves.
+        let mut game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1").unwrap();
+        let mut by_type = Vec::new();
+        for index in 0..6 {
+            by_type.extend(game.legal_moves_of(PieceType::from_index(index).unwrap()));
+        }
+        let mut legal_moves = game.legal_moves();
+        let key = |m: &Move| m.to_string();
+        by_type.sort_by_key(key);
+        legal_moves.sort_by_key(key);
+        assert_eq!(by_type, legal_moves);
+    }
+
+    #[test]
+    fn per_piece_errors() {
+        let mut game = Game::new();
+        let mut list = MoveList::new();
+        game.legal_moves_from_into(&"g1".parse().unwrap(), &mut list).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(matches!(game.legal_moves_from_into(&"e4".parse().unwrap(), &mut list), Err(GetMovesetError::NoTile)));
+        assert!(list.is_empty());
+        assert!(matches!(game.legal_moves_from_into(&"e7".parse().unwrap(), &mut list), Err(GetMovesetError::NotCurrentTurn)));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{board::Tile, piece::PieceType, pos::BoardPos};
+
+use super::{Game, GameState, Move, MovePieceError, Outcome};
+
+/// A piece that a pawn can be promoted to.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PromotionPiece {
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+}
+
+impl PromotionPiece {
+    /// Get the promotion piece of the type, or `None` for a king or a pawn.
+    pub fn from_piece_type(piece: PieceType) -> Option<PromotionPiece> {
+        match piece {
+            PieceType::Queen => Some(Self::Queen),
+            PieceType::Rook => Some(Self::Rook),
+            PieceType::Bishop => Some(Self::Bishop),
+            PieceType::Knight => Some(Self::Knight),
+            PieceType::King | PieceType::Pawn => None,
+        }
+    }
+
+    pub fn piece_type(&self) -> PieceType {
+        match self {
+            Self::Queen => PieceType::Queen,
+            Self::Rook => PieceType::Rook,
+            Self::Bishop => PieceType::Bishop,
+            Self::Knight => PieceType::Knight,
+        }
+    }
+}
+
+/// What happened on the board in a move made by `Game::move_piece`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveEffects {
+    /// The piece that moved.
+    pub piece: Tile,
+    pub from: BoardPos,
+    /// Where the piece moved to. When castling this is where the king ended up,
+    /// also if castling was requested by moving the king onto its own rook.
+    pub to: BoardPos,
+    /// The captured piece and its position, which is not `to` for en passant.
+    pub captured: Option<(BoardPos, Tile)>,
+    pub en_passant: bool,
+    pub kingside_castle: bool,
+    pub queenside_castle: bool,
+    /// Where the rook moved from and to when castling.
+    pub rook_move: Option<(BoardPos, BoardPos)>,
+    /// Whether the pawn must be promoted before the game can continue, see
+    /// `Game::promote`.
+    pub promotion_required: bool,
+}
+
+/// The result of `Game::play_move`.
+#[must_use = "a pending promotion must be completed with `PromotionPending::promote`"]
+#[derive(Debug)]
+pub enum Moved<'a> {
+    /// The move is complete.
+    Done(MoveOutcome),
+    /// A pawn reached the last rank and must be promoted before the game can
+    /// continue.
+    NeedsPromotion(PromotionPending<'a>),
+}
+
+/// The state of the game after a complete move.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveOutcome {
+    /// The move that was made, including the promotion piece.
+    pub m: Move,
+    /// The state of the game, which is never `PromotionRequired`.
+    pub state: GameState,
+    /// How the game ended, or `None` if it continues. See `Game::outcome`.
+    pub outcome: Option<Outcome>,
+}
+
+/// A pawn waiting to be promoted, returned by `Game::play_move`.
+///
+/// The game is borrowed until the pawn is promoted, so no other moves can be
+/// made before that, and `promote` takes the value so the pawn can only be
+/// promoted once.
+#[must_use = "the pawn must be promoted with `promote`"]
+#[derive(Debug)]
+pub struct PromotionPending<'a> {
+    game: &'a mut Game,
+}
+
+impl PromotionPending<'_> {
+    /// Get the position of the pawn that is promoted.
+    pub fn pos(&self) -> BoardPos {
+        self.game.promotion_required.clone().expect("A promotion is pending.")
+    }
+
+    /// Promote the pawn and finish the move.
+    pub fn promote(self, piece: PromotionPiece) -> MoveOutcome {
+        self.game.promote(piece.piece_type());
+        self.game.move_outcome()
+    }
+}
+
+impl Game {
+
+    /// Move a piece like `move_piece`, but return whether a promotion is
+    /// required instead of leaving it to `get_state`.
+    ///
+    /// If a pawn reaches the last rank, the returned `PromotionPending` must be
+    /// used to promote it, which can not be forgotten or done twice.
+    ///
+    /// # Errors
+    /// The errors are the same as for `move_piece`.
+    pub fn play_move(&mut self, from: &BoardPos, to: &BoardPos) -> Result<Moved<'_>, MovePieceError> {
+        if self.promotion_required.is_some() {
+            return Err(MovePieceError::InvalidMove);
+        }
+        self.move_piece(from, to)?;
+        if self.promotion_required.is_some() {
+            Ok(Moved::NeedsPromotion(PromotionPending { game: self }))
+        } else {
+            Ok(Moved::Done(self.move_outcome()))
+        }
+    }
+
+    fn move_outcome(&mut self) -> MoveOutcome {
+        MoveOutcome {
+            m: self.history.last().cloned().expect("A move was made."),
+            state: self.get_state(),
+            outcome: self.outcome,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Color;
+
+    use super::*;
+
+    fn pos(pos: &str) -> BoardPos {
+        pos.parse().unwrap()
+    }
+
+    #[test]
+    fn complete_move() {
+        let mut game = Game::new();
+        let moved = game.play_move(&pos("e2"), &pos("e4")).unwrap();
+        let Moved::Done(outcome) = moved else { panic!("No promotion is needed.") };
+        assert_eq!(outcome.m.to_string(), "e2e4");
+        assert_eq!(outcome.state, GameState::Normal);
+        assert_eq!(outcome.outcome, None);
+        assert!(matches!(game.play_move(&pos("e4"), &pos("e5")), Err(MovePieceError::NotCurrentTurn)));
+    }
+
+    #[test]
+    fn promotion() {
+        let mut game = Game::from_fen("7k/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let outcome = match game.play_move(&pos("a7"), &pos("a8")).unwrap() {
+            Moved::NeedsPromotion(pending) => {
+                assert_eq!(pending.pos(), pos("a8"));
+                pending.promote(PromotionPiece::Rook)
+            },
+            Moved::Done(_) => panic!("A promotion is needed."),
+        };
+        assert_eq!(outcome.m.to_string(), "a7a8r");
+        assert_eq!(outcome.state, GameState::Check(Color::Black));
+        assert!(game.to_fen().starts_with("R6k/8/8/8/8/8/8/4K3 b - -"));
+    }
+
+    #[test]
+    fn promotion_with_mate() {
+        let mut game = Game::from_fen("7k/P7/6K1/8/8/8/8/8 w - - 0 1").unwrap();
+        let Ok(Moved::NeedsPromotion(pending)) = game.play_move(&pos("a7"), &pos("a8")) else { panic!("A promotion is needed.") };
+        let outcome = pending.promote(PromotionPiece::Queen);
+        assert_eq!(outcome.state, GameState::Checkmate(Color::Black));
+        assert_eq!(outcome.outcome, Some(Outcome::Checkmate { winner: Color::White }));
+    }
+
+    #[test]
+    fn old_api_pending_promotion_is_rejected() {
+        let mut game = Game::from_fen("7k/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.move_piece(&pos("a7"), &pos("a8")).unwrap();
+        assert!(matches!(game.play_move(&pos("h8"), &pos("h7")), Err(MovePieceError::InvalidMove)));
+    }
+
+    #[test]
+    fn move_effects() {
+        let mut game = Game::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let effects = game.move_piece(&pos("e5"), &pos("d6")).unwrap();
+        assert_eq!(effects.piece, Tile::new(PieceType::Pawn, Color::White));
+        assert_eq!(effects.captured, Some((pos("d5"), Tile::new(PieceType::Pawn, Color::Black))));
+        assert!(effects.en_passant);
+        assert!(!effects.kingside_castle && !effects.queenside_castle && !effects.promotion_required);
+
+        // Castling by moving the king onto the rook.
+        let effects = game.move_piece(&pos("e8"), &pos("h8")).unwrap();
+        assert_eq!((effects.from, effects.to), (pos("e8"), pos("g8")));
+        assert!(effects.kingside_castle && !effects.queenside_castle);
+        assert_eq!(effects.rook_move, Some((pos("h8"), pos("f8"))));
+        assert_eq!(effects.captured, None);
+
+        let effects = game.move_piece(&pos("b7"), &pos("a8")).unwrap();
+        assert_eq!(effects.captured, Some((pos("a8"), Tile::new(PieceType::Rook, Color::Black))));
+        assert!(effects.promotion_required && !effects.en_passant);
+        game.promote(PieceType::Queen);
+        game.move_piece(&pos("g8"), &pos("g7")).unwrap();
+
+        let effects = game.move_piece(&pos("e1"), &pos("c1")).unwrap();
+        assert!(effects.queenside_castle);
+        assert_eq!(effects.rook_move, Some((pos("a1"), pos("d1"))));
+    }
+
+    #[test]
+    fn promotion_pieces() {
+        assert_eq!(PromotionPiece::from_piece_type(PieceType::King), None);
+        assert_eq!(PromotionPiece::from_piece_type(PieceType::Pawn), None);
+        for piece in [PromotionPiece::Queen, PromotionPiece::Rook, PromotionPiece::Bishop, PromotionPiece::Knight] {
+            assert_eq!(PromotionPiece::from_piece_type(piece.piece_type()), Some(piece));
+        }
+    }
+}
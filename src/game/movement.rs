@@ -1,14 +1,43 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt};
 
-use crate::{pos::BoardPos, board::{Color, Tile}, piece::PieceType};
+use crate::{pos::BoardPos, board::{Color, Tile}, piece::{is_valid_delta, MovementPattern, PieceType}, attacks, square_set::SquareSet};
 
-use super::Game;
+use super::{Game, MoveEffects, UndoRecord};
 
 #[derive(Debug)]
 pub enum MovePieceError {
     NoTile,
     NotCurrentTurn,
     InvalidMove,
+    /// The game has ended, see `Game::outcome`.
+    GameOver,
+}
+
+/// A move of a piece from one position to another.
+/// 
+/// For castling, the move is the king moving two squares towards the rook, and
+/// for en passant it is the pawn moving to the en passant target square. The
+/// promotion is the piece that a pawn is promoted to when it reaches the last
+/// rank, and is `None` for all other moves.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Move {
+    from: BoardPos,
+    to: BoardPos,
+    promotion: Option<PieceType>,
+}
+
+impl Move {
+    /// Create a move, which is not checked to be legal in any game.
+    pub fn new(from: BoardPos, to: BoardPos, promotion: Option<PieceType>) -> Move {
+        Move { from, to, promotion }
+    }
+
+    /// The position the piece moves from.
+    pub fn from(&self) -> &BoardPos { &self.from }
+    /// The position the piece moves to.
+    pub fn to(&self) -> &BoardPos { &self.to }
+    /// The piece a pawn is promoted to, or `None` if the move is not a promotion.
+    pub fn promotion(&self) -> Option<PieceType> { self.promotion }
 }
 
 /// Errors returned from Game's `get_moveset`.
@@ -21,8 +50,35 @@ pub enum GetMovesetError {
     /// This error is returned when `get_moveset` is called on a piece that is of the
     /// color that is not the current turn.
     NotCurrentTurn,
+    /// The game has ended, see `Game::outcome`.
+    GameOver,
 }
 
+impl fmt::Display for MovePieceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoTile => write!(f, "There is no piece to move at that position."),
+            Self::NotCurrentTurn => write!(f, "It is not the turn of that piece's team."),
+            Self::InvalidMove => write!(f, "That is not a valid move."),
+            Self::GameOver => write!(f, "The game is over."),
+        }
+    }
+}
+
+impl std::error::Error for MovePieceError {}
+
+impl fmt::Display for GetMovesetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoTile => write!(f, "There is no piece at that position."),
+            Self::NotCurrentTurn => write!(f, "It is not the turn of that piece's team."),
+            Self::GameOver => write!(f, "The game is over."),
+        }
+    }
+}
+
+impl std::error::Error for GetMovesetError {}
+
 #[derive(PartialEq)]
 enum MoveType {
     /// The piece is being moved to an unoccupied, empty tile.
@@ -32,9 +88,15 @@ enum MoveType {
     Attacking
 }
 
-struct PerformedMove {
+#[derive(Clone, Debug)]
+pub(super) struct PerformedMove {
     changed_tiles: Vec<(BoardPos, Option<Tile>)>,
-    had_capture: bool,
+    /// The captured piece, including a pawn taken en passant.
+    captured: Option<Tile>,
+    /// The position of the pawn taken en passant.
+    en_passant_capture: Option<BoardPos>,
+    /// Where the rook moved from and to when castling.
+    castling_rook: Option<(BoardPos, BoardPos)>,
 }
 
 impl Game {
@@ -48,11 +110,16 @@ impl Game {
     /// move, other pieces than those two positions will also be changed in order
     /// to complete the en passant or castling.
     /// 
+    /// Castling can also be requested by moving the king onto its own rook, like
+    /// `e1h1`, as is done by many GUIs and by `UCI_Chess960`.
+    /// 
     /// This method should always be immediately followed by `get_status` since a
     /// move might result in the player needing to promote a piece. See the
     /// `promote` method.
     /// 
     /// # Errors
+    /// If the game has ended, `GameOver` is returned. See `outcome`.
+    ///
     /// If there is no tile (no piece) at the position `NoTile` will be errored.
     /// 
     /// If the piece at `from` is of the wrong color, aka the color who's turn it is
@@ -62,23 +129,107 @@ impl Game {
     /// was immediately preceded by `get_legal_move` on `from`, and the `to`
     /// position was a part of the returned moveset, this method will never error
     /// since the move is guaranteed to be valid.
-    pub fn move_piece(&mut self, from: &BoardPos, to: &BoardPos) -> Result<(), MovePieceError> {
+    ///
+    /// On success, what happened on the board is returned, like a capture or the
+    /// rook moving when castling. See `MoveEffects`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn move_piece(&mut self, from: &BoardPos, to: &BoardPos) -> Result<MoveEffects, MovePieceError> {
+        // Castling may be written as the king moving onto its own rook.
+        let castling_target = self.castling_by_rook(from, to);
+        let to = castling_target.as_ref().unwrap_or(to);
+
         let moveset = match self.get_legal_moves(from) {
             Ok(moveset) => moveset,
-            Err(GetMovesetError::NoTile) => return Err(MovePieceError::NoTile),
-            Err(GetMovesetError::NotCurrentTurn) => return Err(MovePieceError::NotCurrentTurn),
+            Err(err) => {
+                let err = match err {
+                    GetMovesetError::NoTile => MovePieceError::NoTile,
+                    GetMovesetError::NotCurrentTurn => MovePieceError::NotCurrentTurn,
+                    GetMovesetError::GameOver => MovePieceError::GameOver,
+                };
+                rejected_move!(from, to, err);
+                return Err(err);
+            },
         };
 
         if !moveset.contains(to) {
+            rejected_move!(from, to, MovePieceError::InvalidMove);
             return Err(MovePieceError::InvalidMove);
         }
 
         let tile = self.board.get_tile(from).expect("Move is already validated.");
+        let castling_before = (self.white_castling, self.black_castling);
+
+        let performed_move = self.perform_move(from, to, None);
+        self.invalidate_move_cache();
+        let captured = performed_move.captured;
+        let mut effects = MoveEffects {
+            piece: tile,
+            from: from.clone(),
+            to: to.clone(),
+            captured: captured.map(|captured| {
+                let pos = performed_move.en_passant_capture.clone().unwrap_or_else(|| to.clone());
+                (pos, captured)
+            }),
+            en_passant: performed_move.en_passant_capture.is_some(),
+            kingside_castle: performed_move.castling_rook.is_some() && to.file() > from.file(),
+            queenside_castle: performed_move.castling_rook.is_some() && to.file() < from.file(),
+            rook_move: performed_move.castling_rook.clone(),
+            promotion_required: false,
+        };
+        // `perform_move` only changes the board, so the rest is still as before.
+        let undo_record = self.undo_record(performed_move);
+        self.undo_stack.push(undo_record);
 
-        let performed_move = self.perform_move(from, to);
+        self.update_state_after_move(tile, from, to, captured.is_some());
 
-        self.halfmove_clock += 1;
-        if performed_move.had_capture {
+        // Check if promotion is required
+        let last_rank = if tile.color() == Color::White { 7 } else { 0 };
+        if to.rank() == last_rank && tile.piece() == PieceType::Pawn {
+            self.promotion_required = Some(to.clone());
+            effects.promotion_required = true;
+        }
+
+        self.history.push(Move::new(from.clone(), to.clone(), None));
+
+        // Positions from before a pawn move, capture, castling or loss of castling
+        // availability can never occur again.
+        let is_castling = tile.piece() == PieceType::King && from.file().abs_diff(to.file()) == 2;
+        if tile.piece() == PieceType::Pawn
+            || captured.is_some()
+            || is_castling
+            || castling_before != (self.white_castling, self.black_castling) {
+            self.last_irreversible_ply = self.history.len();
+        }
+
+        if self.promotion_required.is_none() {
+            self.record_position_key();
+            self.update_outcome();
+        }
+
+        Ok(effects)
+    }
+
+    /// Make a legal move, including its promotion, without validating it and
+    /// without recording it in the history or ending the game. The returned record
+    /// undoes the move with `restore_undo_record`.
+    ///
+    /// This is the fast make and unmake pair used by `perft`.
+    pub(super) fn play_unchecked(&mut self, m: &Move) -> UndoRecord {
+        let tile = self.board.get_tile(m.from()).expect("Move is already validated.");
+        let performed_move = self.perform_move(m.from(), m.to(), m.promotion());
+        let captured = performed_move.captured.is_some();
+        let undo_record = self.undo_record(performed_move);
+        self.update_state_after_move(tile, m.from(), m.to(), captured);
+        self.invalidate_move_cache();
+        undo_record
+    }
+
+    /// Update the clocks, the en passant target, castling availability and the
+    /// turn after `tile` was moved from `from` to `to` by `perform_move`.
+    fn update_state_after_move(&mut self, tile: Tile, from: &BoardPos, to: &BoardPos, captured: bool) {
+        // The halfmove clock counts the moves since the last capture or pawn move.
+        self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        if captured || tile.piece() == PieceType::Pawn {
             self.halfmove_clock = 0;
         }
 
@@ -125,21 +276,81 @@ impl Game {
             }
         }
 
-        // Check if promotion is required
-        let last_rank = if tile.color() == Color::White { 7 } else { 0 };
-        if to.rank() == last_rank && tile.piece() == PieceType::Pawn {
-            self.promotion_required = Some(to.clone());
+        // Remove the opponent's castling availability when capturing a rook on its
+        // starting position.
+        let opponent_rank = if tile.color() == Color::White { 7 } else { 0 };
+        if captured && to.rank() == opponent_rank {
+            let castling_availability = match tile.color() {
+                Color::White => &mut self.black_castling,
+                Color::Black => &mut self.white_castling,
+            };
+            if to.file() == 0 {
+                castling_availability.queenside = false;
+            }
+            if to.file() == 7 {
+                castling_availability.kingside = false;
+            }
         }
 
         if self.current_turn == Color::Black {
-            self.fullmove_number += 1;
+            self.fullmove_number = self.fullmove_number.saturating_add(1);
         }
 
         self.current_turn = self.current_turn.opposite();
-        
+    }
+
+    /// Make a move, including the promotion if the move is a promotion.
+    ///
+    /// This is the same as calling `move_piece` followed by `promote`, except that
+    /// the move is rejected without changing the game if the promotion is missing
+    /// or invalid.
+    ///
+    /// # Errors
+    /// The errors are the same as for `move_piece`. `InvalidMove` is also returned
+    /// if the move is a promotion but the promotion piece is `None`, a king or a
+    /// pawn, or if a promotion piece is given for a move that is not a promotion.
+    pub fn make_move(&mut self, m: &Move) -> Result<(), MovePieceError> {
+        if self.outcome.is_some() {
+            return Err(MovePieceError::GameOver);
+        }
+        let tile = self.board.get_tile(m.from()).ok_or(MovePieceError::NoTile)?;
+
+        let last_rank = if tile.color() == Color::White { 7 } else { 0 };
+        let is_promotion = tile.piece() == PieceType::Pawn && m.to().rank() == last_rank;
+        let valid_promotion = match m.promotion() {
+            None => !is_promotion,
+            Some(PieceType::King | PieceType::Pawn) => false,
+            Some(_) => is_promotion,
+        };
+        if !valid_promotion {
+            // Make sure the correct error is returned for pieces of the wrong team.
+            self.get_legal_moves(m.from()).map_err(|err| match err {
+                GetMovesetError::NoTile => MovePieceError::NoTile,
+                GetMovesetError::NotCurrentTurn => MovePieceError::NotCurrentTurn,
+                GetMovesetError::GameOver => MovePieceError::GameOver,
+            })?;
+            rejected_move!(m.from(), m.to(), "The promotion piece is missing or not allowed.");
+            return Err(MovePieceError::InvalidMove);
+        }
+
+        self.move_piece(m.from(), m.to())?;
+        if let Some(piece) = m.promotion() {
+            self.promote(piece);
+        }
         Ok(())
     }
 
+    /// Get all legal moves of the team whose turn it is.
+    ///
+    /// Promotions are listed once for each piece the pawn can be promoted to. The
+    /// moves are ordered by the position of the piece, in the same order as
+    /// `Board::pieces`, but the order of the moves of each piece is unspecified.
+    pub fn legal_moves(&mut self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.legal_moves_into(&mut moves);
+        moves
+    }
+
     /// An internal method for performing moves without validating them or affecting
     /// future gameplay.
     ///
@@ -148,8 +359,9 @@ impl Game {
     ///
     /// This method will only move the pieces in accordance to chess rules. In most
     /// cases this method will move the piece at `from` to the tile at `to`. This
-    /// method will additionally move the rook to perform castling, and capture
-    /// pieces being taken en passant.
+    /// method will additionally move the rook to perform castling, capture
+    /// pieces being taken en passant, and replace the pawn with the `promotion`
+    /// piece if one is given.
     /// 
     /// This method does not change the playing team and does not store information
     /// about changes to castling or en passant as a result of this move.
@@ -157,13 +369,15 @@ impl Game {
     /// Therefore, this method can be used to "preview" a move without affecting
     /// gameplay, and can easially be reversed by calling `undo_performed_move`
     /// with the return value of this method.
-    fn perform_move(&mut self, from: &BoardPos, to: &BoardPos) -> PerformedMove {
+    fn perform_move(&mut self, from: &BoardPos, to: &BoardPos, promotion: Option<PieceType>) -> PerformedMove {
 
         let tile = self.board.get_tile(from).expect("Move is already validated.");
 
         let mut performed_move = PerformedMove {
             changed_tiles: Vec::with_capacity(3),
-            had_capture: false,
+            captured: None,
+            en_passant_capture: None,
+            castling_rook: None,
         };
         
         // Record the tile before it is moved.
@@ -172,9 +386,7 @@ impl Game {
         // Record the tile currently at the position we are about to move to.
         let to_tile = self.board.get_tile(to);
         performed_move.changed_tiles.push((to.clone(), to_tile));
-        if to_tile.is_some() {
-            performed_move.had_capture = true;
-        }
+        performed_move.captured = to_tile;
 
         // Castling
         if tile.piece() == PieceType::King && from.file().abs_diff(to.file()) == 2 {
@@ -191,8 +403,9 @@ impl Game {
 
             // Record tiles before performing the move in case the move
             // needs to be undone.
-            performed_move.changed_tiles.push((rook_pos, Some(rook)));
+            performed_move.changed_tiles.push((rook_pos.clone(), Some(rook)));
             self.record_tile(&new_rook_pos, &mut performed_move);
+            performed_move.castling_rook = Some((rook_pos, new_rook_pos.clone()));
 
             self.board.remove_tile(from);
             self.board.set_tile(to, tile);
@@ -206,6 +419,7 @@ impl Game {
 
         // En passant
         if tile.piece() == PieceType::Pawn
+            && from.file() != to.file()
             && self.en_passant_target.as_ref().is_some_and(|en_passant_target| en_passant_target == to) {
             // A pawn just performed en passant.
             // We need to capture the pawn being taken en passant.
@@ -224,12 +438,25 @@ impl Game {
                     if attacked_pawn.piece() != PieceType::Pawn || attacked_pawn.color() == tile.color() {
                         panic!("Did not attack an enemy pawn.");
                     }
-                    performed_move.had_capture = true;
-                    performed_move.changed_tiles.push((attacked_pawn_pos, Some(attacked_pawn)));
+                    performed_move.captured = Some(attacked_pawn);
+                    performed_move.changed_tiles.push((attacked_pawn_pos.clone(), Some(attacked_pawn)));
+                    performed_move.en_passant_capture = Some(attacked_pawn_pos);
                 }
             }
         }
 
+        if let Some(piece) = promotion {
+            self.board.set_tile(to, Tile::new(piece, tile.color()));
+        }
+
+        // Variants may change more of the board when a piece is captured.
+        if let Some(captured) = performed_move.captured {
+            for (pos, tile) in self.rules.capture_effects(&self.board, to, captured) {
+                self.record_tile(&pos, &mut performed_move);
+                self.board.set_or_remove_tile(&pos, tile);
+            }
+        }
+
         performed_move
     }
 
@@ -239,9 +466,10 @@ impl Game {
     }
 
     /// Undo a move that was just performed by `perform_move`.
-    fn undo_performed_move(&mut self, performed_move: PerformedMove) {
-        // Restore all tiles that changed to their state before the change.
-        for (pos, tile) in performed_move.changed_tiles {
+    pub(super) fn undo_performed_move(&mut self, performed_move: PerformedMove) {
+        // Restore all tiles that changed to their state before the change. A tile
+        // may be recorded more than once, and the first record is the original.
+        for (pos, tile) in performed_move.changed_tiles.into_iter().rev() {
             self.board.set_or_remove_tile(&pos, tile);
         }
     }
@@ -256,6 +484,8 @@ impl Game {
     /// 3. do not move outside of the board.
     /// 4. do not move into a state of check.
     /// 
+    /// If the game has ended, the `GameOver` error variant is returned.
+    ///
     /// This method will ensure there is a tile at the position, otherwise the
     /// `NoTile` error variant is returned.
     /// 
@@ -265,7 +495,12 @@ impl Game {
     /// 
     /// ## Castling and en passant
     /// Not implemented yet!
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
     pub fn get_legal_moves(&mut self, pos: &BoardPos) -> Result<HashSet<BoardPos>, GetMovesetError> {
+        if self.outcome.is_some() {
+            return Err(GetMovesetError::GameOver);
+        }
+
         let tile = self.board.get_tile(pos)
             .ok_or(GetMovesetError::NoTile)?;
 
@@ -273,22 +508,43 @@ impl Game {
             return Err(GetMovesetError::NotCurrentTurn);
         }
 
+        Ok(self.get_legal_moves_unchecked(pos))
+    }
+
+    /// Get the legal moves for a piece without validating the turn.
+    ///
+    /// This is the same as `get_legal_moves`, except that the moves can be gotten
+    /// for pieces of either color.
+    ///
+    /// ## Panics
+    /// This function will panic if there is no piece at the tile.
+    pub(super) fn get_legal_moves_unchecked(&mut self, pos: &BoardPos) -> HashSet<BoardPos> {
+        self.legal_move_set(pos).iter().collect()
+    }
+
+    /// Compute the legal moves for a piece, see `get_legal_moves_unchecked`.
+    pub(super) fn compute_legal_moves(&mut self, pos: &BoardPos) -> HashSet<BoardPos> {
+        let tile = self.board.get_tile(pos)
+            .expect("Attempt to get legal moves from empty tile.");
+
         let mut moveset = self.get_pseudo_legal_moves(pos, true);
         moveset.retain(|move_pos| {
             // Ensure the move does not move into a state of check.
             // Attempt the move.
 
             // Move there by setting the tiles directly.
-            let performed_move = self.perform_move(pos, move_pos);
+            let performed_move = self.perform_move(pos, move_pos, None);
             let check = self.is_check(&tile.color());
             // This move resulted in a state of check. It is not a legal move.
+            let m = Move::new(pos.clone(), move_pos.clone(), None);
+            let allowed = self.rules.allows_move(self, &m, performed_move.captured);
 
             // Undo the move.
             self.undo_performed_move(performed_move);
 
-            !check
+            !check && allowed
         });
-        Ok(moveset)
+        moveset
     }
 
     /// Get the pseudo legal moves for a tile.
@@ -315,138 +571,130 @@ impl Game {
         let tile = self.board.get_tile(pos)
             .expect("Attempt to get pseudo-legal moves from empty tile.");
 
-        let mut moveset = HashSet::new();
+        // The built-in leapers use the precomputed attack tables.
+        let mut moveset = match tile.piece() {
+            PieceType::Knight => self.leaper_moves(&tile.color(), attacks::knight_attacks(pos)),
+            PieceType::King => self.leaper_moves(&tile.color(), attacks::king_attacks(pos)),
+            piece => self.pattern_moves(pos, &tile.color(), &piece.movement()),
+        };
 
-        match tile.piece() {
-            PieceType::Queen => {
-                self.try_moves_multiple(&mut moveset, &pos, &tile.color(), [
-                    (-1,  1), (0,  1), (1,  1),
-                    (-1,  0), /******/ (1,  0),
-                    (-1, -1), (0, -1), (1, -1),
-                ]);
-            },
-            PieceType::Rook => {
-                self.try_moves_multiple(&mut moveset, &pos, &tile.color(), [
-                              (0,  1),
-                    (-1,  0), /******/ (1,  0),
-                              (0, -1),
-                ]);
-            },
-            PieceType::Bishop => {
-                self.try_moves_multiple(&mut moveset, &pos, &tile.color(), [
-                    (-1,  1), (1,  1),
-                    (-1, -1), (1, -1),
-                ]);
-            },
-            PieceType::Knight => {
-                self.try_moves_once(&mut moveset, &pos, &tile.color(), [
-                    (-1,  2), (1,   2),
-                    (2,   1), (2,  -1),
-                    (-1, -2), (1,  -2),
-                    (-2,  1), (-2, -1),
-                ]);
-            },
-            PieceType::King => {
-                self.try_moves_once(&mut moveset, &pos, &tile.color(), [
-                    (-1,  1), (0,  1), (1,  1),
-                    (-1,  0), /******/ (1,  0),
-                    (-1, -1), (0, -1), (1, -1),
-                ]);
+        let castling_availability = match tile.color() {
+            Color::White => &self.white_castling,
+            Color::Black => &self.black_castling,
+        };
 
-                // Castling
+        if tile.piece() == PieceType::King
+            && include_castling
+            && (castling_availability.kingside || castling_availability.queenside)
+            && !self.is_check(&tile.color()) {
+            // Castling is not possible if the king is in check.
 
-                let castling_availability = match tile.color() {
-                    Color::White => &self.white_castling,
-                    Color::Black => &self.black_castling,
-                };
+            if castling_availability.kingside {
+                self.try_castling(pos, &tile.color(), &mut moveset, 1);
+            }
+            if castling_availability.queenside {
+                self.try_castling(pos, &tile.color(), &mut moveset, -1);
+            }
+        }
 
-                if include_castling
-                    && (castling_availability.kingside || castling_availability.queenside)
-                    && !self.is_check(&tile.color()) {
-                    // Castling is not possible if the king is in check.
+        moveset
+    }
 
-                    if castling_availability.kingside {
-                        self.try_castling(&pos, &tile.color(), &mut moveset, 1);
-                    }
-                    if castling_availability.queenside {
-                        self.try_castling(&pos, &tile.color(), &mut moveset, -1);
-                    }
-                }
+    /// Get the moves that a piece of the color that moves by the pattern would
+    /// have from the position, with the other pieces on the board in their
+    /// current places. Castling is never included.
+    ///
+    /// This is how variants can find the moves of pieces that are not part of
+    /// standard chess, like an Amazon that moves like a queen and a knight. Like `get_pseudo_legal_moves`, the
+    /// moves may leave the king in check. Any piece on the position itself is
+    /// ignored.
+    ///
+    /// Offsets and directions that `MovementPattern::is_valid` rejects, like a
+    /// `(0, 0)` slide that would never leave the position, are ignored.
+    pub fn pattern_moves(&self, pos: &BoardPos, color: &Color, pattern: &MovementPattern) -> HashSet<BoardPos> {
+        let mut moveset = HashSet::new();
+
+        for &(delta_file, delta_rank) in pattern.leaps.iter().filter(|&&delta| is_valid_delta(delta)) {
+            if let Some((new_pos, _)) = self.try_move_once(pos, delta_file, delta_rank, color) {
+                moveset.insert(new_pos);
             }
-            PieceType::Pawn => {
-                // Calculate the forward direction for this team.
-                let dir: i8 = if tile.color() == Color::White { 1 } else { -1 };
+        }
+        for &(delta_file, delta_rank) in pattern.slides.iter().filter(|&&delta| is_valid_delta(delta)) {
+            self.try_move_multiple(&mut moveset, pos, color, delta_file, delta_rank);
+        }
+        if pattern.pawn {
+            self.try_pawn_moves(&mut moveset, pos, color);
+        }
+
+        moveset
+    }
+
+    /// Get the attacked squares that are not occupied by friendly pieces.
+    fn leaper_moves(&self, friendly_color: &Color, attacked: SquareSet) -> HashSet<BoardPos> {
+        // Moving onto a friendly piece is not possible, but capturing enemy pieces
+        // is fine.
+        attacked.into_iter()
+            .filter(|pos| self.board.get_tile(pos).is_none_or(|tile| tile.color() != *friendly_color))
+            .collect()
+    }
+
+    /// Add the moves of a pawn to the moveset.
+    fn try_pawn_moves(&self, moveset: &mut HashSet<BoardPos>, pos: &BoardPos, color: &Color) {
+        // Calculate the forward direction for this team.
+        let dir: i8 = if *color == Color::White { 1 } else { -1 };
 
-                // Note that ranks are specified in their internal form, aka the 0-indexed index.
-                let first_rank = if tile.color() == Color::White { 1 } else { 6 };
+        // Note that ranks are specified in their internal form, aka the 0-indexed index.
+        let first_rank = if *color == Color::White { 1 } else { 6 };
 
-                // Since pawns can never move backwards, we can be sure that it is the pawn's
-                // first move if it is located at the starting rank for pawns.
-                let is_first_move = pos.rank() == first_rank;
+        // Since pawns can never move backwards, we can be sure that it is the pawn's
+        // first move if it is located at the starting rank for pawns.
+        let is_first_move = pos.rank() == first_rank;
 
-                // Moving forward one tile is possible when it is an empty tile since pawns are
-                // not allowed to capture pieces forward.
-                self.try_specific_move(&mut moveset, &pos, &tile.color(),
+        // Moving forward one tile is possible when it is an empty tile since pawns are
+        // not allowed to capture pieces forward.
+        self.try_specific_move(moveset, pos, color,
+            MoveType::ToEmpty,
+            0, dir
+        );
+
+        // Moving two tiles is only possible if it is the pawn's first move and...
+        if is_first_move {
+            let pos_one_forward = pos.offset(0, dir);
+            let piece_one_forward = pos_one_forward.and_then(
+                |pos_one_forward| self.board.get_tile(&pos_one_forward)
+            );
+
+            // ...there is no piece, regardless of color, one tile forward.
+            if piece_one_forward.is_none() {
+                self.try_specific_move(moveset, pos, color,
                     MoveType::ToEmpty,
-                    0, dir
+                    0, 2 * dir
                 );
+            }
+        }
 
-                // Moving two tiles is only possible if it is the pawn's first move and...
-                if is_first_move {
-                    let pos_one_forward = pos.offset(0, dir);
-                    let piece_one_forward = pos_one_forward.and_then(
-                        |pos_one_forward| self.board.get_tile(&pos_one_forward)
-                    );
-
-                    // ...there is no piece, regardless of color, one tile forward.
-                    if piece_one_forward.is_none() {
-                        self.try_specific_move(&mut moveset, &pos, &tile.color(),
-                            MoveType::ToEmpty,
-                            0, 2 * dir
-                        );
-                    }
-                }
+        // Diagonal moves are only possible when attacking.
+        for attack_pos in attacks::pawn_attacks(pos, color) {
+            if self.board.get_tile(&attack_pos).is_some_and(|target| target.color() != *color) {
+                moveset.insert(attack_pos);
+            }
+        }
 
-                // Diagonal moves are only possible when attacking.
-                self.try_specific_move(&mut moveset, &pos, &tile.color(), MoveType::Attacking, -1, dir);
-                self.try_specific_move(&mut moveset, &pos, &tile.color(), MoveType::Attacking, 1, dir);
-
-                // En passant
-                if let Some(en_passant_target) = &self.en_passant_target {
-                    if en_passant_target.rank() as i8 == pos.rank() as i8 + dir && en_passant_target.file().abs_diff(pos.file()) == 1 {
-                        // There is an en passant target square forward-diagonally to this pawn.
-
-                        // The position of the pawn that will be attacked by this en passant.
-                        let attacked_pawn_pos = BoardPos::new(en_passant_target.file(), pos.rank());
-
-                        let attacked_pawn = self.board.get_tile(&attacked_pawn_pos);
-                        if let Some(attacked_pawn) = attacked_pawn {
-                            if attacked_pawn.piece() == PieceType::Pawn && attacked_pawn.color() != tile.color() {
-                                // There is an enemy pawn at the location. En passant is possible.
-                                moveset.insert(en_passant_target.clone());
-                            }                            
-                        }
+        // En passant
+        if let Some(en_passant_target) = &self.en_passant_target {
+            if en_passant_target.rank() as i8 == pos.rank() as i8 + dir && en_passant_target.file().abs_diff(pos.file()) == 1 {
+                // There is an en passant target square forward-diagonally to this pawn.
+
+                // The position of the pawn that will be attacked by this en passant.
+                let attacked_pawn_pos = BoardPos::new(en_passant_target.file(), pos.rank());
+
+                let attacked_pawn = self.board.get_tile(&attacked_pawn_pos);
+                if let Some(attacked_pawn) = attacked_pawn {
+                    if attacked_pawn.piece() == PieceType::Pawn && attacked_pawn.color() != *color {
+                        // There is an enemy pawn at the location. En passant is possible.
+                        moveset.insert(en_passant_target.clone());
                     }
                 }
-            },
-        }
-
-        moveset
-    }
-
-    /// Test the specified delta position moves and add the valid moves to the
-    /// moveset.
-    fn try_moves_once<const COUNT: usize>(&self,
-        moveset: &mut HashSet<BoardPos>,
-        start: &BoardPos,
-        friendly_color: &Color,
-        delta_positions: [(i8, i8); COUNT]
-    ) {
-        for (delta_file, delta_rank) in delta_positions {
-            let option_move = self.try_move_once(&start, delta_file, delta_rank, friendly_color);
-
-            if let Some((pos, _move_type)) = option_move {
-                moveset.insert(pos);
             }
         }
     }
@@ -459,10 +707,7 @@ impl Game {
         friendly_color: &Color
     ) -> Option<(BoardPos, MoveType)> {
         let pos = start.offset(delta_file, delta_rank);
-        let pos = match pos {
-            Some(pos) => pos,
-            None => return None,
-        };
+        let pos = pos?;
 
         let tile = self.board.get_tile(&pos);
         let tile = match tile {
@@ -498,21 +743,6 @@ impl Game {
         }
     }
 
-    /// Test the specified direction and add all possible moves to the moveset.
-    ///
-    /// The vectors array provides the directions that this method should try in a
-    /// repeated fashion until moving is no longer possible.
-    fn try_moves_multiple<const COUNT: usize>(&self,
-        moveset: &mut HashSet<BoardPos>,
-        start: &BoardPos,
-        friendly_color: &Color,
-        vectors: [(i8, i8); COUNT]
-    ) {
-        for (delta_file, delta_rank) in vectors {
-            self.try_move_multiple(moveset, start, friendly_color, delta_file, delta_rank);
-        }
-    }
-
     /// Test a direction and add all the possible moves to the moveset.
     fn try_move_multiple(&self,
         moveset: &mut HashSet<BoardPos>,
@@ -575,7 +805,7 @@ impl Game {
         }
     }
 
-    fn find_rook(&self, start: &BoardPos, color: &Color, dir: i8) -> Option<BoardPos> {
+    pub(super) fn find_rook(&self, start: &BoardPos, color: &Color, dir: i8) -> Option<BoardPos> {
         // Traverse until we find a rook
         let mut pos = (*start).clone();
         loop {
@@ -588,10 +818,7 @@ impl Game {
             }
 
             // A vacant slot, let's keep searching for a rook.
-            pos = match pos.offset(dir, 0) {
-                None => return None,
-                Some(new_pos) => new_pos,
-            };
+            pos = pos.offset(dir, 0)?;
         }
     }
 }
@@ -599,14 +826,25 @@ impl Game {
 
 #[cfg(test)]
 mod tests {
-    use crate::{board::{Tile, Board}, piece::PieceType, game::CastlingAvailability};
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use crate::{board::{Tile, Board}, piece::PieceType, game::{CastlingAvailability, MoveCache, StandardRules}};
     use super::*;
 
+    #[test]
+    fn perft_positions() {
+        assert_eq!(Game::new().perft(3), 8902);
+        let mut kiwipete = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(kiwipete.perft(2), 2039);
+        let mut promotions = Game::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap();
+        assert_eq!(promotions.perft(2), 496);
+    }
+
     #[test]
     fn move_piece() {
         let mut game = Game::new();
         game.move_piece(&"e2".parse().unwrap(), &"e4".parse().unwrap()).unwrap();
-        assert_eq!("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 1 1", game.to_fen());
+        assert_eq!("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", game.to_fen());
     }
 
     /// Prepare a game for a moveset test.
@@ -623,19 +861,32 @@ mod tests {
     fn prepare_moveset_test_at(piece: PieceType, pos: &BoardPos) -> Game {
         const COLOR: Color = Color::White;
 
-        let mut board = Board::empty();
-        let tile = Tile::new(piece, COLOR);
-        board.set_tile(&pos, tile);
+        let board: Board = [(pos.clone(), Tile::new(piece, COLOR))].into_iter().collect();
+        game_with_board(board)
+    }
 
+    /// Create a game with white to move and no castling or en passant.
+    fn game_with_board(board: Board) -> Game {
         Game {
             board,
-            current_turn: COLOR,
+            current_turn: Color::White,
             white_castling: CastlingAvailability { kingside: false, queenside: false },
             black_castling: CastlingAvailability { kingside: false, queenside: false },
             en_passant_target: None,
             promotion_required: None,
             halfmove_clock: 0,
             fullmove_number: 0,
+            starting_fen: String::new(),
+            history: Vec::new(),
+            undo_stack: Vec::new(),
+            position_keys: Vec::new(),
+            last_irreversible_ply: 0,
+            rules: Arc::new(StandardRules),
+            outcome: None,
+            adjudication_note: None,
+            annotations: BTreeMap::new(),
+            generation: 0,
+            move_cache: MoveCache::default(),
         }
     }
 
@@ -651,7 +902,7 @@ mod tests {
 
     /// Assert that the expected moves exist in the move set. There may be other
     /// moves in the actual moveset.
-    fn assert_moves_exist(actual: &HashSet<BoardPos>, expected: &str) {
+    fn assert_moves_exist(game: &Game, pos: &BoardPos, actual: &HashSet<BoardPos>, expected: &str) {
         let mut missing = HashSet::new();
         for str in expected.split_whitespace() {
             let pos = str.parse().unwrap();
@@ -659,11 +910,14 @@ mod tests {
                 missing.insert(pos);
             }
         }
-        assert!(missing.is_empty(), "Expected {} to be valid moves.", format_positions(&missing));
+        assert!(missing.is_empty(), "Expected {} to be valid moves.\n\n{}\n",
+            format_positions(&missing),
+            game.render_moveset(pos, actual),
+        );
     }
 
     /// Assert that the moves dont exist in the move set.
-    fn assert_moves_dont_exist(actual: &HashSet<BoardPos>, unexpected: &str) {
+    fn assert_moves_dont_exist(game: &Game, pos: &BoardPos, actual: &HashSet<BoardPos>, unexpected: &str) {
         let mut existing = HashSet::new();
         for str in unexpected.split_whitespace() {
             let pos = str.parse().unwrap();
@@ -671,20 +925,28 @@ mod tests {
                 existing.insert(pos);
             }
         }
-        assert!(existing.is_empty(), "Expected {} to be invalid moves.", format_positions(&existing));
+        assert!(existing.is_empty(), "Expected {} to be invalid moves.\n\n{}\n",
+            format_positions(&existing),
+            game.render_moveset(pos, actual),
+        );
     }
 
     /// Assert that the moveset matches exactly the specified moves and no
     /// other moves.
-    fn assert_moves(actual: &HashSet<BoardPos>, expected: &str) {
+    ///
+    /// The failure message shows the expected and actual moves of the piece at
+    /// `pos` on the board.
+    fn assert_moves(game: &Game, pos: &BoardPos, actual: &HashSet<BoardPos>, expected: &str) {
         let expected_set: HashSet<BoardPos> = expected
             .split_whitespace()
             .map(|str| str.parse().unwrap())
             .collect();
         assert_eq!(actual, &expected_set,
-            "\n\nexpected {}\n   found {}\n",
+            "\n\nexpected {}\n{}\n\n   found {}\n{}\n",
             format_positions(&expected_set),
+            game.render_moveset(pos, &expected_set),
             format_positions(actual),
+            game.render_moveset(pos, actual),
         );
     }
 
@@ -694,15 +956,15 @@ mod tests {
     fn king_moves() {
         let (mut game, pos) = prepare_moveset_test(PieceType::King);
         let actual = game.get_legal_moves(&pos).unwrap();
-        assert_moves(&actual, "d5 e5 f5 d4 f4 d3 e3 f3");
+        assert_moves(&game, &pos, &actual, "d5 e5 f5 d4 f4 d3 e3 f3");
     }
 
     #[test]
     fn queen_moves() {
         let (mut game, pos) = prepare_moveset_test(PieceType::Queen);
         let actual = game.get_legal_moves(&pos).unwrap();
-        assert_moves_exist(&actual, "f5 f4 h7 b4");
-        assert_moves_dont_exist(&actual, "e4 d2 f6");
+        assert_moves_exist(&game, &pos, &actual, "f5 f4 h7 b4");
+        assert_moves_dont_exist(&game, &pos, &actual, "e4 d2 f6");
     }
 
     #[test]
@@ -714,15 +976,15 @@ mod tests {
         game.board.set_tile(&pos2, enemy);
 
         let actual = game.get_legal_moves(&pos).unwrap();
-        assert_moves_exist(&actual, "d4 c4 e5 e7 h4 e3");
-        assert_moves_dont_exist(&actual, "e4 f5 d2 d7 b4 a4");
+        assert_moves_exist(&game, &pos, &actual, "d4 c4 e5 e7 h4 e3");
+        assert_moves_dont_exist(&game, &pos, &actual, "e4 f5 d2 d7 b4 a4");
     }
 
     #[test]
     fn knight_moves() {
         let (mut game, pos) = prepare_moveset_test(PieceType::Knight);
         let actual = game.get_legal_moves(&pos).unwrap();
-        assert_moves(&actual, "d6 f6 g5 g3 f2 d2 c3 c5");
+        assert_moves(&game, &pos, &actual, "d6 f6 g5 g3 f2 d2 c3 c5");
     }
 
     #[test]
@@ -730,39 +992,46 @@ mod tests {
         let pos = "e2".parse().unwrap();
         let mut game = prepare_moveset_test_at(PieceType::Pawn, &pos);
         let actual = game.get_legal_moves(&pos).unwrap();
-        assert_moves(&actual, "e3 e4");
+        assert_moves(&game, &pos, &actual, "e3 e4");
     }
 
     #[test]
     fn moved_pawn_moves() {
         let (mut game, pos) = prepare_moveset_test(PieceType::Pawn);
         let actual = game.get_legal_moves(&pos).unwrap();
-        assert_moves(&actual, "e5");
+        assert_moves(&game, &pos, &actual, "e5");
     }
 
     #[test]
     fn attacking_pawn_moves() {
-        let (mut game, pos) = prepare_moveset_test(PieceType::Pawn);
-        
-        let enemy = Tile::new(PieceType::Pawn, Color::Black);
-        game.board.set_tile(&"d5".parse().unwrap(), enemy);
-        
+        let board: Board = [
+            ("e4", PieceType::Pawn, Color::White),
+            ("d5", PieceType::Pawn, Color::Black),
+        ]
+            .into_iter()
+            .map(|(pos, piece, color)| (pos.parse().unwrap(), Tile::new(piece, color)))
+            .collect();
+        let mut game = game_with_board(board);
+
+        let pos = "e4".parse().unwrap();
         let actual = game.get_legal_moves(&pos).unwrap();
-        assert_moves(&actual, "d5 e5");
+        assert_moves(&game, &pos, &actual, "d5 e5");
     }
 
     #[test]
     fn non_attacking_pawn_moves() {
         let mut game = Game::from_fen("4k3/8/8/8/4p3/3ppp2/4P3/4K3 w - - 0 1").unwrap();
-        let actual = game.get_legal_moves(&"e2".parse().unwrap()).unwrap();
-        assert_moves(&actual, "d3 f3");
+        let pos = "e2".parse().unwrap();
+        let actual = game.get_legal_moves(&pos).unwrap();
+        assert_moves(&game, &pos, &actual, "d3 f3");
     }
 
     #[test]
     fn non_attacking_double_pawn_moves() {
         let mut game = Game::from_fen("4k3/8/8/8/4p3/3p1p2/4P3/4K3 w - - 0 1").unwrap();
-        let actual = game.get_legal_moves(&"e2".parse().unwrap()).unwrap();
-        assert_moves(&actual, "d3 e3 f3");
+        let pos = "e2".parse().unwrap();
+        let actual = game.get_legal_moves(&pos).unwrap();
+        assert_moves(&game, &pos, &actual, "d3 e3 f3");
     }
 
     // Movement tests including check
@@ -776,7 +1045,7 @@ mod tests {
 
         // It is not legal to move the rook so that it unblocks the black rook's
         // attacking path to the white king, which would result in a state of check.
-        assert_moves(&moves, "e7 e6 e5 e3");
+        assert_moves(&game, &pos, &moves, "e7 e6 e5 e3");
     }
 
     #[test]
@@ -789,8 +1058,8 @@ mod tests {
         game.current_turn = Color::Black;
         let black_moves = game.get_legal_moves(&black_king_pos).unwrap();
 
-        assert_moves(&white_moves, "c1 d1 d2 e2 f2 f1 g1");
-        assert_moves(&black_moves, "c8 d8 d7 e7 f7 f8 g8");
+        assert_moves(&game, &white_king_pos, &white_moves, "c1 d1 d2 e2 f2 f1 g1");
+        assert_moves(&game, &black_king_pos, &black_moves, "c8 d8 d7 e7 f7 f8 g8");
     }
 
     #[test]
@@ -802,7 +1071,7 @@ mod tests {
         game.move_piece(&"a1".parse().unwrap(), &"a2".parse().unwrap()).unwrap();
         game.move_piece(&"h8".parse().unwrap(), &"h7".parse().unwrap()).unwrap();
 
-        assert_eq!(game.to_fen(), "rnbqkbn1/pppppppr/7p/8/8/P7/RPPPPPPP/1NBQKBNR w Kq - 4 3");
+        assert_eq!(game.to_fen(), "rnbqkbn1/pppppppr/7p/8/8/P7/RPPPPPPP/1NBQKBNR w Kq - 2 3");
     }
 
     #[test]
@@ -812,7 +1081,19 @@ mod tests {
         let white_king_pos = "e1".parse().unwrap();
         let white_moves = game.get_legal_moves(&white_king_pos).unwrap();
 
-        assert_moves(&white_moves, "");
+        assert_moves(&game, &white_king_pos, &white_moves, "");
+    }
+
+    #[test]
+    fn castling_through_pawn_attack() {
+        // The pawn on g2 attacks f1 although there is nothing to capture there.
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/6p1/4K2R w K - 0 1").unwrap();
+
+        let white_king_pos = "e1".parse().unwrap();
+        let white_moves = game.get_legal_moves(&white_king_pos).unwrap();
+
+        assert!(!white_moves.contains(&"g1".parse().unwrap()));
+        assert!(!white_moves.contains(&"f1".parse().unwrap()));
     }
 
     #[test]
@@ -825,14 +1106,29 @@ mod tests {
         assert_eq!(game.to_fen(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
     }
 
+    #[test]
+    fn capturing_rook_removes_castling() {
+        let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        game.move_piece(&"h1".parse().unwrap(), &"h8".parse().unwrap()).unwrap();
+
+        let fen = "r3k2R/8/8/8/8/8/8/R3K3 b Qq - 0 1";
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.repetition_key(), Game::from_fen(fen).unwrap().repetition_key());
+
+        game.undo().unwrap();
+        assert_eq!(game.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    }
+
     #[test]
     fn en_passant() {
         let mut game = Game::from_fen("4k3/8/8/8/2p5/8/1P6/4K3 w - - 0 1").unwrap();
 
         game.move_piece(&"b2".parse().unwrap(), &"b4".parse().unwrap()).unwrap();
 
-        let moves = game.get_legal_moves(&"c4".parse().unwrap()).unwrap();
-        assert_moves(&moves, "c3 b3");
+        let pos = "c4".parse().unwrap();
+        let moves = game.get_legal_moves(&pos).unwrap();
+        assert_moves(&game, &pos, &moves, "c3 b3");
 
         game.move_piece(&"c4".parse().unwrap(), &"b3".parse().unwrap()).unwrap();
 
@@ -845,17 +1141,18 @@ mod tests {
 
         game.move_piece(&"d2".parse().unwrap(), &"d4".parse().unwrap()).unwrap();
 
-        let moves = game.get_legal_moves(&"e4".parse().unwrap()).unwrap();
+        let pos = "e4".parse().unwrap();
+        let moves = game.get_legal_moves(&pos).unwrap();
 
         // Taking en passant, which unblocks the rook to attack the king. This move is illegal.
-        assert_moves_dont_exist(&moves, "d3");
+        assert_moves_dont_exist(&game, &pos, &moves, "d3");
     }
 
     #[test]
     fn undoing_performed_castling() {
         let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
 
-        let performed_move = game.perform_move(&"e1".parse().unwrap(), &"c1".parse().unwrap());
+        let performed_move = game.perform_move(&"e1".parse().unwrap(), &"c1".parse().unwrap(), None);
         game.undo_performed_move(performed_move);
 
         assert_eq!(game.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
@@ -865,7 +1162,7 @@ mod tests {
     fn undoing_performed_en_passant() {
         let mut game = Game::from_fen("4k3/8/8/1pP5/8/8/8/4K3 w - b6 0 1").unwrap();
 
-        let performed_move = game.perform_move(&"c5".parse().unwrap(), &"b6".parse().unwrap());
+        let performed_move = game.perform_move(&"c5".parse().unwrap(), &"b6".parse().unwrap(), None);
         game.undo_performed_move(performed_move);
 
         assert_eq!(game.to_fen(), "4k3/8/8/1pP5/8/8/8/4K3 w - b6 0 1");
@@ -879,14 +1176,17 @@ mod tests {
 
         // When there is check, only moves that make the game exit check are legal.
 
-        let moves1 = game.get_legal_moves(&"d2".parse().unwrap()).unwrap();
-        assert_moves(&moves1, "d3");
+        let pos1 = "d2".parse().unwrap();
+        let moves1 = game.get_legal_moves(&pos1).unwrap();
+        assert_moves(&game, &pos1, &moves1, "d3");
 
-        let moves2 = game.get_legal_moves(&"e2".parse().unwrap()).unwrap();
-        assert_moves(&moves2, "e3 f3 f2 e1 d1");
+        let pos2 = "e2".parse().unwrap();
+        let moves2 = game.get_legal_moves(&pos2).unwrap();
+        assert_moves(&game, &pos2, &moves2, "e3 f3 f2 e1 d1");
 
         // Moving this pawn is usually legal, but it does not help the check situation.
-        let moves3 = game.get_legal_moves(&"h2".parse().unwrap()).unwrap();
-        assert_moves(&moves3, "");
+        let pos3 = "h2".parse().unwrap();
+        let moves3 = game.get_legal_moves(&pos3).unwrap();
+        assert_moves(&game, &pos3, &moves3, "");
     }
 }
\ No newline at end of file
@@ -0,0 +1,213 @@
+use std::{fmt, sync::Arc};
+
+use crate::{board::Color, pos::BoardPos};
+
+use super::Game;
+
+/// How a finished game ended.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The team whose turn it is was checkmated. The color is the team that won.
+    Checkmate { winner: Color },
+    /// The team whose turn it is has no legal moves but is not in check.
+    Stalemate,
+    /// The game was ended by an arbiter, see `Game::adjudicate`. The color is the
+    /// team that won, or `None` for a draw.
+    Adjudicated { winner: Option<Color> },
+    /// The game was ended by a rule of its variant, see `Rules::outcome`. The
+    /// color is the team that won, or `None` for a draw.
+    VariantEnd { winner: Option<Color> },
+}
+
+impl Outcome {
+    /// Get the team that won, or `None` if the game was drawn.
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Self::Checkmate { winner } => Some(*winner),
+            Self::Stalemate => None,
+            Self::Adjudicated { winner } | Self::VariantEnd { winner } => *winner,
+        }
+    }
+}
+
+/// Errors returned when adjudicating a game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AdjudicateError {
+    /// The game has already ended.
+    GameOver,
+    /// A pawn is waiting to be promoted, see `Game::promote`.
+    PromotionRequired,
+    /// The outcome is not `Outcome::Adjudicated`.
+    InvalidOutcome,
+}
+
+impl fmt::Display for AdjudicateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::GameOver => write!(f, "The game is over."),
+            Self::PromotionRequired => write!(f, "A pawn must be promoted first."),
+            Self::InvalidOutcome => write!(f, "Only an adjudicated outcome can be used."),
+        }
+    }
+}
+
+impl std::error::Error for AdjudicateError {}
+
+impl Game {
+
+    /// Get how the game ended, or `None` if the game is still in progress.
+    ///
+    /// Once the game has ended, no more moves can be made.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Whether the game has ended, see `outcome`.
+    pub fn is_game_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// End the game with a result decided by an arbiter, for example to stop an
+    /// engine game in a position that is clearly won or drawn.
+    ///
+    /// The outcome must be `Outcome::Adjudicated` with the winner, or `None` for
+    /// a draw. The note explains the decision and can be read with
+    /// `adjudication_note`. No more moves can be made afterwards.
+    ///
+    /// # Errors
+    /// `GameOver` is returned if the game has already ended, `PromotionRequired`
+    /// if a pawn is waiting to be promoted and `InvalidOutcome` if the outcome is
+    /// not `Outcome::Adjudicated`.
+    pub fn adjudicate(&mut self, outcome: Outcome, note: &str) -> Result<(), AdjudicateError> {
+        if self.outcome.is_some() {
+            return Err(AdjudicateError::GameOver);
+        }
+        if self.promotion_required.is_some() {
+            return Err(AdjudicateError::PromotionRequired);
+        }
+        if !matches!(outcome, Outcome::Adjudicated { .. }) {
+            return Err(AdjudicateError::InvalidOutcome);
+        }
+        self.outcome = Some(outcome);
+        self.adjudication_note = Some(note.to_string());
+        Ok(())
+    }
+
+    /// Get the note given when the game was adjudicated, or `None` if the game
+    /// was not adjudicated.
+    pub fn adjudication_note(&self) -> Option<&str> {
+        self.adjudication_note.as_deref()
+    }
+
+    /// Decide whether the game has ended after the last move.
+    ///
+    /// This must be called whenever the position changes, except while a promotion
+    /// is pending since the promoted piece can change the outcome.
+    pub(super) fn update_outcome(&mut self) {
+        let color = self.current_turn;
+        let rules = Arc::clone(&self.rules);
+        self.outcome = if let Some(outcome) = rules.outcome(self) {
+            Some(outcome)
+        } else if self.has_legal_moves() || self.is_kingless() {
+            None
+        } else if self.is_check(&color) {
+            Some(Outcome::Checkmate { winner: color.opposite() })
+        } else {
+            Some(Outcome::Stalemate)
+        };
+    }
+
+    /// Whether the team whose turn it is has at least one legal move.
+    pub(super) fn has_legal_moves(&mut self) -> bool {
+        if let Some(has_legal_moves) = self.cached_has_legal_moves() {
+            return has_legal_moves;
+        }
+        let has_legal_moves = self.find_legal_move();
+        self.cache_has_legal_moves(has_legal_moves);
+        has_legal_moves
+    }
+
+    fn find_legal_move(&mut self) -> bool {
+        let color = self.current_turn;
+        for file in 0..8 {
+            for rank in 0..8 {
+                let pos = BoardPos::new(file, rank);
+                let is_friendly = self.board.get_tile(&pos).is_some_and(|tile| tile.color() == color);
+                if is_friendly && !self.get_legal_moves_unchecked(&pos).is_empty() {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{GameState, GetMovesetError, MovePieceError};
+    use crate::piece::PieceType;
+
+    use super::*;
+
+    #[test]
+    fn no_moves_after_checkmate() {
+        let mut game = Game::from_fen("8/8/8/5K1k/8/8/8/6R1 w - - 0 1").unwrap();
+        assert_eq!(game.outcome(), None);
+
+        game.move_piece(&"g1".parse().unwrap(), &"h1".parse().unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "8/8/8/5K1k/8/8/8/7R b - - 1 1");
+        assert_eq!(game.outcome(), Some(Outcome::Checkmate { winner: Color::White }));
+        assert!(game.is_game_over());
+
+        assert!(matches!(game.move_piece(&"h5".parse().unwrap(), &"h4".parse().unwrap()), Err(MovePieceError::GameOver)));
+        assert!(matches!(game.get_legal_moves(&"h5".parse().unwrap()), Err(GetMovesetError::GameOver)));
+        assert!(matches!(game.move_piece(&"h1".parse().unwrap(), &"h2".parse().unwrap()), Err(MovePieceError::GameOver)));
+
+        // The result is still reported after the rejected moves.
+        assert_eq!(game.get_state(), GameState::Checkmate(Color::Black));
+        assert_eq!(game.outcome().unwrap().winner(), Some(Color::White));
+        assert_eq!(game.current_turn(), Color::Black);
+    }
+
+    #[test]
+    fn stalemate() {
+        let mut game = Game::from_fen("7k/8/5Q1K/8/8/8/8/8 w - - 0 1").unwrap();
+        game.move_piece(&"f6".parse().unwrap(), &"g6".parse().unwrap()).unwrap();
+
+        assert_eq!(game.outcome(), Some(Outcome::Stalemate));
+        assert_eq!(Outcome::Stalemate.winner(), None);
+    }
+
+    #[test]
+    fn promotion_decides_outcome() {
+        let mut game = Game::from_fen("k7/2P5/1K6/8/8/8/8/8 w - - 0 1").unwrap();
+        game.move_piece(&"c7".parse().unwrap(), &"c8".parse().unwrap()).unwrap();
+        assert_eq!(game.outcome(), None);
+
+        game.promote(PieceType::Queen);
+        assert_eq!(game.outcome(), Some(Outcome::Checkmate { winner: Color::White }));
+    }
+
+    #[test]
+    fn adjudicate_draw() {
+        let mut game = Game::from_fen("4k3/8/8/3p4/3P4/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.adjudicate(Outcome::Stalemate, "Blocked"), Err(AdjudicateError::InvalidOutcome));
+        assert_eq!(game.adjudication_note(), None);
+
+        game.adjudicate(Outcome::Adjudicated { winner: None }, "Dead position").unwrap();
+        assert_eq!(game.outcome(), Some(Outcome::Adjudicated { winner: None }));
+        assert_eq!(game.adjudication_note(), Some("Dead position"));
+        assert!(matches!(game.move_piece(&"e1".parse().unwrap(), &"e2".parse().unwrap()), Err(MovePieceError::GameOver)));
+
+        let white_wins = Outcome::Adjudicated { winner: Some(Color::White) };
+        assert_eq!(game.adjudicate(white_wins, "Changed my mind"), Err(AdjudicateError::GameOver));
+        assert_eq!(game.outcome().unwrap().winner(), None);
+    }
+
+    #[test]
+    fn finished_position_from_fen() {
+        let game = Game::from_fen("8/8/8/5K1k/8/8/8/7R b - - 0 1").unwrap();
+        assert!(game.is_game_over());
+    }
+}
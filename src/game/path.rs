@@ -0,0 +1,108 @@
+use crate::{pos::BoardPos, piece::PieceType};
+
+use super::Game;
+
+/// The squares that pieces travel across during a move, for example to animate
+/// the move in a GUI.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MovePath {
+    /// A single piece moves. The path starts at the position the piece moves from
+    /// and ends at the position it moves to.
+    ///
+    /// Knights jump over the squares in between, so their path is empty.
+    Piece(Vec<BoardPos>),
+    /// The king castles, which moves both the king and the rook.
+    Castling {
+        king: Vec<BoardPos>,
+        rook: Vec<BoardPos>,
+    },
+}
+
+impl Game {
+
+    /// Get the ordered squares that the piece at `from` travels across when moving
+    /// to `to`.
+    ///
+    /// This method does not validate that the move is legal, it is meant to be used
+    /// with moves from `get_legal_moves` before they are performed.
+    ///
+    /// Returns `None` if there is no piece at `from`, or if the piece can not
+    /// travel between the positions in a straight line.
+    pub fn move_path(&self, from: &BoardPos, to: &BoardPos) -> Option<MovePath> {
+        let tile = self.board.get_tile(from)?;
+
+        if tile.piece() == PieceType::Knight {
+            return Some(MovePath::Piece(Vec::new()));
+        }
+
+        let path = Self::straight_path(from, to)?;
+
+        if tile.piece() == PieceType::King && from.rank() == to.rank() && from.file().abs_diff(to.file()) == 2 {
+            // The king moved two tiles. This means we are castling.
+            let dir = if to.file() > from.file() { 1 } else { -1 };
+            let new_rook_pos = from.offset(dir, 0)?;
+            let rook_pos = self.find_rook(&new_rook_pos, &tile.color(), dir)?;
+
+            return Some(MovePath::Castling {
+                king: path,
+                rook: Self::straight_path(&rook_pos, &new_rook_pos)?,
+            });
+        }
+
+        Some(MovePath::Piece(path))
+    }
+
+    /// Get the positions from `from` to `to` (inclusive) along a rank, file or
+    /// diagonal.
+    fn straight_path(from: &BoardPos, to: &BoardPos) -> Option<Vec<BoardPos>> {
+        let mut path = vec![from.clone()];
+        path.extend(from.squares_between(to)?);
+        path.push(to.clone());
+        Some(path)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(str: &str) -> Vec<BoardPos> {
+        str.split_whitespace().map(|str| str.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn bishop_path() {
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/B3K3 w - - 0 1").unwrap();
+        let path = game.move_path(&"a1".parse().unwrap(), &"h8".parse().unwrap());
+
+        assert_eq!(path, Some(MovePath::Piece(positions("a1 b2 c3 d4 e5 f6 g7 h8"))));
+    }
+
+    #[test]
+    fn knight_path() {
+        let game = Game::new();
+        let path = game.move_path(&"g1".parse().unwrap(), &"f3".parse().unwrap());
+
+        assert_eq!(path, Some(MovePath::Piece(Vec::new())));
+    }
+
+    #[test]
+    fn castling_path() {
+        let game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let path = game.move_path(&"e1".parse().unwrap(), &"g1".parse().unwrap());
+
+        assert_eq!(path, Some(MovePath::Castling {
+            king: positions("e1 f1 g1"),
+            rook: positions("h1 g1 f1"),
+        }));
+    }
+
+    #[test]
+    fn no_path() {
+        let game = Game::new();
+
+        assert_eq!(game.move_path(&"e4".parse().unwrap(), &"e5".parse().unwrap()), None);
+        assert_eq!(game.move_path(&"a1".parse().unwrap(), &"b3".parse().unwrap()), None);
+    }
+}
@@ -0,0 +1,86 @@
+use super::{Game, Move, MoveList};
+
+impl Game {
+
+    /// Count the positions at the end of every sequence of legal moves of the
+    /// depth, which is the standard way of checking a move generator against
+    /// published numbers.
+    ///
+    /// Promotions count once for each piece. Moves are made and undone on this
+    /// game instead of copying it, and the game is the same afterwards. The game
+    /// does not end during the search, so repetitions and the fifty-move rule do
+    /// not cut it short, but checkmate and stalemate leave no moves to count.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut moves = MoveList::new();
+        self.legal_moves_into(&mut moves);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for m in &moves {
+            let record = self.play_unchecked(m);
+            nodes += self.perft(depth - 1);
+            self.restore_undo_record(record);
+        }
+        nodes
+    }
+
+    /// Count the positions of `perft` after each legal move, for finding the move
+    /// that a move generator gets wrong by comparing with another program.
+    ///
+    /// The moves are in the order of `legal_moves`, and the counts add up to
+    /// `perft(depth)`. The list is empty if the depth is 0.
+    pub fn perft_divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+        let mut divide = Vec::new();
+        for m in self.legal_moves() {
+            let record = self.play_unchecked(&m);
+            let nodes = self.perft(depth - 1);
+            self.restore_undo_record(record);
+            divide.push((m, nodes));
+        }
+        divide
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starting_position() {
+        let mut game = Game::new();
+        let counts: Vec<u64> = (0..=4).map(|depth| game.perft(depth)).collect();
+        assert_eq!(counts, [1, 20, 400, 8902, 197281]);
+        assert_eq!(game.to_fen(), Game::new().to_fen());
+    }
+
+    #[test]
+    fn kiwipete() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut game = Game::from_fen(fen).unwrap();
+        assert_eq!(game.perft(3), 97862);
+        assert_eq!(game.to_fen(), fen);
+
+        let divide = game.perft_divide(2);
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2039);
+        let castling = divide.iter().find(|(m, _)| m.to_string() == "e1g1").unwrap();
+        assert_eq!(castling.1, 43);
+    }
+
+    #[test]
+    fn promotions() {
+        let fen = "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1";
+        let mut game = Game::from_fen(fen).unwrap();
+        assert_eq!(game.perft(3), 9483);
+        assert_eq!(game.to_fen(), fen);
+    }
+}
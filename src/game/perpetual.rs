@@ -0,0 +1,110 @@
+use super::{Game, Move};
+
+impl Game {
+
+    /// Find a perpetual check, a sequence of checks by the team to move that the
+    /// opponent can not escape from without repeating a position.
+    ///
+    /// Only checking moves, see `checking_moves`, are tried for the team to move,
+    /// while every legal reply is tried for the opponent. A perpetual is found if
+    /// every reply leads to a position that has occurred before, counting the
+    /// positions of the game before the search, within `max_plies` half-moves.
+    /// Checks that checkmate also succeed, since the opponent has no reply.
+    ///
+    /// The line is returned starting with the first check, following the reply
+    /// that delays the repetition the longest. `None` is returned if the checks
+    /// run out or the repetition can not be forced within `max_plies`.
+    pub fn find_perpetual(&self, max_plies: usize) -> Option<Vec<Move>> {
+        if self.outcome.is_some() || self.promotion_required.is_some() {
+            return None;
+        }
+        self.perpetual_checks(max_plies)
+    }
+
+    /// Find a check after which the opponent can not avoid a repetition.
+    fn perpetual_checks(&self, max_plies: usize) -> Option<Vec<Move>> {
+        if max_plies == 0 {
+            return None;
+        }
+
+        let mut checks = self.clone().checking_moves();
+        checks.sort_by_key(Move::to_string);
+        checks.into_iter().find_map(|check| {
+            let mut game = self.clone();
+            game.make_move(&check).expect("Checking moves are legal.");
+            let mut line = game.perpetual_replies(max_plies - 1)?;
+            line.insert(0, check);
+            Some(line)
+        })
+    }
+
+    /// Check that every reply to a check repeats a position or allows another
+    /// perpetual check, returning the longest line.
+    fn perpetual_replies(&self, max_plies: usize) -> Option<Vec<Move>> {
+        if self.repetition_count() > 1 {
+            return Some(Vec::new());
+        }
+        if self.outcome.is_some() {
+            // Only checkmate can end the game after a check.
+            return Some(Vec::new());
+        }
+        if max_plies == 0 {
+            return None;
+        }
+
+        let mut replies = self.clone().legal_moves();
+        replies.sort_by_key(Move::to_string);
+        let mut longest: Option<Vec<Move>> = None;
+        for reply in replies {
+            let mut game = self.clone();
+            game.make_move(&reply).expect("Legal moves are legal.");
+            let mut line = if game.repetition_count() > 1 {
+                Vec::new()
+            } else {
+                game.perpetual_checks(max_plies - 1)?
+            };
+            line.insert(0, reply);
+            if longest.as_ref().is_none_or(|longest| line.len() > longest.len()) {
+                longest = Some(line);
+            }
+        }
+        longest
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(fen: &str, max_plies: usize) -> Option<Vec<String>> {
+        let game = Game::from_fen(fen).unwrap();
+        game.find_perpetual(max_plies).map(|line| line.iter().map(Move::to_string).collect())
+    }
+
+    #[test]
+    fn queen_perpetual() {
+        // Qh5+ Kg8 Qe8+ Kh7 repeats, and the black king can not get away.
+        let fen = "8/6pk/8/8/8/8/8/K2Q4 w - - 0 1";
+        let found = line(fen, 10).unwrap();
+        assert_eq!(found, ["d1h5", "h7g8", "h5e8", "g8h7", "e8h5"]);
+
+        let mut game = Game::from_fen(fen).unwrap();
+        for (ply, m) in found.iter().enumerate() {
+            let m = m.parse().unwrap();
+            if ply % 2 == 0 {
+                assert!(game.gives_check(&m));
+            }
+            game.make_move(&m).unwrap();
+        }
+        assert!(game.repetition_count() > 1);
+    }
+
+    #[test]
+    fn checks_run_out() {
+        // The king escapes to the center after a few checks.
+        assert_eq!(line("8/8/8/4k3/8/8/8/Q5K1 w - - 0 1", 8), None);
+        // There are no checks at all.
+        assert_eq!(line("4k3/8/8/8/8/8/8/4K3 w - - 0 1", 8), None);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::pgn::{PgnError, PgnGame, PgnTags, PgnWriter};
+
+use super::Game;
+
+impl Game {
+
+    /// Write the game as a PGN document with the tags, for example to save a
+    /// finished game or send it to a game database.
+    ///
+    /// The Seven Tag Roster is written first, then the other tags and the moves
+    /// in Standard Algebraic Notation with move numbers, wrapped to fit in 80
+    /// columns. The result token is `1-0`, `0-1` or `1/2-1/2` if the game is over,
+    /// see `get_state` and `outcome`, and `*` if it is still going on. Everything
+    /// else is written like `PgnGame::from_game`, including annotations and a
+    /// custom starting position.
+    pub fn to_pgn(&self, tags: &PgnTags) -> String {
+        let mut pgn = PgnGame::from_game(self);
+        let tags = PgnTags {
+            other: tags.other.iter()
+                .filter(|(name, _)| !matches!(name.as_str(), "FEN" | "SetUp" | "Variant" | "Result"))
+                .cloned()
+                .collect(),
+            ..tags.clone()
+        };
+        pgn.set_tags(&tags).expect("Tags that change the starting position are left out.");
+
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_game(&pgn).expect("Writing to a Vec does not fail.");
+        String::from_utf8(writer.into_inner()).expect("PGN is written as UTF-8.")
+    }
+
+    /// Load a game from a PGN document, for example to analyze its positions.
+    ///
+    /// The moves are played from the starting position, or from the position in
+    /// the `FEN` tag if there is one, and are in `history`. Comments, NAGs and
+    /// variations are ignored, use `PgnGame::parse` to read them.
+    ///
+    /// # Errors
+    /// The errors of `PgnGame::parse` are returned. An illegal move returns
+    /// `IllegalMove` with the ply of the move, counting from 1.
+    pub fn from_pgn(pgn: &str) -> Result<Game, PgnError> {
+        let pgn = PgnGame::parse(pgn)?;
+        let mut game = pgn.starting_position()?;
+        for m in pgn.moves() {
+            game.make_move(m.get()).expect("Moves were validated when parsing.");
+        }
+        Ok(game)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{board::Color, pgn::PgnGame, piece::PieceType, time_control::TimeControl};
+
+    use super::*;
+
+    #[test]
+    fn scholars_mate() {
+        let mut game = Game::new();
+        for (from, to) in [("e2", "e4"), ("e7", "e5"), ("f1", "c4"), ("b8", "c6"), ("d1", "h5"), ("g8", "f6"), ("h5", "f7")] {
+            game.move_piece(&from.parse().unwrap(), &to.parse().unwrap()).unwrap();
+        }
+        let tags = PgnTags {
+            event: Some("Casual game".to_string()),
+            date: Some("2024.03.15".to_string()),
+            white: Some("Anna".to_string()),
+            black: Some("Bert".to_string()),
+            time_control: Some("300+3".parse().unwrap()),
+            ..PgnTags::default()
+        };
+
+        assert_eq!(game.to_pgn(&tags), "[Event \"Casual game\"]\n[Site \"?\"]\n[Date \"2024.03.15\"]\n[Round \"?\"]\n\
+            [White \"Anna\"]\n[Black \"Bert\"]\n[Result \"1-0\"]\n[TimeControl \"300+3\"]\n\n\
+            1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0\n\n");
+        let parsed = PgnGame::parse(&game.to_pgn(&tags)).unwrap();
+        assert_eq!(parsed.time_control(), Some(TimeControl::with_increment(Duration::from_secs(300), Duration::from_secs(3))));
+    }
+
+    #[test]
+    fn from_pgn() {
+        let pgn = "[Event \"Casual game\"]\n[SetUp \"1\"]\n[FEN \"r3k2r/1P6/8/8/8/8/6p1/R3K2R w KQkq - 0 1\"]\n\n\
+            1. O-O-O {Safety first} (1. bxa8=Q+ Ke7) gxh1=N $2 2. bxa8=Q+ *\n";
+        let game = Game::from_pgn(pgn).unwrap();
+        let history: Vec<String> = game.history().iter().map(ToString::to_string).collect();
+        assert_eq!(history, ["e1c1", "g2h1n", "b7a8q"]);
+        assert_eq!(game.starting_fen(), "r3k2r/1P6/8/8/8/8/6p1/R3K2R w KQkq - 0 1");
+        assert!(game.to_fen().starts_with("Q3k2r/8/8/8/8/8/8/2KR3n b "), "{}", game.to_fen());
+
+        let mut game = Game::from_pgn("1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0").unwrap();
+        assert_eq!(game.get_state(), super::super::GameState::Checkmate(Color::Black));
+
+        let err = Game::from_pgn("1. e4 e5 2. Ke3 Nc6 *").unwrap_err();
+        assert!(matches!(err, PgnError::IllegalMove { ply: 3, .. }), "{err}");
+    }
+
+    #[test]
+    fn castling_promotion_and_wrapping() {
+        let mut game = Game::from_fen("r3k2r/1P6/8/8/8/8/6p1/R3K2R w KQkq - 0 1").unwrap();
+        game.move_piece(&"e1".parse().unwrap(), &"c1".parse().unwrap()).unwrap();
+        game.move_piece(&"g2".parse().unwrap(), &"h1".parse().unwrap()).unwrap();
+        game.promote(PieceType::Knight);
+        game.move_piece(&"b7".parse().unwrap(), &"a8".parse().unwrap()).unwrap();
+        game.promote(PieceType::Queen);
+
+        let pgn = game.to_pgn(&PgnTags::default());
+        assert!(pgn.starts_with("[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n"), "{pgn}");
+        assert!(pgn.ends_with("\n\n1. O-O-O gxh1=N 2. bxa8=Q+ *\n\n"), "{pgn}");
+
+        // Long games are wrapped and can be read back.
+        let mut game = Game::new();
+        for _ in 0..10 {
+            for m in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                game.make_move(&m.parse().unwrap()).unwrap();
+            }
+        }
+        let pgn = game.to_pgn(&PgnTags::default());
+        assert!(pgn.lines().all(|line| line.len() <= 79), "{pgn}");
+        assert_eq!(PgnGame::parse(&pgn).unwrap().final_position().to_fen(), game.to_fen());
+    }
+}
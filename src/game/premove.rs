@@ -0,0 +1,186 @@
+use std::fmt;
+
+use crate::{attacks, board::Color, pos::BoardPos, piece::PieceType};
+
+use super::{Game, MoveOutcome, MovePieceError, Moved, PromotionPiece};
+
+/// Why a premove was cancelled by `Game::try_apply_premove`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PremoveError {
+    /// The piece is no longer on the square, because it was captured.
+    PieceGone,
+    /// The move is not legal in the new position.
+    IllegalMove,
+    /// The game has ended, see `Game::outcome`.
+    GameOver,
+}
+
+impl fmt::Display for PremoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PieceGone => write!(f, "The premoved piece was captured."),
+            Self::IllegalMove => write!(f, "The premove is not legal after the opponent's move."),
+            Self::GameOver => write!(f, "The game is over."),
+        }
+    }
+}
+
+impl std::error::Error for PremoveError {}
+
+impl Game {
+
+    /// Whether a move can be queued as a premove by the team that is waiting for
+    /// the opponent to move, in online play.
+    ///
+    /// The piece on `from` must belong to the team that is not to move, and `to`
+    /// must be reachable by the piece on an empty board, since the opponent's move
+    /// can change everything else. A pawn may premove diagonally in case a piece
+    /// to capture appears, and a king may premove the castling pattern while the
+    /// team can still castle to that side.
+    ///
+    /// A valid premove may still be cancelled by `try_apply_premove`.
+    pub fn is_valid_premove(&self, from: &BoardPos, to: &BoardPos) -> bool {
+        let color = self.current_turn.opposite();
+        let tile = match self.board.get_tile(from) {
+            Some(tile) if tile.color() == color => tile,
+            _ => return false,
+        };
+        if from == to || self.outcome.is_some() {
+            return false;
+        }
+
+        let file_delta = to.file() as i8 - from.file() as i8;
+        let rank_delta = to.rank() as i8 - from.rank() as i8;
+        let diagonal = file_delta.abs() == rank_delta.abs();
+        let straight = file_delta == 0 || rank_delta == 0;
+
+        match tile.piece() {
+            PieceType::Pawn => {
+                let (forward, start_rank) = match color {
+                    Color::White => (1, 1),
+                    Color::Black => (-1, 6),
+                };
+                rank_delta == forward && file_delta.abs() <= 1
+                    || file_delta == 0 && rank_delta == 2 * forward && from.rank() == start_rank
+            },
+            PieceType::Knight => attacks::knight_attacks(from).contains(to),
+            PieceType::Bishop => diagonal,
+            PieceType::Rook => straight,
+            PieceType::Queen => diagonal || straight,
+            PieceType::King => {
+                if attacks::king_attacks(from).contains(to) {
+                    return true;
+                }
+                let back_rank = match color {
+                    Color::White => 0,
+                    Color::Black => 7,
+                };
+                let castling = self.castling_availability(&color);
+                let castles = from.file() == 4 && from.rank() == back_rank && rank_delta == 0;
+                castles && (file_delta == 2 && castling.kingside || file_delta == -2 && castling.queenside)
+            },
+        }
+    }
+
+    /// Play a queued premove once the opponent has moved, if it is legal in the
+    /// new position. This must be called after the opponent's move, when it is
+    /// the turn of the team that queued the premove. A pawn reaching the last rank
+    /// is promoted to a queen.
+    ///
+    /// # Errors
+    /// The reason the premove was cancelled is returned, and the game is left
+    /// unchanged. `PieceGone` is returned if there is no piece of the team to move
+    /// on `from` since it was captured, `IllegalMove` if the move is not legal
+    /// and `GameOver` if the opponent's move ended the game.
+    pub fn try_apply_premove(&mut self, from: &BoardPos, to: &BoardPos) -> Result<MoveOutcome, PremoveError> {
+        if self.outcome.is_some() {
+            return Err(PremoveError::GameOver);
+        }
+        // The team's own pieces can not move during the opponent's turn, so the
+        // piece is gone only if it was captured.
+        if !self.board.get_tile(from).is_some_and(|tile| tile.color() == self.current_turn) {
+            return Err(PremoveError::PieceGone);
+        }
+
+        match self.play_move(from, to) {
+            Ok(Moved::Done(outcome)) => Ok(outcome),
+            Ok(Moved::NeedsPromotion(pending)) => Ok(pending.promote(PromotionPiece::Queen)),
+            Err(MovePieceError::GameOver) => Err(PremoveError::GameOver),
+            Err(_) => Err(PremoveError::IllegalMove),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(text: &str) -> BoardPos {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn premove_geometry() {
+        // Black premoves while white is thinking.
+        let game = Game::from_fen("r3k2r/pppp1ppp/2n5/1b6/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert!(game.is_valid_premove(&pos("b5"), &pos("e2")));
+        assert!(game.is_valid_premove(&pos("b5"), &pos("f1")));
+        assert!(!game.is_valid_premove(&pos("b5"), &pos("b4")));
+        assert!(game.is_valid_premove(&pos("c6"), &pos("d4")));
+        assert!(game.is_valid_premove(&pos("d7"), &pos("d5")));
+        assert!(game.is_valid_premove(&pos("d7"), &pos("e6")));
+        assert!(!game.is_valid_premove(&pos("d7"), &pos("d8")));
+        assert!(game.is_valid_premove(&pos("e8"), &pos("g8")));
+        assert!(game.is_valid_premove(&pos("e8"), &pos("c8")));
+        assert!(!game.is_valid_premove(&pos("e8"), &pos("e6")));
+        // White pieces can not be premoved by black.
+        assert!(!game.is_valid_premove(&pos("e2"), &pos("e4")));
+        assert!(!game.is_valid_premove(&pos("e5"), &pos("e4")));
+    }
+
+    #[test]
+    fn premove_becomes_legal() {
+        let mut game = Game::new();
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+        // White premoves a capture on d5 before black has moved.
+        assert!(game.is_valid_premove(&pos("e4"), &pos("d5")));
+        game.make_move(&"d7d5".parse().unwrap()).unwrap();
+        let outcome = game.try_apply_premove(&pos("e4"), &pos("d5")).unwrap();
+        assert_eq!(outcome.m.to_string(), "e4d5");
+        assert_eq!(game.current_turn(), Color::Black);
+    }
+
+    #[test]
+    fn premove_becomes_illegal() {
+        let mut game = Game::new();
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+        assert!(game.is_valid_premove(&pos("e4"), &pos("d5")));
+        game.make_move(&"e7e5".parse().unwrap()).unwrap();
+        let fen = game.to_fen();
+        assert!(matches!(game.try_apply_premove(&pos("e4"), &pos("d5")), Err(PremoveError::IllegalMove)));
+        assert_eq!(game.to_fen(), fen);
+
+        // The premoved pawn is captured.
+        let mut game = Game::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2").unwrap();
+        assert!(game.is_valid_premove(&pos("e4"), &pos("e5")));
+        game.make_move(&"d5e4".parse().unwrap()).unwrap();
+        assert!(matches!(game.try_apply_premove(&pos("e4"), &pos("e5")), Err(PremoveError::PieceGone)));
+    }
+
+    #[test]
+    fn castling_premove() {
+        let mut game = Game::from_fen("r3k3/8/8/8/8/8/8/4K2R b K - 0 1").unwrap();
+        assert!(game.is_valid_premove(&pos("e1"), &pos("g1")));
+        assert!(!game.is_valid_premove(&pos("e1"), &pos("c1")));
+
+        let mut checked = game.clone();
+        game.make_move(&"e8d8".parse().unwrap()).unwrap();
+        assert_eq!(game.try_apply_premove(&pos("e1"), &pos("g1")).unwrap().m.to_string(), "e1g1");
+        assert!(game.to_fen().starts_with("r2k4/8/8/8/8/8/8/5RK1 b"));
+
+        // Castling out of check is not allowed.
+        checked.make_move(&"a8a1".parse().unwrap()).unwrap();
+        assert!(matches!(checked.try_apply_premove(&pos("e1"), &pos("g1")), Err(PremoveError::IllegalMove)));
+    }
+}
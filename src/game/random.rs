@@ -0,0 +1,302 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use rand::Rng;
+
+use crate::{board::{Board, Color, Tile}, pos::BoardPos, piece::PieceType};
+
+use super::{Game, CastlingAvailability, MoveCache, StandardRules};
+
+/// Limits for the positions generated by `Game::random_position`.
+#[derive(Clone, Debug)]
+pub struct RandomPositionConstraints {
+    /// The maximum number of pieces of each team, including the king. Must be
+    /// between 1 and 16.
+    pub max_pieces: u8,
+    /// The maximum number of pawns of each team. Must be at most 8.
+    pub max_pawns: u8,
+}
+
+impl Default for RandomPositionConstraints {
+    fn default() -> Self {
+        Self { max_pieces: 16, max_pawns: 8 }
+    }
+}
+
+/// The pieces, apart from the king, that are placed on the board.
+const PIECES: [PieceType; 5] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Pawn,
+];
+
+impl Game {
+
+    /// Generate a random position that looks legal.
+    ///
+    /// The generated positions are meant for testing, for example fuzzing the move
+    /// generator. They are not guaranteed to be reachable from the starting
+    /// position, but they satisfy the following:
+    /// 1. each team has exactly one king and the kings are not adjacent.
+    /// 2. there are no pawns on the first or last rank.
+    /// 3. the team that is not to move is not in check.
+    /// 4. the number of pieces of each team is within the constraints.
+    /// 5. castling is only available when the king and rook are on their starting
+    ///    squares, and the en passant target is only set behind a pawn that could
+    ///    just have moved two squares.
+    ///
+    /// ## Panics
+    /// This function will panic if `max_pieces` is not between 1 and 16 or if
+    /// `max_pawns` is larger than 8.
+    pub fn random_position<R: Rng + ?Sized>(rng: &mut R, constraints: &RandomPositionConstraints) -> Game {
+        if !(1..=16).contains(&constraints.max_pieces) {
+            panic!("max_pieces must be in the inclusive range [1-16], got {}", constraints.max_pieces);
+        }
+        if constraints.max_pawns > 8 {
+            panic!("max_pawns must be in the inclusive range [0-8], got {}", constraints.max_pawns);
+        }
+
+        loop {
+            let game = Self::random_candidate(rng, constraints);
+            if !game.is_check(&game.current_turn.opposite()) {
+                return game;
+            }
+        }
+    }
+
+    /// Generate a random position that may have the team not to move in check.
+    fn random_candidate<R: Rng + ?Sized>(rng: &mut R, constraints: &RandomPositionConstraints) -> Game {
+        let mut board = Board::empty();
+
+        let white_king = random_empty_pos(rng, &board, 0..=7);
+        board.set_tile(&white_king, Tile::new(PieceType::King, Color::White));
+        let black_king = loop {
+            let pos = random_empty_pos(rng, &board, 0..=7);
+            if pos.file().abs_diff(white_king.file()) > 1 || pos.rank().abs_diff(white_king.rank()) > 1 {
+                break pos;
+            }
+        };
+        board.set_tile(&black_king, Tile::new(PieceType::King, Color::Black));
+
+        for color in [Color::White, Color::Black] {
+            let count = rng.random_range(0..constraints.max_pieces);
+            let mut pawns = 0;
+            for _ in 0..count {
+                let mut piece = PIECES[rng.random_range(0..PIECES.len())];
+                if piece == PieceType::Pawn && pawns >= constraints.max_pawns {
+                    piece = PieceType::Queen;
+                }
+                let pos = if piece == PieceType::Pawn {
+                    pawns += 1;
+                    // Pawns can never stand on the first or last rank.
+                    random_empty_pos(rng, &board, 1..=6)
+                } else {
+                    random_empty_pos(rng, &board, 0..=7)
+                };
+                board.set_tile(&pos, Tile::new(piece, color));
+            }
+        }
+
+        let current_turn = if rng.random() { Color::White } else { Color::Black };
+
+        let white_castling = random_castling(rng, &board, Color::White);
+        let black_castling = random_castling(rng, &board, Color::Black);
+
+        // The team that is not to move might just have moved a pawn two squares.
+        let en_passant_target = if rng.random_bool(0.5) {
+            random_en_passant_target(rng, &board, current_turn.opposite())
+        } else {
+            None
+        };
+
+        let halfmove_clock = if en_passant_target.is_some() { 0 } else { rng.random_range(0..50) };
+
+        let mut game = Game {
+            board,
+            current_turn,
+            white_castling,
+            black_castling,
+            en_passant_target,
+            promotion_required: None,
+            halfmove_clock,
+            fullmove_number: rng.random_range(1..100),
+            starting_fen: String::new(),
+            history: Vec::new(),
+            undo_stack: Vec::new(),
+            position_keys: Vec::new(),
+            last_irreversible_ply: 0,
+            rules: Arc::new(StandardRules),
+            outcome: None,
+            adjudication_note: None,
+            annotations: BTreeMap::new(),
+            generation: 0,
+            move_cache: MoveCache::default(),
+        };
+        game.starting_fen = game.to_fen();
+        game.record_position_key();
+        game.update_outcome();
+        game
+    }
+}
+
+/// Get a random empty position within the ranks.
+fn random_empty_pos<R: Rng + ?Sized>(rng: &mut R, board: &Board, ranks: std::ops::RangeInclusive<u8>) -> BoardPos {
+    loop {
+        let pos = BoardPos::new(rng.random_range(0..8), rng.random_range(ranks.clone()));
+        if board.get_tile(&pos).is_none() {
+            return pos;
+        }
+    }
+}
+
+/// Randomly grant castling rights for the sides where the king and rook are on
+/// their starting squares.
+fn random_castling<R: Rng + ?Sized>(rng: &mut R, board: &Board, color: Color) -> CastlingAvailability {
+    let rank = if color == Color::White { 0 } else { 7 };
+    let has = |file: u8, piece: PieceType| {
+        board.get_tile(&BoardPos::new(file, rank)) == Some(Tile::new(piece, color))
+    };
+    let king = has(4, PieceType::King);
+    CastlingAvailability {
+        kingside: king && has(7, PieceType::Rook) && rng.random(),
+        queenside: king && has(0, PieceType::Rook) && rng.random(),
+    }
+}
+
+/// Pick the en passant target behind a random pawn of `color` that could just
+/// have moved two squares, if there is any.
+fn random_en_passant_target<R: Rng + ?Sized>(rng: &mut R, board: &Board, color: Color) -> Option<BoardPos> {
+    // The ranks the pawn would have moved from, through and to.
+    let (start_rank, target_rank, pawn_rank) = if color == Color::White { (1, 2, 3) } else { (6, 5, 4) };
+
+    let candidates: Vec<BoardPos> = (0..8)
+        .filter(|file| {
+            board.get_tile(&BoardPos::new(*file, pawn_rank)) == Some(Tile::new(PieceType::Pawn, color))
+                && board.get_tile(&BoardPos::new(*file, target_rank)).is_none()
+                && board.get_tile(&BoardPos::new(*file, start_rank)).is_none()
+        })
+        .map(|file| BoardPos::new(file, target_rank))
+        .collect();
+
+    if candidates.is_empty() {
+        return None;
+    }
+    Some(candidates[rng.random_range(0..candidates.len())].clone())
+}
+
+#[cfg(feature = "proptest")]
+mod arbitrary {
+    use proptest::{prelude::*, strategy::BoxedStrategy};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{board::{Board, Color}, game::Game, piece::PieceType, pos::BoardPos};
+
+    use super::RandomPositionConstraints;
+
+    impl Arbitrary for BoardPos {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<BoardPos>;
+
+        fn arbitrary_with(_args: ()) -> Self::Strategy {
+            (0..8_u8, 0..8_u8).prop_map(|(file, rank)| BoardPos::new(file, rank)).boxed()
+        }
+    }
+
+    impl Arbitrary for PieceType {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<PieceType>;
+
+        fn arbitrary_with(_args: ()) -> Self::Strategy {
+            prop_oneof![
+                Just(PieceType::King),
+                Just(PieceType::Queen),
+                Just(PieceType::Rook),
+                Just(PieceType::Bishop),
+                Just(PieceType::Knight),
+                Just(PieceType::Pawn),
+            ].boxed()
+        }
+    }
+
+    impl Arbitrary for Color {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Color>;
+
+        fn arbitrary_with(_args: ()) -> Self::Strategy {
+            prop_oneof![Just(Color::White), Just(Color::Black)].boxed()
+        }
+    }
+
+    /// Games are generated by `Game::random_position` from a random seed, so they
+    /// do not shrink to simpler positions.
+    impl Arbitrary for Game {
+        type Parameters = RandomPositionConstraints;
+        type Strategy = BoxedStrategy<Game>;
+
+        fn arbitrary_with(constraints: RandomPositionConstraints) -> Self::Strategy {
+            any::<u64>()
+                .prop_map(move |seed| {
+                    Game::random_position(&mut StdRng::seed_from_u64(seed), &constraints)
+                })
+                .boxed()
+        }
+    }
+
+    /// Boards are the placement of a random `Game`.
+    impl Arbitrary for Board {
+        type Parameters = RandomPositionConstraints;
+        type Strategy = BoxedStrategy<Board>;
+
+        fn arbitrary_with(constraints: RandomPositionConstraints) -> Self::Strategy {
+            any_with::<Game>(constraints).prop_map(|game| game.board).boxed()
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn random_positions_look_legal() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let constraints = RandomPositionConstraints { max_pieces: 6, max_pawns: 3 };
+
+        for _ in 0..200 {
+            let game = Game::random_position(&mut rng, &constraints);
+
+            let white_king = game.get_king_pos(&Color::White).unwrap();
+            let black_king = game.get_king_pos(&Color::Black).unwrap();
+            assert!(white_king.file().abs_diff(black_king.file()) > 1
+                || white_king.rank().abs_diff(black_king.rank()) > 1);
+
+            assert!(!game.is_check(&game.current_turn.opposite()), "{}", game.to_fen());
+
+            for color in [Color::White, Color::Black] {
+                let pieces: Vec<_> = game.board.pieces().filter(|(_, tile)| tile.color() == color).collect();
+                let pawns: Vec<_> = pieces.iter().filter(|(_, tile)| tile.piece() == PieceType::Pawn).collect();
+                assert!(pieces.len() <= 6);
+                assert!(pawns.len() <= 3);
+                assert!(pawns.iter().all(|(pos, _)| pos.rank() != 0 && pos.rank() != 7));
+            }
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn fen_round_trip(game: Game) {
+            let fen = game.to_fen();
+            proptest::prop_assert_eq!(Game::from_fen(&fen).unwrap().to_fen(), fen);
+        }
+
+        #[test]
+        fn board_pos_round_trip(pos: BoardPos) {
+            proptest::prop_assert_eq!(pos.to_string().parse::<BoardPos>().unwrap(), pos);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt;
+
+use crate::board::Color;
+
+use super::{human::last_square, FenParseError, Game, Move, MovePieceError, SanError};
+
+/// What was wrong with a move in a game record, see `RecordError`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RecordErrorKind {
+    /// The token is neither coordinate notation nor Standard Algebraic Notation.
+    Unreadable,
+    /// The move can not be made in the position.
+    Illegal,
+    /// More than one legal move matches the notation. The matching moves are
+    /// included.
+    Ambiguous(Vec<Move>),
+    /// The game was already over before the move.
+    GameOver,
+}
+
+/// A move in a game record that could not be played, see
+/// `validate_game_record`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RecordError {
+    /// The index of the move in the record, starting at 0.
+    pub ply: usize,
+    /// The move as it was written in the record.
+    pub token: String,
+    /// The FEN of the position that the move was played in.
+    pub fen: String,
+    pub kind: RecordErrorKind,
+    /// Legal moves in Standard Algebraic Notation that were probably meant: every
+    /// matching move if the notation is ambiguous, or the only legal move to the
+    /// same square if it is illegal.
+    pub suggestions: Vec<String>,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Move {} ({}): ", self.ply, self.token)?;
+        match self.kind {
+            RecordErrorKind::Unreadable => write!(f, "That is not a move.")?,
+            RecordErrorKind::Illegal => write!(f, "That move is not legal.")?,
+            RecordErrorKind::Ambiguous(_) => write!(f, "The move is ambiguous.")?,
+            RecordErrorKind::GameOver => write!(f, "The game is over.")?,
+        }
+        if !self.suggestions.is_empty() {
+            write!(f, " Did you mean {}?", self.suggestions.join(" or "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// The result of replaying a game record with `validate_game_record`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GameRecordReport {
+    /// The moves that could not be played, in the order of the record.
+    pub errors: Vec<RecordError>,
+    /// The number of moves that were played.
+    pub played: usize,
+    /// The FEN of the position after the last move that was replayed.
+    pub final_fen: String,
+}
+
+impl GameRecordReport {
+    /// Whether every move in the record was played.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Replay the moves of a game record and report every move that can not be
+/// played, for example to check games before importing them into a database.
+///
+/// The game starts from `start_fen`, or from the standard starting position if
+/// it is `None`. Each move is read as coordinate notation, like `e2e4` or
+/// `e7e8q`, if it is written like that, and otherwise as Standard Algebraic
+/// Notation, like `Nf3` or `exd5`, so the notations can be mixed.
+///
+/// Without `best_effort`, the replay stops at the first bad move. With it, bad
+/// moves are skipped and the turn passes to the other team, so that the moves
+/// after a typo are still played by the right team. Moves that depended on a
+/// skipped move are reported too.
+///
+/// # Errors
+/// An error is returned if `start_fen` is not a valid FEN.
+pub fn validate_game_record<'a>(start_fen: Option<&'a str>, moves: &[&str], best_effort: bool) -> Result<GameRecordReport, FenParseError<'a>> {
+    let mut game = match start_fen {
+        Some(fen) => Game::from_fen(fen)?,
+        None => Game::new(),
+    };
+
+    let mut errors = Vec::new();
+    let mut played = 0;
+    for (ply, token) in moves.iter().enumerate() {
+        match game.play_record_token(token) {
+            Ok(()) => played += 1,
+            Err((kind, suggestions)) => {
+                errors.push(RecordError { ply, token: token.to_string(), fen: game.to_fen(), kind, suggestions });
+                if !best_effort || game.outcome.is_some() {
+                    break;
+                }
+                game.pass_turn();
+            },
+        }
+    }
+
+    Ok(GameRecordReport { errors, played, final_fen: game.to_fen() })
+}
+
+impl Game {
+
+    /// Play a move from a game record in coordinate notation or Standard
+    /// Algebraic Notation, or return what was wrong with it and suggestions.
+    fn play_record_token(&mut self, token: &str) -> Result<(), (RecordErrorKind, Vec<String>)> {
+        if self.outcome.is_some() {
+            return Err((RecordErrorKind::GameOver, Vec::new()));
+        }
+
+        if let Ok(m) = token.parse::<Move>() {
+            return match self.make_move(&m) {
+                Ok(()) => Ok(()),
+                Err(MovePieceError::GameOver) => Err((RecordErrorKind::GameOver, Vec::new())),
+                Err(_) => Err((RecordErrorKind::Illegal, self.suggest_for_illegal(m.to(), Some(m.from())).into_iter().collect())),
+            };
+        }
+
+        match self.parse_san(token) {
+            Ok(m) => {
+                self.make_move(&m).expect("Parsed moves are legal.");
+                Ok(())
+            },
+            Err(SanError::InvalidSyntax) => Err((RecordErrorKind::Unreadable, Vec::new())),
+            Err(SanError::IllegalMove) => {
+                let suggestion = last_square(token).and_then(|to| self.suggest_for_illegal(&to, None));
+                Err((RecordErrorKind::Illegal, suggestion.into_iter().collect()))
+            },
+            Err(SanError::Ambiguous(moves)) => {
+                let suggestions = moves.iter().map(|m| self.move_to_san(m)).collect();
+                Err((RecordErrorKind::Ambiguous(moves), suggestions))
+            },
+        }
+    }
+
+    /// Give the turn to the other team without moving, in place of a move that
+    /// was skipped.
+    fn pass_turn(&mut self) {
+        if self.current_turn == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.current_turn = self.current_turn.opposite();
+        self.en_passant_target = None;
+        self.invalidate_move_cache();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORD: [&str; 10] = ["e4", "e5", "Nf3", "Nc6", "d3", "d6", "a3", "g1f3", "Nd2", "Bg4"];
+
+    #[test]
+    fn valid_record() {
+        let report = validate_game_record(None, &["e2e4", "e5", "Nf3", "b8c6", "Bb5+"], false).unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.played, 5);
+        assert!(report.final_fen.starts_with("r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R b KQkq -"));
+    }
+
+    #[test]
+    fn ambiguous_and_illegal_moves() {
+        // g1f3 is illegal for black, and both white knights can go to d2.
+        let report = validate_game_record(None, &RECORD, true).unwrap();
+        assert_eq!(report.played, 8);
+        let errors: Vec<(usize, &str, &RecordErrorKind)> = report.errors.iter().map(|e| (e.ply, e.token.as_str(), &e.kind)).collect();
+        let knights = vec!["b1d2".parse().unwrap(), "f3d2".parse().unwrap()];
+        assert_eq!(errors, [(7, "g1f3", &RecordErrorKind::Illegal), (8, "Nd2", &RecordErrorKind::Ambiguous(knights))]);
+
+        let ambiguous = &report.errors[1];
+        assert!(ambiguous.fen.starts_with("r1bqkbnr/ppp2ppp/2np4/4p3/4P3/P2P1N2/1PP2PPP/RNBQKB1R w KQkq -"));
+        assert_eq!(ambiguous.to_string(), "Move 8 (Nd2): The move is ambiguous. Did you mean Nbd2 or Nfd2?");
+        // The last move is played by black after both skips.
+        assert!(report.final_fen.starts_with("r2qkbnr/ppp2ppp/2np4/4p3/4P1b1/P2P1N2/1PP2PPP/RNBQKB1R w KQkq -"));
+
+        // Without best effort, the replay stops at the first error.
+        let report = validate_game_record(None, &RECORD, false).unwrap();
+        assert_eq!((report.played, report.errors.len()), (7, 1));
+    }
+
+    #[test]
+    fn suggestions_and_game_over() {
+        let report = validate_game_record(None, &["e4", "e5", "Bh5", "Nc6", "hello"], true).unwrap();
+        let errors: Vec<String> = report.errors.iter().map(ToString::to_string).collect();
+        assert_eq!(errors, ["Move 2 (Bh5): That move is not legal. Did you mean Qh5?", "Move 4 (hello): That is not a move."]);
+
+        let report = validate_game_record(None, &["f3", "e5", "g4", "Qh4#", "a3", "a6"], true).unwrap();
+        assert_eq!(report.played, 4);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].kind, RecordErrorKind::GameOver);
+
+        assert!(validate_game_record(Some("not a fen"), &[], true).is_err());
+    }
+}
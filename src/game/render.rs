@@ -0,0 +1,152 @@
+use std::{collections::HashSet, fmt};
+
+use crate::{board::Color, pos::BoardPos};
+
+use super::Game;
+
+impl fmt::Display for Game {
+    // The board is the readable part of the position, see `Board::to_ascii`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.board)
+    }
+}
+
+impl Game {
+
+    /// Draw the board as text, see `Board::to_ascii`. This is also the `Display`
+    /// implementation of `Game`.
+    pub fn to_ascii(&self) -> String {
+        self.board.to_ascii()
+    }
+
+    /// Render the board as text with the legal moves of the piece at `pos` marked.
+    ///
+    /// The piece itself is surrounded by brackets, empty squares it can move to
+    /// are marked with `x` and pieces it can capture are marked with `*`. Other
+    /// pieces are shown with their FEN letter and other empty squares with `.`.
+    /// See `render_moveset` for an example.
+    ///
+    /// If there is no piece at `pos`, no moves are marked. The moves are rendered
+    /// for the piece regardless of whose turn it is.
+    pub fn render_moves(&mut self, pos: &BoardPos) -> String {
+        let moves = match self.board.get_tile(pos) {
+            Some(_) => self.get_legal_moves_unchecked(pos),
+            None => HashSet::new(),
+        };
+        self.render_moveset(pos, &moves)
+    }
+
+    /// Render the board as text with `pos` and the positions in `moves` marked.
+    ///
+    /// This is the same as `render_moves` except that the moves are passed in,
+    /// which is useful for showing a moveset that was computed elsewhere. A knight
+    /// on `e4` that can capture on `f6` is rendered as follows:
+    ///
+    /// ```text
+    /// 8  .  .  .  .  k  .  .  .
+    /// 7  .  .  .  .  .  .  .  .
+    /// 6  .  .  .  x  .  *  .  .
+    /// 5  .  .  x  .  .  .  x  .
+    /// 4  .  .  .  . [N] .  .  .
+    /// 3  .  .  x  .  .  .  x  .
+    /// 2  .  .  .  x  .  x  .  .
+    /// 1  .  .  .  .  K  .  .  .
+    ///    a  b  c  d  e  f  g  h
+    /// ```
+    pub fn render_moveset(&self, pos: &BoardPos, moves: &HashSet<BoardPos>) -> String {
+        self.render(Some(pos), moves)
+    }
+
+    /// Render the board with the positions in `moves` marked and the piece at
+    /// `pos`, if any, surrounded by brackets.
+    pub(super) fn render(&self, pos: Option<&BoardPos>, moves: &HashSet<BoardPos>) -> String {
+        let mut str = String::new();
+        for (rank_index, rank) in self.board.ranks().enumerate() {
+            str.push_str(&(8 - rank_index).to_string());
+            str.push(' ');
+            for (square, tile) in rank {
+                let char = match tile {
+                    Some(_) if moves.contains(&square) => '*',
+                    None if moves.contains(&square) => 'x',
+                    Some(tile) if tile.color() == Color::White => tile.piece().char().to_ascii_uppercase(),
+                    Some(tile) => tile.piece().char(),
+                    None => '.',
+                };
+                if Some(&square) == pos {
+                    str.push('[');
+                    str.push(char);
+                    str.push(']');
+                } else {
+                    str.push(' ');
+                    str.push(char);
+                    str.push(' ');
+                }
+            }
+            // Trailing spaces make the output harder to compare.
+            str.truncate(str.trim_end().len());
+            str.push('\n');
+        }
+        str.push_str("   a  b  c  d  e  f  g  h");
+        str
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_game() {
+        let mut game = Game::new();
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+
+        assert_eq!(game.to_string(), [
+            "8 r n b q k b n r",
+            "7 p p p p p p p p",
+            "6 . . . . . . . .",
+            "5 . . . . . . . .",
+            "4 . . . . P . . .",
+            "3 . . . . . . . .",
+            "2 P P P P . P P P",
+            "1 R N B Q K B N R",
+            "  a b c d e f g h",
+        ].join("\n"));
+        assert_eq!(game.to_ascii(), game.to_string());
+    }
+
+    #[test]
+    fn render_knight() {
+        let mut game = Game::from_fen("4k3/8/5p2/8/4N3/8/8/4K3 w - - 0 1").unwrap();
+
+        assert_eq!(game.render_moves(&"e4".parse().unwrap()), [
+            "8  .  .  .  .  k  .  .  .",
+            "7  .  .  .  .  .  .  .  .",
+            "6  .  .  .  x  .  *  .  .",
+            "5  .  .  x  .  .  .  x  .",
+            "4  .  .  .  . [N] .  .  .",
+            "3  .  .  x  .  .  .  x  .",
+            "2  .  .  .  x  .  x  .  .",
+            "1  .  .  .  .  K  .  .  .",
+            "   a  b  c  d  e  f  g  h",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn render_pinned_rook() {
+        // The rook is pinned to the king by the black rook on e8.
+        let mut game = Game::from_fen("k3r3/8/8/8/4R3/8/4K3/8 w - - 0 1").unwrap();
+
+        assert_eq!(game.render_moves(&"e4".parse().unwrap()), [
+            "8  k  .  .  .  *  .  .  .",
+            "7  .  .  .  .  x  .  .  .",
+            "6  .  .  .  .  x  .  .  .",
+            "5  .  .  .  .  x  .  .  .",
+            "4  .  .  .  . [R] .  .  .",
+            "3  .  .  .  .  x  .  .  .",
+            "2  .  .  .  .  K  .  .  .",
+            "1  .  .  .  .  .  .  .  .",
+            "   a  b  c  d  e  f  g  h",
+        ].join("\n"));
+    }
+}
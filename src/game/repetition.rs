@@ -0,0 +1,145 @@
+use super::Game;
+
+impl Game {
+
+    /// Get the plies at which the current position occurred before, oldest first.
+    /// Ply 0 is the starting position and ply `n` is the position after the `n`th
+    /// move in `history`.
+    ///
+    /// Positions are the same if the pieces, the turn, the castling availability
+    /// and the possible en passant captures are the same. An en passant target
+    /// where no capture is legal does not make a position different.
+    ///
+    /// While a promotion is pending, the position is not complete and nothing is
+    /// returned.
+    pub fn repetition_plies(&self) -> Vec<usize> {
+        if self.promotion_required.is_some() {
+            return Vec::new();
+        }
+        let (current, previous) = match self.position_keys.split_last() {
+            Some(keys) => keys,
+            None => return Vec::new(),
+        };
+        // Positions from before the last irreversible move can not repeat.
+        let start = self.last_irreversible_ply.min(previous.len());
+        previous[start..].iter()
+            .enumerate()
+            .filter(|(_, key)| *key == current)
+            .map(|(ply, _)| start + ply)
+            .collect()
+    }
+
+    /// Get the ply of the last pawn move, capture, castling or move that lost
+    /// castling availability, or 0 if there has been no such move. Positions from
+    /// before this ply can never occur again, see `repetition_plies`.
+    ///
+    /// The ply is counted like in `repetition_plies`, so ply `n` is the position
+    /// after the `n`th move in `history`.
+    pub fn last_irreversible_ply(&self) -> usize {
+        self.last_irreversible_ply
+    }
+
+    /// Get how many times the current position has occurred in the game,
+    /// including now. See `repetition_plies`.
+    pub fn repetition_count(&self) -> usize {
+        self.repetition_plies().len() + 1
+    }
+
+    /// Whether the current position has occurred at least three times, which
+    /// allows a player to claim a draw.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    /// Record the repetition key of the current position. This must be called
+    /// once after every move, when the move is complete.
+    pub(super) fn record_position_key(&mut self) {
+        let key = self.repetition_key();
+        self.position_keys.push(key);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &str) {
+        for san in moves.split_whitespace() {
+            let m = game.parse_san(san).unwrap();
+            game.make_move(&m).unwrap();
+        }
+    }
+
+    #[test]
+    fn knight_shuffle() {
+        let mut game = Game::new();
+        play(&mut game, "Nf3 Nf6 Ng1 Ng8");
+        assert_eq!(game.repetition_plies(), [0]);
+        assert_eq!(game.repetition_count(), 2);
+
+        play(&mut game, "Nf3");
+        assert_eq!(game.repetition_plies(), [1]);
+        play(&mut game, "Nf6 Ng1 Ng8");
+        assert_eq!(game.repetition_plies(), [0, 4]);
+        assert!(game.is_threefold_repetition());
+
+        play(&mut game, "e4");
+        assert!(game.repetition_plies().is_empty());
+        assert_eq!(game.repetition_count(), 1);
+    }
+
+    #[test]
+    fn last_irreversible_ply() {
+        let mut game = Game::new();
+        assert_eq!(game.last_irreversible_ply(), 0);
+        play(&mut game, "Nf3 Nf6");
+        assert_eq!(game.last_irreversible_ply(), 0);
+        play(&mut game, "e4");
+        assert_eq!(game.last_irreversible_ply(), 3);
+        play(&mut game, "Nc6 Nc3");
+        assert_eq!(game.last_irreversible_ply(), 3);
+        play(&mut game, "Nxe4");
+        assert_eq!(game.last_irreversible_ply(), 6);
+        play(&mut game, "Rg1");
+        assert_eq!(game.last_irreversible_ply(), 7);
+        play(&mut game, "Nf6 Rh1");
+        assert_eq!(game.last_irreversible_ply(), 7);
+
+        let mut game = Game::from_fen("r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1").unwrap();
+        play(&mut game, "O-O");
+        assert_eq!(game.last_irreversible_ply(), 1);
+    }
+
+    #[test]
+    fn repeats_after_irreversible_move() {
+        let mut game = Game::new();
+        play(&mut game, "Nf3 Nf6 Ng1 Ng8 e4 e5 Nf3 Nf6 Ng1 Ng8");
+        assert_eq!(game.last_irreversible_ply(), 6);
+        assert_eq!(game.repetition_plies(), [6]);
+        play(&mut game, "Nf3 Nf6 Ng1 Ng8");
+        assert_eq!(game.repetition_plies(), [6, 10]);
+        assert!(game.is_threefold_repetition());
+    }
+
+    #[test]
+    fn castling_rights_make_positions_different() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        play(&mut game, "Rh2 Kd8 Rh1 Ke8");
+        assert!(game.repetition_plies().is_empty());
+        play(&mut game, "Rh2 Kd8 Rh1 Ke8");
+        assert_eq!(game.repetition_plies(), [4]);
+    }
+
+    #[test]
+    fn en_passant_only_counts_if_possible() {
+        let mut game = Game::new();
+        play(&mut game, "e4 Nf6 Nf3 Ng8 Ng1");
+        // After e4 black could not capture en passant, so the position is the same.
+        assert_eq!(game.repetition_plies(), [1]);
+
+        let mut game = Game::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        play(&mut game, "e4 Kd7 Ke2 Ke8 Ke1 Kd7 Kd1 Ke8 Ke1");
+        assert_eq!(game.repetition_plies(), [5]);
+    }
+}
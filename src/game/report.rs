@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+
+use super::{fen_validation::validate_fen, Game, Severity};
+
+/// The number of moves listed in `Game::debug_report`.
+const REPORT_MOVES: usize = 10;
+
+impl Game {
+
+    /// Describe the game in detail, for attaching to a bug report about the
+    /// rules.
+    ///
+    /// The report has the FEN, a diagram of the board, every field of the
+    /// position, the last moves in Standard Algebraic Notation, the number of
+    /// legal moves, the state of the game and the problems found by
+    /// `validate_fen`.
+    ///
+    /// The report can be made for any game, even one whose board was changed into
+    /// an invalid position with `board`. The legal moves and the state are then
+    /// left out, since they can not be computed for invalid positions.
+    pub fn debug_report(&self) -> String {
+        let fen = self.to_fen();
+        let issues = validate_fen(&fen);
+        let valid = issues.iter().all(|issue| issue.severity != Severity::Error);
+
+        let mut lines = vec![
+            format!("FEN: {fen}"),
+            String::new(),
+            self.render(None, &HashSet::new()),
+            String::new(),
+            format!("Side to move: {}", self.current_turn),
+            format!("Castling: {}", fen.split(' ').nth(2).unwrap_or("-")),
+            format!("En passant: {}", self.en_passant_target.as_ref().map_or("-".to_string(), |pos| pos.to_string())),
+            format!("Halfmove clock: {}", self.halfmove_clock),
+            format!("Fullmove number: {}", self.fullmove_number),
+            format!("Pending promotion: {}", self.promotion_required.as_ref().map_or("none".to_string(), |pos| pos.to_string())),
+            format!("Starting FEN: {}", self.starting_fen),
+            format!("Last moves: {}", self.last_moves_san()),
+        ];
+
+        let mut game = self.clone();
+        if valid {
+            lines.push(format!("Legal moves: {}", game.legal_moves().len()));
+            lines.push(format!("State: {:?}", game.get_state()));
+        } else {
+            lines.push("Legal moves: unavailable, the position is invalid".to_string());
+            lines.push("State: unavailable, the position is invalid".to_string());
+        }
+        lines.push(format!("Outcome: {:?}", self.outcome));
+
+        lines.push(String::new());
+        lines.push("Validation:".to_string());
+        if issues.is_empty() {
+            lines.push("  no problems found".to_string());
+        }
+        for issue in issues {
+            lines.push(format!("  {:?} in {:?}: {}", issue.severity, issue.field, issue.message));
+        }
+        lines.join("\n")
+    }
+
+    /// Get the last moves of the history in Standard Algebraic Notation, by
+    /// replaying the game from the starting position. Moves that can not be
+    /// replayed are written in coordinate notation.
+    fn last_moves_san(&self) -> String {
+        if self.history.is_empty() {
+            return "none".to_string();
+        }
+        let first = self.history.len().saturating_sub(REPORT_MOVES);
+        // Moves can only be generated safely in valid positions.
+        let valid_start = validate_fen(&self.starting_fen).iter().all(|issue| issue.severity != Severity::Error);
+        let mut replay = Game::from_fen(&self.starting_fen).ok().filter(|_| valid_start);
+
+        let mut moves = Vec::new();
+        for (ply, m) in self.history.iter().enumerate() {
+            let legal = replay.as_mut().is_some_and(|game| game.legal_moves().contains(m));
+            if ply >= first {
+                moves.push(match (&mut replay, legal) {
+                    (Some(game), true) => game.move_to_san(m),
+                    _ => m.to_string(),
+                });
+            }
+            if !legal || replay.as_mut().is_some_and(|game| game.make_move(m).is_err()) {
+                replay = None;
+            }
+        }
+        format!("{}{}", if first > 0 { "... " } else { "" }, moves.join(" "))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_of_valid_game() {
+        let mut game = Game::new();
+        for san in ["e4", "e5", "Nf3"] {
+            let m = game.parse_san(san).unwrap();
+            game.make_move(&m).unwrap();
+        }
+        let report = game.debug_report();
+        assert!(report.starts_with(&format!("FEN: {}\n", game.to_fen())));
+        assert!(report.contains("8  r  n  b  q  k  b  n  r\n"));
+        assert!(report.contains("Side to move: Black\n"));
+        assert!(report.contains("Castling: KQkq\n"));
+        assert!(report.contains("Last moves: e4 e5 Nf3\n"));
+        assert!(report.contains("Legal moves: 29\n"));
+        assert!(report.contains("State: Normal\n"));
+        assert!(report.ends_with("Validation:\n  no problems found"));
+    }
+
+    #[test]
+    fn report_of_corrupted_game() {
+        let mut game = Game::new();
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+        game.board().remove_tile(&"e8".parse().unwrap());
+        game.board().remove_tile(&"d1".parse().unwrap());
+
+        let report = game.debug_report();
+        assert!(report.starts_with("FEN: rnbq1bnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNB1KBNR b KQkq"));
+        assert!(report.contains("Last moves: e4\n"));
+        assert!(report.contains("Legal moves: unavailable"));
+        let validation = report.split("Validation:\n").nth(1).unwrap();
+        assert!(validation.contains("Error in Placement"), "{report}");
+    }
+
+    #[test]
+    fn report_with_pending_promotion() {
+        let mut game = Game::from_fen("7k/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.move_piece(&"a7".parse().unwrap(), &"a8".parse().unwrap()).unwrap();
+        let report = game.debug_report();
+        assert!(report.contains("Pending promotion: a8\n"));
+        assert!(report.contains("Last moves: a7a8\n"));
+    }
+}
@@ -0,0 +1,218 @@
+use std::{fmt, sync::Arc};
+
+use crate::{board::{Board, Tile}, pos::BoardPos};
+
+use super::{FenParseError, Game, Move, Outcome, Variant, STARTING_POSITION_FEN};
+
+/// The rules of a variant of chess, which a `Game` consults where variants
+/// differ from standard chess.
+///
+/// Every method has a default implementation that follows the standard rules,
+/// so a variant only implements what it changes. `StandardRules` keeps all the
+/// defaults. Games are created with rules by `Game::from_fen_with_rules`, and
+/// the built-in variants can be found with `Variant::rules`.
+///
+/// How the pieces move is the same in every variant, see `MovementPattern` for
+/// describing other pieces.
+pub trait Rules: fmt::Debug + Send + Sync {
+    /// Get the built-in variant that the rules implement, or `None` for rules
+    /// defined outside the crate.
+    fn variant(&self) -> Option<Variant> {
+        None
+    }
+
+    /// Get the FEN of the position that games start from.
+    fn starting_fen(&self) -> &str {
+        STARTING_POSITION_FEN
+    }
+
+    /// Whether a move that the standard rules allow is legal. The rules can only
+    /// forbid moves, not add new ones.
+    ///
+    /// The move, including the effects of `capture_effects`, has been made on the
+    /// board of the game when this is called, but the turn has not changed and
+    /// the promotion of the move is `None`. `captured` is the piece that was
+    /// captured, which includes a pawn taken en passant.
+    fn allows_move(&self, _game: &Game, _m: &Move, _captured: Option<Tile>) -> bool {
+        true
+    }
+
+    /// Get the tiles that a capture changes in addition to the move itself, for
+    /// example the pieces destroyed by an explosion in atomic chess. Each square
+    /// is given with the tile it gets, or `None` to empty it.
+    ///
+    /// The capturing piece has moved to `to` on the board. The effects are applied
+    /// both when moves are made and when checking whether moves are legal.
+    fn capture_effects(&self, _board: &Board, _to: &BoardPos, _captured: Tile) -> Vec<(BoardPos, Option<Tile>)> {
+        Vec::new()
+    }
+
+    /// Decide whether the game has ended by a rule of the variant. This is checked
+    /// after every move, before checkmate and stalemate.
+    ///
+    /// The outcome should be `Outcome::VariantEnd`, which `get_state` reports as
+    /// `GameState::VariantEnd`.
+    fn outcome(&self, _game: &mut Game) -> Option<Outcome> {
+        None
+    }
+
+    /// Get extra fields that `Game::to_fen` writes after the six standard fields,
+    /// or `None` to write none.
+    fn fen_extension(&self, _game: &Game) -> Option<String> {
+        None
+    }
+
+    /// Check the extra fields after the six standard fields of a FEN read by
+    /// `Game::from_fen_with_rules`. The extension is empty if there are none.
+    ///
+    /// # Errors
+    /// The reason the extension is invalid is returned. The default accepts any
+    /// extension, since standard FEN readers ignore extra fields.
+    fn read_fen_extension(&self, _extension: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The rules of standard chess.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct StandardRules;
+
+impl Rules for StandardRules {
+    fn variant(&self) -> Option<Variant> {
+        Some(Variant::Standard)
+    }
+}
+
+impl Game {
+
+    /// Create a new game played by the rules, starting from the position of the
+    /// rules.
+    ///
+    /// ## Panics
+    /// This method will panic if the starting FEN of the rules is invalid.
+    pub fn new_with_rules(rules: Arc<dyn Rules>) -> Game {
+        let fen = rules.starting_fen().to_string();
+        Self::from_fen_with_rules(&fen, rules).expect("The starting FEN of the rules is valid.")
+    }
+
+    /// Create a game played by the rules from a FEN string, see `from_fen`.
+    ///
+    /// # Errors
+    /// The same errors as `from_fen` are returned, and `InvalidExtension` if the
+    /// rules reject the extra fields of the FEN, see `Rules::read_fen_extension`.
+    pub fn from_fen_with_rules(fen: &str, rules: Arc<dyn Rules>) -> Result<Game, FenParseError<'_>> {
+        let mut game = Game::from_fen(fen)?;
+        let extension = fen.split_whitespace().skip(6).collect::<Vec<_>>().join(" ");
+        rules.read_fen_extension(&extension).map_err(FenParseError::InvalidExtension)?;
+
+        game.rules = rules;
+        game.invalidate_move_cache();
+        game.starting_fen = game.to_fen();
+        game.position_keys.clear();
+        game.record_position_key();
+        game.update_outcome();
+        Ok(game)
+    }
+
+    /// Get the rules that the game is played by.
+    pub fn rules(&self) -> &dyn Rules {
+        self.rules.as_ref()
+    }
+
+    /// Get the built-in variant that the game is played by, or `None` if it is
+    /// played by rules defined outside the crate.
+    pub fn variant(&self) -> Option<Variant> {
+        self.rules.variant()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::GameState, piece::PieceType};
+
+    use super::*;
+
+    /// A variant where pawns may not capture, not even en passant.
+    #[derive(Debug)]
+    struct PawnsDoNotCapture;
+
+    impl Rules for PawnsDoNotCapture {
+        fn allows_move(&self, game: &Game, m: &Move, captured: Option<Tile>) -> bool {
+            let pawn = game.get_tile(m.to()).is_some_and(|tile| tile.piece() == PieceType::Pawn);
+            !pawn || captured.is_none()
+        }
+
+        fn fen_extension(&self, _game: &Game) -> Option<String> {
+            Some("no-pawn-captures".to_string())
+        }
+
+        fn read_fen_extension(&self, extension: &str) -> Result<(), String> {
+            match extension {
+                "" | "no-pawn-captures" => Ok(()),
+                _ => Err(format!("Unknown extension {extension}.")),
+            }
+        }
+    }
+
+    /// A variant where a capturing piece is removed together with the piece it
+    /// captured.
+    #[derive(Debug)]
+    struct Kamikaze;
+
+    impl Rules for Kamikaze {
+        fn capture_effects(&self, _board: &Board, to: &BoardPos, _captured: Tile) -> Vec<(BoardPos, Option<Tile>)> {
+            vec![(to.clone(), None)]
+        }
+    }
+
+    fn moves(game: &mut Game) -> Vec<String> {
+        let mut moves: Vec<String> = game.legal_moves().iter().map(Move::to_string).collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn standard_rules_are_the_default() {
+        let mut game = Game::new();
+        assert_eq!(game.variant(), Some(Variant::Standard));
+        let mut standard = Game::new_with_rules(Arc::new(StandardRules));
+        assert_eq!(standard.to_fen(), game.to_fen());
+        assert_eq!(moves(&mut standard), moves(&mut game));
+    }
+
+    #[test]
+    fn pawns_do_not_capture() {
+        let fen = "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3";
+        let mut game = Game::from_fen_with_rules(fen, Arc::new(PawnsDoNotCapture)).unwrap();
+        assert_eq!(game.variant(), None);
+        assert_eq!(game.to_fen(), format!("{fen} no-pawn-captures"));
+        assert!(game.make_move(&"e5d6".parse().unwrap()).is_err());
+        game.make_move(&"e5e6".parse().unwrap()).unwrap();
+
+        // The pawn on e6 can not capture, but the other pieces still can.
+        let mut game = Game::from_fen_with_rules("4k3/3r4/4P3/8/8/8/8/3RK3 w - - 0 1", Arc::new(PawnsDoNotCapture)).unwrap();
+        assert!(!moves(&mut game).contains(&"e6d7".to_string()));
+        assert!(moves(&mut game).contains(&"d1d7".to_string()));
+        assert!(moves(&mut Game::from_fen("4k3/3r4/4P3/8/8/8/8/3RK3 w - - 0 1").unwrap()).contains(&"e6d7".to_string()));
+
+        assert!(Game::from_fen_with_rules(&format!("{fen} other"), Arc::new(PawnsDoNotCapture)).is_err());
+        game.reset();
+        assert_eq!(game.variant(), None);
+        assert_eq!(game.to_fen(), format!("{} no-pawn-captures", STARTING_POSITION_FEN));
+    }
+
+    #[test]
+    fn capture_effects() {
+        let mut game = Game::from_fen_with_rules("4k3/8/8/3r4/8/8/8/3RK3 w - - 0 1", Arc::new(Kamikaze)).unwrap();
+        game.make_move(&"d1d5".parse().unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+
+        // The effects are undone after checking whether moves are legal.
+        let fen = "3q4/8/8/8/8/8/3R4/3K2k1 w - - 0 1";
+        let mut game = Game::from_fen_with_rules(fen, Arc::new(Kamikaze)).unwrap();
+        assert!(moves(&mut game).contains(&"d2d8".to_string()));
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.get_state(), GameState::Normal);
+    }
+}
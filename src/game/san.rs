@@ -0,0 +1,454 @@
+use std::fmt;
+
+use crate::{pos::BoardPos, piece::PieceType};
+
+use super::{Game, GetMovesetError, Move, MovePieceError};
+
+/// Errors returned when resolving a move in Standard Algebraic Notation.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SanError {
+    /// The string is not a move in Standard Algebraic Notation.
+    InvalidSyntax,
+    /// No legal move matches the notation.
+    IllegalMove,
+    /// More than one legal move matches the notation. The matching moves are
+    /// included.
+    Ambiguous(Vec<Move>),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidSyntax => write!(f, "That is not a move in algebraic notation."),
+            Self::IllegalMove => write!(f, "That is not a legal move."),
+            Self::Ambiguous(moves) => {
+                let moves: Vec<String> = moves.iter().map(|m| format!("{}{}", m.from(), m.to())).collect();
+                write!(f, "The move is ambiguous, it could be any of {}.", moves.join(", "))
+            },
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+/// The letters used for the pieces in Standard Algebraic Notation, which differ
+/// between languages. Pawns have no letter.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PieceLetters {
+    pub king: char,
+    pub queen: char,
+    pub rook: char,
+    pub bishop: char,
+    pub knight: char,
+}
+
+impl PieceLetters {
+    pub const ENGLISH: PieceLetters = PieceLetters { king: 'K', queen: 'Q', rook: 'R', bishop: 'B', knight: 'N' };
+    pub const GERMAN: PieceLetters = PieceLetters { king: 'K', queen: 'D', rook: 'T', bishop: 'L', knight: 'S' };
+    pub const FRENCH: PieceLetters = PieceLetters { king: 'R', queen: 'D', rook: 'T', bishop: 'F', knight: 'C' };
+    pub const SPANISH: PieceLetters = PieceLetters { king: 'R', queen: 'D', rook: 'T', bishop: 'A', knight: 'C' };
+
+    /// Get the piece with the letter, or `None` if no piece has the letter.
+    fn piece(&self, letter: char) -> Option<PieceType> {
+        [
+            (self.king, PieceType::King),
+            (self.queen, PieceType::Queen),
+            (self.rook, PieceType::Rook),
+            (self.bishop, PieceType::Bishop),
+            (self.knight, PieceType::Knight),
+        ].into_iter().find(|&(char, _)| char == letter).map(|(_, piece)| piece)
+    }
+}
+
+impl Default for PieceLetters {
+    fn default() -> PieceLetters {
+        PieceLetters::ENGLISH
+    }
+}
+
+/// The parts of a move in Standard Algebraic Notation.
+struct San {
+    piece: PieceType,
+    from_file: Option<u8>,
+    from_rank: Option<u8>,
+    to: BoardPos,
+    promotion: Option<PieceType>,
+}
+
+enum ParsedSan {
+    Normal(San),
+    Castling { kingside: bool },
+}
+
+impl Game {
+
+    /// Find the legal move described by a move in Standard Algebraic Notation, for
+    /// example `Nf3`, `exd5`, `O-O` or `e8=Q+`.
+    ///
+    /// Check and checkmate suffixes and annotations like `!?` are ignored, and
+    /// castling may also be written with zeros, like `0-0`. The game is not
+    /// changed, use `make_move` to make the move.
+    ///
+    /// # Errors
+    /// `InvalidSyntax` is returned if the string can not be parsed, `IllegalMove`
+    /// if no legal move matches and `Ambiguous` if several legal moves match.
+    pub fn parse_san(&mut self, san: &str) -> Result<Move, SanError> {
+        self.parse_san_with_letters(san, &PieceLetters::ENGLISH)
+    }
+
+    /// Find the legal move described by a move in Standard Algebraic Notation
+    /// written with other piece letters, for example `Sf3` in German. See
+    /// `parse_san`.
+    ///
+    /// # Errors
+    /// The same errors as `parse_san` are returned.
+    pub fn parse_san_with_letters(&mut self, san: &str, letters: &PieceLetters) -> Result<Move, SanError> {
+        let parsed = parse(san, letters).ok_or(SanError::InvalidSyntax)?;
+        let piece = match &parsed {
+            ParsedSan::Normal(san) => san.piece,
+            ParsedSan::Castling { .. } => PieceType::King,
+        };
+
+        let mut candidates: Vec<Move> = self.legal_moves_of(piece)
+            .into_iter()
+            .filter(|m| self.matches_san(m, &parsed))
+            .collect();
+
+        match candidates.len() {
+            0 => Err(SanError::IllegalMove),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(SanError::Ambiguous(candidates)),
+        }
+    }
+
+    /// Make a move written in Standard Algebraic Notation, see `parse_san`.
+    ///
+    /// Promotions are completed in the same move, so `e8=Q+` promotes the pawn to
+    /// a queen. A promotion without a piece, like `e8`, is not a legal move.
+    ///
+    /// # Errors
+    /// The same errors as `parse_san` are returned, and the game is not changed.
+    pub fn move_san(&mut self, san: &str) -> Result<(), SanError> {
+        let m = self.parse_san(san)?;
+        self.make_move(&m).expect("Moves found by parse_san are legal.");
+        Ok(())
+    }
+
+    /// Write a move in Standard Algebraic Notation before it is made, for example
+    /// to show it in a move list.
+    ///
+    /// Pieces are only told apart by their file or rank when another piece of the
+    /// same type can move to the same square. En passant is written like any other
+    /// pawn capture, and the check or checkmate suffix is included. Castling may
+    /// also be given as the king moving onto its own rook, like in `move_piece`.
+    /// The move returned by `parse_san` for the notation is the same move.
+    ///
+    /// # Errors
+    /// The errors are the same as for `make_move`: `GameOver`, `NoTile`,
+    /// `NotCurrentTurn`, and `InvalidMove` if the move is not legal, the promotion
+    /// piece is missing or not allowed, or a promotion is pending.
+    pub fn san_for_move(&mut self, from: &BoardPos, to: &BoardPos, promotion: Option<PieceType>) -> Result<String, MovePieceError> {
+        let mut moves = Vec::new();
+        self.legal_moves_from_into(from, &mut moves).map_err(|err| match err {
+            GetMovesetError::NoTile => MovePieceError::NoTile,
+            GetMovesetError::NotCurrentTurn => MovePieceError::NotCurrentTurn,
+            GetMovesetError::GameOver => MovePieceError::GameOver,
+        })?;
+
+        let to = self.castling_by_rook(from, to).unwrap_or_else(|| to.clone());
+        let m = Move::new(from.clone(), to, promotion);
+        if self.promotion_required.is_some() || !moves.contains(&m) {
+            return Err(MovePieceError::InvalidMove);
+        }
+        Ok(self.move_to_san(&m))
+    }
+
+    /// Write a legal move in Standard Algebraic Notation, including the check or
+    /// checkmate suffix.
+    ///
+    /// ## Panics
+    /// This method will panic if the move is not legal.
+    pub(crate) fn move_to_san(&mut self, m: &Move) -> String {
+        let tile = self.board.get_tile(m.from()).expect("The move must be legal.");
+        let is_castling = tile.piece() == PieceType::King && m.from().file().abs_diff(m.to().file()) == 2;
+
+        let mut san = String::new();
+        if is_castling {
+            san.push_str(if m.to().file() > m.from().file() { "O-O" } else { "O-O-O" });
+        } else {
+            let is_capture = self.board.get_tile(m.to()).is_some()
+                || (tile.piece() == PieceType::Pawn && m.from().file() != m.to().file());
+
+            if tile.piece() == PieceType::Pawn {
+                if is_capture {
+                    san.push(m.from().file_char());
+                }
+            } else {
+                san.push(tile.piece().char().to_ascii_uppercase());
+                san.push_str(&self.disambiguation(m));
+            }
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&m.to().to_string());
+            if let Some(promotion) = m.promotion() {
+                san.push('=');
+                san.push(promotion.char().to_ascii_uppercase());
+            }
+        }
+
+        let mut after = self.clone();
+        after.make_move(m).expect("The move must be legal.");
+        let opponent = after.current_turn;
+        if after.is_checkmate(&opponent) {
+            san.push('#');
+        } else if after.is_check(&opponent) {
+            san.push('+');
+        }
+        san
+    }
+
+    /// Get the shortest prefix that tells the piece apart from the other pieces
+    /// of the same type that can move to the same square.
+    fn disambiguation(&mut self, m: &Move) -> String {
+        let piece = self.board.get_tile(m.from()).expect("The move must be legal.").piece();
+        let others: Vec<Move> = self.legal_moves_of(piece)
+            .into_iter()
+            .filter(|other| other.to() == m.to() && other.from() != m.from())
+            .collect();
+
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|other| other.from().file() != m.from().file()) {
+            m.from().file_char().to_string()
+        } else if others.iter().all(|other| other.from().rank() != m.from().rank()) {
+            (m.from().rank() + 1).to_string()
+        } else {
+            m.from().to_string()
+        }
+    }
+
+    fn matches_san(&self, m: &Move, parsed: &ParsedSan) -> bool {
+        let tile = match self.board.get_tile(m.from()) {
+            Some(tile) => tile,
+            None => return false,
+        };
+        match parsed {
+            ParsedSan::Castling { kingside } => {
+                tile.piece() == PieceType::King
+                    && m.from().rank() == m.to().rank()
+                    && m.from().file().abs_diff(m.to().file()) == 2
+                    && (m.to().file() > m.from().file()) == *kingside
+            },
+            ParsedSan::Normal(san) => {
+                tile.piece() == san.piece
+                    && *m.to() == san.to
+                    && m.promotion() == san.promotion
+                    && san.from_file.is_none_or(|file| m.from().file() == file)
+                    && san.from_rank.is_none_or(|rank| m.from().rank() == rank)
+                    // Castling is only written as O-O or O-O-O.
+                    && !(san.piece == PieceType::King && m.from().file().abs_diff(m.to().file()) == 2)
+            },
+        }
+    }
+}
+
+/// Parse the notation without looking at the position.
+fn parse(san: &str, letters: &PieceLetters) -> Option<ParsedSan> {
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+
+    match san {
+        "O-O" | "0-0" => return Some(ParsedSan::Castling { kingside: true }),
+        "O-O-O" | "0-0-0" => return Some(ParsedSan::Castling { kingside: false }),
+        _ => {},
+    }
+
+    if !san.is_ascii() {
+        return None;
+    }
+
+    let mut rest = san;
+
+    let piece = letters.piece(rest.chars().next()?);
+    if piece.is_some() {
+        rest = &rest[1..];
+    }
+    let piece = piece.unwrap_or(PieceType::Pawn);
+
+    // The promotion is written as `=Q`, but `Q` on its own is also accepted.
+    let mut promotion = None;
+    if piece == PieceType::Pawn {
+        if let Some(last) = rest.chars().last() {
+            let promotion_piece = letters.piece(last).filter(|&piece| piece != PieceType::King);
+            if promotion_piece.is_some() {
+                promotion = promotion_piece;
+                rest = &rest[..rest.len() - 1];
+                rest = rest.strip_suffix('=').unwrap_or(rest);
+            }
+        }
+    }
+
+    if rest.len() < 2 {
+        return None;
+    }
+    let to: BoardPos = rest[rest.len() - 2..].parse().ok()?;
+    let mut disambiguation = &rest[..rest.len() - 2];
+    disambiguation = disambiguation.strip_suffix('x').unwrap_or(disambiguation);
+
+    let mut from_file = None;
+    let mut from_rank = None;
+    for char in disambiguation.chars() {
+        match char {
+            'a'..='h' if from_file.is_none() && from_rank.is_none() => from_file = Some(char as u8 - b'a'),
+            '1'..='8' if from_rank.is_none() => from_rank = Some(char as u8 - b'1'),
+            _ => return None,
+        }
+    }
+
+    Some(ParsedSan::Normal(San { piece, from_file, from_rank, to, promotion }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::Color, game::Outcome};
+
+    use super::*;
+
+    fn play(game: &mut Game, moves: &str) {
+        for san in moves.split_whitespace() {
+            let m = game.parse_san(san).unwrap_or_else(|err| panic!("{san}: {err}"));
+            game.make_move(&m).unwrap();
+        }
+    }
+
+    #[test]
+    fn play_opening() {
+        let mut game = Game::new();
+        play(&mut game, "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6 dxc6 O-O f6 d4 exd4 Nxd4 c5 Nb3 Qxd1 Rxd1");
+
+        assert_eq!(game.to_fen(), "r1b1kbnr/1pp3pp/p4p2/2p5/4P3/1N6/PPP2PPP/RNBR2K1 b kq - 0 9");
+    }
+
+    #[test]
+    fn opera_game() {
+        // Morphy vs the Duke of Brunswick and Count Isouard, Paris 1858.
+        let mut game = Game::new();
+        let moves = "e4 e5 Nf3 d6 d4 Bg4 dxe5 Bxf3 Qxf3 dxe5 Bc4 Nf6 Qb3 Qe7 Nc3 c6 Bg5 b5 Nxb5 cxb5 \
+            Bxb5+ Nbd7 O-O-O Rd8 Rxd7 Rxd7 Rd1 Qe6 Bxd7+ Nxd7 Qb8+ Nxb8 Rd8#";
+        for san in moves.split_whitespace() {
+            game.move_san(san).unwrap_or_else(|err| panic!("{san}: {err}"));
+        }
+        assert_eq!(game.to_fen(), "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17");
+        assert_eq!(game.outcome(), Some(Outcome::Checkmate { winner: Color::White }));
+    }
+
+    #[test]
+    fn move_san_errors() {
+        let mut game = Game::from_fen("4k3/1P6/8/8/8/8/8/R3K2R w K - 0 1").unwrap();
+        let fen = game.to_fen();
+        assert_eq!(game.move_san("O-O-O"), Err(SanError::IllegalMove));
+        assert_eq!(game.move_san("b8"), Err(SanError::IllegalMove));
+        assert_eq!(game.move_san("Rb1x"), Err(SanError::InvalidSyntax));
+        assert_eq!(game.to_fen(), fen);
+
+        game.move_san("b8=N").unwrap();
+        assert_eq!(game.get_tile(&"b8".parse().unwrap()).unwrap().piece(), PieceType::Knight);
+        game.move_san("Kf7").unwrap();
+        game.move_san("O-O+").unwrap();
+        assert!(game.to_fen().starts_with("1N6/5k2/8/8/8/8/8/R4RK1 b - -"));
+    }
+
+    #[test]
+    fn disambiguation() {
+        let mut game = Game::from_fen("4k3/8/8/R7/8/5N2/8/RN2K2R w - - 0 1").unwrap();
+
+        assert!(matches!(game.parse_san("Nd2"), Err(SanError::Ambiguous(moves)) if moves.len() == 2));
+        assert_eq!(game.parse_san("Nbd2").unwrap().from(), &"b1".parse().unwrap());
+        assert_eq!(game.parse_san("Nfxd2").unwrap().from(), &"f3".parse().unwrap());
+        assert_eq!(game.parse_san("R1a3").unwrap().from(), &"a1".parse().unwrap());
+        assert_eq!(game.parse_san("R5a3").unwrap().from(), &"a5".parse().unwrap());
+        assert_eq!(game.parse_san("Rb1"), Err(SanError::IllegalMove));
+    }
+
+    #[test]
+    fn promotion_and_en_passant() {
+        let mut game = Game::from_fen("4k3/2P5/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+
+        let m = game.parse_san("exd6").unwrap();
+        assert_eq!(m, Move::new("e5".parse().unwrap(), "d6".parse().unwrap(), None));
+
+        let m = game.parse_san("c8=Q+").unwrap();
+        assert_eq!(m.promotion(), Some(PieceType::Queen));
+        assert_eq!(game.parse_san("c8N").unwrap().promotion(), Some(PieceType::Knight));
+        assert_eq!(game.parse_san("c8"), Err(SanError::IllegalMove));
+    }
+
+    #[test]
+    fn moves_to_san() {
+        let mut game = Game::from_fen("4k3/2P5/8/R2pP3/8/5N2/8/RN2K2R w K d6 0 1").unwrap();
+        let san = |game: &mut Game, from: &str, to: &str, promotion| {
+            game.move_to_san(&Move::new(from.parse().unwrap(), to.parse().unwrap(), promotion))
+        };
+
+        assert_eq!(san(&mut game, "b1", "d2", None), "Nbd2");
+        assert_eq!(san(&mut game, "a1", "a3", None), "R1a3");
+        assert_eq!(san(&mut game, "e5", "d6", None), "exd6");
+        assert_eq!(san(&mut game, "c7", "c8", Some(PieceType::Queen)), "c8=Q+");
+        assert_eq!(san(&mut game, "e1", "g1", None), "O-O");
+        assert_eq!(san(&mut game, "a5", "a8", None), "Ra8+");
+
+        // Every legal move is parsed back to the same move.
+        for fen in ["4k3/2P5/8/R2pP3/8/5N2/8/RN2K2R w K d6 0 1", "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1"] {
+            let mut game = Game::from_fen(fen).unwrap();
+            for m in game.legal_moves() {
+                let san = game.move_to_san(&m);
+                assert_eq!(game.parse_san(&san), Ok(m), "{san}");
+            }
+        }
+    }
+
+    #[test]
+    fn san_for_move() {
+        let pos = |pos: &str| pos.parse::<BoardPos>().unwrap();
+        let mut game = Game::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        assert_eq!(game.san_for_move(&pos("e5"), &pos("d6"), None).unwrap(), "exd6");
+        assert_eq!(game.san_for_move(&pos("b7"), &pos("a8"), Some(PieceType::Knight)).unwrap(), "bxa8=N");
+        assert_eq!(game.san_for_move(&pos("e1"), &pos("h1"), None).unwrap(), "O-O");
+        assert_eq!(game.san_for_move(&pos("a1"), &pos("a8"), None).unwrap(), "Rxa8+");
+
+        assert!(matches!(game.san_for_move(&pos("b7"), &pos("b8"), None), Err(MovePieceError::InvalidMove)));
+        assert!(matches!(game.san_for_move(&pos("e5"), &pos("e7"), None), Err(MovePieceError::InvalidMove)));
+        assert!(matches!(game.san_for_move(&pos("e4"), &pos("e5"), None), Err(MovePieceError::NoTile)));
+        assert!(matches!(game.san_for_move(&pos("d5"), &pos("d4"), None), Err(MovePieceError::NotCurrentTurn)));
+
+        for m in game.legal_moves() {
+            let san = game.san_for_move(m.from(), m.to(), m.promotion()).unwrap();
+            assert_eq!(game.parse_san(&san), Ok(m), "{san}");
+        }
+
+        let mut game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(game.san_for_move(&pos("a1"), &pos("a8"), None).unwrap(), "Ra8#");
+        game.make_move(&"a1a8".parse().unwrap()).unwrap();
+        assert!(matches!(game.san_for_move(&pos("g8"), &pos("h8"), None), Err(MovePieceError::GameOver)));
+    }
+
+    #[test]
+    fn localized_letters() {
+        let mut game = Game::from_fen("r3k3/1P6/8/8/8/8/8/R3K2R w KQq - 0 1").unwrap();
+
+        assert_eq!(game.parse_san_with_letters("Td1", &PieceLetters::GERMAN), game.parse_san("Rd1"));
+        assert_eq!(game.parse_san_with_letters("Re2", &PieceLetters::FRENCH), game.parse_san("Ke2"));
+        assert_eq!(game.parse_san_with_letters("bxa8=C", &PieceLetters::SPANISH), game.parse_san("bxa8=N"));
+        assert_eq!(game.parse_san("0-0-0"), game.parse_san("O-O-O"));
+        assert_eq!(game.parse_san_with_letters("Sd2", &PieceLetters::ENGLISH), Err(SanError::InvalidSyntax));
+    }
+
+    #[test]
+    fn invalid_syntax() {
+        let mut game = Game::new();
+        for san in ["", "Z4", "e9", "Nf", "Nabc3", "e4-e5"] {
+            assert_eq!(game.parse_san(san), Err(SanError::InvalidSyntax), "{san}");
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{board::Color, pos::BoardPos};
+
+use super::{Annotation, Game, Move, Outcome, Variant};
+
+/// The version of the save format written by `Game::to_saved`.
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+/// A complete game that can be stored with serde and loaded again with
+/// `Game::from_saved`.
+///
+/// Unlike a FEN, which only describes the current position, this includes
+/// everything needed to continue the game: the starting position, every move
+/// made since then, a pending promotion, annotations and adjudication.
+///
+/// The position after the moves is not stored. It is recreated by replaying the
+/// moves when the game is loaded, so a corrupted save is detected rather than
+/// trusted. The clocks and the pending promotion are stored to check the replay.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub version: u32,
+    /// The variant of the game. Saves without it are standard chess.
+    #[serde(default)]
+    pub variant: Variant,
+    pub starting_fen: String,
+    /// The moves in coordinate notation separated by spaces, like `e2e4 e7e5`.
+    /// A move waiting for a promotion is written without the promotion.
+    pub moves: String,
+    pub pending_promotion: Option<BoardPos>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    /// The annotations of the moves, by ply. See `Game::annotate`.
+    #[serde(default)]
+    pub annotations: BTreeMap<usize, Vec<Annotation>>,
+    #[serde(default)]
+    pub adjudication: Option<SavedAdjudication>,
+}
+
+/// How a saved game was adjudicated, see `Game::adjudicate`.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct SavedAdjudication {
+    /// The team that won, or `None` for a draw.
+    pub winner: Option<Color>,
+    pub note: String,
+}
+
+/// Errors returned when loading a saved game.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SavedGameError {
+    /// The save was written by a newer version of the format.
+    UnsupportedVersion(u32),
+    /// The starting FEN is not valid.
+    InvalidFen,
+    /// A move could not be read or is not legal. The ply starts at 1 for the
+    /// first move.
+    InvalidMove { ply: usize },
+    /// An annotation belongs to a move that does not exist.
+    InvalidAnnotation { ply: usize },
+    /// The replayed game does not match the saved value with this name.
+    Mismatch(&'static str),
+}
+
+impl fmt::Display for SavedGameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => write!(f, "Save format version {version} is not supported."),
+            Self::InvalidFen => write!(f, "The starting FEN is invalid."),
+            Self::InvalidMove { ply } => write!(f, "Move {ply} is invalid."),
+            Self::InvalidAnnotation { ply } => write!(f, "There is no move {ply} to annotate."),
+            Self::Mismatch(name) => write!(f, "The saved {name} does not match the moves."),
+        }
+    }
+}
+
+impl std::error::Error for SavedGameError {}
+
+impl Game {
+
+    /// Save the game so that it can be stored with serde, see `SavedGame`.
+    ///
+    /// Only the built-in variants are saved. A game played by rules defined
+    /// outside the crate is saved as standard chess.
+    pub fn to_saved(&self) -> SavedGame {
+        let moves: Vec<String> = self.history.iter().enumerate()
+            .map(|(index, m)| {
+                let pending = index + 1 == self.history.len() && self.promotion_required.is_some();
+                if pending { Move::new(m.from().clone(), m.to().clone(), None) } else { m.clone() }
+            })
+            .map(|m| m.to_string())
+            .collect();
+        let adjudication = match self.outcome {
+            Some(Outcome::Adjudicated { winner }) => Some(SavedAdjudication {
+                winner,
+                note: self.adjudication_note.clone().unwrap_or_default(),
+            }),
+            _ => None,
+        };
+
+        SavedGame {
+            version: SAVE_FORMAT_VERSION,
+            variant: self.variant().unwrap_or_default(),
+            starting_fen: self.starting_fen.clone(),
+            moves: moves.join(" "),
+            pending_promotion: self.promotion_required.clone(),
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            annotations: self.annotations.clone(),
+            adjudication,
+        }
+    }
+
+    /// Load a game saved with `to_saved` by replaying its moves from the starting
+    /// position.
+    ///
+    /// # Errors
+    /// `UnsupportedVersion` is returned for saves from newer versions of the
+    /// format. `InvalidFen` and `InvalidMove` are returned if the starting
+    /// position or a move can not be read or replayed, `InvalidAnnotation` if an
+    /// annotation belongs to a move that does not exist and `Mismatch` if the
+    /// replayed game does not match the clocks, the pending promotion or the
+    /// adjudication of the save.
+    pub fn from_saved(saved: &SavedGame) -> Result<Game, SavedGameError> {
+        if saved.version > SAVE_FORMAT_VERSION {
+            return Err(SavedGameError::UnsupportedVersion(saved.version));
+        }
+        let mut game = Game::from_fen_with_variant(&saved.starting_fen, saved.variant).map_err(|_| SavedGameError::InvalidFen)?;
+
+        for (index, text) in saved.moves.split_whitespace().enumerate() {
+            let invalid = SavedGameError::InvalidMove { ply: index + 1 };
+            // Only the last move may leave a promotion pending.
+            if game.promotion_required.is_some() {
+                return Err(invalid);
+            }
+            let m: Move = text.parse().map_err(|_| invalid.clone())?;
+            game.move_piece(m.from(), m.to()).map_err(|_| invalid.clone())?;
+            match (m.promotion(), game.promotion_required.is_some()) {
+                (Some(piece), true) => game.promote(piece),
+                (Some(_), false) => return Err(invalid),
+                (None, _) => {},
+            }
+        }
+
+        if game.promotion_required != saved.pending_promotion {
+            return Err(SavedGameError::Mismatch("pending promotion"));
+        }
+        if game.halfmove_clock != saved.halfmove_clock {
+            return Err(SavedGameError::Mismatch("halfmove clock"));
+        }
+        if game.fullmove_number != saved.fullmove_number {
+            return Err(SavedGameError::Mismatch("fullmove number"));
+        }
+
+        if let Some((&ply, _)) = saved.annotations.iter().find(|(&ply, _)| ply == 0 || ply > game.history.len()) {
+            return Err(SavedGameError::InvalidAnnotation { ply });
+        }
+        game.annotations = saved.annotations.clone();
+
+        if let Some(adjudication) = &saved.adjudication {
+            game.adjudicate(Outcome::Adjudicated { winner: adjudication.winner }, &adjudication.note)
+                .map_err(|_| SavedGameError::Mismatch("adjudication"))?;
+        }
+        Ok(game)
+    }
+}
+
+/// Serializes the game as a `SavedGame`.
+impl Serialize for Game {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_saved().serialize(serializer)
+    }
+}
+
+/// Deserializes a `SavedGame` and replays it with `Game::from_saved`.
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = SavedGame::deserialize(deserializer)?;
+        Game::from_saved(&saved).map_err(serde::de::Error::custom)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::GameState, piece::PieceType};
+
+    use super::*;
+
+    /// A game where white is about to promote on b8.
+    fn promotion_game() -> Game {
+        let mut game = Game::new();
+        for san in ["a4", "b5", "axb5", "a6", "bxa6", "Nf6", "a7", "Ne4", "axb8=Q", "Ba6", "Qxa8"] {
+            let m = game.parse_san(san).unwrap();
+            game.make_move(&m).unwrap();
+        }
+        game.annotate(9, Annotation::Nag(1));
+        game.annotate(10, Annotation::Comment("Black is lost".to_string()));
+        game
+    }
+
+    #[test]
+    fn serde_json_round_trip() {
+        let game = promotion_game();
+        let json = serde_json::to_string(&game).unwrap();
+        let loaded: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.to_fen(), game.to_fen());
+        assert_eq!(loaded.history(), game.history());
+        assert_eq!(loaded.annotations(10), game.annotations(10));
+        assert_eq!(loaded.to_saved(), game.to_saved());
+    }
+
+    #[test]
+    fn arrows_and_highlights() {
+        let mut game = promotion_game();
+        game.add_arrow(10, "a6".parse().unwrap(), "f1".parse().unwrap(), 'R');
+        game.add_highlight(11, "a8".parse().unwrap(), 'G');
+        let json = serde_json::to_string(&game).unwrap();
+        let loaded: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.arrows(10), [("a6".parse().unwrap(), "f1".parse().unwrap(), 'R')]);
+        assert_eq!(loaded.highlights(11), game.highlights(11));
+        assert_eq!(loaded.annotations(10), game.annotations(10));
+    }
+
+    #[test]
+    fn pending_promotion() {
+        let mut game = Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 40").unwrap();
+        game.move_piece(&"b7".parse().unwrap(), &"b8".parse().unwrap()).unwrap();
+        let saved = game.to_saved();
+        assert_eq!(saved.moves, "b7b8");
+        assert_eq!(saved.pending_promotion, Some("b8".parse().unwrap()));
+
+        let json = serde_json::to_string(&saved).unwrap();
+        let mut loaded: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.get_state(), GameState::PromotionRequired("b8".parse().unwrap()));
+        loaded.promote(PieceType::Queen);
+        assert_eq!(loaded.history()[0].to_string(), "b7b8q");
+        assert_eq!(loaded.current_turn(), Color::Black);
+    }
+
+    #[test]
+    fn adjudication() {
+        let mut game = promotion_game();
+        game.adjudicate(Outcome::Adjudicated { winner: Some(Color::White) }, "Black resigned").unwrap();
+        let loaded = Game::from_saved(&game.to_saved()).unwrap();
+        assert_eq!(loaded.outcome(), game.outcome());
+        assert_eq!(loaded.adjudication_note(), Some("Black resigned"));
+    }
+
+    #[test]
+    fn variant() {
+        let mut game = Game::new_variant(Variant::RacingKings);
+        game.make_move(&"h2h3".parse().unwrap()).unwrap();
+        let json = serde_json::to_string(&game).unwrap();
+        let loaded: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.variant(), Some(Variant::RacingKings));
+        assert_eq!(loaded.to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn corrupted_saves() {
+        let saved = promotion_game().to_saved();
+
+        let mut corrupted = saved.clone();
+        corrupted.moves = corrupted.moves.replacen("b5", "b4", 1);
+        assert_eq!(Game::from_saved(&corrupted).unwrap_err(), SavedGameError::InvalidMove { ply: 2 });
+
+        let mut corrupted = saved.clone();
+        corrupted.moves = corrupted.moves.replace("a7b8q", "a7b8");
+        assert_eq!(Game::from_saved(&corrupted).unwrap_err(), SavedGameError::InvalidMove { ply: 10 });
+
+        let mut corrupted = saved.clone();
+        corrupted.fullmove_number += 1;
+        assert_eq!(Game::from_saved(&corrupted).unwrap_err(), SavedGameError::Mismatch("fullmove number"));
+
+        let mut corrupted = saved.clone();
+        corrupted.annotations.insert(12, vec![Annotation::Nag(2)]);
+        assert_eq!(Game::from_saved(&corrupted).unwrap_err(), SavedGameError::InvalidAnnotation { ply: 12 });
+
+        let mut corrupted = saved.clone();
+        corrupted.version = SAVE_FORMAT_VERSION + 1;
+        assert!(matches!(Game::from_saved(&corrupted), Err(SavedGameError::UnsupportedVersion(_))));
+
+        let err = serde_json::from_str::<Game>(r#"{"version":1,"starting_fen":"8/8","moves":"","pending_promotion":null,"halfmove_clock":0,"fullmove_number":1}"#).unwrap_err();
+        assert!(err.to_string().contains("starting FEN"));
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashSet;
+
+use crate::{attacks, board::{Color, Tile}, pos::BoardPos, piece::PieceType};
+
+use super::Game;
+
+impl Game {
+
+    /// Get the positions of the current team's pieces that the opponent could
+    /// capture on their next move.
+    ///
+    /// This is the same as calling `threatened_pieces` with the color of the
+    /// current turn. See that method for more information.
+    pub fn threatened_squares(&mut self) -> HashSet<BoardPos> {
+        let color = self.current_turn;
+        self.threatened_pieces(&color)
+    }
+
+    /// Get the positions of the pieces of the specified color that the opposing
+    /// team could capture on their next move.
+    ///
+    /// Only legal captures are considered. An enemy piece that is pinned to its
+    /// king does therefore not threaten pieces that it could only capture by
+    /// exposing its own king.
+    ///
+    /// If `color` is the team whose turn it currently is, the opponent's moves are
+    /// computed as if the current team had passed their turn. The en passant target
+    /// is ignored in that case since it can only be used by the current team.
+    pub fn threatened_pieces(&mut self, color: &Color) -> HashSet<BoardPos> {
+        let enemy_color = color.opposite();
+
+        // En passant is only possible for the team whose turn it is.
+        let en_passant_target = self.en_passant_target.take();
+        if enemy_color == self.current_turn {
+            self.en_passant_target = en_passant_target.clone();
+        }
+
+        let mut threatened = HashSet::new();
+
+        for file in 0..8 {
+            for rank in 0..8 {
+                let enemy_pos = BoardPos::new(file, rank);
+                let tile = match self.board.get_tile(&enemy_pos) {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+                if tile.color() != enemy_color {
+                    continue;
+                }
+
+                for move_pos in self.get_legal_moves_unchecked(&enemy_pos) {
+                    if let Some(target) = self.board.get_tile(&move_pos) {
+                        if target.color() == *color {
+                            threatened.insert(move_pos);
+                        }
+                    } else if tile.piece() == PieceType::Pawn
+                        && self.en_passant_target.as_ref() == Some(&move_pos) {
+                        // The pawn being taken en passant is not standing on the
+                        // square that the attacking pawn moves to.
+                        threatened.insert(BoardPos::new(move_pos.file(), enemy_pos.rank()));
+                    }
+                }
+            }
+        }
+
+        self.en_passant_target = en_passant_target;
+
+        threatened
+    }
+
+    /// Get the positions of the pieces of the specified color that attack the
+    /// position.
+    ///
+    /// A piece attacks a position if it could capture an enemy piece standing
+    /// there. Pawns therefore only attack diagonally. Whether the capture would
+    /// expose the attacker's own king is not considered.
+    ///
+    /// The positions are sorted by file and then by rank.
+    pub fn attackers(&mut self, pos: &BoardPos, color: &Color) -> Vec<BoardPos> {
+        // Pretend that an enemy piece is standing on the square so that every piece
+        // that can capture there, including pawns, will have it in its moveset.
+        let original = self.board.get_tile(pos);
+        self.board.set_tile(pos, Tile::new(PieceType::Pawn, color.opposite()));
+
+        let mut attackers = Vec::new();
+        for file in 0..8 {
+            for rank in 0..8 {
+                let attacker_pos = BoardPos::new(file, rank);
+                let tile = match self.board.get_tile(&attacker_pos) {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+                if tile.color() != *color {
+                    continue;
+                }
+                if self.get_pseudo_legal_moves(&attacker_pos, false).contains(pos) {
+                    attackers.push(attacker_pos);
+                }
+            }
+        }
+
+        self.board.set_or_remove_tile(pos, original);
+
+        attackers
+    }
+
+    /// Get the positions of the pieces of the specified color that would be able to
+    /// recapture on the position if a piece standing there was taken.
+    ///
+    /// If `xray` is `true`, pieces that only defend the position once the pieces in
+    /// front of them have recaptured are also included, for example the rear rook
+    /// of two rooks on the same file. These are listed after the direct defenders,
+    /// in the order they would get to recapture.
+    pub fn defenders(&mut self, pos: &BoardPos, color: &Color, xray: bool) -> Vec<BoardPos> {
+        let mut defenders = self.attackers(pos, color);
+        if !xray {
+            return defenders;
+        }
+
+        // Temporarily remove the defenders to uncover the pieces behind them.
+        let mut removed = Vec::new();
+        let mut uncovered = defenders.clone();
+        while !uncovered.is_empty() {
+            for defender_pos in &uncovered {
+                let tile = self.board.remove_tile(defender_pos);
+                removed.push((defender_pos.clone(), tile));
+            }
+            uncovered = self.attackers(pos, color);
+            defenders.extend(uncovered.iter().cloned());
+        }
+
+        for (removed_pos, tile) in removed {
+            self.board.set_or_remove_tile(&removed_pos, tile);
+        }
+
+        defenders
+    }
+
+    /// Get the enemy pieces attacking the king of the specified color and each
+    /// square around it, for shading the squares that the king can not move to.
+    ///
+    /// The king is removed from the board while the attackers are found, so a
+    /// rook or other sliding piece checking the king also attacks the square
+    /// behind the king, which the king can not escape to. Squares occupied by
+    /// pieces of the king's own team are included, with the enemy pieces that
+    /// would recapture there. See `attackers` for what counts as an attack.
+    ///
+    /// The squares are ordered by rank and then by file, each listed with the
+    /// attackers, which may be none. If the team has no king, the list is empty.
+    pub fn king_danger_squares(&mut self, color: &Color) -> Vec<(BoardPos, Vec<BoardPos>)> {
+        let Some(king_pos) = self.get_king_pos(color) else { return Vec::new() };
+        let mut squares = attacks::king_attacks(&king_pos);
+        squares.insert(&king_pos);
+
+        let king = self.board.remove_tile(&king_pos);
+        let danger = squares.iter()
+            .map(|pos| {
+                let attackers = self.attackers(&pos, &color.opposite());
+                (pos, attackers)
+            })
+            .collect();
+        self.board.set_or_remove_tile(&king_pos, king);
+
+        danger
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_attacker_is_not_a_threat() {
+        // The black knight on e5 could take the queen on d3, but it is pinned to the
+        // black king by the rook on e1.
+        let mut game = Game::from_fen("4k3/8/p7/1N2n3/8/3Q4/8/K3R3 w - - 0 1").unwrap();
+
+        let expected: HashSet<BoardPos> = ["b5".parse().unwrap()].into();
+        assert_eq!(game.threatened_squares(), expected);
+    }
+
+    #[test]
+    fn unpinned_attacker_is_a_threat() {
+        let mut game = Game::from_fen("4k3/8/p7/1N2n3/8/3Q4/8/K6R w - - 0 1").unwrap();
+
+        let expected: HashSet<BoardPos> = ["b5".parse().unwrap(), "d3".parse().unwrap()].into();
+        assert_eq!(game.threatened_squares(), expected);
+    }
+
+    #[test]
+    fn threatened_opponent_pieces() {
+        let mut game = Game::from_fen("4k3/8/p7/1N2n3/8/3Q4/8/K3R3 w - - 0 1").unwrap();
+
+        let expected: HashSet<BoardPos> = ["e5".parse().unwrap()].into();
+        assert_eq!(game.threatened_pieces(&Color::Black), expected);
+    }
+
+    #[test]
+    fn threatened_en_passant() {
+        let mut game = Game::from_fen("4k3/8/8/8/2pP4/8/8/4K3 b - d3 0 1").unwrap();
+
+        let expected: HashSet<BoardPos> = ["d4".parse().unwrap()].into();
+        assert_eq!(game.threatened_pieces(&Color::White), expected);
+
+        // The en passant target is not affected by the call.
+        assert_eq!(game.to_fen(), "4k3/8/8/8/2pP4/8/8/4K3 b - d3 0 1");
+    }
+    #[test]
+    fn pawn_defended_by_knight() {
+        let mut game = Game::from_fen("4k3/8/8/4P3/8/3N4/8/4K3 w - - 0 1").unwrap();
+        let pos = "e5".parse().unwrap();
+
+        assert_eq!(game.defenders(&pos, &Color::White, false), ["d3".parse().unwrap()]);
+        assert_eq!(game.defenders(&pos, &Color::White, true), ["d3".parse().unwrap()]);
+        assert!(game.defenders(&pos, &Color::Black, false).is_empty());
+    }
+
+    #[test]
+    fn doubled_rook_defenders() {
+        let mut game = Game::from_fen("4k3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1").unwrap();
+        let pos = "d5".parse().unwrap();
+
+        let direct: Vec<BoardPos> = vec!["d2".parse().unwrap()];
+        let xray: Vec<BoardPos> = vec!["d2".parse().unwrap(), "d1".parse().unwrap()];
+        assert_eq!(game.defenders(&pos, &Color::White, false), direct);
+        assert_eq!(game.defenders(&pos, &Color::White, true), xray);
+
+        // The board is restored after looking for x-ray defenders.
+        assert_eq!(game.to_fen(), "4k3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1");
+    }
+
+    #[test]
+    fn pawns_only_attack_diagonally() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+
+        assert_eq!(game.attackers(&"d3".parse().unwrap(), &Color::White), ["e2".parse().unwrap()]);
+        assert!(game.attackers(&"e3".parse().unwrap(), &Color::White).is_empty());
+    }
+
+    #[test]
+    fn rook_attacks_through_king() {
+        // The rook checks the king along the eighth rank, so f8 is not safe either.
+        let mut game = Game::from_fen("R3k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        let danger = game.king_danger_squares(&Color::Black);
+        assert_eq!(danger.len(), 6);
+
+        let rook: BoardPos = "a8".parse().unwrap();
+        let attacked: Vec<String> = danger.iter()
+            .filter(|(_, attackers)| attackers.contains(&rook))
+            .map(|(pos, _)| pos.to_string())
+            .collect();
+        assert_eq!(attacked, ["d8", "e8", "f8"]);
+        assert!(danger.iter().all(|(_, attackers)| attackers.len() <= 1));
+        assert_eq!(game.to_fen(), "R3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
+}
@@ -0,0 +1,284 @@
+use std::io::{self, Write};
+
+use crate::{board::Color, pos::BoardPos};
+
+use super::Game;
+
+/// The number of bytes of a position in the binary format, see
+/// `TrainingSample::position_bytes`.
+pub const POSITION_BYTES: usize = 26;
+
+/// The format that training samples are written in, see
+/// `Game::export_training_samples`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TrainingFormat {
+    /// One line of `fen,result` per sample, with the result written like in PGN,
+    /// for example `1-0`.
+    Csv,
+    /// `POSITION_BYTES` bytes of the position followed by a result byte per
+    /// sample. The result byte is 2 if white won, 1 for a draw and 0 if black won.
+    Binary,
+}
+
+/// Which positions of a game become training samples.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TrainingOptions {
+    pub format: TrainingFormat,
+    /// The number of plies at the start of the game to skip, for example moves
+    /// from an opening book.
+    pub skip_plies: usize,
+    /// Whether to skip positions where the side to move is in check.
+    pub skip_in_check: bool,
+}
+
+impl TrainingOptions {
+    /// Options that keep every position of the game.
+    pub fn new(format: TrainingFormat) -> TrainingOptions {
+        TrainingOptions { format, skip_plies: 0, skip_in_check: false }
+    }
+}
+
+/// A position from a finished game labelled with the result of the game.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TrainingSample {
+    fen: String,
+    position_bytes: Option<[u8; POSITION_BYTES]>,
+    side_to_move: Color,
+    winner: Option<Color>,
+}
+
+impl TrainingSample {
+    /// Get the FEN of the position.
+    pub fn fen(&self) -> &str {
+        &self.fen
+    }
+
+    /// Get the position in the compact binary encoding, or `None` unless the
+    /// sample was made for `TrainingFormat::Binary`.
+    ///
+    /// The first 8 bytes are the occupied squares as a little endian bitboard,
+    /// with bit `rank * 8 + file` set for every piece. The next 16 bytes hold a
+    /// nibble for each piece in the same order, the low nibble first, which is
+    /// the color index times 6 plus the piece index. The last 2 bytes hold the
+    /// side to move in bit 0 and the castling availability in bits 1 to 4, in the
+    /// order white kingside, white queenside, black kingside and black
+    /// queenside, followed by the en passant file plus one, or 0 if there is no
+    /// en passant target. Positions with more than 32 pieces can not be encoded.
+    pub fn position_bytes(&self) -> Option<&[u8; POSITION_BYTES]> {
+        self.position_bytes.as_ref()
+    }
+
+    /// Get the team whose turn it is in the position.
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    /// Get the team that won the game, or `None` if it was drawn.
+    pub fn winner(&self) -> Option<Color> {
+        self.winner
+    }
+
+    /// Get the result of the game like in PGN, for example `1-0`.
+    pub fn result(&self) -> &'static str {
+        match self.winner {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+
+    fn result_byte(&self) -> u8 {
+        match self.winner {
+            Some(Color::White) => 2,
+            Some(Color::Black) => 0,
+            None => 1,
+        }
+    }
+}
+
+impl Game {
+
+    /// Get a training sample for the position before every move in the history,
+    /// labelled with the result of the game. The game must have ended, see
+    /// `outcome`.
+    ///
+    /// The binary encoding is only computed for `TrainingFormat::Binary`, and
+    /// positions with more than 32 pieces, which it can not encode, are skipped
+    /// in that format.
+    ///
+    /// Returns `None` if the game has not ended.
+    pub fn training_samples(&self, options: &TrainingOptions) -> Option<Vec<TrainingSample>> {
+        let winner = self.outcome?.winner();
+        let mut position = Game::from_fen(&self.starting_fen).expect("The starting FEN is valid.");
+
+        let mut samples = Vec::new();
+        for (ply, m) in self.history.iter().enumerate() {
+            let color = position.current_turn;
+            if ply >= options.skip_plies && !(options.skip_in_check && position.is_check(&color)) {
+                let position_bytes = match options.format {
+                    TrainingFormat::Binary => position.position_bytes(),
+                    TrainingFormat::Csv => None,
+                };
+                if options.format == TrainingFormat::Csv || position_bytes.is_some() {
+                    samples.push(TrainingSample {
+                        fen: position.to_fen(),
+                        position_bytes,
+                        side_to_move: color,
+                        winner,
+                    });
+                }
+            }
+            position.make_move(m).expect("Moves in the history are legal.");
+        }
+        Some(samples)
+    }
+
+    /// Write the training samples of the game, see `training_samples`, in the
+    /// format of the options. The number of samples written is returned.
+    ///
+    /// # Errors
+    /// Errors from the writer are returned. An error of the kind `InvalidInput` is
+    /// returned if the game has not ended.
+    pub fn export_training_samples<W: Write>(&self, options: &TrainingOptions, mut writer: W) -> io::Result<usize> {
+        let samples = self.training_samples(options)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "The game has not ended."))?;
+
+        for sample in &samples {
+            match options.format {
+                TrainingFormat::Csv => writeln!(writer, "{},{}", sample.fen, sample.result())?,
+                TrainingFormat::Binary => {
+                    writer.write_all(sample.position_bytes().expect("Binary samples are encoded."))?;
+                    writer.write_all(&[sample.result_byte()])?;
+                },
+            }
+        }
+        writer.flush()?;
+        Ok(samples.len())
+    }
+
+    /// Encode the position, see `TrainingSample::position_bytes`. Returns `None`
+    /// if there are more than 32 pieces.
+    fn position_bytes(&self) -> Option<[u8; POSITION_BYTES]> {
+        if self.board.pieces().count() > 32 {
+            return None;
+        }
+        let mut bytes = [0; POSITION_BYTES];
+        let mut occupied = 0u64;
+        for (index, (pos, tile)) in self.board.pieces().enumerate() {
+            occupied |= 1 << square(&pos);
+            let nibble = (tile.color().index() * 6 + tile.piece().index()) as u8;
+            bytes[8 + index / 2] |= nibble << (index % 2 * 4);
+        }
+        bytes[..8].copy_from_slice(&occupied.to_le_bytes());
+
+        let flags = [
+            self.current_turn == Color::Black,
+            self.white_castling.kingside,
+            self.white_castling.queenside,
+            self.black_castling.kingside,
+            self.black_castling.queenside,
+        ];
+        bytes[24] = flags.iter().enumerate().fold(0, |byte, (bit, &set)| byte | (set as u8) << bit);
+        bytes[25] = self.en_passant_target.as_ref().map_or(0, |pos| pos.file() + 1);
+        Some(bytes)
+    }
+}
+
+fn square(pos: &BoardPos) -> u8 {
+    pos.rank() * 8 + pos.file()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fool's mate, won by black.
+    fn fools_mate() -> Game {
+        let mut game = Game::new();
+        for m in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            game.make_move(&m.parse().unwrap()).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn samples_of_decisive_game() {
+        let game = fools_mate();
+        let samples = game.training_samples(&TrainingOptions::new(TrainingFormat::Binary)).unwrap();
+        assert_eq!(samples.len(), 4);
+        assert!(samples.iter().all(|sample| sample.result() == "0-1" && sample.winner() == Some(Color::Black)));
+        assert_eq!(samples[1].side_to_move(), Color::Black);
+
+        // Every FEN is the position before the move with the same ply.
+        let mut replay = Game::new();
+        for (sample, m) in samples.iter().zip(game.history()) {
+            assert_eq!(sample.fen(), replay.to_fen());
+            assert_eq!(Game::from_fen(sample.fen()).unwrap().position_bytes().as_ref(), sample.position_bytes());
+            replay.make_move(m).unwrap();
+        }
+    }
+
+    #[test]
+    fn skipping_positions() {
+        let mut game = Game::new();
+        for m in ["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7"] {
+            game.make_move(&m.parse().unwrap()).unwrap();
+        }
+        let mut options = TrainingOptions::new(TrainingFormat::Csv);
+        options.skip_plies = 2;
+        assert_eq!(game.training_samples(&options).unwrap().len(), 5);
+        options.skip_in_check = true;
+        assert_eq!(game.training_samples(&options).unwrap().len(), 5);
+
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/3PPP2/R3K3 w - - 0 1").unwrap();
+        for m in ["a1a8", "e8e7", "a8a7", "e7e8", "a7a8", "e8e7"] {
+            game.make_move(&m.parse().unwrap()).unwrap();
+        }
+        game.adjudicate(crate::game::Outcome::Adjudicated { winner: Some(Color::White) }, "Resigned").unwrap();
+        let options = TrainingOptions { format: TrainingFormat::Csv, skip_plies: 0, skip_in_check: true };
+        let samples = game.training_samples(&options).unwrap();
+        assert_eq!(samples.len(), 3);
+        assert!(samples.iter().all(|sample| !Game::from_fen(sample.fen()).unwrap().is_check(&sample.side_to_move())));
+    }
+
+    #[test]
+    fn export_formats() {
+        let game = fools_mate();
+        let mut csv = Vec::new();
+        assert_eq!(game.export_training_samples(&TrainingOptions::new(TrainingFormat::Csv), &mut csv).unwrap(), 4);
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().next(), Some("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1,0-1"));
+        assert_eq!(csv.lines().count(), 4);
+
+        let mut binary = Vec::new();
+        game.export_training_samples(&TrainingOptions::new(TrainingFormat::Binary), &mut binary).unwrap();
+        assert_eq!(binary.len(), 4 * (POSITION_BYTES + 1));
+        let start = &binary[..POSITION_BYTES + 1];
+        assert_eq!(start[..8], 0xFFFF_0000_0000_FFFFu64.to_le_bytes());
+        // The white rook on a1 and knight on b1, then all castling rights.
+        assert_eq!(start[8], 0x42);
+        assert_eq!(start[24], 0b11110);
+        assert_eq!(start[POSITION_BYTES], 0);
+
+        let unfinished = Game::new();
+        let err = unfinished.export_training_samples(&TrainingOptions::new(TrainingFormat::Csv), Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn too_many_pieces_to_encode() {
+        // 48 pieces, more than the binary encoding has room for.
+        let mut game = Game::from_fen("rnbqkbnr/pppppppp/pppppppp/8/8/PPPPPPPP/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        game.make_move(&"a3a4".parse().unwrap()).unwrap();
+        game.adjudicate(crate::game::Outcome::Adjudicated { winner: None }, "").unwrap();
+
+        let csv = game.training_samples(&TrainingOptions::new(TrainingFormat::Csv)).unwrap();
+        assert_eq!(csv.len(), 1);
+        assert_eq!(csv[0].position_bytes(), None);
+
+        let mut binary = Vec::new();
+        assert_eq!(game.export_training_samples(&TrainingOptions::new(TrainingFormat::Binary), &mut binary).unwrap(), 0);
+        assert!(binary.is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{board::Color, pos::BoardPos};
+
+use super::{movement::PerformedMove, CastlingAvailability, Game, Move};
+
+/// The state before a move that is needed to undo it, see `Game::undo`.
+#[derive(Clone, Debug)]
+pub(super) struct UndoRecord {
+    performed_move: PerformedMove,
+    current_turn: Color,
+    white_castling: CastlingAvailability,
+    black_castling: CastlingAvailability,
+    en_passant_target: Option<BoardPos>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    last_irreversible_ply: usize,
+}
+
+impl Game {
+
+    /// Undo the last move, including the promotion if the pawn has been promoted,
+    /// and return it. `None` is returned if there are no moves in `history`.
+    ///
+    /// The board, the turn, castling availability, the en passant target and both
+    /// clocks are restored exactly, so `to_fen` returns the same FEN as before the
+    /// move. If the move is waiting for a promotion, only the pawn move is undone.
+    ///
+    /// The annotations of the move are removed. The game is no longer over, which
+    /// also removes an adjudication made after the move.
+    pub fn undo(&mut self) -> Option<Move> {
+        let record = self.undo_stack.pop()?;
+        let m = self.history.pop().expect("Every undo record has a move.");
+        self.restore_undo_record(record);
+
+        // The position key is only recorded once the move is complete.
+        if self.promotion_required.take().is_none() {
+            self.position_keys.pop();
+        }
+        self.annotations.split_off(&(self.history.len() + 1));
+        self.outcome = None;
+        self.adjudication_note = None;
+        Some(m)
+    }
+
+    /// Restore the board and the state saved in the record, which undoes the
+    /// move of the record if it was the last move made.
+    pub(super) fn restore_undo_record(&mut self, record: UndoRecord) {
+        self.undo_performed_move(record.performed_move);
+        self.current_turn = record.current_turn;
+        self.white_castling = record.white_castling;
+        self.black_castling = record.black_castling;
+        self.en_passant_target = record.en_passant_target;
+        self.halfmove_clock = record.halfmove_clock;
+        self.fullmove_number = record.fullmove_number;
+        self.last_irreversible_ply = record.last_irreversible_ply;
+        self.invalidate_move_cache();
+    }
+
+    /// Save the state needed to undo a move that was just performed by
+    /// `perform_move`, before anything else about the move is changed.
+    pub(super) fn undo_record(&self, performed_move: PerformedMove) -> UndoRecord {
+        UndoRecord {
+            performed_move,
+            current_turn: self.current_turn,
+            white_castling: self.white_castling,
+            black_castling: self.black_castling,
+            en_passant_target: self.en_passant_target.clone(),
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            last_irreversible_ply: self.last_irreversible_ply,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::{Annotation, GameState}, piece::PieceType};
+
+    use super::*;
+
+    /// Make the move and undo it, checking that the FEN is unchanged.
+    fn move_and_undo(fen: &str, m: &str) -> Game {
+        let mut game = Game::from_fen(fen).unwrap();
+        let keys = game.repetition_count();
+        game.make_move(&m.parse().unwrap()).unwrap();
+        assert_ne!(game.to_fen(), fen);
+        assert_eq!(game.undo().unwrap().to_string(), m);
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.repetition_count(), keys);
+        assert!(game.history().is_empty());
+        game
+    }
+
+    #[test]
+    fn undo_castling() {
+        let mut game = move_and_undo("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 3 20", "e1g1");
+        assert!(game.legal_moves().iter().any(|m| m.to_string() == "e1c1"));
+        move_and_undo("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 3 20", "e8c8");
+    }
+
+    #[test]
+    fn undo_en_passant() {
+        let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        let mut game = move_and_undo(fen, "e5f6");
+        assert!(game.legal_moves().iter().any(|m| m.to_string() == "e5f6"));
+
+        // The double step that allowed en passant.
+        let mut game = Game::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2").unwrap();
+        game.make_move(&"f7f5".parse().unwrap()).unwrap();
+        assert_eq!(game.en_passant_target(), Some("f6".parse().unwrap()));
+        game.undo();
+        assert_eq!(game.en_passant_target(), None);
+    }
+
+    #[test]
+    fn undo_promotion() {
+        let fen = "1n2k3/P7/8/8/8/8/8/4K3 w - - 5 40";
+        move_and_undo(fen, "a7b8q");
+        move_and_undo(fen, "a7a8n");
+
+        // Undoing a pending promotion.
+        let mut game = Game::from_fen(fen).unwrap();
+        game.move_piece(&"a7".parse().unwrap(), &"a8".parse().unwrap()).unwrap();
+        assert!(matches!(game.get_state(), GameState::PromotionRequired(_)));
+        assert_eq!(game.undo().unwrap().to_string(), "a7a8");
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.get_state(), GameState::Normal);
+        game.make_move(&"a7a8r".parse().unwrap()).unwrap();
+        assert_eq!(game.get_tile(&"a8".parse().unwrap()).unwrap().piece(), PieceType::Rook);
+    }
+
+    #[test]
+    fn undo_game() {
+        let mut game = Game::new();
+        let mut fens = vec![game.to_fen()];
+        for m in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "f3g5", "d7d5", "e4d5", "f6d5", "g5f7", "e8f7"] {
+            game.make_move(&m.parse().unwrap()).unwrap();
+            fens.push(game.to_fen());
+        }
+        game.annotate(12, Annotation::Comment("The Fried Liver.".to_string()));
+        for ply in (0..12).rev() {
+            game.undo().unwrap();
+            assert_eq!(game.to_fen(), fens[ply]);
+            assert_eq!(game.history().len(), ply);
+            assert!(game.annotations(12).is_empty());
+        }
+        assert_eq!(game.undo(), None);
+
+        // Undoing checkmate lets the game continue.
+        for m in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            game.make_move(&m.parse().unwrap()).unwrap();
+        }
+        assert!(game.outcome().is_some());
+        game.undo();
+        assert_eq!(game.outcome(), None);
+        game.make_move(&"d8g5".parse().unwrap()).unwrap();
+        game.make_move(&"e1f2".parse().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use crate::board::{Color, Tile};
+
+use super::{FenParseError, Game, Move, Outcome, Rules, StandardRules, STARTING_POSITION_FEN};
+
+/// The built-in variants of chess, see `Rules` for how the rules are defined.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Variant {
+    /// Standard chess.
+    #[default]
+    Standard,
+    /// Both teams race their kings to the eighth rank, see `RacingKingsRules`.
+    RacingKings,
+}
+
+impl Variant {
+    /// Get the FEN of the position that games of the variant start from.
+    pub fn starting_fen(&self) -> &'static str {
+        match self {
+            Self::Standard => STARTING_POSITION_FEN,
+            Self::RacingKings => RACING_KINGS_FEN,
+        }
+    }
+
+    /// Get the rules of the variant.
+    pub fn rules(&self) -> Arc<dyn Rules> {
+        match self {
+            Self::Standard => Arc::new(StandardRules),
+            Self::RacingKings => Arc::new(RacingKingsRules),
+        }
+    }
+    /// Get the name of the variant as written in the `Variant` tag of PGN, for
+    /// example `"Racing Kings"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Standard => "Standard",
+            Self::RacingKings => "Racing Kings",
+        }
+    }
+
+    /// Get the variant with the name, see `name`. Case, spaces and hyphens are
+    /// ignored, so `"racingKings"` and `"racing-kings"` are read as well.
+    pub fn from_name(name: &str) -> Option<Variant> {
+        let name: String = name.chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        match name.as_str() {
+            "standard" | "chess" => Some(Self::Standard),
+            "racingkings" => Some(Self::RacingKings),
+            _ => None,
+        }
+    }
+}
+
+const RACING_KINGS_FEN: &str = "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1";
+
+/// The rules of Racing Kings, where both teams race their kings to the eighth
+/// rank. Moves that give check are not allowed, so there is no checkmate.
+///
+/// The first king on the eighth rank wins. If white gets there first, black may
+/// answer with a move that brings the black king there too, which makes the
+/// game a draw.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct RacingKingsRules;
+
+impl Rules for RacingKingsRules {
+    fn variant(&self) -> Option<Variant> {
+        Some(Variant::RacingKings)
+    }
+
+    fn starting_fen(&self) -> &str {
+        RACING_KINGS_FEN
+    }
+
+    fn allows_move(&self, game: &Game, m: &Move, _captured: Option<Tile>) -> bool {
+        game.get_tile(m.to()).is_none_or(|tile| !game.is_check(&tile.color().opposite()))
+    }
+
+    fn outcome(&self, game: &mut Game) -> Option<Outcome> {
+        let white = on_last_rank(game, Color::White);
+        let black = on_last_rank(game, Color::Black);
+
+        match (white, black) {
+            (true, true) => Some(Outcome::VariantEnd { winner: None }),
+            (false, true) => Some(Outcome::VariantEnd { winner: Some(Color::Black) }),
+            // Black gets one move to bring the king to the eighth rank as well.
+            (true, false) if game.current_turn == Color::Black && king_can_reach_last_rank(game, &Color::Black) => None,
+            (true, false) => Some(Outcome::VariantEnd { winner: Some(Color::White) }),
+            (false, false) => None,
+        }
+    }
+}
+
+fn on_last_rank(game: &Game, color: Color) -> bool {
+    game.get_king_pos(&color).is_some_and(|pos| pos.rank() == 7)
+}
+
+fn king_can_reach_last_rank(game: &mut Game, color: &Color) -> bool {
+    game.get_king_pos(color)
+        .is_some_and(|king| game.get_legal_moves_unchecked(&king).iter().any(|pos| pos.rank() == 7))
+}
+
+impl Game {
+
+    /// Create a new game of the variant with the variant's starting position.
+    pub fn new_variant(variant: Variant) -> Game {
+        Self::new_with_rules(variant.rules())
+    }
+
+    /// Create a game of the variant from a FEN string, see `from_fen`.
+    ///
+    /// # Errors
+    /// The same errors as `from_fen` are returned.
+    pub fn from_fen_with_variant(fen: &str, variant: Variant) -> Result<Game, FenParseError<'_>> {
+        Self::from_fen_with_rules(fen, variant.rules())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for m in moves {
+            game.make_move(&m.parse().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn racing_kings_start() {
+        let mut game = Game::new_variant(Variant::RacingKings);
+        assert_eq!(game.variant(), Some(Variant::RacingKings));
+        assert_eq!(game.to_fen(), Variant::RacingKings.starting_fen());
+        let fen = game.to_fen();
+        assert_eq!(Game::from_fen_with_variant(&fen, Variant::RacingKings).unwrap().to_fen(), fen);
+        assert_eq!(game.legal_moves().len(), 21);
+        assert_eq!(game.outcome(), None);
+    }
+
+    #[test]
+    fn names() {
+        for variant in [Variant::Standard, Variant::RacingKings] {
+            assert_eq!(Variant::from_name(variant.name()), Some(variant));
+        }
+        assert_eq!(Variant::from_name("racing-kings"), Some(Variant::RacingKings));
+        assert_eq!(Variant::from_name("Atomic"), None);
+    }
+
+    #[test]
+    fn checks_are_illegal() {
+        let mut game = Game::from_fen_with_variant("8/8/8/8/8/k7/8/1R5K w - - 0 1", Variant::RacingKings).unwrap();
+        let moves: Vec<String> = game.legal_moves().iter().map(|m| m.to_string()).collect();
+        assert!(!moves.contains(&"b1b3".to_string()));
+        assert!(!moves.contains(&"b1a1".to_string()));
+        assert!(moves.contains(&"b1b2".to_string()));
+        assert!(game.make_move(&"b1a1".parse().unwrap()).is_err());
+
+        // The same move is legal in standard chess.
+        let mut standard = Game::from_fen("8/8/8/8/8/k7/8/1R5K w - - 0 1").unwrap();
+        assert!(standard.make_move(&"b1a1".parse().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn king_reaching_the_top_wins() {
+        // Black can not reach the eighth rank in one move.
+        let mut game = Game::from_fen_with_variant("8/7K/8/8/8/k7/8/8 w - - 0 1", Variant::RacingKings).unwrap();
+        play(&mut game, &["h7h8"]);
+        assert_eq!(game.outcome(), Some(Outcome::VariantEnd { winner: Some(Color::White) }));
+        assert!(game.is_game_over());
+
+        let mut game = Game::from_fen_with_variant("8/k7/8/8/8/8/8/7K b - - 0 1", Variant::RacingKings).unwrap();
+        play(&mut game, &["a7a8"]);
+        assert_eq!(game.outcome(), Some(Outcome::VariantEnd { winner: Some(Color::Black) }));
+    }
+
+    #[test]
+    fn black_can_equalize() {
+        let mut game = Game::from_fen_with_variant("8/k6K/8/8/8/8/8/8 w - - 0 1", Variant::RacingKings).unwrap();
+        play(&mut game, &["h7h8"]);
+        assert_eq!(game.outcome(), None);
+        play(&mut game, &["a7a8"]);
+        assert_eq!(game.outcome(), Some(Outcome::VariantEnd { winner: None }));
+        assert_eq!(game.get_state(), crate::game::GameState::VariantEnd(None));
+
+        // Black does not reach the eighth rank and loses.
+        let mut game = Game::from_fen_with_variant("8/k6K/8/8/8/8/8/8 w - - 0 1", Variant::RacingKings).unwrap();
+        play(&mut game, &["h7h8", "a7a6"]);
+        assert_eq!(game.outcome(), Some(Outcome::VariantEnd { winner: Some(Color::White) }));
+    }
+}
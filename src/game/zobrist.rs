@@ -0,0 +1,280 @@
+use crate::{board::{Color, Tile}, per_square::PerSquare, piece::PieceType, pos::BoardPos};
+
+use super::{FenParseError, Game};
+
+/// Random keys for every piece on every square, indexed by the color index, the
+/// piece index and then the position.
+const PIECE_KEYS: [[PerSquare<u64>; 6]; 2] = piece_keys();
+
+/// The key that is added when it is black's turn.
+const BLACK_TO_MOVE_KEY: u64 = splitmix64(12 * 64);
+
+/// The keys for white kingside, white queenside, black kingside and black
+/// queenside castling rights.
+const CASTLING_KEYS: [u64; 4] = [
+    splitmix64(12 * 64 + 1),
+    splitmix64(12 * 64 + 2),
+    splitmix64(12 * 64 + 3),
+    splitmix64(12 * 64 + 4),
+];
+
+/// The keys for an en passant target on each file.
+const EN_PASSANT_KEYS: [u64; 8] = en_passant_keys();
+
+/// Generate the pseudo-random number with the index using the SplitMix64
+/// generator, so that the keys are the same for every build.
+const fn splitmix64(index: u64) -> u64 {
+    let mut z = (index + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const fn piece_keys() -> [[PerSquare<u64>; 6]; 2] {
+    let mut keys = [[PerSquare::from_array([0; 64]); 6]; 2];
+    let mut color = 0;
+    while color < 2 {
+        let mut piece = 0;
+        while piece < 6 {
+            let mut squares = [0; 64];
+            let mut square = 0;
+            while square < 64 {
+                squares[square] = splitmix64(((color * 6 + piece) * 64 + square) as u64);
+                square += 1;
+            }
+            keys[color][piece] = PerSquare::from_array(squares);
+            piece += 1;
+        }
+        color += 1;
+    }
+    keys
+}
+
+const fn en_passant_keys() -> [u64; 8] {
+    let mut keys = [0; 8];
+    let mut file = 0;
+    while file < 8 {
+        keys[file] = splitmix64(12 * 64 + 5 + file as u64);
+        file += 1;
+    }
+    keys
+}
+
+impl Game {
+
+    /// Get the Zobrist hash of the position.
+    ///
+    /// The hash covers the pieces on the board, the current turn, the castling
+    /// availability and the en passant target. The move counters are not
+    /// included, so the same position reached by different move orders has the
+    /// same hash.
+    ///
+    /// Different positions can have the same hash, but this is very unlikely.
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+
+        for (pos, tile) in self.board.pieces() {
+            hash ^= PIECE_KEYS[tile.color().index()][tile.piece().index()][pos];
+        }
+
+        if self.current_turn == Color::Black {
+            hash ^= BLACK_TO_MOVE_KEY;
+        }
+
+        let castling = [
+            self.white_castling.kingside,
+            self.white_castling.queenside,
+            self.black_castling.kingside,
+            self.black_castling.queenside,
+        ];
+        for (available, key) in castling.into_iter().zip(CASTLING_KEYS) {
+            if available {
+                hash ^= key;
+            }
+        }
+
+        if let Some(en_passant_target) = &self.en_passant_target {
+            hash ^= EN_PASSANT_KEYS[en_passant_target.file() as usize];
+        }
+
+        hash
+    }
+
+    /// Get the key of the position for detecting repetitions, for example to count
+    /// repetitions in game records kept outside the crate. See `repetition_plies`
+    /// for how the crate uses it.
+    ///
+    /// The key is the Zobrist hash of the pieces on the board, the turn, the
+    /// castling availability and the en passant target, but the en passant target
+    /// is only counted if an en passant capture is legal. Positions that are the
+    /// same according to the repetition rules therefore have the same key, and the
+    /// move counters are not included.
+    ///
+    /// The keys are generated from fixed numbers, so they are the same on every
+    /// platform and build. Changing the key of a position is a breaking change of
+    /// the crate, so keys can be stored and compared across versions.
+    pub fn repetition_key(&mut self) -> u64 {
+        let mut hash = self.zobrist_hash();
+        if let Some(target) = self.en_passant_target.clone() {
+            let pawn_rank = if self.current_turn == Color::White { 4 } else { 3 };
+            let pawn = Tile::new(PieceType::Pawn, self.current_turn);
+            let pawns: Vec<BoardPos> = [-1, 1].into_iter()
+                .filter_map(|delta_file| BoardPos::new(target.file(), pawn_rank).offset(delta_file, 0))
+                .filter(|pos| self.board.get_tile(pos) == Some(pawn))
+                .collect();
+            let can_capture = pawns.iter().any(|pos| self.get_legal_moves_unchecked(pos).contains(&target));
+            if !can_capture {
+                hash ^= EN_PASSANT_KEYS[target.file() as usize];
+            }
+        }
+        hash
+    }
+
+    /// Get a key that is the same for the position and its color flipped mirror,
+    /// see `color_flipped`. This is the smaller of the two Zobrist hashes.
+    ///
+    /// This is useful to store a position and its mirror as one entry, for example
+    /// when classifying endgames.
+    pub fn canonical_key(&self) -> u64 {
+        self.zobrist_hash().min(self.color_flipped().zobrist_hash())
+    }
+
+    /// Get a fingerprint of the game, for example to skip games that are already
+    /// in a database when importing.
+    ///
+    /// The hash starts as the repetition key of the starting position, and every
+    /// move in `history` is mixed in with `hash = splitmix64(hash.rotate_left(1) ^
+    /// code)`, where `code` is `from + 64 * to + 4096 * promotion`. The squares are
+    /// numbered from 0 for `a1` to 63 for `h8` and the promotion is 0 for none or
+    /// `PieceType::index` plus one. Tags, annotations and the move counters are
+    /// not included, and the hash is the same on every platform and version.
+    ///
+    /// Different move orders that reach the same position have different hashes,
+    /// see `final_position_hash`.
+    pub fn game_hash(&self) -> u64 {
+        let start = *self.position_keys.first().expect("The key of the starting position is recorded.");
+        self.history.iter().fold(start, |hash, m| {
+            let square = |pos: &BoardPos| u64::from(pos.rank()) * 8 + u64::from(pos.file());
+            let promotion = m.promotion().map_or(0, |piece| piece.index() as u64 + 1);
+            let code = square(m.from()) + 64 * square(m.to()) + 4096 * promotion;
+            splitmix64(hash.rotate_left(1) ^ code)
+        })
+    }
+
+    /// Get a fingerprint of the current position, for example to find games in a
+    /// database that reached the same final position by any move order. This is
+    /// the repetition key, see `repetition_key`.
+    pub fn final_position_hash(&mut self) -> u64 {
+        self.repetition_key()
+    }
+}
+
+
+/// Whether the positions of two FEN strings are the same according to the
+/// repetition rules, see `Game::repetition_key`.
+///
+/// # Errors
+/// The error of the first FEN string that is invalid is returned.
+pub fn repetition_keys_equal<'a>(fen_a: &'a str, fen_b: &'a str) -> Result<bool, FenParseError<'a>> {
+    let mut a = Game::from_fen(fen_a)?;
+    let mut b = Game::from_fen(fen_b)?;
+    Ok(a.repetition_key() == b.repetition_key())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[(&str, &str)]) {
+        for (from, to) in moves {
+            game.move_piece(&from.parse().unwrap(), &to.parse().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn transpositions_have_the_same_hash() {
+        let mut game1 = Game::new();
+        play(&mut game1, &[("g1", "f3"), ("g8", "f6"), ("b1", "c3"), ("b8", "c6")]);
+        let mut game2 = Game::new();
+        play(&mut game2, &[("b1", "c3"), ("b8", "c6"), ("g1", "f3"), ("g8", "f6")]);
+
+        assert_eq!(game1.zobrist_hash(), game2.zobrist_hash());
+        assert_ne!(game1.zobrist_hash(), Game::new().zobrist_hash());
+    }
+
+    #[test]
+    fn turn_castling_and_en_passant_change_the_hash() {
+        let hash = |fen| Game::from_fen(fen).unwrap().zobrist_hash();
+
+        let base = hash("4k3/8/8/3pP3/8/8/8/R3K3 w Q - 0 1");
+        assert_ne!(base, hash("4k3/8/8/3pP3/8/8/8/R3K3 b Q - 0 1"));
+        assert_ne!(base, hash("4k3/8/8/3pP3/8/8/8/R3K3 w - - 0 1"));
+        assert_ne!(base, hash("4k3/8/8/3pP3/8/8/8/R3K3 w Q d6 0 1"));
+        assert_eq!(base, hash("4k3/8/8/3pP3/8/8/8/R3K3 w Q - 12 40"));
+    }
+
+    #[test]
+    fn mirrored_positions_have_the_same_canonical_key() {
+        let game = Game::from_fen("4k3/8/8/3pP3/8/8/8/R3K3 w Q d6 0 1").unwrap();
+        let mirror = Game::from_fen("r3k3/8/8/8/3Pp3/8/8/4K3 b q d3 0 1").unwrap();
+
+        assert_ne!(game.zobrist_hash(), mirror.zobrist_hash());
+        assert_eq!(game.canonical_key(), mirror.canonical_key());
+        assert_ne!(game.canonical_key(), Game::new().canonical_key());
+    }
+
+    #[test]
+    fn repetition_keys() {
+        let mut game1 = Game::new();
+        play(&mut game1, &[("e2", "e4"), ("e7", "e5"), ("g1", "f3")]);
+        let mut game2 = Game::new();
+        play(&mut game2, &[("g1", "f3"), ("e7", "e5"), ("e2", "e4")]);
+        assert_eq!(game1.repetition_key(), game2.repetition_key());
+        // The en passant target after e2e4 can not be used, so it is ignored.
+        assert_ne!(game1.zobrist_hash(), game2.zobrist_hash());
+
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert!(repetition_keys_equal(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+        ).unwrap());
+        assert!(!repetition_keys_equal(
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1",
+        ).unwrap());
+        assert!(!repetition_keys_equal(start, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1").unwrap());
+        assert!(repetition_keys_equal(start, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 5 9").unwrap());
+        assert!(repetition_keys_equal(start, "8/8 w").is_err());
+    }
+
+    #[test]
+    fn repetition_keys_are_stable() {
+        // The keys must not change between versions, see `repetition_key`.
+        assert_eq!(Game::new().repetition_key(), 7937750264439259352);
+
+        let mut game = Game::new();
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+        assert_eq!(game.game_hash(), 14387015593488595829);
+    }
+
+    #[test]
+    fn game_hashes() {
+        let mut game1 = Game::new();
+        play(&mut game1, &[("g1", "f3"), ("g8", "f6"), ("b1", "c3"), ("b8", "c6")]);
+        let mut game2 = Game::new();
+        play(&mut game2, &[("b1", "c3"), ("b8", "c6"), ("g1", "f3"), ("g8", "f6")]);
+
+        assert_ne!(game1.game_hash(), game2.game_hash());
+        assert_eq!(game1.final_position_hash(), game2.final_position_hash());
+        assert_eq!(Game::new().game_hash(), Game::new().repetition_key());
+        assert_eq!(game1.game_hash(), game1.clone().game_hash());
+
+        // Promotions to different pieces are different games.
+        let fen = "4k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+        let mut queen = Game::from_fen(fen).unwrap();
+        queen.make_move(&"a7a8q".parse().unwrap()).unwrap();
+        let mut rook = Game::from_fen(fen).unwrap();
+        rook.make_move(&"a7a8r".parse().unwrap()).unwrap();
+        assert_ne!(queen.game_hash(), rook.game_hash());
+    }
+}
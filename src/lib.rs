@@ -1,4 +1,27 @@
+#[macro_use]
+mod trace;
+
 pub mod board;
 pub mod pos;
 pub mod piece;
-pub mod game;
\ No newline at end of file
+pub mod game;
+pub mod square_set;
+pub mod per_square;
+pub mod attacks;
+pub mod tablebase;
+pub mod pgn;
+pub mod opening_tree;
+pub mod puzzle;
+pub mod time_control;
+
+mod error;
+pub use error::{Error, Result};
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "shakmaty")]
+mod shakmaty_compat;
+
+#[cfg(feature = "testing")]
+pub mod testing;
@@ -0,0 +1,262 @@
+//! Statistics about the moves played from positions in a collection of games.
+//!
+//! Positions are looked up by their Zobrist hash, see `Game::zobrist_hash`, so
+//! the statistics of a position include every game that reached it, no matter
+//! the move order.
+
+use std::{cmp::Reverse, collections::HashMap, io::{self, Read, Write}};
+
+use crate::{game::{Game, Move}, pgn::{PgnError, PgnGame}, piece::PieceType, pos::BoardPos};
+
+/// The bytes that start an opening tree file.
+const MAGIC: &[u8; 4] = b"AWOT";
+const VERSION: u8 = 1;
+
+/// How often a move was played from a position and how the games ended.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveStats {
+    pub m: Move,
+    /// The number of games where the move was played, including unfinished games.
+    pub games: u32,
+    pub white_wins: u32,
+    pub draws: u32,
+    pub black_wins: u32,
+}
+
+impl MoveStats {
+    fn new(m: Move) -> MoveStats {
+        MoveStats { m, games: 0, white_wins: 0, draws: 0, black_wins: 0 }
+    }
+}
+
+/// A tree of the moves played from every position in a collection of games.
+#[derive(Clone, Default, Debug)]
+pub struct OpeningTree {
+    positions: HashMap<u64, Vec<MoveStats>>,
+}
+
+impl OpeningTree {
+    pub fn new() -> OpeningTree {
+        OpeningTree::default()
+    }
+
+    /// Add the moves of a game to the tree, counting the result of the game for
+    /// every move.
+    ///
+    /// # Errors
+    /// `InvalidFen` is returned if the `FEN` tag of the game is invalid.
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<(), PgnError> {
+        let mut position = game.starting_position()?;
+        let moves: Vec<Move> = game.moves().iter().map(|m| m.get().clone()).collect();
+        self.add_moves(&mut position, &moves, game.result());
+        Ok(())
+    }
+
+    /// Add the moves played in a game to the tree. The result is only counted if
+    /// the game has ended, see `PgnGame::from_game`.
+    pub fn add_played_game(&mut self, game: &Game) {
+        self.add_game(&PgnGame::from_game(game)).expect("The starting FEN is valid.");
+    }
+
+    fn add_moves(&mut self, position: &mut Game, moves: &[Move], result: &str) {
+        for m in moves {
+            let stats = self.positions.entry(position.zobrist_hash()).or_default();
+            let index = match stats.iter().position(|stats| stats.m == *m) {
+                Some(index) => index,
+                None => {
+                    stats.push(MoveStats::new(m.clone()));
+                    stats.len() - 1
+                },
+            };
+            let stats = &mut stats[index];
+            stats.games += 1;
+            match result {
+                "1-0" => stats.white_wins += 1,
+                "0-1" => stats.black_wins += 1,
+                "1/2-1/2" => stats.draws += 1,
+                _ => {},
+            }
+
+            position.make_move(m).expect("Moves in a game are legal.");
+        }
+    }
+
+    /// Get the moves played from the position of the game, the most played move
+    /// first.
+    pub fn moves_from(&self, game: &Game) -> Vec<MoveStats> {
+        let mut moves = self.positions.get(&game.zobrist_hash()).cloned().unwrap_or_default();
+        moves.sort_by_key(|stats| Reverse(stats.games));
+        moves
+    }
+
+    /// Get the number of positions in the tree.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Write the tree in a compact binary format that can be read with
+    /// `read_from`.
+    ///
+    /// # Errors
+    /// Errors from the writer are returned.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&(self.positions.len() as u32).to_le_bytes())?;
+
+        for (hash, moves) in &self.positions {
+            writer.write_all(&hash.to_le_bytes())?;
+            writer.write_all(&(moves.len() as u16).to_le_bytes())?;
+            for stats in moves {
+                let promotion = stats.m.promotion().map_or(0, |piece| piece.index() as u8 + 1);
+                writer.write_all(&[square(stats.m.from()), square(stats.m.to()), promotion])?;
+                for count in [stats.games, stats.white_wins, stats.draws, stats.black_wins] {
+                    writer.write_all(&count.to_le_bytes())?;
+                }
+            }
+        }
+        writer.flush()
+    }
+
+    /// Read a tree that was written with `write_to`.
+    ///
+    /// # Errors
+    /// Errors from the reader are returned. An error of the kind `InvalidData` is
+    /// returned if the data is not an opening tree.
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<OpeningTree> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_bytes::<1>(&mut reader)? != [VERSION] {
+            return Err(invalid_data("Not an opening tree file."));
+        }
+
+        let mut tree = OpeningTree::new();
+        let position_count = u32::from_le_bytes(read_bytes(&mut reader)?);
+        for _ in 0..position_count {
+            let hash = u64::from_le_bytes(read_bytes(&mut reader)?);
+            let move_count = u16::from_le_bytes(read_bytes(&mut reader)?);
+
+            let mut moves = Vec::with_capacity(move_count as usize);
+            for _ in 0..move_count {
+                let [from, to, promotion] = read_bytes(&mut reader)?;
+                if from >= 64 || to >= 64 {
+                    return Err(invalid_data("Invalid square."));
+                }
+                let promotion = match promotion {
+                    0 => None,
+                    index => Some(PieceType::from_index(index as usize - 1).ok_or_else(|| invalid_data("Invalid promotion."))?),
+                };
+                let mut stats = MoveStats::new(Move::new(to_pos(from), to_pos(to), promotion));
+                for count in [&mut stats.games, &mut stats.white_wins, &mut stats.draws, &mut stats.black_wins] {
+                    *count = u32::from_le_bytes(read_bytes(&mut reader)?);
+                }
+                moves.push(stats);
+            }
+            tree.positions.insert(hash, moves);
+        }
+        Ok(tree)
+    }
+}
+
+fn square(pos: &BoardPos) -> u8 {
+    pos.rank() * 8 + pos.file()
+}
+
+fn to_pos(square: u8) -> BoardPos {
+    BoardPos::new(square % 8, square / 8)
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::pgn::PgnReader;
+
+    use super::*;
+
+    const GAMES: &str = r#"[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 1-0
+
+[Result "1/2-1/2"]
+
+1. e4 e5 2. Nf3 Nf6 1/2-1/2
+
+[Result "0-1"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bc4 0-1
+"#;
+
+    fn tree() -> OpeningTree {
+        let mut tree = OpeningTree::new();
+        for game in PgnReader::new(GAMES.as_bytes()) {
+            tree.add_game(&game.unwrap()).unwrap();
+        }
+        tree
+    }
+
+    fn position(moves: &str) -> Game {
+        let mut game = Game::new();
+        for san in moves.split_whitespace() {
+            let m = game.parse_san(san).unwrap();
+            game.make_move(&m).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn counts_at_branch_point() {
+        let tree = tree();
+
+        let moves = tree.moves_from(&position("e4 e5"));
+        assert_eq!(moves.len(), 1);
+        assert_eq!((moves[0].games, moves[0].white_wins, moves[0].draws, moves[0].black_wins), (3, 1, 1, 1));
+
+        let moves = tree.moves_from(&position("e4 e5 Nf3"));
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].m, Move::new("b8".parse().unwrap(), "c6".parse().unwrap(), None));
+        assert_eq!((moves[0].games, moves[0].white_wins, moves[0].black_wins), (2, 1, 1));
+        assert_eq!((moves[1].games, moves[1].draws), (1, 1));
+
+        assert!(tree.moves_from(&position("d4")).is_empty());
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let tree = tree();
+        let mut bytes = Vec::new();
+        tree.write_to(&mut bytes).unwrap();
+
+        let read = OpeningTree::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(read.len(), tree.len());
+        let position = position("e4 e5 Nf3 Nc6");
+        assert_eq!(read.moves_from(&position).len(), 2);
+
+        let err = OpeningTree::read_from(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let err = OpeningTree::read_from(&b"PGN!"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn played_game() {
+        let mut tree = OpeningTree::new();
+        tree.add_played_game(&position("f3 e5 g4 Qh4"));
+
+        let moves = tree.moves_from(&position("f3 e5 g4"));
+        assert_eq!((moves[0].games, moves[0].black_wins), (1, 1));
+    }
+}
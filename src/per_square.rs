@@ -0,0 +1,145 @@
+use std::ops::{Index, IndexMut};
+
+use crate::pos::BoardPos;
+
+/// A value for every square of the board, indexed by `BoardPos`.
+///
+/// The values are stored in an array with the value of a position at index
+/// `rank * 8 + file`, the same order as `SquareSet`.
+///
+/// ```
+/// use alvinw_chess::{attacks, board::Color, game::Game, per_square::PerSquare, piece::PieceType, pos::BoardPos};
+///
+/// // How many more white knights than black knights attack each square.
+/// let mut game = Game::new();
+/// let mut influence: PerSquare<i32> = PerSquare::default();
+/// for (pos, tile) in game.board().pieces().filter(|(_, tile)| tile.piece() == PieceType::Knight) {
+///     let sign = if tile.color() == Color::White { 1 } else { -1 };
+///     for square in attacks::knight_attacks(&pos).iter() {
+///         influence[square] += sign;
+///     }
+/// }
+/// assert_eq!(influence["f3".parse::<BoardPos>().unwrap()], 1);
+/// assert_eq!(influence["c6".parse::<BoardPos>().unwrap()], -1);
+/// assert_eq!(influence["e4".parse::<BoardPos>().unwrap()], 0);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PerSquare<T>([T; 64]);
+
+impl<T> PerSquare<T> {
+    /// Create the values from an array indexed by `rank * 8 + file`.
+    pub const fn from_array(values: [T; 64]) -> PerSquare<T> {
+        PerSquare(values)
+    }
+
+    /// Create the values by calling the function for every position.
+    pub fn from_fn(mut f: impl FnMut(BoardPos) -> T) -> PerSquare<T> {
+        PerSquare(std::array::from_fn(|index| f(position(index))))
+    }
+
+    /// Get the values as an array indexed by `rank * 8 + file`.
+    pub fn as_array(&self) -> &[T; 64] {
+        &self.0
+    }
+
+    /// Get an iterator over the positions and their values.
+    ///
+    /// The positions are ordered by rank and then by file, starting with `a1`.
+    pub fn iter(&self) -> impl Iterator<Item = (BoardPos, &T)> {
+        self.0.iter().enumerate().map(|(index, value)| (position(index), value))
+    }
+
+    /// Get an iterator over the positions and mutable references to their values,
+    /// in the same order as `iter`.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (BoardPos, &mut T)> {
+        self.0.iter_mut().enumerate().map(|(index, value)| (position(index), value))
+    }
+
+    /// Create new values by calling the function with every value.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> PerSquare<U> {
+        PerSquare(self.0.map(f))
+    }
+}
+
+fn index(pos: &BoardPos) -> usize {
+    (pos.rank() * 8 + pos.file()) as usize
+}
+
+fn position(index: usize) -> BoardPos {
+    BoardPos::new(index as u8 % 8, index as u8 / 8)
+}
+
+impl<T: Default> Default for PerSquare<T> {
+    fn default() -> Self {
+        PerSquare(std::array::from_fn(|_| T::default()))
+    }
+}
+
+impl<T> Index<BoardPos> for PerSquare<T> {
+    type Output = T;
+
+    fn index(&self, pos: BoardPos) -> &T {
+        &self.0[index(&pos)]
+    }
+}
+
+impl<T> IndexMut<BoardPos> for PerSquare<T> {
+    fn index_mut(&mut self, pos: BoardPos) -> &mut T {
+        &mut self.0[index(&pos)]
+    }
+}
+
+impl<T> Index<&BoardPos> for PerSquare<T> {
+    type Output = T;
+
+    fn index(&self, pos: &BoardPos) -> &T {
+        &self.0[index(pos)]
+    }
+}
+
+impl<T> IndexMut<&BoardPos> for PerSquare<T> {
+    fn index_mut(&mut self, pos: &BoardPos) -> &mut T {
+        &mut self.0[index(pos)]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexing() {
+        let mut values: PerSquare<u8> = PerSquare::default();
+        let e4: BoardPos = "e4".parse().unwrap();
+        values[&e4] = 5;
+        values["h8".parse::<BoardPos>().unwrap()] += 2;
+
+        assert_eq!(values[e4.clone()], 5);
+        assert_eq!(values.as_array()[3 * 8 + 4], 5);
+        assert_eq!(values.as_array()[63], 2);
+        assert_eq!(values.iter().filter(|(_, value)| **value != 0).count(), 2);
+
+        let files = PerSquare::from_fn(|pos| pos.file_char());
+        assert_eq!(files[e4], 'e');
+    }
+
+    #[test]
+    fn iteration_order() {
+        let squares: Vec<String> = PerSquare::<()>::default().iter().map(|(pos, _)| pos.to_string()).collect();
+        assert_eq!(squares.len(), 64);
+        assert_eq!(squares[..3], ["a1", "b1", "c1"]);
+        assert_eq!(squares[8], "a2");
+        assert_eq!(squares[63], "h8");
+    }
+
+    #[test]
+    fn map_values() {
+        let mut ranks = PerSquare::from_fn(|pos| pos.rank());
+        for (pos, value) in ranks.iter_mut() {
+            *value += pos.file();
+        }
+        let doubled = ranks.map(|value| value as u32 * 2);
+        assert_eq!(doubled["c5".parse::<BoardPos>().unwrap()], 12);
+    }
+}
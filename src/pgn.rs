@@ -0,0 +1,928 @@
+//! Reading and writing games in Portable Game Notation (PGN).
+//!
+//! A single game can be parsed with `PgnGame::parse`. Files with many games are
+//! best read with `PgnReader` and written with `PgnWriter`, which handle one game
+//! at a time.
+
+use std::{fmt, io, time::Duration};
+
+use crate::{board::Color, game::{Annotation, Game, GameState, Move, PieceLetters, SanError, Variant}, pos::BoardPos, time_control::TimeControl};
+
+mod reader;
+pub use reader::PgnReader;
+
+mod writer;
+pub use writer::PgnWriter;
+
+mod recorder;
+pub use recorder::{MatchRecorder, MatchSummary, PlayerScore};
+
+mod search;
+pub use search::{find_position, PositionHit};
+
+/// The result tokens that end the movetext of a game.
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// A game read from or to be written to PGN.
+///
+/// The moves are always legal, they are checked when the game is parsed.
+#[derive(Clone, Debug)]
+pub struct PgnGame {
+    tags: Vec<(String, String)>,
+    /// The comments before the first move.
+    comments: Vec<String>,
+    moves: Vec<PgnMove>,
+    result: String,
+}
+
+/// A move in the movetext of a PGN game, with the comments and numeric
+/// annotation glyphs (NAGs) that follow it.
+///
+/// The clock and evaluation commands used by Lichess and chess.com, like
+/// `{[%clk 0:02:30] [%eval -0.45]}`, are read from the comments into `clock`
+/// and `eval`, and the elapsed move time `[%emt 0:00:05]` into `time_spent`.
+/// Highlighted squares like `[%csl Ge4,Rd5]` and arrows like `[%cal Gg1f3]` are
+/// read into `highlights` and `arrows`. Other commands are kept in the comments.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PgnMove {
+    m: Move,
+    san: String,
+    comments: Vec<String>,
+    nags: Vec<u8>,
+    clock: Option<Duration>,
+    time_spent: Option<Duration>,
+    eval: Option<Score>,
+    arrows: Vec<(BoardPos, BoardPos, char)>,
+    highlights: Vec<(BoardPos, char)>,
+}
+
+impl PgnMove {
+    /// Get the move.
+    pub fn get(&self) -> &Move { &self.m }
+    /// Get the move in Standard Algebraic Notation with English piece letters,
+    /// followed by the `!` and `?` suffixes that it was written with.
+    pub fn san(&self) -> &str { &self.san }
+    /// Get the comments after the move, without the surrounding braces.
+    pub fn comments(&self) -> &[String] { &self.comments }
+    /// Get the numeric annotation glyphs after the move, for example `2` for `$2`.
+    pub fn nags(&self) -> &[u8] { &self.nags }
+    /// Get the time left on the clock of the player after the move, from `%clk`.
+    pub fn clock(&self) -> Option<Duration> { self.clock }
+    /// Get the time the player spent on the move, from `%emt`.
+    pub fn time_spent(&self) -> Option<Duration> { self.time_spent }
+    /// Get the evaluation of the position after the move, from `%eval`.
+    pub fn eval(&self) -> Option<Score> { self.eval }
+    /// Get the arrows drawn after the move as (from, to, color tag), from `%cal`.
+    pub fn arrows(&self) -> &[(BoardPos, BoardPos, char)] { &self.arrows }
+    /// Get the squares highlighted after the move as (square, color tag), from
+    /// `%csl`.
+    pub fn highlights(&self) -> &[(BoardPos, char)] { &self.highlights }
+
+    fn new(m: Move, san: String) -> PgnMove {
+        PgnMove {
+            m, san, comments: Vec::new(), nags: Vec::new(), clock: None, time_spent: None, eval: None,
+            arrows: Vec::new(), highlights: Vec::new(),
+        }
+    }
+}
+
+/// The tags of a game to be written to PGN, see `Game::to_pgn`.
+///
+/// Tags of the Seven Tag Roster that are `None` are written as unknown, with
+/// `?`. The `Result` tag is not included since it is taken from the game.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PgnTags {
+    pub event: Option<String>,
+    pub site: Option<String>,
+    /// The date the game started, written like `2024.03.15`.
+    pub date: Option<String>,
+    pub round: Option<String>,
+    pub white: Option<String>,
+    pub black: Option<String>,
+    /// The time control, written in the `TimeControl` tag after the Seven Tag
+    /// Roster if it is set.
+    pub time_control: Option<TimeControl>,
+    /// Other tags, like `WhiteElo` or `Annotator`, as (name, value) pairs. They
+    /// are written after the Seven Tag Roster and the time control in this order. The `FEN`, `SetUp`,
+    /// `Variant` and `Result` tags are taken from the game and are left out.
+    pub other: Vec<(String, String)>,
+}
+
+/// An evaluation of a position from the perspective of white, as written in a
+/// `%eval` command.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Score {
+    /// An advantage in centipawns, written in pawns like `-0.45`.
+    Centipawns(i32),
+    /// A forced mate in the number of moves, negative if black mates. Written like
+    /// `#3` or `#-2`.
+    Mate(i32),
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Centipawns(centipawns) => write!(f, "{:.2}", *centipawns as f64 / 100.0),
+            Self::Mate(moves) => write!(f, "#{moves}"),
+        }
+    }
+}
+
+/// Errors returned when reading PGN.
+#[derive(Debug)]
+pub enum PgnError {
+    /// Reading from the underlying reader failed.
+    Io(io::Error),
+    /// A line in the tag section is not a valid tag. The line is included.
+    InvalidTag(String),
+    /// The `FEN` tag is not a valid FEN string. The error message is included.
+    InvalidFen(String),
+    /// The `Variant` tag names a variant that is not built in, see `Variant`.
+    /// The name is included.
+    UnknownVariant(String),
+    /// A move in the movetext is not legal. `ply` is the number of the half-move,
+    /// starting at 1 for the first move of the movetext.
+    IllegalMove { ply: usize, san: String, error: SanError },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Failed to read PGN: {err}"),
+            Self::InvalidTag(line) => write!(f, "Invalid tag: {line}"),
+            Self::InvalidFen(err) => write!(f, "Invalid FEN tag: {err}"),
+            Self::UnknownVariant(name) => write!(f, "Unknown variant: {name}"),
+            Self::IllegalMove { ply, san, error } => write!(f, "Invalid move '{san}' at ply {ply}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<io::Error> for PgnError {
+    fn from(err: io::Error) -> PgnError {
+        PgnError::Io(err)
+    }
+}
+
+/// Errors returned when changing the tags or the result of a `PgnGame`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PgnTagError {
+    /// The tags would not describe a valid starting position. The error message
+    /// is included.
+    InvalidStartingPosition(String),
+    /// A move of the game would not be legal from the starting position of the
+    /// tags. `ply` is the number of the half-move, starting at 1.
+    IllegalMove { ply: usize },
+    /// The result is not `1-0`, `0-1`, `1/2-1/2` or `*`. The result is included.
+    InvalidResult(String),
+}
+
+impl fmt::Display for PgnTagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidStartingPosition(err) => write!(f, "{err}"),
+            Self::IllegalMove { ply } => write!(f, "Move {ply} is not legal from the new starting position."),
+            Self::InvalidResult(result) => write!(f, "'{result}' is not a valid result, expected 1-0, 0-1, 1/2-1/2 or *."),
+        }
+    }
+}
+
+impl std::error::Error for PgnTagError {}
+
+/// The tags that change the starting position of a game.
+const POSITION_TAGS: [&str; 3] = ["FEN", "SetUp", "Variant"];
+
+impl PgnGame {
+
+    /// Parse a single game.
+    ///
+    /// Comments and NAGs are kept with the move they follow, and comments before
+    /// the first move with the game, while variations in parentheses are skipped. Lines starting with `%` are ignored. If the game
+    /// has a `FEN` tag, the moves are played from that position, and if it has a
+    /// `Variant` tag, by the rules of that variant.
+    ///
+    /// # Errors
+    /// An error is returned if a tag can not be parsed, the `FEN` or `Variant`
+    /// tag is invalid or if a move is not legal.
+    pub fn parse(pgn: &str) -> Result<PgnGame, PgnError> {
+        PgnGame::parse_with_letters(pgn, &PieceLetters::ENGLISH)
+    }
+
+    /// Parse a single game where the moves are written with other piece letters,
+    /// for example German PGN files where a knight move is written as `Sf3`. See
+    /// `parse`.
+    ///
+    /// # Errors
+    /// The same errors as `parse` are returned.
+    pub fn parse_with_letters(pgn: &str, letters: &PieceLetters) -> Result<PgnGame, PgnError> {
+        let pgn = pgn.strip_prefix('\u{feff}').unwrap_or(pgn);
+
+        let mut tags = Vec::new();
+        let mut movetext = String::new();
+        for line in pgn.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('%') {
+                continue;
+            }
+            if movetext.trim().is_empty() && trimmed.starts_with('[') {
+                tags.push(parse_tag(trimmed).ok_or_else(|| PgnError::InvalidTag(trimmed.to_string()))?);
+            } else {
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
+
+        let mut game = PgnGame { tags, comments: Vec::new(), moves: Vec::new(), result: String::new() };
+        let mut position = game.starting_position()?;
+
+        for token in tokenize(&movetext) {
+            match token {
+                Token::Move(san) => {
+                    let ply = game.moves.len() + 1;
+                    let illegal = |error| PgnError::IllegalMove { ply, san: san.to_string(), error };
+                    let m = position.parse_san_with_letters(san, letters).map_err(illegal)?;
+                    // The SAN is written again with English letters, so that the
+                    // game can be written to PGN that other programs read. The
+                    // `!` and `?` suffixes of the movetext are kept.
+                    let suffix = &san[san.trim_end_matches(['!', '?']).len()..];
+                    let english = position.move_to_san(&m) + suffix;
+                    position.make_move(&m).map_err(|_| illegal(SanError::IllegalMove))?;
+                    game.moves.push(PgnMove::new(m, english));
+                },
+                Token::Comment(comment) => match game.moves.last_mut() {
+                    Some(last) => last.push_comment(comment),
+                    None => game.comments.push(comment.trim().to_string()),
+                },
+                Token::Nag(nag) => {
+                    if let Some(last) = game.moves.last_mut() {
+                        last.nags.push(nag);
+                    }
+                },
+                Token::Result(result) => {
+                    game.result = result.to_string();
+                    break;
+                },
+            }
+        }
+
+        if game.result.is_empty() {
+            game.result = game.tag("Result").unwrap_or("*").to_string();
+        }
+
+        Ok(game)
+    }
+
+    /// Create a game with the moves played in `game`.
+    ///
+    /// The tags of the Seven Tag Roster are set to unknown values, except for the
+    /// result. If the game did not start from the standard starting position, the
+    /// `SetUp` and `FEN` tags are set, and games of a variant other than standard
+    /// chess get a `Variant` tag with `Variant::name`. The result is taken from `Game::outcome`,
+    /// and is `*` if the game has not ended. A last move that is waiting for a
+    /// promotion is left out.
+    ///
+    /// Annotations of the moves are kept, with evaluations, clock times, the time
+    /// spent, arrows and highlights in `eval`, `clock`, `time_spent`, `arrows` and
+    /// `highlights`. If the game was adjudicated, the `Termination` tag is
+    /// set to `adjudication` and the note is added as a comment after the last
+    /// move, or before the moves if there are none.
+    pub fn from_game(game: &Game) -> PgnGame {
+        let variant = game.variant().unwrap_or_default();
+        let mut position = Game::from_fen_with_variant(game.starting_fen(), variant).expect("The starting FEN is valid.");
+
+        let mut history = game.history();
+        if let GameState::PromotionRequired(_) = game.clone().get_state() {
+            history = &history[..history.len() - 1];
+        }
+
+        let mut moves = Vec::new();
+        for (index, m) in history.iter().enumerate() {
+            let san = position.move_to_san(m);
+            position.make_move(m).expect("Moves in the history are legal.");
+            let mut pgn_move = PgnMove::new(m.clone(), san);
+            for annotation in game.annotations(index + 1) {
+                match annotation {
+                    Annotation::Comment(comment) => pgn_move.comments.push(comment.clone()),
+                    Annotation::Nag(nag) => pgn_move.nags.push(*nag),
+                    Annotation::Eval(centipawns) => pgn_move.eval = Some(Score::Centipawns(*centipawns)),
+                    Annotation::Clock(time) => pgn_move.clock = Some(*time),
+                    Annotation::TimeSpent(time) => pgn_move.time_spent = Some(*time),
+                    Annotation::Arrow { from, to, color } => pgn_move.arrows.push((from.clone(), to.clone(), *color)),
+                    Annotation::Highlight { square, color } => pgn_move.highlights.push((square.clone(), *color)),
+                }
+            }
+            moves.push(pgn_move);
+        }
+
+        let result = match game.outcome() {
+            Some(outcome) => match outcome.winner() {
+                Some(Color::White) => "1-0",
+                Some(Color::Black) => "0-1",
+                None => "1/2-1/2",
+            },
+            None => "*",
+        };
+
+        let mut pgn = PgnGame { tags: Vec::new(), comments: Vec::new(), moves, result: result.to_string() };
+        for (name, value) in [("Event", "?"), ("Site", "?"), ("Date", "????.??.??"), ("Round", "?"), ("White", "?"), ("Black", "?")] {
+            pgn.insert_tag(name, value);
+        }
+        pgn.insert_tag("Result", result);
+        if variant != Variant::Standard {
+            pgn.insert_tag("Variant", variant.name());
+        }
+        if game.starting_fen() != Game::new_variant(variant).to_fen() {
+            pgn.insert_tag("SetUp", "1");
+            pgn.insert_tag("FEN", game.starting_fen());
+        }
+        if let Some(note) = game.adjudication_note() {
+            pgn.insert_tag("Termination", "adjudication");
+            if !note.is_empty() {
+                match pgn.moves.last_mut() {
+                    Some(last) => last.comments.push(note.to_string()),
+                    None => pgn.comments.push(note.to_string()),
+                }
+            }
+        }
+        pgn
+    }
+
+    /// Get the tags in the order they were written.
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// Get the value of the tag with the name, for example `"White"`.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Set the value of a tag, replacing the value if the tag already exists.
+    ///
+    /// Changing the `FEN`, `SetUp` or `Variant` tag changes the starting position
+    /// or the rules, so the
+    /// moves are played again from the new position and their SAN is written
+    /// for it. Nothing is changed if that fails.
+    ///
+    /// # Errors
+    /// `InvalidStartingPosition` is returned if the `FEN` or `Variant` tag would
+    /// not be valid and `IllegalMove` if a move would not be legal from the new position.
+    pub fn set_tag(&mut self, name: &str, value: &str) -> Result<(), PgnTagError> {
+        if !POSITION_TAGS.contains(&name) {
+            self.insert_tag(name, value);
+            return Ok(());
+        }
+
+        let mut changed = self.clone();
+        changed.insert_tag(name, value);
+        let mut position = changed.starting_position()
+            .map_err(|err| PgnTagError::InvalidStartingPosition(err.to_string()))?;
+        for (index, pgn_move) in changed.moves.iter_mut().enumerate() {
+            let m = pgn_move.m.clone();
+            if !position.legal_moves().contains(&m) {
+                return Err(PgnTagError::IllegalMove { ply: index + 1 });
+            }
+            pgn_move.san = position.move_to_san(&m);
+            position.make_move(&m).map_err(|_| PgnTagError::IllegalMove { ply: index + 1 })?;
+        }
+        *self = changed;
+        Ok(())
+    }
+
+    /// Set a tag without checking it, see `set_tag`.
+    fn insert_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Set the tags that are given in `tags`, see `set_tag`. Tags that are `None`
+    /// are left unchanged.
+    ///
+    /// # Errors
+    /// The errors of `set_tag` are returned. The tags before the one that failed
+    /// are set.
+    pub fn set_tags(&mut self, tags: &PgnTags) -> Result<(), PgnTagError> {
+        let roster = [
+            ("Event", &tags.event), ("Site", &tags.site), ("Date", &tags.date),
+            ("Round", &tags.round), ("White", &tags.white), ("Black", &tags.black),
+        ];
+        for (name, value) in roster {
+            if let Some(value) = value {
+                self.set_tag(name, value)?;
+            }
+        }
+        if let Some(time_control) = &tags.time_control {
+            self.set_tag("TimeControl", &time_control.to_string())?;
+        }
+        for (name, value) in &tags.other {
+            self.set_tag(name, value)?;
+        }
+        Ok(())
+    }
+
+    /// Get the time control in the `TimeControl` tag, or `None` if there is no
+    /// such tag or it can not be read.
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.tag("TimeControl")?.parse().ok()
+    }
+
+    /// Get the comments before the first move, without the surrounding braces.
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// Get the moves of the game.
+    pub fn moves(&self) -> &[PgnMove] {
+        &self.moves
+    }
+
+    /// Get the result token of the game: `1-0`, `0-1`, `1/2-1/2` or `*` if the
+    /// game is not finished or the result is unknown.
+    pub fn result(&self) -> &str {
+        &self.result
+    }
+
+    /// Set the result token of the game. This also sets the `Result` tag.
+    ///
+    /// # Errors
+    /// `InvalidResult` is returned if the result is not `1-0`, `0-1`, `1/2-1/2`
+    /// or `*`.
+    pub fn set_result(&mut self, result: &str) -> Result<(), PgnTagError> {
+        if !RESULTS.contains(&result) {
+            return Err(PgnTagError::InvalidResult(result.to_string()));
+        }
+        self.result = result.to_string();
+        self.insert_tag("Result", result);
+        Ok(())
+    }
+
+    /// Get the position that the game started from, which is the starting
+    /// position of the variant in the `Variant` tag, or standard chess if there
+    /// is none, unless the game has a `FEN` tag.
+    ///
+    /// # Errors
+    /// `InvalidFen` is returned if the `FEN` tag is invalid and `UnknownVariant`
+    /// if the `Variant` tag is not a built-in variant.
+    pub fn starting_position(&self) -> Result<Game, PgnError> {
+        let variant = match self.tag("Variant") {
+            Some(name) => Variant::from_name(name).ok_or_else(|| PgnError::UnknownVariant(name.to_string()))?,
+            None => Variant::Standard,
+        };
+        match self.tag("FEN") {
+            Some(fen) => Game::from_fen_with_variant(fen, variant).map_err(|err| PgnError::InvalidFen(err.to_string())),
+            None => Ok(Game::new_variant(variant)),
+        }
+    }
+
+    /// Get the position after the last move of the game.
+    pub fn final_position(&self) -> Game {
+        let mut game = self.starting_position().expect("The FEN tag was validated when parsing.");
+        for m in &self.moves {
+            game.make_move(&m.m).expect("Moves were validated when parsing.");
+        }
+        game
+    }
+
+    /// Get the fingerprint of the moves of the game, see `Game::game_hash`. The
+    /// tags, comments and result are not included, so the same game exported by
+    /// different programs has the same hash.
+    pub fn game_hash(&self) -> u64 {
+        self.final_position().game_hash()
+    }
+
+    /// Get the fingerprint of the final position of the game, see
+    /// `Game::final_position_hash`.
+    pub fn final_position_hash(&self) -> u64 {
+        self.final_position().final_position_hash()
+    }
+}
+
+impl PgnMove {
+    /// Add a comment, taking out the `%clk`, `%emt`, `%eval`, `%csl` and `%cal`
+    /// commands that can be read.
+    /// A comment that only had such commands is not added.
+    fn push_comment(&mut self, comment: &str) {
+        let mut text = String::new();
+        let mut rest = comment;
+        let mut extracted = false;
+        while let Some(start) = rest.find("[%") {
+            let end = match rest[start..].find(']') {
+                Some(end) => start + end,
+                None => break,
+            };
+            text.push_str(&rest[..start]);
+            let command = &rest[start + 2..end];
+            let (name, value) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+            let value = value.trim();
+            match name {
+                "clk" if parse_clock(value).is_some() => self.clock = parse_clock(value),
+                "emt" if parse_clock(value).is_some() => self.time_spent = parse_clock(value),
+                "eval" if parse_score(value).is_some() => self.eval = parse_score(value),
+                "csl" if parse_highlights(value).is_some() => self.highlights.extend(parse_highlights(value).unwrap_or_default()),
+                "cal" if parse_arrows(value).is_some() => self.arrows.extend(parse_arrows(value).unwrap_or_default()),
+                _ => {
+                    text.push_str(&rest[start..=end]);
+                    rest = &rest[end + 1..];
+                    continue;
+                },
+            }
+            extracted = true;
+            rest = &rest[end + 1..];
+        }
+        text.push_str(rest);
+
+        if !extracted {
+            self.comments.push(text.trim().to_string());
+        } else if !text.trim().is_empty() {
+            self.comments.push(text.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+    }
+}
+
+/// Parse a clock time like `0:02:30` or `0:00:05.3`.
+fn parse_clock(value: &str) -> Option<Duration> {
+    let mut parts = value.split(':');
+    let (hours, minutes, seconds) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    let hours: u64 = hours.parse().ok()?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let seconds: u64 = seconds.parse().ok()?;
+    if minutes >= 60 || seconds >= 60 || fraction.len() > 9 || !fraction.chars().all(|char| char.is_ascii_digit()) {
+        return None;
+    }
+    let nanos = format!("{fraction:0<9}").parse().ok()?;
+    Some(Duration::new(hours * 3600 + minutes * 60 + seconds, nanos))
+}
+
+/// Format a clock time like `0:02:30`, with the fraction of a second only if
+/// there is one.
+fn format_clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    let mut str = format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if time.subsec_nanos() != 0 {
+        let fraction = format!("{:09}", time.subsec_nanos());
+        str.push('.');
+        str.push_str(fraction.trim_end_matches('0'));
+    }
+    str
+}
+
+/// Parse an evaluation like `-0.45` or `#3`.
+fn parse_score(value: &str) -> Option<Score> {
+    match value.strip_prefix('#') {
+        Some(moves) => moves.parse().ok().map(Score::Mate),
+        None => {
+            let pawns: f64 = value.parse().ok()?;
+            pawns.is_finite().then(|| Score::Centipawns((pawns * 100.0).round() as i32))
+        },
+    }
+}
+
+/// Parse highlighted squares like `Ge4,Rd5`.
+fn parse_highlights(value: &str) -> Option<Vec<(BoardPos, char)>> {
+    value.split(',').map(|mark| {
+        let (color, square) = split_color_tag(mark)?;
+        (square.len() == 2).then_some(())?;
+        Some((square.parse().ok()?, color))
+    }).collect()
+}
+
+/// Parse arrows like `Gg1f3,Rd8h4`.
+fn parse_arrows(value: &str) -> Option<Vec<(BoardPos, BoardPos, char)>> {
+    value.split(',').map(|mark| {
+        let (color, squares) = split_color_tag(mark)?;
+        (squares.len() == 4).then_some(())?;
+        Some((squares[..2].parse().ok()?, squares[2..].parse().ok()?, color))
+    }).collect()
+}
+
+/// Split a mark of `%csl` or `%cal` into its color letter and the ASCII squares.
+fn split_color_tag(mark: &str) -> Option<(char, &str)> {
+    let mark = mark.trim();
+    let color = mark.chars().next().filter(char::is_ascii_alphabetic)?;
+    let squares = &mark[1..];
+    squares.is_ascii().then_some((color, squares))
+}
+
+/// Format highlighted squares like `Ge4,Rd5`.
+fn format_highlights(highlights: &[(BoardPos, char)]) -> String {
+    highlights.iter().map(|(square, color)| format!("{color}{square}")).collect::<Vec<_>>().join(",")
+}
+
+/// Format arrows like `Gg1f3,Rd8h4`.
+fn format_arrows(arrows: &[(BoardPos, BoardPos, char)]) -> String {
+    arrows.iter().map(|(from, to, color)| format!("{color}{from}{to}")).collect::<Vec<_>>().join(",")
+}
+
+/// Parse a tag like `[White "Carlsen, Magnus"]` into its name and value.
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char == '\\' {
+            unescaped.push(chars.next()?);
+        } else {
+            unescaped.push(char);
+        }
+    }
+    Some((name.to_string(), unescaped))
+}
+
+enum Token<'a> {
+    Move(&'a str),
+    Comment(&'a str),
+    Nag(u8),
+    Result(&'a str),
+}
+
+/// Split movetext into moves, comments, NAGs and the result. Move numbers and
+/// variations are skipped.
+fn tokenize(movetext: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = movetext;
+    let mut variation_depth: usize = 0;
+
+    while let Some(char) = rest.chars().next() {
+        if char.is_whitespace() {
+            rest = &rest[char.len_utf8()..];
+            continue;
+        }
+        let (token, remaining) = match char {
+            '{' => {
+                let end = rest.find('}').unwrap_or(rest.len());
+                (Some(Token::Comment(&rest[1..end])), &rest[(end + 1).min(rest.len())..])
+            },
+            ';' => {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                (Some(Token::Comment(&rest[1..end])), &rest[end..])
+            },
+            '(' => {
+                variation_depth += 1;
+                (None, &rest[1..])
+            },
+            ')' => {
+                // An unbalanced `)` is skipped instead of hiding the rest of the
+                // mainline in a variation, like in `PgnReader`.
+                variation_depth = variation_depth.saturating_sub(1);
+                (None, &rest[1..])
+            },
+            // A `}` outside of a comment is skipped.
+            '}' => (None, &rest[1..]),
+            '$' => {
+                let end = rest[1..].find(|char: char| !char.is_ascii_digit()).map_or(rest.len(), |end| end + 1);
+                (rest[1..end].parse().ok().map(Token::Nag), &rest[end..])
+            },
+            _ => {
+                let end = rest.find(|char: char| char.is_whitespace() || "{};()$".contains(char)).unwrap_or(rest.len());
+                let word = &rest[..end];
+                let token = if RESULTS.contains(&word) {
+                    Some(Token::Result(word))
+                } else {
+                    // Strip move numbers like `12.` and `12...`, which may be written
+                    // directly in front of the move. Castling written with zeros, like
+                    // `0-0`, also starts with a digit.
+                    let after_number = word.trim_start_matches(|char: char| char.is_ascii_digit());
+                    let san = if after_number.is_empty() || after_number.starts_with('.') {
+                        after_number.trim_start_matches('.')
+                    } else {
+                        word
+                    };
+                    (!san.is_empty()).then_some(Token::Move(san))
+                };
+                (token, &rest[end..])
+            },
+        };
+        rest = remaining;
+
+        // Everything inside variations is skipped.
+        if variation_depth > 0 {
+            continue;
+        }
+        if let Some(token) = token {
+            tokens.push(token);
+        }
+    }
+
+    tokens
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::game::Outcome;
+
+    use super::*;
+
+    #[test]
+    fn parse_game() {
+        let pgn = r#"[Event "Casual \"blitz\""]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+1.e4 e5 2. Bc4 {Aiming at f7} Nc6 3. Qh5 $2 Nf6?? (3... g6 4. Qf3) 4. Qxf7# 1-0
+"#;
+        let game = PgnGame::parse(pgn).unwrap();
+
+        assert_eq!(game.tag("Event"), Some("Casual \"blitz\""));
+        assert_eq!(game.tag("Round"), None);
+        assert_eq!(game.result(), "1-0");
+        assert_eq!(game.moves().len(), 7);
+        assert_eq!(game.moves()[2].comments(), ["Aiming at f7"]);
+        assert_eq!(game.moves()[4].nags(), [2]);
+        assert_eq!(game.moves()[6].san(), "Qxf7#");
+        assert_eq!(game.final_position().to_fen(), "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4");
+    }
+
+    #[test]
+    fn hashes_ignore_tags() {
+        let a = PgnGame::parse("[Event \"Club\"]\n[White \"A\"]\n\n1. e4 e5 2. Nf3 {Main line} Nc6 1-0").unwrap();
+        let b = PgnGame::parse("[Site \"Online\"]\n[Black \"B\"]\n[Result \"*\"]\n\n1.e4 e5 2.Nf3 $1 Nc6 *").unwrap();
+        assert_eq!(a.game_hash(), b.game_hash());
+        assert_eq!(a.game_hash(), Game::from_pgn("1. e4 e5 2. Nf3 Nc6 *").unwrap().game_hash());
+
+        let transposed = PgnGame::parse("1. Nf3 Nc6 2. e4 e5 *").unwrap();
+        assert_ne!(a.game_hash(), transposed.game_hash());
+        assert_eq!(a.final_position_hash(), transposed.final_position_hash());
+    }
+
+    #[test]
+    fn parse_game_from_fen() {
+        let pgn = "[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 30\"]\n\n30... Kd7 31. O-O-O+ *";
+        let game = PgnGame::parse(pgn).unwrap();
+
+        assert_eq!(game.result(), "*");
+        assert_eq!(game.final_position().to_fen(), "8/3k4/8/8/8/8/8/2KR4 b - - 2 31");
+    }
+
+    #[test]
+    fn variant_tag() {
+        let mut game = Game::new_variant(Variant::RacingKings);
+        game.make_move(&"h2h3".parse().unwrap()).unwrap();
+        game.make_move(&"a2a3".parse().unwrap()).unwrap();
+        let pgn = PgnGame::from_game(&game);
+        assert_eq!(pgn.tag("Variant"), Some("Racing Kings"));
+        assert_eq!(pgn.tag("FEN"), None);
+        assert_eq!(pgn.moves()[0].san(), "Kh3");
+
+        let loaded = Game::from_pgn(&game.to_pgn(&PgnTags::default())).unwrap();
+        assert_eq!(loaded.variant(), Some(Variant::RacingKings));
+        assert_eq!(loaded.to_fen(), game.to_fen());
+
+        assert_eq!(PgnGame::from_game(&Game::new()).tag("Variant"), None);
+        let err = PgnGame::parse("[Variant \"Atomic\"]\n\n1. e4 *").unwrap_err();
+        assert!(matches!(err, PgnError::UnknownVariant(name) if name == "Atomic"));
+    }
+
+    #[test]
+    fn parse_german_game() {
+        let pgn = "1. e4 e5 2. Sf3 Sc6 3. Lc4 Lc5 4. 0-0 Sf6 5. Te1 0-0 6. c3 d6 7. d4 exd4 8. cxd4 Lb4 9. Dd3 *";
+        let game = PgnGame::parse_with_letters(pgn, &PieceLetters::GERMAN).unwrap();
+
+        assert_eq!(game.moves()[6].san(), "O-O");
+        assert_eq!(game.moves()[2].san(), "Nf3");
+        assert_eq!(game.final_position().to_fen(), "r1bq1rk1/ppp2ppp/2np1n2/8/1bBPP3/3Q1N2/PP3PPP/RNB1R1K1 b - - 2 9");
+        assert!(PgnGame::parse(pgn).is_err());
+
+        // The game is written with English letters, so it can be read back.
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_game(&game).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(written.contains("2. Nf3 Nc6 3. Bc4 Bc5 4. O-O Nf6 5. Re1 O-O"), "{written}");
+        let read = PgnGame::parse(&written).unwrap();
+        assert_eq!(read.final_position().to_fen(), game.final_position().to_fen());
+    }
+
+    #[test]
+    fn adjudicated_game() {
+        let mut game = Game::new();
+        for san in ["e4", "e5", "Ke2", "Ke7"] {
+            let m = game.parse_san(san).unwrap();
+            game.make_move(&m).unwrap();
+        }
+        game.adjudicate(Outcome::Adjudicated { winner: None }, "Agreed by the arbiter").unwrap();
+
+        let pgn = PgnGame::from_game(&game);
+        assert_eq!(pgn.result(), "1/2-1/2");
+        assert_eq!(pgn.tag("Result"), Some("1/2-1/2"));
+        assert_eq!(pgn.tag("Termination"), Some("adjudication"));
+        assert_eq!(pgn.moves()[3].comments(), ["Agreed by the arbiter"]);
+        assert!(pgn.comments().is_empty());
+
+        let mut game = Game::new();
+        game.adjudicate(Outcome::Adjudicated { winner: Some(Color::Black) }, "White did not show up").unwrap();
+        assert_eq!(game.get_state(), GameState::Adjudicated(Some(Color::Black)));
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_played_game(&game).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(written.ends_with("[Termination \"adjudication\"]\n\n{White did not show up} 0-1\n\n"), "{written}");
+        assert_eq!(PgnGame::parse(&written).unwrap().comments(), ["White did not show up"]);
+    }
+
+    #[test]
+    fn clock_and_eval_commands() {
+        let pgn = r#"[Event "Rated Blitz game"]
+[White "A"]
+[Black "B"]
+[Result "0-1"]
+[TimeControl "180+0"]
+
+1. e4 { [%eval 0.36] [%clk 0:03:00] } 1... e5 { [%eval 0.2] [%clk 0:03:00] } 2. Nf3 { [%eval 0.17] [%clk 0:02:58] } 2... Nc6 { [%eval 0.25] [%clk 0:02:59] } 3. Bc4 { [%eval 0.0] [%clk 0:02:56.4] } 3... Nd4 { [%eval 0.68] [%clk 0:02:57] } 4. Nxe5?? { (0.68 → -4.95) Blunder. Nxd4 was best. } { [%eval -4.95] [%clk 0:02:51] [%csl Ge5] } 4... Qg5 { [%eval -5.02] [%clk 0:02:53] } 5. Nxf7 { [%eval #-2] [%clk 0:02:40] } 5... Qxg2 { [%eval #-1] [%clk 0:02:50] } 6. Rf1 { [%clk 0:02:38] } 6... Qxe4+ { [%eval #-1] [%clk 0:02:49] } 7. Be2 { [%eval -7.47] [%clk 0:02:36] } 7... Nf3# { [%clk 0:02:48] } 0-1
+"#;
+        let game = PgnGame::parse(pgn).unwrap();
+        let moves = game.moves();
+        assert_eq!(moves[0].clock(), Some(Duration::from_secs(180)));
+        assert_eq!(moves[0].eval(), Some(Score::Centipawns(36)));
+        assert!(moves[0].comments().is_empty());
+        assert_eq!(moves[4].clock(), Some(Duration::from_millis(176_400)));
+        assert_eq!(moves[6].eval(), Some(Score::Centipawns(-495)));
+        assert_eq!(moves[6].comments(), ["(0.68 → -4.95) Blunder. Nxd4 was best."]);
+        assert_eq!(moves[6].highlights(), [("e5".parse().unwrap(), 'G')]);
+        assert_eq!(moves[8].eval(), Some(Score::Mate(-2)));
+        assert_eq!(moves[10].eval(), None);
+        assert_eq!(moves[13].clock(), Some(Duration::from_secs(168)));
+
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_game(&game).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+        let words = written.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert!(words.contains("4. Nxe5?? {[%eval -4.95] [%clk 0:02:51] [%csl Ge5]} {(0.68 → -4.95) Blunder."), "{written}");
+        assert!(words.contains("{[%eval 0.00] [%clk 0:02:56.4]}"), "{written}");
+
+        // Long comments are wrapped over several lines by the writer.
+        let read = PgnGame::parse(&written).unwrap();
+        assert_eq!(read.moves().len(), moves.len());
+        for (read, original) in read.moves().iter().zip(moves) {
+            assert_eq!((read.get(), read.clock(), read.eval()), (original.get(), original.clock(), original.eval()));
+            let comments: Vec<String> = read.comments().iter().map(|comment| comment.split_whitespace().collect::<Vec<_>>().join(" ")).collect();
+            assert_eq!(comments, original.comments());
+        }
+    }
+
+    #[test]
+    fn invalid_commands_are_kept() {
+        let game = PgnGame::parse("1. e4 {[%clk 3 minutes] [%eval] text [%eval 1.5]} *").unwrap();
+        assert_eq!(game.moves()[0].clock(), None);
+        assert_eq!(game.moves()[0].eval(), Some(Score::Centipawns(150)));
+        assert_eq!(game.moves()[0].comments(), ["[%clk 3 minutes] [%eval] text"]);
+
+        let game = PgnGame::parse("1. e4 {[%csl Ge9] [%cal Ge2] [%csl Rd4,Ye4]} *").unwrap();
+        assert_eq!(game.moves()[0].highlights().len(), 2);
+        assert!(game.moves()[0].arrows().is_empty());
+        assert_eq!(game.moves()[0].comments(), ["[%csl Ge9] [%cal Ge2]"]);
+    }
+
+    #[test]
+    fn position_tags_are_checked() {
+        let mut game = PgnGame::parse("1. e4 e5 2. Nf3 *").unwrap();
+        let err = game.set_tag("FEN", "4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap_err();
+        assert_eq!(err, PgnTagError::IllegalMove { ply: 1 });
+        assert!(matches!(game.set_tag("FEN", "not a fen"), Err(PgnTagError::InvalidStartingPosition(_))));
+        assert_eq!(game.tag("FEN"), None);
+        assert_eq!(game.final_position().to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+        // The moves are still legal without the queenside knight.
+        game.set_tag("SetUp", "1").unwrap();
+        game.set_tag("FEN", "r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1").unwrap();
+        assert_eq!(game.final_position().to_fen(), "r1bqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/R1BQKB1R b KQkq - 1 2");
+
+        // Another knight can also go to f3 in the new position.
+        let mut game = PgnGame::parse("[FEN \"4k3/8/8/8/8/8/8/4K1N1 w - - 0 1\"]\n\n1. Nf3 *").unwrap();
+        game.set_tag("FEN", "4k3/8/8/8/8/8/3N4/4K1N1 w - - 0 1").unwrap();
+        assert_eq!(game.moves()[0].san(), "Ngf3");
+
+        assert_eq!(game.set_result("2-0"), Err(PgnTagError::InvalidResult("2-0".to_string())));
+        assert_eq!(game.result(), "*");
+        game.set_result("1-0").unwrap();
+        assert_eq!(game.tag("Result"), Some("1-0"));
+    }
+
+    #[test]
+    fn unbalanced_braces_and_parentheses() {
+        let game = PgnGame::parse("1. e4 } e5 2. Nf3 ) Nc6 (2... d6 3. d4) 3. Bb5 *").unwrap();
+        let moves: Vec<&str> = game.moves().iter().map(PgnMove::san).collect();
+        assert_eq!(moves, ["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+        assert_eq!(game.result(), "*");
+    }
+
+    #[test]
+    fn illegal_move() {
+        let err = PgnGame::parse("1. e4 e5 2. Ke3 *").unwrap_err();
+        assert!(matches!(err, PgnError::IllegalMove { ply: 3, ref san, .. } if san == "Ke3"), "{err}");
+
+        assert!(matches!(PgnGame::parse("[White A]\n1. e4 *"), Err(PgnError::InvalidTag(_))));
+        assert!(matches!(PgnGame::parse("[FEN \"8/8 w\"]\n*"), Err(PgnError::InvalidFen(_))));
+    }
+}
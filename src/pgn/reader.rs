@@ -0,0 +1,270 @@
+use std::io::BufRead;
+
+use crate::game::PieceLetters;
+
+use super::{PgnError, PgnGame, RESULTS};
+
+/// Reads the games of a PGN database one at a time.
+///
+/// The reader is an iterator over the games, so only one game is kept in memory
+/// at a time. It is lenient about the layout of the file: a byte order mark at
+/// the start is skipped, lines starting with `%` are ignored and games do not
+/// have to be separated by blank lines.
+///
+/// Reading stops after the first error, unless recovery mode is enabled with
+/// `recover`. Errors from the underlying reader always stop reading.
+///
+/// ```
+/// use alvinw_chess::pgn::PgnReader;
+///
+/// let pgn = "[White \"A\"]\n\n1. e4 e5 1-0\n[White \"B\"]\n\n1. d4 *\n";
+/// let games: Vec<_> = PgnReader::new(pgn.as_bytes()).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(games.len(), 2);
+/// assert_eq!(games[1].tag("White"), Some("B"));
+/// ```
+pub struct PgnReader<R> {
+    reader: R,
+    /// A tag line that was read while reading the previous game.
+    pending_line: Option<String>,
+    at_start: bool,
+    recover: bool,
+    letters: PieceLetters,
+    done: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            reader,
+            pending_line: None,
+            at_start: true,
+            recover: false,
+            letters: PieceLetters::ENGLISH,
+            done: false,
+        }
+    }
+
+    /// Set whether to continue with the next game after a game that can not be
+    /// parsed, for example because it has an illegal move. The error is still
+    /// returned for the game that failed.
+    pub fn recover(mut self, recover: bool) -> PgnReader<R> {
+        self.recover = recover;
+        self
+    }
+
+    /// Set the piece letters that the moves are written with, for example
+    /// `PieceLetters::GERMAN`. English letters are used by default.
+    pub fn piece_letters(mut self, letters: PieceLetters) -> PgnReader<R> {
+        self.letters = letters;
+        self
+    }
+
+    fn read_line(&mut self) -> std::io::Result<Option<String>> {
+        if let Some(line) = self.pending_line.take() {
+            return Ok(Some(line));
+        }
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if self.at_start {
+            self.at_start = false;
+            if let Some(stripped) = line.strip_prefix('\u{feff}') {
+                line = stripped.to_string();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// Read the text of the next game, or `None` at the end of the input.
+    ///
+    /// A game ends after its result token, when a tag is found after the
+    /// movetext or at the end of the input.
+    fn read_game(&mut self) -> std::io::Result<Option<String>> {
+        let mut text = String::new();
+        let mut in_movetext = false;
+        let mut scanner = MovetextScanner::default();
+
+        while let Some(line) = self.read_line()? {
+            let trimmed = line.trim();
+            if !scanner.in_comment {
+                if trimmed.starts_with('%') {
+                    continue;
+                }
+                if trimmed.starts_with('[') {
+                    if in_movetext {
+                        self.pending_line = Some(line);
+                        break;
+                    }
+                } else if !trimmed.is_empty() {
+                    in_movetext = true;
+                }
+            }
+
+            text.push_str(&line);
+            if !line.ends_with('\n') {
+                text.push('\n');
+            }
+            if in_movetext && scanner.scan_line(&line) {
+                break;
+            }
+        }
+
+        if text.trim().is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(text))
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let text = match self.read_game() {
+            Ok(Some(text)) => text,
+            Ok(None) => {
+                self.done = true;
+                return None;
+            },
+            Err(err) => {
+                self.done = true;
+                return Some(Err(PgnError::Io(err)));
+            },
+        };
+
+        let game = PgnGame::parse_with_letters(&text, &self.letters);
+        if game.is_err() && !self.recover {
+            self.done = true;
+        }
+        Some(game)
+    }
+}
+
+/// Keeps track of comments and variations across the lines of movetext, to find
+/// the result token that ends the game.
+#[derive(Default)]
+struct MovetextScanner {
+    in_comment: bool,
+    variation_depth: usize,
+}
+
+impl MovetextScanner {
+    /// Scan a line of movetext and return whether it ends with a result token.
+    fn scan_line(&mut self, line: &str) -> bool {
+        let mut word_start = None;
+        for (index, char) in line.char_indices() {
+            if self.in_comment {
+                self.in_comment = char != '}';
+                continue;
+            }
+            let is_separator = char.is_whitespace() || "{};()".contains(char);
+            if is_separator {
+                if let Some(start) = word_start.take() {
+                    if self.variation_depth == 0 && RESULTS.contains(&&line[start..index]) {
+                        return true;
+                    }
+                }
+            } else if word_start.is_none() {
+                word_start = Some(index);
+            }
+            match char {
+                '{' => self.in_comment = true,
+                ';' => return false,
+                '(' => self.variation_depth += 1,
+                ')' => self.variation_depth = self.variation_depth.saturating_sub(1),
+                _ => {},
+            }
+        }
+        match word_start {
+            Some(start) => self.variation_depth == 0 && RESULTS.contains(&&line[start..]),
+            None => false,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three games where the second game has an illegal move. The games are not
+    /// separated by blank lines and the file starts with a byte order mark.
+    const DATABASE: &str = "\u{feff}% Exported from a test database
+[Event \"First\"]
+[Result \"1-0\"]
+
+1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0
+[Event \"Second\"]
+[Result \"*\"]
+
+1. e4 e5 2. Ke3 {The king can not move two squares} *
+[Event \"Third\"]
+[Result \"0-1\"]
+% This line is ignored
+1. f3 e5 2. g4 {A famous
+blunder} Qh4# 0-1
+";
+
+    #[test]
+    fn recover_after_illegal_move() {
+        let results: Vec<_> = PgnReader::new(DATABASE.as_bytes()).recover(true).collect();
+        assert_eq!(results.len(), 3);
+
+        let first = results[0].as_ref().unwrap();
+        assert_eq!(first.tag("Event"), Some("First"));
+        assert_eq!(first.moves().len(), 7);
+        assert_eq!(first.result(), "1-0");
+
+        assert!(matches!(results[1], Err(PgnError::IllegalMove { ply: 3, .. })));
+
+        let third = results[2].as_ref().unwrap();
+        assert_eq!(third.tag("Event"), Some("Third"));
+        assert_eq!(third.moves()[2].comments(), ["A famous\nblunder"]);
+        assert_eq!(third.result(), "0-1");
+    }
+
+    #[test]
+    fn stop_after_error_without_recovery() {
+        let results: Vec<_> = PgnReader::new(DATABASE.as_bytes()).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn games_without_tags() {
+        let pgn = "1. e4 e5 1/2-1/2\n1. d4 d5 2. c4 *\n\n\n";
+        let games: Vec<_> = PgnReader::new(pgn.as_bytes()).map(Result::unwrap).collect();
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].result(), "1/2-1/2");
+        assert_eq!(games[1].moves().len(), 3);
+        assert_eq!(PgnReader::new("\n \n".as_bytes()).count(), 0);
+    }
+
+    #[test]
+    fn stray_closing_brace() {
+        let pgn = "1. e4 } e5 1-0\n1. d4 ) d5 *\n";
+        let games: Vec<_> = PgnReader::new(pgn.as_bytes()).map(Result::unwrap).collect();
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].moves().len(), 2);
+        assert_eq!(games[1].moves().len(), 2);
+    }
+
+    #[test]
+    fn french_letters() {
+        let pgn = "1. e4 e5 2. Cf3 Cc6 3. Fc4 Fc5 4. 0-0 *\n1. d4 Cf6 2. Rd2 *\n";
+        let games: Vec<_> = PgnReader::new(pgn.as_bytes()).piece_letters(PieceLetters::FRENCH).map(Result::unwrap).collect();
+
+        assert_eq!(games.len(), 2);
+        assert!(games[0].final_position().to_fen().starts_with("r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq -"));
+        assert_eq!(games[1].moves()[2].get().to(), &"d2".parse().unwrap());
+    }
+}
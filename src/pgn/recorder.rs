@@ -0,0 +1,284 @@
+use std::{io::{self, Write}, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{board::Color, game::Game};
+
+use super::{PgnGame, PgnWriter};
+
+/// The results of the games of one player in a match, see `MatchSummary`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerScore {
+    pub name: String,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl PlayerScore {
+    /// Get the points of the player, with one point for a win and half a point
+    /// for a draw.
+    pub fn points(&self) -> f32 {
+        self.wins as f32 + self.draws as f32 / 2.0
+    }
+}
+
+/// The results of a match recorded by `MatchRecorder`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MatchSummary {
+    /// The players in the order they first played.
+    pub players: Vec<PlayerScore>,
+    /// The points that each player scored against each other player, indexed by
+    /// the positions of the players in `players`.
+    pub crosstable: Vec<Vec<f32>>,
+    /// The number of games recorded, including unfinished games.
+    pub games: u32,
+}
+
+impl MatchSummary {
+    /// Get the results of the player with the name, or `None` if they did not
+    /// play.
+    pub fn player(&self, name: &str) -> Option<&PlayerScore> {
+        self.players.iter().find(|player| player.name == name)
+    }
+
+    /// Get the points that `player` scored against `opponent`, or `None` if
+    /// either did not play.
+    pub fn points_against(&self, player: &str, opponent: &str) -> Option<f32> {
+        let player = self.players.iter().position(|score| score.name == player)?;
+        let opponent = self.players.iter().position(|score| score.name == opponent)?;
+        Some(self.crosstable[player][opponent])
+    }
+}
+
+/// Records the games of an engine match or a tournament and writes them to a
+/// PGN database as they finish.
+///
+/// The recorder fills in the `Event`, `Date`, `Round`, `White`, `Black` and
+/// `Result` tags of every game. Rounds are numbered from 1 in the order the
+/// games are recorded. The colors alternate every time the same two players
+/// meet, so over a match each player gets white in half the games.
+///
+/// ```
+/// use alvinw_chess::{game::Game, pgn::MatchRecorder};
+///
+/// let mut recorder = MatchRecorder::new(Vec::new(), "Engine match");
+/// let mut game = Game::new();
+/// for m in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+///     game.make_move(&m.parse().unwrap()).unwrap();
+/// }
+/// // Alice has white in the first game.
+/// recorder.record(&game, "Alice", "Bob").unwrap();
+/// let (pgn, summary) = recorder.finish().unwrap();
+/// assert!(String::from_utf8(pgn).unwrap().contains("[Black \"Bob\"]"));
+/// assert_eq!(summary.points_against("Bob", "Alice"), Some(1.0));
+/// ```
+pub struct MatchRecorder<W> {
+    writer: PgnWriter<W>,
+    event: String,
+    date: Option<String>,
+    summary: MatchSummary,
+    /// The number of games between each pair of players, indexed like the
+    /// crosstable.
+    meetings: Vec<Vec<u32>>,
+}
+
+impl<W: Write> MatchRecorder<W> {
+    /// Create a recorder that writes the games of the event to the writer.
+    pub fn new(writer: W, event: &str) -> MatchRecorder<W> {
+        MatchRecorder {
+            writer: PgnWriter::new(writer),
+            event: event.to_string(),
+            date: None,
+            summary: MatchSummary { players: Vec::new(), crosstable: Vec::new(), games: 0 },
+            meetings: Vec::new(),
+        }
+    }
+
+    /// Set the `Date` tag of the games, written like `2024.03.17`. By default the
+    /// date in UTC is used when each game is recorded.
+    pub fn date(mut self, date: &str) -> MatchRecorder<W> {
+        self.date = Some(date.to_string());
+        self
+    }
+
+    /// Get the names of the players that get white and black the next time the
+    /// two players meet.
+    pub fn colors<'a>(&self, player: &'a str, opponent: &'a str) -> (&'a str, &'a str) {
+        let meetings = self.index(player)
+            .zip(self.index(opponent))
+            .map_or(0, |(player, opponent)| self.meetings[player][opponent]);
+        if meetings.is_multiple_of(2) {
+            (player, opponent)
+        } else {
+            (opponent, player)
+        }
+    }
+
+    /// Record a game between two players and write it, returning the game as it
+    /// was written. See `colors` for which player had white.
+    ///
+    /// The result is taken from `Game::outcome`. Unfinished games are written with
+    /// the result `*` and only count in `games` of the summary.
+    ///
+    /// # Errors
+    /// Errors from the underlying writer are returned. The game is still counted
+    /// in the summary.
+    pub fn record(&mut self, game: &Game, player: &str, opponent: &str) -> io::Result<PgnGame> {
+        let (white, black) = self.colors(player, opponent);
+        let white_index = self.add_player(white);
+        let black_index = self.add_player(black);
+        self.summary.games += 1;
+        self.meetings[white_index][black_index] += 1;
+        self.meetings[black_index][white_index] += 1;
+
+        let mut pgn = PgnGame::from_game(game);
+        pgn.insert_tag("Event", &self.event);
+        pgn.insert_tag("Date", &self.date.clone().unwrap_or_else(today));
+        pgn.insert_tag("Round", &self.summary.games.to_string());
+        pgn.insert_tag("White", white);
+        pgn.insert_tag("Black", black);
+
+        if let Some(outcome) = game.outcome() {
+            let (white_points, black_points) = match outcome.winner() {
+                Some(Color::White) => (1.0, 0.0),
+                Some(Color::Black) => (0.0, 1.0),
+                None => (0.5, 0.5),
+            };
+            self.summary.crosstable[white_index][black_index] += white_points;
+            self.summary.crosstable[black_index][white_index] += black_points;
+            for (index, color) in [(white_index, Color::White), (black_index, Color::Black)] {
+                let score = &mut self.summary.players[index];
+                match outcome.winner() {
+                    None => score.draws += 1,
+                    Some(winner) if winner == color => score.wins += 1,
+                    Some(_) => score.losses += 1,
+                }
+            }
+        }
+
+        self.writer.write_game(&pgn)?;
+        Ok(pgn)
+    }
+
+    /// Get the results of the games recorded so far.
+    pub fn summary(&self) -> &MatchSummary {
+        &self.summary
+    }
+
+    /// Flush the writer and return it together with the results of the match.
+    ///
+    /// # Errors
+    /// Errors from the underlying writer are returned.
+    pub fn finish(mut self) -> io::Result<(W, MatchSummary)> {
+        self.writer.flush()?;
+        Ok((self.writer.into_inner(), self.summary))
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.summary.players.iter().position(|player| player.name == name)
+    }
+
+    fn add_player(&mut self, name: &str) -> usize {
+        if let Some(index) = self.index(name) {
+            return index;
+        }
+        self.summary.players.push(PlayerScore { name: name.to_string(), wins: 0, draws: 0, losses: 0 });
+        let len = self.summary.players.len();
+        for row in &mut self.summary.crosstable {
+            row.push(0.0);
+        }
+        self.summary.crosstable.push(vec![0.0; len]);
+        for row in &mut self.meetings {
+            row.push(0);
+        }
+        self.meetings.push(vec![0; len]);
+        self.summary.players.len() - 1
+    }
+}
+
+/// Get the current date in UTC, written like `2024.03.17`.
+fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs() / 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+/// Convert a number of days since 1970-01-01 to a date in the proleptic
+/// Gregorian calendar, using Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::Outcome, pgn::PgnReader};
+
+    use super::*;
+
+    fn finished(winner: Option<Color>) -> Game {
+        let mut game = Game::new();
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+        game.adjudicate(Outcome::Adjudicated { winner }, "").unwrap();
+        game
+    }
+
+    #[test]
+    fn mini_match() {
+        let mut recorder = MatchRecorder::new(Vec::new(), "Mini match").date("2024.03.17");
+        assert_eq!(recorder.colors("Alice", "Bob"), ("Alice", "Bob"));
+        recorder.record(&finished(Some(Color::White)), "Alice", "Bob").unwrap();
+        assert_eq!(recorder.colors("Alice", "Bob"), ("Bob", "Alice"));
+        recorder.record(&finished(None), "Alice", "Bob").unwrap();
+        recorder.record(&finished(Some(Color::Black)), "Alice", "Bob").unwrap();
+        let last = recorder.record(&finished(Some(Color::White)), "Alice", "Bob").unwrap();
+        assert_eq!(last.tag("Round"), Some("4"));
+        assert_eq!(last.tag("White"), Some("Bob"));
+
+        let (pgn, summary) = recorder.finish().unwrap();
+        let games: Vec<PgnGame> = PgnReader::new(pgn.as_slice()).collect::<Result<_, _>>().unwrap();
+        let tags: Vec<(&str, &str, &str, &str)> = games.iter()
+            .map(|game| (game.tag("Round").unwrap(), game.tag("White").unwrap(), game.tag("Black").unwrap(), game.result()))
+            .collect();
+        assert_eq!(tags, [
+            ("1", "Alice", "Bob", "1-0"),
+            ("2", "Bob", "Alice", "1/2-1/2"),
+            ("3", "Alice", "Bob", "0-1"),
+            ("4", "Bob", "Alice", "1-0"),
+        ]);
+        assert!(games.iter().all(|game| game.tag("Event") == Some("Mini match") && game.tag("Date") == Some("2024.03.17")));
+
+        assert_eq!(summary.games, 4);
+        assert_eq!(summary.points_against("Alice", "Bob"), Some(1.5));
+        assert_eq!(summary.points_against("Bob", "Alice"), Some(2.5));
+        let alice = summary.player("Alice").unwrap();
+        assert_eq!((alice.wins, alice.draws, alice.losses), (1, 1, 2));
+        assert_eq!(summary.player("Bob").unwrap().points(), 2.5);
+    }
+
+    #[test]
+    fn unfinished_games_are_not_scored() {
+        let mut recorder = MatchRecorder::new(Vec::new(), "?");
+        let pgn = recorder.record(&Game::new(), "Alice", "Bob").unwrap();
+        assert_eq!(pgn.result(), "*");
+        assert_eq!(recorder.summary().games, 1);
+        assert_eq!(recorder.summary().points_against("Alice", "Bob"), Some(0.0));
+        assert_eq!(recorder.colors("Alice", "Bob"), ("Bob", "Alice"));
+        assert_eq!(pgn.tag("Date").unwrap().len(), 10);
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_799), (2024, 3, 17));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+}
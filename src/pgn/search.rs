@@ -0,0 +1,91 @@
+use std::io::BufRead;
+
+use crate::game::Game;
+
+use super::PgnReader;
+
+/// A game that reached the position searched for by `find_position`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PositionHit {
+    /// The index of the game in the collection, starting at 0. Games that could
+    /// not be read are counted too.
+    pub game: usize,
+    /// The number of half-moves played when the position was first reached, so 0
+    /// is the starting position of the game.
+    pub ply: usize,
+    /// The tags of the game, like `White`, `Black` and `Event`.
+    pub tags: Vec<(String, String)>,
+}
+
+impl PositionHit {
+    /// Get the value of a tag of the game, or `None` if it has no such tag.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Find every game in a collection that reached the position of `target`, and
+/// the ply at which it was first reached.
+///
+/// Positions are compared by their repetition key, see `Game::repetition_key`,
+/// so the move counters and an en passant target that no pawn can capture on do
+/// not matter. Transpositions are found no matter the move order.
+///
+/// Games that can not be read are skipped. Use `PgnReader::recover` to continue
+/// with the following games after an error.
+pub fn find_position<R: BufRead>(reader: PgnReader<R>, target: &Game) -> Vec<PositionHit> {
+    let key = target.clone().repetition_key();
+    let mut hits = Vec::new();
+    for (index, game) in reader.enumerate() {
+        let Ok(game) = game else { continue };
+        let Ok(mut position) = game.starting_position() else { continue };
+
+        let mut ply = 0;
+        let mut found = position.repetition_key() == key;
+        for m in game.moves() {
+            if found {
+                break;
+            }
+            position.make_move(m.get()).expect("Moves were validated when parsing.");
+            ply += 1;
+            found = position.repetition_key() == key;
+        }
+
+        if found {
+            hits.push(PositionHit { game: index, ply, tags: game.tags().to_vec() });
+        }
+    }
+    hits
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAMES: &str = "[White \"A\"]\n\n1. d4 Nf6 2. c4 e6 3. Nc3 Bb4 *\n\n\
+        [White \"B\"]\n\n1. e4 e5 2. Nf3 Nc6 1-0\n\n\
+        [White \"C\"]\n\n1. Nf3 Nc6 2. Ng1 Nb8 3. c4 Nf6 4. d4 g6 1/2-1/2\n";
+
+    #[test]
+    fn transpositions() {
+        // After 1. d4 Nf6 2. c4, with other clocks.
+        let target = Game::from_fen("rnbqkb1r/pppppppp/5n2/8/2PP4/8/PP2PPPP/RNBQKBNR b KQkq - 7 12").unwrap();
+        let hits = find_position(PgnReader::new(GAMES.as_bytes()), &target);
+        let found: Vec<(usize, usize, Option<&str>)> = hits.iter().map(|hit| (hit.game, hit.ply, hit.tag("White"))).collect();
+        assert_eq!(found, [(0, 3, Some("A")), (2, 7, Some("C"))]);
+
+        // Only the first time is found, although game C returns to the start.
+        let hits = find_position(PgnReader::new(GAMES.as_bytes()), &Game::new());
+        let found: Vec<(usize, usize)> = hits.iter().map(|hit| (hit.game, hit.ply)).collect();
+        assert_eq!(found, [(0, 0), (1, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn unreadable_games_are_counted() {
+        let pgn = format!("[White \"X\"]\n\n1. e5 *\n\n{GAMES}");
+        let target = Game::from_fen("rnbqkb1r/pppppppp/5n2/8/2PP4/8/PP2PPPP/RNBQKBNR b KQkq - 0 2").unwrap();
+        let hits = find_position(PgnReader::new(pgn.as_bytes()).recover(true), &target);
+        assert_eq!(hits.iter().map(|hit| hit.game).collect::<Vec<_>>(), [1, 3]);
+    }
+}
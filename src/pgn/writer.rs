@@ -0,0 +1,306 @@
+use std::io::{self, Write};
+
+use crate::{board::Color, game::Game};
+
+use super::{format_arrows, format_clock, format_highlights, PgnGame};
+
+/// The tags that every game has, in the order they are written.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// The maximum length of a line of movetext, not counting the line break.
+const MAX_LINE_LENGTH: usize = 79;
+
+/// Writes games to a PGN database one at a time.
+///
+/// The games are written in the PGN export format. The Seven Tag Roster is
+/// written first, with `?` for missing tags, followed by the other tags in their
+/// original order. The movetext is wrapped to fit in 80 columns and every game
+/// is followed by a blank line. A comment can not contain `}`, which would end
+/// it early, so `}` is left out of the comments that are written.
+pub struct PgnWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> PgnWriter<W> {
+    pub fn new(writer: W) -> PgnWriter<W> {
+        PgnWriter { writer }
+    }
+
+    /// Write a game.
+    ///
+    /// # Errors
+    /// Errors from the underlying writer are returned.
+    pub fn write_game(&mut self, game: &PgnGame) -> io::Result<()> {
+        self.writer.write_all(format_game(game).as_bytes())
+    }
+
+    /// Write the moves played in a game, see `PgnGame::from_game`.
+    ///
+    /// # Errors
+    /// Errors from the underlying writer are returned.
+    pub fn write_played_game(&mut self, game: &Game) -> io::Result<()> {
+        self.write_game(&PgnGame::from_game(game))
+    }
+
+    /// Flush the underlying writer.
+    ///
+    /// # Errors
+    /// Errors from the underlying writer are returned.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Get the underlying writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Format a game in the PGN export format, followed by a blank line.
+pub(super) fn format_game(game: &PgnGame) -> String {
+    let mut str = String::new();
+
+    for name in SEVEN_TAG_ROSTER {
+        let value = match name {
+            "Result" => Some(game.result()),
+            _ => game.tag(name),
+        };
+        let default = if name == "Date" { "????.??.??" } else { "?" };
+        push_tag(&mut str, name, value.unwrap_or(default));
+    }
+    for (name, value) in game.tags() {
+        if !SEVEN_TAG_ROSTER.contains(&name.as_str()) {
+            push_tag(&mut str, name, value);
+        }
+    }
+    str.push('\n');
+
+    let mut movetext = Movetext::default();
+    let (mut fullmove_number, mut turn) = match game.starting_position() {
+        Ok(position) => (position.fullmove_number(), position.current_turn()),
+        Err(_) => (1, Color::White),
+    };
+    // The move number is also written before black's move when something else
+    // was written after white's move.
+    let mut needs_number = true;
+    for comment in game.comments() {
+        push_comment(&mut movetext, comment);
+    }
+    for m in game.moves() {
+        match turn {
+            Color::White => movetext.push(&format!("{fullmove_number}.")),
+            Color::Black if needs_number => movetext.push(&format!("{fullmove_number}...")),
+            Color::Black => {},
+        }
+        movetext.push(m.san());
+        needs_number = false;
+
+        for nag in m.nags() {
+            movetext.push(&format!("${nag}"));
+            needs_number = true;
+        }
+        let commands: Vec<String> = [
+            m.eval().map(|eval| format!("[%eval {eval}]")),
+            m.clock().map(|clock| format!("[%clk {}]", format_clock(clock))),
+            m.time_spent().map(|time| format!("[%emt {}]", format_clock(time))),
+            (!m.highlights().is_empty()).then(|| format!("[%csl {}]", format_highlights(m.highlights()))),
+            (!m.arrows().is_empty()).then(|| format!("[%cal {}]", format_arrows(m.arrows()))),
+        ].into_iter().flatten().collect();
+        let commands = (!commands.is_empty()).then(|| commands.join(" "));
+
+        for comment in commands.iter().chain(m.comments()) {
+            push_comment(&mut movetext, comment);
+            needs_number = true;
+        }
+
+        if turn == Color::Black {
+            fullmove_number += 1;
+        }
+        turn = turn.opposite();
+    }
+    movetext.push(game.result());
+
+    str.push_str(&movetext.text);
+    str.push_str("\n\n");
+    str
+}
+
+/// Write a comment in braces, leaving out `}` since it would end the comment.
+fn push_comment(movetext: &mut Movetext, comment: &str) {
+    let comment = comment.replace('}', "");
+    // The words are written separately so that long comments are wrapped. A
+    // word starting with `%` is kept on the line of the word before it, since a
+    // line starting with `%` is skipped when reading.
+    let mut words: Vec<String> = Vec::new();
+    for word in comment.split_whitespace() {
+        match words.last_mut() {
+            Some(last) if word.starts_with('%') => *last = format!("{last} {word}"),
+            _ => words.push(word.to_string()),
+        }
+    }
+    match words.as_slice() {
+        [] => movetext.push("{}"),
+        [word] => movetext.push(&format!("{{{word}}}")),
+        [first, middle @ .., last] => {
+            movetext.push(&format!("{{{first}"));
+            for word in middle {
+                movetext.push(word);
+            }
+            movetext.push(&format!("{last}}}"));
+        },
+    }
+}
+
+fn push_tag(str: &mut String, name: &str, value: &str) {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    str.push_str(&format!("[{name} \"{value}\"]\n"));
+}
+
+/// Movetext that is wrapped as tokens are added.
+#[derive(Default)]
+struct Movetext {
+    text: String,
+    line_length: usize,
+}
+
+impl Movetext {
+    fn push(&mut self, token: &str) {
+        if self.line_length > 0 && self.line_length + 1 + token.len() > MAX_LINE_LENGTH {
+            self.text.push('\n');
+            self.line_length = 0;
+        }
+        if self.line_length > 0 {
+            self.text.push(' ');
+            self.line_length += 1;
+        }
+        self.text.push_str(token);
+        self.line_length += token.len();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game::Annotation, pgn::PgnReader};
+
+    #[test]
+    fn game_from_custom_fen() {
+        let fen = "3r2k1/5ppp/8/8/3P4/8/5PPP/3R2K1 b - - 0 23";
+        let mut played = Game::from_fen(fen).unwrap();
+        for san in ["Rxd4", "Rxd4", "Kf8", "Rd7", "Ke8"] {
+            let m = played.parse_san(san).unwrap();
+            played.make_move(&m).unwrap();
+        }
+
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_played_game(&played).unwrap();
+        let pgn = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"3r2k1/5ppp/8/8/3P4/8/5PPP/3R2K1 b - - 0 23\"]\n"), "{pgn}");
+        assert!(pgn.ends_with("\n\n23... Rxd4 24. Rxd4 Kf8 25. Rd7 Ke8 *\n\n"), "{pgn}");
+
+        let read = PgnGame::parse(&pgn).unwrap();
+        assert_eq!(read.starting_position().unwrap().to_fen(), fen);
+        assert_eq!(read.final_position().to_fen(), played.to_fen());
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let mut played = Game::new();
+        for (from, to) in [("e2", "e4"), ("e7", "e5"), ("d1", "h5"), ("b8", "c6"), ("f1", "c4"), ("g8", "f6"), ("h5", "f7")] {
+            played.move_piece(&from.parse().unwrap(), &to.parse().unwrap()).unwrap();
+        }
+
+        let mut annotated = PgnGame::parse("[White \"Anna \\\"The Rook\\\"\"]\n[Annotator \"B\\\\C\"]\n\n1. d4 $1 {Solid} d5 2. c4 *").unwrap();
+        annotated.set_result("1/2-1/2").unwrap();
+
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_played_game(&played).unwrap();
+        writer.write_game(&annotated).unwrap();
+        let pgn = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!(pgn, [
+            "[Event \"?\"]",
+            "[Site \"?\"]",
+            "[Date \"????.??.??\"]",
+            "[Round \"?\"]",
+            "[White \"?\"]",
+            "[Black \"?\"]",
+            "[Result \"1-0\"]",
+            "",
+            "1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0",
+            "",
+            "[Event \"?\"]",
+            "[Site \"?\"]",
+            "[Date \"????.??.??\"]",
+            "[Round \"?\"]",
+            "[White \"Anna \\\"The Rook\\\"\"]",
+            "[Black \"?\"]",
+            "[Result \"1/2-1/2\"]",
+            "[Annotator \"B\\\\C\"]",
+            "",
+            "1. d4 $1 {Solid} 1... d5 2. c4 1/2-1/2",
+            "",
+            "",
+        ].join("\n"));
+
+        let games: Vec<PgnGame> = PgnReader::new(pgn.as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].moves(), PgnGame::from_game(&played).moves());
+        assert_eq!(games[0].result(), "1-0");
+        assert_eq!(games[1].tag("White"), Some("Anna \"The Rook\""));
+        assert_eq!(games[1].tag("Annotator"), Some("B\\C"));
+        assert_eq!(games[1].moves(), annotated.moves());
+        assert_eq!(games[1].result(), "1/2-1/2");
+    }
+
+    #[test]
+    fn closing_braces_in_comments() {
+        let mut game = Game::new();
+        game.make_move(&"e2e4".parse().unwrap()).unwrap();
+        game.make_move(&"e7e5".parse().unwrap()).unwrap();
+        game.annotate(1, Annotation::Comment("Best by test} e6 {really}".to_string()));
+        game.annotate(2, Annotation::Comment("}".to_string()));
+
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_played_game(&game).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(written.contains("1. e4 {Best by test e6 {really} 1... e5 {} *"), "{written}");
+
+        let read = PgnGame::parse(&written).unwrap();
+        assert_eq!(read.moves().len(), 2);
+        assert_eq!(read.moves()[0].comments(), ["Best by test e6 {really"]);
+        assert_eq!(read.moves()[1].comments(), [""]);
+    }
+
+    #[test]
+    fn no_line_starts_with_percent() {
+        for length in 60..80 {
+            let pgn = format!("1. e4 {{{} %50 off}} e5 2. Nf3 *", "a".repeat(length));
+            let game = PgnGame::parse(&pgn).unwrap();
+            let mut writer = PgnWriter::new(Vec::new());
+            writer.write_game(&game).unwrap();
+            let written = String::from_utf8(writer.into_inner()).unwrap();
+
+            assert!(written.lines().all(|line| !line.starts_with('%')), "{written}");
+            assert_eq!(PgnGame::parse(&written).unwrap().moves().len(), 3);
+        }
+    }
+
+    #[test]
+    fn long_movetext_is_wrapped() {
+        let mut game = Game::new();
+        for _ in 0..10 {
+            for (from, to) in [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")] {
+                game.move_piece(&from.parse().unwrap(), &to.parse().unwrap()).unwrap();
+            }
+        }
+        let pgn = format_game(&PgnGame::from_game(&game));
+
+        assert!(pgn.lines().all(|line| line.len() <= MAX_LINE_LENGTH), "{pgn}");
+        assert!(pgn.lines().filter(|line| line.starts_with(char::is_numeric)).count() > 1, "{pgn}");
+        let read = PgnGame::parse(&pgn).unwrap();
+        assert_eq!(read.moves().len(), 40);
+    }
+}
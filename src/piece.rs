@@ -1,5 +1,7 @@
+use crate::attacks::{BISHOP_DIRECTIONS, KING_OFFSETS, KNIGHT_OFFSETS, QUEEN_DIRECTIONS, ROOK_DIRECTIONS};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum PieceType {
     King,
     Queen,
@@ -22,7 +24,65 @@ impl PieceType {
         }
     }
 
+    /// Get the lowercase English name of this piece, for example `"knight"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PieceType::King => "king",
+            PieceType::Queen => "queen",
+            PieceType::Rook => "rook",
+            PieceType::Bishop => "bishop",
+            PieceType::Knight => "knight",
+            PieceType::Pawn => "pawn",
+        }
+    }
+
+    /// Get the conventional material value of this piece in centipawns: 100 for
+    /// a pawn, 300 for a knight or bishop, 500 for a rook and 900 for a queen.
+    ///
+    /// The king is never captured, so its value is 0.
+    pub fn value(&self) -> i32 {
+        match self {
+            PieceType::King => 0,
+            PieceType::Queen => 900,
+            PieceType::Rook => 500,
+            PieceType::Bishop => 300,
+            PieceType::Knight => 300,
+            PieceType::Pawn => 100,
+        }
+    }
+
+    /// Get a stable index between `[0-5]` (inclusive) for this piece, in the order
+    /// king, queen, rook, bishop, knight, pawn.
+    ///
+    /// This is useful for indexing tables and for compact encodings.
+    pub fn index(&self) -> usize {
+        match self {
+            PieceType::King => 0,
+            PieceType::Queen => 1,
+            PieceType::Rook => 2,
+            PieceType::Bishop => 3,
+            PieceType::Knight => 4,
+            PieceType::Pawn => 5,
+        }
+    }
+
+    /// Get the piece type from its index, see `index`.
+    ///
+    /// Returns `None` if the index is larger than `5`.
+    pub fn from_index(index: usize) -> Option<PieceType> {
+        match index {
+            0 => Some(PieceType::King),
+            1 => Some(PieceType::Queen),
+            2 => Some(PieceType::Rook),
+            3 => Some(PieceType::Bishop),
+            4 => Some(PieceType::Knight),
+            5 => Some(PieceType::Pawn),
+            _ => None,
+        }
+    }
+
     /// Get the piece type from a lowercase character from the FEN-notation.
+    #[allow(clippy::result_unit_err)]
     pub fn from_char(char: char) -> Result<Self, ()> {
         match char {
             'k' => Ok(PieceType::King),
@@ -34,4 +94,102 @@ impl PieceType {
             _ => Err(()),
         }
     }
-}
\ No newline at end of file
+
+    /// Get how this piece moves. Castling is not part of the pattern of the king.
+    pub fn movement(&self) -> MovementPattern {
+        match self {
+            PieceType::King => MovementPattern { leaps: &KING_OFFSETS, ..MovementPattern::NONE },
+            PieceType::Queen => MovementPattern { slides: &QUEEN_DIRECTIONS, ..MovementPattern::NONE },
+            PieceType::Rook => MovementPattern { slides: &ROOK_DIRECTIONS, ..MovementPattern::NONE },
+            PieceType::Bishop => MovementPattern { slides: &BISHOP_DIRECTIONS, ..MovementPattern::NONE },
+            PieceType::Knight => MovementPattern { leaps: &KNIGHT_OFFSETS, ..MovementPattern::NONE },
+            PieceType::Pawn => MovementPattern { pawn: true, ..MovementPattern::NONE },
+        }
+    }
+}
+
+/// A description of how a piece moves, see `Game::pattern_moves`.
+///
+/// Offsets and directions are `(file, rank)` pairs from the perspective of white,
+/// like `(1, 2)` for one file to the right and two ranks up.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MovementPattern {
+    /// The offsets that the piece jumps by, moving or capturing, like a knight.
+    pub leaps: &'static [(i8, i8)],
+    /// The directions that the piece slides in until it is blocked, like a rook.
+    pub slides: &'static [(i8, i8)],
+    /// Whether the piece also moves like a pawn, with double steps, diagonal
+    /// captures and en passant.
+    pub pawn: bool,
+}
+
+impl MovementPattern {
+    /// A pattern without any moves, useful as a base for other patterns.
+    pub const NONE: MovementPattern = MovementPattern { leaps: &[], slides: &[], pawn: false };
+
+    /// Whether every offset and direction in the pattern moves the piece, with
+    /// both deltas between -7 and 7.
+    ///
+    /// `Game::pattern_moves` ignores the offsets and directions that are not.
+    pub fn is_valid(&self) -> bool {
+        self.leaps.iter().chain(self.slides).all(|&delta| is_valid_delta(delta))
+    }
+}
+
+/// Whether the `(file, rank)` delta moves the piece and stays within the size of
+/// the board.
+pub(crate) fn is_valid_delta((delta_file, delta_rank): (i8, i8)) -> bool {
+    (delta_file, delta_rank) != (0, 0)
+        && (-7..=7).contains(&delta_file)
+        && (-7..=7).contains(&delta_rank)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::Color, game::Game};
+
+    use super::*;
+
+    #[test]
+    fn index_round_trip() {
+        for index in 0..6 {
+            let piece = PieceType::from_index(index).unwrap();
+            assert_eq!(piece.index(), index);
+        }
+        assert!(PieceType::from_index(6).is_none());
+    }
+
+    /// An Amazon moves like a queen and a knight.
+    const AMAZON: MovementPattern = MovementPattern { leaps: &KNIGHT_OFFSETS, slides: &QUEEN_DIRECTIONS, pawn: false };
+
+    #[test]
+    fn amazon_moves_on_empty_board() {
+        let game = Game::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").unwrap();
+        let pos = "d4".parse().unwrap();
+        let moves = game.pattern_moves(&pos, &Color::White, &AMAZON);
+        let queen_moves = game.pattern_moves(&pos, &Color::White, &PieceType::Queen.movement());
+        let knight_moves = game.pattern_moves(&pos, &Color::White, &PieceType::Knight.movement());
+
+        assert_eq!(moves.len(), 35);
+        assert_eq!(moves, queen_moves.union(&knight_moves).cloned().collect());
+        assert!(moves.contains(&"e6".parse().unwrap()) && moves.contains(&"h8".parse().unwrap()));
+    }
+
+    #[test]
+    fn invalid_patterns_are_ignored() {
+        const STUCK: MovementPattern = MovementPattern { leaps: &[(127, 0)], slides: &[(0, 0)], pawn: false };
+        const PARTLY_VALID: MovementPattern = MovementPattern { leaps: &[(-128, 1), (1, 2)], slides: &[(0, 0)], pawn: false };
+        assert!(AMAZON.is_valid());
+        assert!(!STUCK.is_valid());
+        assert!(!PARTLY_VALID.is_valid());
+
+        let game = Game::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").unwrap();
+        let pos = "d4".parse().unwrap();
+        assert!(game.pattern_moves(&pos, &Color::White, &STUCK).is_empty());
+        assert_eq!(
+            game.pattern_moves(&pos, &Color::White, &PARTLY_VALID),
+            ["e6".parse().unwrap()].into_iter().collect(),
+        );
+    }
+}
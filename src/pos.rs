@@ -1,5 +1,20 @@
 use std::fmt;
 
+use crate::board::Color;
+
+/// The names of the squares, indexed by `rank * 8 + file`, the same order as
+/// `PerSquare` and `SquareSet`.
+pub const SQUARE_NAMES: [&str; 64] = [
+    "a1", "b1", "c1", "d1", "e1", "f1", "g1", "h1",
+    "a2", "b2", "c2", "d2", "e2", "f2", "g2", "h2",
+    "a3", "b3", "c3", "d3", "e3", "f3", "g3", "h3",
+    "a4", "b4", "c4", "d4", "e4", "f4", "g4", "h4",
+    "a5", "b5", "c5", "d5", "e5", "f5", "g5", "h5",
+    "a6", "b6", "c6", "d6", "e6", "f6", "g6", "h6",
+    "a7", "b7", "c7", "d7", "e7", "f7", "g7", "h7",
+    "a8", "b8", "c8", "d8", "e8", "f8", "g8", "h8",
+];
+
 /// A struct that represends valid positions on a chess board.
 /// 
 /// The file is a number between `[0-7]` (inclusive) where `0` represents the
@@ -43,7 +58,7 @@ impl BoardPos {
     /// Get the lowercase character for the file represented by this board position.
     pub fn file_char(&self) -> char {
         // 0 -> a, 1 -> b, ..., 7 -> h
-        ('a' as u8 + self.file as u8) as char
+        (b'a' + self.file) as char
     }
 
     /// Get the internal representation of the rank as an integer between `[0-7]`.
@@ -57,14 +72,80 @@ impl BoardPos {
     ///
     /// In case the resulting position is outside the board, `None` is returned.
     pub fn offset(&self, delta_file: i8, delta_rank: i8) -> Option<BoardPos> {
-        let file = self.file as i8 + delta_file;
-        let rank = self.rank as i8 + delta_rank;
-        if file < 0 || file > 7 || rank < 0 || rank > 7 {
+        // Widened so that large deltas can not overflow.
+        let file = self.file as i16 + delta_file as i16;
+        let rank = self.rank as i16 + delta_rank as i16;
+        if !(0..=7).contains(&file) || !(0..=7).contains(&rank) {
             return None;
         }
         Some(BoardPos::new(file as u8, rank as u8))
     }
 
+    /// Get the number of king moves between this position and the other position,
+    /// which is the larger of the file and rank differences.
+    pub fn distance(&self, other: &BoardPos) -> u8 {
+        self.file.abs_diff(other.file).max(self.rank.abs_diff(other.rank))
+    }
+
+    /// Get the single-step direction `(delta_file, delta_rank)` that leads from this
+    /// position towards the other position.
+    ///
+    /// Returns `None` if the positions are the same or do not lie on the same rank,
+    /// file or diagonal.
+    pub fn direction_to(&self, other: &BoardPos) -> Option<(i8, i8)> {
+        let delta_file = other.file as i8 - self.file as i8;
+        let delta_rank = other.rank as i8 - self.rank as i8;
+        if delta_file == 0 && delta_rank == 0 {
+            return None;
+        }
+        if delta_file != 0 && delta_rank != 0 && delta_file.abs() != delta_rank.abs() {
+            return None;
+        }
+        Some((delta_file.signum(), delta_rank.signum()))
+    }
+
+    /// Get the positions strictly between this position and the other position, in
+    /// order starting from this position.
+    ///
+    /// Returns `None` if the positions do not lie on the same rank, file or
+    /// diagonal. Adjacent positions have no positions between them.
+    pub fn squares_between(&self, other: &BoardPos) -> Option<Vec<BoardPos>> {
+        let (delta_file, delta_rank) = self.direction_to(other)?;
+        let mut squares = Vec::new();
+        let mut pos = self.offset(delta_file, delta_rank)?;
+        while pos != *other {
+            let next = pos.offset(delta_file, delta_rank)?;
+            squares.push(pos);
+            pos = next;
+        }
+        Some(squares)
+    }
+
+    /// Get the position drawn at a column and row of a grid, as used by GUIs,
+    /// with column 0 and row 0 in the top-left corner.
+    ///
+    /// The board is drawn from the perspective of the team at the bottom. For
+    /// white, the top-left corner is `a8`, and for black it is `h1`.
+    ///
+    /// In case the column or row is outside the grid, `None` is returned.
+    pub fn from_grid(col: u8, row: u8, perspective: &Color) -> Option<BoardPos> {
+        if col > 7 || row > 7 {
+            return None;
+        }
+        Some(match perspective {
+            Color::White => BoardPos::new(col, 7 - row),
+            Color::Black => BoardPos::new(7 - col, row),
+        })
+    }
+
+    /// Get the column and row of the grid that the position is drawn at, see
+    /// `from_grid`.
+    pub fn to_grid(&self, perspective: &Color) -> (u8, u8) {
+        match perspective {
+            Color::White => (self.file, 7 - self.rank),
+            Color::Black => (7 - self.file, self.rank),
+        }
+    }
 }
 
 impl fmt::Display for BoardPos {
@@ -92,33 +173,68 @@ impl ParseBoardPosError {
     }
 }
 
+impl fmt::Display for ParseBoardPosError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl std::error::Error for ParseBoardPosError {}
+
 impl std::str::FromStr for BoardPos {
     type Err = ParseBoardPosError;
 
+    /// Parse a position like `e4`. Uppercase files like `E4` and surrounding
+    /// whitespace are also accepted.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
+        let mut chars = s.trim().chars();
 
         let file_char = chars.next()
-            .ok_or(ParseBoardPosError { msg: "String too short." })?;
+            .ok_or(ParseBoardPosError { msg: "String too short." })?
+            .to_ascii_lowercase();
 
-        let rank = chars.next()
+        let rank_char = chars.next()
             .ok_or(ParseBoardPosError { msg: "String too short." })?;
 
-        if !chars.next().is_none() {
+        if chars.next().is_some() {
             return Err(ParseBoardPosError { msg: "String too long." });
         }
 
-        let rank = rank.to_digit(10)
-            .ok_or(ParseBoardPosError { msg: "Second character must be a digit." })? as u8;
-
-        let rank = rank - 1;
+        if !rank_char.is_ascii_digit() {
+            return Err(ParseBoardPosError { msg: "Second character must be a digit." });
+        }
+        if !('1'..='8').contains(&rank_char) {
+            return Err(ParseBoardPosError { msg: "Rank must be between 1 and 8." });
+        }
+        if !('a'..='h').contains(&file_char) {
+            return Err(ParseBoardPosError { msg: "File must be between a and h." });
+        }
 
-        let file = file_char as u8 - 'a' as u8;
+        let rank = rank_char as u8 - b'1';
+        let file = file_char as u8 - b'a';
 
         Ok(BoardPos { file, rank })
     }
 }
 
+/// Positions are serialized as strings like `"e4"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for BoardPos {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BoardPos {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let str = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        str.parse().map_err(|err: ParseBoardPosError| {
+            serde::de::Error::custom(format!("invalid square \"{str}\": {err}"))
+        })
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -145,6 +261,24 @@ mod tests {
         BoardPos::new(2, 8);
     }
 
+    #[test]
+    fn offset_outside_board() {
+        let pos: BoardPos = "h8".parse().unwrap();
+        assert_eq!(pos.offset(-7, -7), Some("a1".parse().unwrap()));
+        assert_eq!(pos.offset(1, 0), None);
+        assert_eq!(pos.offset(i8::MAX, i8::MAX), None);
+        assert_eq!(BoardPos::new(0, 0).offset(i8::MIN, 0), None);
+    }
+
+    #[test]
+    fn distance() {
+        let pos: BoardPos = "e4".parse().unwrap();
+        assert_eq!(pos.distance(&pos), 0);
+        assert_eq!(pos.distance(&"f5".parse().unwrap()), 1);
+        assert_eq!(pos.distance(&"a8".parse().unwrap()), 4);
+        assert_eq!(pos.distance(&"h2".parse().unwrap()), 3);
+    }
+
     #[test]
     fn format_board_pos() {
         let pos = BoardPos::new(1, 3);
@@ -161,6 +295,38 @@ mod tests {
         assert_eq!(pos1.to_string(), "b4");
     }
 
+    #[test]
+    fn parse_invalid_board_pos() {
+        assert!("z9".parse::<BoardPos>().is_err());
+        assert!("i1".parse::<BoardPos>().is_err());
+        assert!("a0".parse::<BoardPos>().is_err());
+        assert!("a9".parse::<BoardPos>().is_err());
+        assert!("e 4".parse::<BoardPos>().is_err());
+    }
+
+    #[test]
+    fn parse_board_pos_leniently() {
+        let pos = BoardPos::new(4, 3);
+
+        assert_eq!("E4".parse::<BoardPos>().unwrap(), pos);
+        assert_eq!(" e4\n".parse::<BoardPos>().unwrap(), pos);
+        assert!("I1".parse::<BoardPos>().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json_round_trip() {
+        let positions = vec![BoardPos::new(4, 3), BoardPos::new(0, 7)];
+
+        let json = serde_json::to_string(&positions).unwrap();
+        assert_eq!(json, r#"["e4","a8"]"#);
+        assert_eq!(serde_json::from_str::<Vec<BoardPos>>(&json).unwrap(), positions);
+
+        let err = serde_json::from_str::<BoardPos>(r#""z9""#).unwrap_err();
+        assert_eq!(err.to_string(), "invalid square \"z9\": Rank must be between 1 and 8.");
+        assert!(serde_json::from_str::<BoardPos>("34").is_err());
+    }
+
     #[test]
     fn valid_offset() {
         let pos1 = BoardPos::new(1, 3);
@@ -178,4 +344,35 @@ mod tests {
         assert!(pos2.is_none())
     }
 
+    #[test]
+    fn squares_between() {
+        let a1: BoardPos = "a1".parse().unwrap();
+        let d4: BoardPos = "d4".parse().unwrap();
+
+        let between = a1.squares_between(&d4).unwrap();
+        assert_eq!(between, ["b2".parse().unwrap(), "c3".parse().unwrap()]);
+        assert_eq!(d4.direction_to(&a1), Some((-1, -1)));
+
+        assert!(a1.squares_between(&"b3".parse().unwrap()).is_none());
+        assert!(a1.squares_between(&"a2".parse().unwrap()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn grid_coordinates() {
+        let e4 = BoardPos::new(4, 3);
+        assert_eq!(e4.to_grid(&Color::White), (4, 4));
+        assert_eq!(e4.to_grid(&Color::Black), (3, 3));
+        assert_eq!(BoardPos::from_grid(0, 0, &Color::White), Some("a8".parse().unwrap()));
+        assert_eq!(BoardPos::from_grid(0, 0, &Color::Black), Some("h1".parse().unwrap()));
+        assert_eq!(BoardPos::from_grid(8, 0, &Color::White), None);
+
+        for index in 0..64 {
+            let pos = BoardPos::new(index % 8, index / 8);
+            assert_eq!(SQUARE_NAMES[index as usize], pos.to_string());
+            for perspective in [Color::White, Color::Black] {
+                let (col, row) = pos.to_grid(&perspective);
+                assert_eq!(BoardPos::from_grid(col, row, &perspective), Some(pos.clone()));
+            }
+        }
+    }
 }
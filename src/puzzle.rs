@@ -0,0 +1,191 @@
+//! Tactics puzzles in the format of the Lichess puzzle database.
+//!
+//! A puzzle is a FEN and a line of moves in coordinate notation. The first move
+//! is made by the opponent and leads to the position that the solver sees. After
+//! that, the solver's moves and the opponent's replies alternate.
+
+use std::fmt;
+
+use crate::game::{Game, Move, Outcome};
+
+/// A tactics puzzle, see the module documentation.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    fen: String,
+    /// The opponent's first move followed by the solution.
+    moves: Vec<Move>,
+}
+
+/// Errors returned when parsing a puzzle.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PuzzleError {
+    /// The FEN is not valid. The error message is included.
+    InvalidFen(String),
+    /// A move can not be read or is not legal. The index starts at 0 for the
+    /// opponent's first move.
+    InvalidMove { index: usize },
+    /// There is no move for the solver to make.
+    NoSolution,
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidFen(err) => write!(f, "Invalid FEN: {err}"),
+            Self::InvalidMove { index } => write!(f, "Move {} of the puzzle is invalid.", index + 1),
+            Self::NoSolution => write!(f, "The puzzle has no solution moves."),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+/// How far an attempt at a puzzle has come, see `Puzzle::verify`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PuzzleProgress {
+    /// The moves are correct so far, but the puzzle is not solved yet. The reply
+    /// is the opponent's answer to the last move, or `None` if no moves have been
+    /// made.
+    InProgress { reply: Option<Move> },
+    /// The puzzle is solved.
+    Solved,
+    /// The move with the index in the attempt is not the solution.
+    Wrong { index: usize },
+}
+
+impl Puzzle {
+
+    /// Parse a puzzle from a FEN and the moves in coordinate notation separated
+    /// by spaces, like the `FEN` and `Moves` columns of the Lichess puzzle
+    /// database.
+    ///
+    /// # Errors
+    /// `InvalidFen` is returned if the FEN is invalid, `InvalidMove` if a move can
+    /// not be read or is not legal, and `NoSolution` if there is no move after the
+    /// opponent's first move.
+    pub fn parse(fen: &str, moves: &str) -> Result<Puzzle, PuzzleError> {
+        let mut game = Game::from_fen(fen).map_err(|err| PuzzleError::InvalidFen(err.to_string()))?;
+
+        let mut parsed = Vec::new();
+        for (index, text) in moves.split_whitespace().enumerate() {
+            let m: Move = text.parse().map_err(|_| PuzzleError::InvalidMove { index })?;
+            game.make_move(&m).map_err(|_| PuzzleError::InvalidMove { index })?;
+            parsed.push(m);
+        }
+        if parsed.len() < 2 {
+            return Err(PuzzleError::NoSolution);
+        }
+
+        Ok(Puzzle { fen: fen.to_string(), moves: parsed })
+    }
+
+    /// Get the position that the solver sees, after the opponent's first move.
+    pub fn position(&self) -> Game {
+        let mut game = Game::from_fen(&self.fen).expect("The FEN was validated when parsing.");
+        game.make_move(&self.moves[0]).expect("The moves were validated when parsing.");
+        game
+    }
+
+    /// Get the opponent's first move, which leads to the position of the puzzle.
+    pub fn setup_move(&self) -> &Move {
+        &self.moves[0]
+    }
+
+    /// Get the solution, with the solver's moves and the opponent's replies.
+    pub fn solution(&self) -> &[Move] {
+        &self.moves[1..]
+    }
+
+    /// Check the solver's moves, without the opponent's replies, against the
+    /// solution.
+    ///
+    /// A move that mates is always correct, even if the solution has another
+    /// move, since a puzzle can have several mating moves at the end.
+    pub fn verify(&self, attempt: &[Move]) -> PuzzleProgress {
+        let mut game = self.position();
+        let solution = self.solution();
+
+        for (index, m) in attempt.iter().enumerate() {
+            let expected = match solution.get(index * 2) {
+                Some(expected) => expected,
+                None => return PuzzleProgress::Wrong { index },
+            };
+            if game.make_move(m).is_err() {
+                return PuzzleProgress::Wrong { index };
+            }
+            if matches!(game.outcome(), Some(Outcome::Checkmate { .. })) {
+                return if index + 1 == attempt.len() { PuzzleProgress::Solved } else { PuzzleProgress::Wrong { index: index + 1 } };
+            }
+            if m != expected {
+                return PuzzleProgress::Wrong { index };
+            }
+
+            match solution.get(index * 2 + 1) {
+                Some(reply) => {
+                    game.make_move(reply).expect("The moves were validated when parsing.");
+                    if index + 1 == attempt.len() {
+                        return PuzzleProgress::InProgress { reply: Some(reply.clone()) };
+                    }
+                },
+                None if index + 1 == attempt.len() => return PuzzleProgress::Solved,
+                None => return PuzzleProgress::Wrong { index: index + 1 },
+            }
+        }
+
+        PuzzleProgress::InProgress { reply: None }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(moves: &str) -> Vec<Move> {
+        moves.split_whitespace().map(|m| m.parse().unwrap()).collect()
+    }
+
+    /// A back rank mate in 2: 1. Qd8 Rxd8 2. Rxd8#.
+    fn mate_in_two() -> Puzzle {
+        Puzzle::parse("4r1k1/1b3ppp/8/Q7/8/8/5PPP/3R2K1 b - - 0 1", "b7a6 a5d8 e8d8 d1d8").unwrap()
+    }
+
+    #[test]
+    fn solve_mate_in_two() {
+        let puzzle = mate_in_two();
+        assert_eq!(puzzle.position().to_fen(), "4r1k1/5ppp/b7/Q7/8/8/5PPP/3R2K1 w - - 1 2");
+        assert_eq!(puzzle.solution().len(), 3);
+
+        assert_eq!(puzzle.verify(&[]), PuzzleProgress::InProgress { reply: None });
+        assert_eq!(puzzle.verify(&moves("a5d8")), PuzzleProgress::InProgress { reply: Some("e8d8".parse().unwrap()) });
+        assert_eq!(puzzle.verify(&moves("a5d8 d1d8")), PuzzleProgress::Solved);
+    }
+
+    #[test]
+    fn wrong_attempt() {
+        let puzzle = mate_in_two();
+        assert_eq!(puzzle.verify(&moves("a5e5")), PuzzleProgress::Wrong { index: 0 });
+        assert_eq!(puzzle.verify(&moves("a5a6")), PuzzleProgress::Wrong { index: 0 });
+        assert_eq!(puzzle.verify(&moves("a5d8 d1d7")), PuzzleProgress::Wrong { index: 1 });
+        assert_eq!(puzzle.verify(&moves("a5d8 d1d8 g1f1")), PuzzleProgress::Wrong { index: 2 });
+        // Illegal moves are wrong too.
+        assert_eq!(puzzle.verify(&moves("a5a8")), PuzzleProgress::Wrong { index: 0 });
+    }
+
+    #[test]
+    fn alternate_mate_is_accepted() {
+        let puzzle = Puzzle::parse("6k1/2p2ppp/8/8/8/8/5PPP/RR4K1 b - - 0 1", "c7c6 a1a8").unwrap();
+        assert_eq!(puzzle.verify(&moves("a1a8")), PuzzleProgress::Solved);
+        assert_eq!(puzzle.verify(&moves("b1b8")), PuzzleProgress::Solved);
+        assert_eq!(puzzle.verify(&moves("b1b7")), PuzzleProgress::Wrong { index: 0 });
+    }
+
+    #[test]
+    fn invalid_puzzles() {
+        assert!(matches!(Puzzle::parse("8/8 w", "e2e4 e7e5"), Err(PuzzleError::InvalidFen(_))));
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(Puzzle::parse(fen, "e2e4 e2e4").unwrap_err(), PuzzleError::InvalidMove { index: 1 });
+        assert_eq!(Puzzle::parse(fen, "e2e4 e7").unwrap_err(), PuzzleError::InvalidMove { index: 1 });
+        assert_eq!(Puzzle::parse(fen, "e2e4").unwrap_err(), PuzzleError::NoSolution);
+    }
+}
@@ -0,0 +1,260 @@
+//! Conversions to and from the types of the `shakmaty` crate.
+//!
+//! This module is only available with the `shakmaty` feature. It implements
+//! `From` and `TryFrom` between the following types:
+//!
+//! | This crate  | `shakmaty`        |
+//! |-------------|-------------------|
+//! | `BoardPos`  | `Square`          |
+//! | `Color`     | `Color`           |
+//! | `PieceType` | `Role`            |
+//! | `Tile`      | `Piece`           |
+//! | `Board`     | `Board`           |
+//! | `Game`      | `Chess`           |
+//! | `Move`      | `Move`, `UciMove` |
+//!
+//! Games are converted through FEN since `shakmaty` stores the position in its
+//! own representation. Moves are converted to `UciMove`, which can be turned into
+//! a `shakmaty::Move` with `UciMove::to_move` given the position the move is made
+//! in.
+
+use shakmaty::{fen::Fen, uci::UciMove, CastlingMode, Chess, EnPassantMode, File, Piece, PositionError, Rank, Role, Square};
+
+use crate::{board::{Board, Color, Tile}, game::{Game, Move}, piece::PieceType, pos::BoardPos};
+
+impl From<&BoardPos> for Square {
+    fn from(pos: &BoardPos) -> Square {
+        Square::from_coords(File::new(pos.file() as u32), Rank::new(pos.rank() as u32))
+    }
+}
+
+impl From<BoardPos> for Square {
+    fn from(pos: BoardPos) -> Square {
+        Square::from(&pos)
+    }
+}
+
+impl From<Square> for BoardPos {
+    fn from(square: Square) -> BoardPos {
+        BoardPos::new(square.file().to_u32() as u8, square.rank().to_u32() as u8)
+    }
+}
+
+impl From<Color> for shakmaty::Color {
+    fn from(color: Color) -> shakmaty::Color {
+        match color {
+            Color::White => shakmaty::Color::White,
+            Color::Black => shakmaty::Color::Black,
+        }
+    }
+}
+
+impl From<shakmaty::Color> for Color {
+    fn from(color: shakmaty::Color) -> Color {
+        match color {
+            shakmaty::Color::White => Color::White,
+            shakmaty::Color::Black => Color::Black,
+        }
+    }
+}
+
+impl From<PieceType> for Role {
+    fn from(piece: PieceType) -> Role {
+        match piece {
+            PieceType::King => Role::King,
+            PieceType::Queen => Role::Queen,
+            PieceType::Rook => Role::Rook,
+            PieceType::Bishop => Role::Bishop,
+            PieceType::Knight => Role::Knight,
+            PieceType::Pawn => Role::Pawn,
+        }
+    }
+}
+
+impl From<Role> for PieceType {
+    fn from(role: Role) -> PieceType {
+        match role {
+            Role::King => PieceType::King,
+            Role::Queen => PieceType::Queen,
+            Role::Rook => PieceType::Rook,
+            Role::Bishop => PieceType::Bishop,
+            Role::Knight => PieceType::Knight,
+            Role::Pawn => PieceType::Pawn,
+        }
+    }
+}
+
+impl From<Tile> for Piece {
+    fn from(tile: Tile) -> Piece {
+        Piece { color: tile.color().into(), role: tile.piece().into() }
+    }
+}
+
+impl From<Piece> for Tile {
+    fn from(piece: Piece) -> Tile {
+        Tile::new(piece.role.into(), piece.color.into())
+    }
+}
+
+impl From<&Board> for shakmaty::Board {
+    fn from(board: &Board) -> shakmaty::Board {
+        let mut converted = shakmaty::Board::empty();
+        for (pos, tile) in board.pieces() {
+            converted.set_piece_at(pos.into(), tile.into());
+        }
+        converted
+    }
+}
+
+impl From<&shakmaty::Board> for Board {
+    fn from(board: &shakmaty::Board) -> Board {
+        let mut converted = Board::empty();
+        for (square, piece) in board {
+            converted.set_tile(&square.into(), piece.into());
+        }
+        converted
+    }
+}
+
+/// Convert a game to a `shakmaty` position.
+///
+/// This fails if `shakmaty` does not consider the position valid, for example if
+/// a king is missing or a promotion is still required.
+impl TryFrom<&Game> for Chess {
+    type Error = PositionError<Chess>;
+
+    fn try_from(game: &Game) -> Result<Chess, Self::Error> {
+        Fen::from_ascii(game.to_fen().as_bytes())
+            .expect("Exported FEN is valid.")
+            .into_position(CastlingMode::Standard)
+    }
+}
+
+impl From<&Chess> for Game {
+    fn from(chess: &Chess) -> Game {
+        let fen = Fen::from_position(chess, EnPassantMode::Always).to_string();
+        Game::from_fen(&fen).expect("FEN exported by shakmaty is valid.")
+    }
+}
+
+/// Convert a `shakmaty` move to a move.
+///
+/// Castling is converted to the king moving two squares towards the rook. Piece
+/// drops can not be represented and are returned unchanged as the error.
+impl TryFrom<shakmaty::Move> for Move {
+    type Error = shakmaty::Move;
+
+    fn try_from(m: shakmaty::Move) -> Result<Move, Self::Error> {
+        match m {
+            shakmaty::Move::Normal { from, to, promotion, .. } => {
+                Ok(Move::new(from.into(), to.into(), promotion.map(PieceType::from)))
+            },
+            shakmaty::Move::EnPassant { from, to } => Ok(Move::new(from.into(), to.into(), None)),
+            shakmaty::Move::Castle { king, rook } => {
+                let dir = if rook.file() > king.file() { 2 } else { -2 };
+                let king = BoardPos::from(king);
+                let to = king.offset(dir, 0).ok_or(m)?;
+                Ok(Move::new(king, to, None))
+            },
+            shakmaty::Move::Put { .. } => Err(m),
+        }
+    }
+}
+
+impl From<&Move> for UciMove {
+    fn from(m: &Move) -> UciMove {
+        UciMove::Normal {
+            from: m.from().into(),
+            to: m.to().into(),
+            promotion: m.promotion().map(Role::from),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use shakmaty::Position;
+
+    use super::*;
+
+    const POSITIONS: [&str; 5] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+        "4k3/8/8/1pP5/8/8/8/4K3 w - b6 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "4k3/2P5/8/8/8/8/8/4K3 w - - 3 40",
+    ];
+
+    #[test]
+    fn square_and_piece_round_trip() {
+        for file in 0..8 {
+            for rank in 0..8 {
+                let pos = BoardPos::new(file, rank);
+                let square = Square::from(&pos);
+                assert_eq!(square.to_string(), pos.to_string());
+                assert_eq!(BoardPos::from(square), pos);
+            }
+        }
+
+        for piece in ['k', 'q', 'r', 'b', 'n', 'p'] {
+            let piece = PieceType::from_char(piece).unwrap();
+            for color in [Color::White, Color::Black] {
+                let tile = Tile::new(piece, color);
+                assert_eq!(Tile::from(Piece::from(tile)), tile);
+            }
+        }
+    }
+
+    #[test]
+    fn game_round_trip() {
+        for fen in POSITIONS {
+            let game = Game::from_fen(fen).unwrap();
+            let chess = Chess::try_from(&game).unwrap();
+
+            assert_eq!(Game::from(&chess).to_fen(), fen);
+            let board = Board::from(chess.board());
+            assert_eq!(&shakmaty::Board::from(&board), chess.board());
+        }
+    }
+
+    #[test]
+    fn invalid_game() {
+        let mut game = Game::from_fen("4k3/2P5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.move_piece(&"c7".parse().unwrap(), &"c8".parse().unwrap()).unwrap();
+
+        // The pawn on the last rank has not been promoted yet.
+        assert!(Chess::try_from(&game).is_err());
+    }
+
+    #[test]
+    fn move_round_trip() {
+        for fen in POSITIONS {
+            let mut game = Game::from_fen(fen).unwrap();
+            let chess = Chess::try_from(&game).unwrap();
+
+            for m in chess.legal_moves() {
+                let converted = Move::try_from(m).unwrap();
+
+                let moves = game.get_legal_moves(converted.from()).unwrap();
+                assert!(moves.contains(converted.to()), "{fen}: {m} is not legal");
+
+                let uci: UciMove = (&converted).into();
+                assert_eq!(uci.to_move(&chess).unwrap(), m);
+            }
+        }
+    }
+
+    #[test]
+    fn castling_and_en_passant_moves() {
+        let e1: BoardPos = "e1".parse().unwrap();
+        let castle = shakmaty::Move::Castle { king: Square::E1, rook: Square::A1 };
+        assert_eq!(Move::try_from(castle).unwrap(), Move::new(e1, "c1".parse().unwrap(), None));
+
+        let en_passant = shakmaty::Move::EnPassant { from: Square::C5, to: Square::B6 };
+        assert_eq!(Move::try_from(en_passant).unwrap(), Move::new("c5".parse().unwrap(), "b6".parse().unwrap(), None));
+
+        let put = shakmaty::Move::Put { role: Role::Knight, to: Square::E4 };
+        assert_eq!(Move::try_from(put), Err(put));
+    }
+}
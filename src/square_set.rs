@@ -0,0 +1,157 @@
+use std::{fmt, ops::{BitAnd, BitOr, Not}};
+
+use crate::pos::BoardPos;
+
+/// A set of positions on the board, stored as one bit per square.
+///
+/// Bit `rank * 8 + file` is set when the position is in the set, so `a1` is the
+/// least significant bit and `h8` is the most significant bit.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SquareSet(u64);
+
+impl SquareSet {
+    /// The set without any positions.
+    pub const EMPTY: SquareSet = SquareSet(0);
+
+    /// The set of all 64 positions.
+    pub const FULL: SquareSet = SquareSet(u64::MAX);
+
+    /// Create a set from its bit representation.
+    pub const fn from_bits(bits: u64) -> SquareSet {
+        SquareSet(bits)
+    }
+
+    /// Get the bit representation of the set.
+    pub const fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Check whether the position is in the set.
+    pub fn contains(&self, pos: &BoardPos) -> bool {
+        self.0 & bit(pos) != 0
+    }
+
+    /// Add the position to the set.
+    pub fn insert(&mut self, pos: &BoardPos) {
+        self.0 |= bit(pos);
+    }
+
+    /// Remove the position from the set.
+    pub fn remove(&mut self, pos: &BoardPos) {
+        self.0 &= !bit(pos);
+    }
+
+    /// Get the number of positions in the set.
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Get an iterator over the positions in the set.
+    ///
+    /// The positions are ordered by rank and then by file, starting with `a1`.
+    pub fn iter(&self) -> SquareSetIter {
+        SquareSetIter(self.0)
+    }
+}
+
+fn bit(pos: &BoardPos) -> u64 {
+    1 << (pos.rank() * 8 + pos.file())
+}
+
+impl BitOr for SquareSet {
+    type Output = SquareSet;
+
+    fn bitor(self, rhs: SquareSet) -> SquareSet {
+        SquareSet(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for SquareSet {
+    type Output = SquareSet;
+
+    fn bitand(self, rhs: SquareSet) -> SquareSet {
+        SquareSet(self.0 & rhs.0)
+    }
+}
+
+impl Not for SquareSet {
+    type Output = SquareSet;
+
+    fn not(self) -> SquareSet {
+        SquareSet(!self.0)
+    }
+}
+
+impl FromIterator<BoardPos> for SquareSet {
+    fn from_iter<T: IntoIterator<Item = BoardPos>>(iter: T) -> SquareSet {
+        let mut set = SquareSet::EMPTY;
+        for pos in iter {
+            set.insert(&pos);
+        }
+        set
+    }
+}
+
+impl IntoIterator for SquareSet {
+    type Item = BoardPos;
+    type IntoIter = SquareSetIter;
+
+    fn into_iter(self) -> SquareSetIter {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for SquareSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the positions in a `SquareSet`.
+pub struct SquareSetIter(u64);
+
+impl Iterator for SquareSetIter {
+    type Item = BoardPos;
+
+    fn next(&mut self) -> Option<BoardPos> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as u8;
+        // Clear the lowest set bit.
+        self.0 &= self.0 - 1;
+        Some(BoardPos::new(index % 8, index / 8))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_iterate() {
+        let mut set = SquareSet::EMPTY;
+        set.insert(&"h8".parse().unwrap());
+        set.insert(&"a1".parse().unwrap());
+        set.insert(&"e4".parse().unwrap());
+        set.remove(&"e4".parse().unwrap());
+
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&"a1".parse().unwrap()));
+        assert!(!set.contains(&"e4".parse().unwrap()));
+
+        let positions: Vec<BoardPos> = set.iter().collect();
+        assert_eq!(positions, ["a1".parse().unwrap(), "h8".parse().unwrap()]);
+        assert_eq!(format!("{set:?}"), "{a1, h8}");
+    }
+}
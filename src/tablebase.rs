@@ -0,0 +1,119 @@
+//! An integration point for endgame tablebases.
+//!
+//! This crate does not probe any tablebase format itself. Implement
+//! `TablebaseProber` for a type that wraps a tablebase library, like a Syzygy
+//! prober, to make the tablebase usable wherever this crate accepts a prober.
+//! Probers are only useful for positions with few pieces, see
+//! `Game::piece_count_total`.
+
+use std::collections::HashMap;
+
+use crate::game::{Game, Move};
+
+/// The result of a position with perfect play, from the perspective of the team
+/// whose turn it is.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Wdl {
+    /// The team to move wins.
+    Win,
+    /// The team to move wins, but not within the fifty-move rule.
+    CursedWin,
+    Draw,
+    /// The team to move loses, but the opponent can not win within the fifty-move
+    /// rule.
+    BlessedLoss,
+    /// The team to move loses.
+    Loss,
+}
+
+impl Wdl {
+    /// Get the result from the perspective of the other team.
+    pub fn opposite(&self) -> Wdl {
+        match self {
+            Self::Win => Self::Loss,
+            Self::CursedWin => Self::BlessedLoss,
+            Self::Draw => Self::Draw,
+            Self::BlessedLoss => Self::CursedWin,
+            Self::Loss => Self::Win,
+        }
+    }
+}
+
+/// A source of perfect endgame information.
+///
+/// Both methods return `None` when the position is not in the tablebase, for
+/// example because it has too many pieces.
+pub trait TablebaseProber {
+    /// Get the result of the position from the perspective of the team to move.
+    fn probe_wdl(&self, game: &Game) -> Option<Wdl>;
+
+    /// Get the best move in the position.
+    fn probe_best_move(&self, game: &Game) -> Option<Move>;
+}
+
+/// A tablebase that holds its positions in memory.
+///
+/// This is mostly useful for tests and for small, hand-made tablebases. Positions
+/// are looked up by their Zobrist hash, see `Game::zobrist_hash`.
+#[derive(Default)]
+pub struct MemoryTablebase {
+    positions: HashMap<u64, (Wdl, Option<Move>)>,
+}
+
+impl MemoryTablebase {
+    pub fn new() -> MemoryTablebase {
+        MemoryTablebase::default()
+    }
+
+    /// Add the position with its result and best move, replacing any previous
+    /// entry for the position.
+    pub fn insert(&mut self, game: &Game, wdl: Wdl, best_move: Option<Move>) {
+        self.positions.insert(game.zobrist_hash(), (wdl, best_move));
+    }
+
+    /// Get the number of positions in the tablebase.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+impl TablebaseProber for MemoryTablebase {
+    fn probe_wdl(&self, game: &Game) -> Option<Wdl> {
+        self.positions.get(&game.zobrist_hash()).map(|(wdl, _)| *wdl)
+    }
+
+    fn probe_best_move(&self, game: &Game) -> Option<Move> {
+        self.positions.get(&game.zobrist_hash()).and_then(|(_, best_move)| best_move.clone())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn king_and_queen_against_king() {
+        let mut game = Game::from_fen("6k1/8/6K1/8/8/8/8/Q7 w - - 0 1").unwrap();
+        assert_eq!(game.piece_count_total(), 3);
+
+        let mate = Move::new("a1".parse().unwrap(), "a8".parse().unwrap(), None);
+        let mut tablebase = MemoryTablebase::new();
+        tablebase.insert(&game, Wdl::Win, Some(mate.clone()));
+
+        let prober: &dyn TablebaseProber = &tablebase;
+        assert_eq!(prober.probe_wdl(&game), Some(Wdl::Win));
+
+        let best_move = prober.probe_best_move(&game).unwrap();
+        assert_eq!(best_move, mate);
+        game.move_piece(best_move.from(), best_move.to()).unwrap();
+
+        // The position after the move is not in the tablebase.
+        assert_eq!(prober.probe_wdl(&game), None);
+        assert_eq!(Wdl::Win.opposite(), Wdl::Loss);
+    }
+}
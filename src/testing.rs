@@ -0,0 +1,120 @@
+//! Utilities for testing the rules and code built on top of them.
+//!
+//! `play_random_game` plays games of random legal moves and checks that the
+//! state of the game stays consistent after every move, which finds bugs in the
+//! move generation and in how the game keeps track of the position.
+
+use rand::{rngs::StdRng, seq::IndexedRandom, SeedableRng};
+
+use crate::{game::{Game, Move, Outcome}, pos::BoardPos};
+
+/// Why a game played by `play_random_game` ended.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    /// The same position occurred three times.
+    ThreefoldRepetition,
+    /// No pawn has moved and nothing has been captured in the last fifty moves.
+    FiftyMoves,
+    /// Neither team can checkmate, see `Game::is_insufficient_material`.
+    InsufficientMaterial,
+    /// The maximum number of plies was reached.
+    PlyLimit,
+}
+
+/// A game played by `play_random_game`.
+#[derive(Clone, Debug)]
+pub struct CompletedGame {
+    pub game: Game,
+    pub termination: Termination,
+}
+
+/// Play a game from the starting position where every move is picked uniformly
+/// at random among the legal moves, including the piece of promotions.
+///
+/// The game ends with checkmate, stalemate, when a draw could be claimed or
+/// when it has `max_plies` moves. The same seed always gives the same game.
+///
+/// ## Panics
+/// This function will panic if the state of the game is inconsistent after a
+/// move, for example if the legal moves differ from those of a game created
+/// from the FEN of the position. The message includes the moves of the game so
+/// that the problem can be reproduced.
+pub fn play_random_game(seed: u64, max_plies: usize) -> CompletedGame {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut game = Game::new();
+    check(&mut game, seed);
+
+    let termination = loop {
+        if let Some(termination) = termination(&mut game) {
+            break termination;
+        }
+        if game.history().len() >= max_plies {
+            break Termination::PlyLimit;
+        }
+
+        // The order of the moves of each piece is unspecified, so they are sorted to
+        // make the game depend on the seed only.
+        let mut moves = game.legal_moves();
+        moves.sort_by_key(|m| (square(m.from()), square(m.to()), m.promotion().map(|piece| piece.index())));
+        let m = moves.choose(&mut rng).expect("The game is not over.");
+        game.make_move(m).unwrap_or_else(|err| panic!("The legal move {m} failed with {err:?}. {}", describe(&game, seed)));
+        check(&mut game, seed);
+    };
+    CompletedGame { game, termination }
+}
+
+fn square(pos: &BoardPos) -> u8 {
+    pos.rank() * 8 + pos.file()
+}
+
+fn termination(game: &mut Game) -> Option<Termination> {
+    match game.outcome() {
+        Some(Outcome::Checkmate { .. }) => Some(Termination::Checkmate),
+        Some(Outcome::Stalemate) => Some(Termination::Stalemate),
+        Some(Outcome::Adjudicated { .. }) => unreachable!("Random games are not adjudicated."),
+        Some(Outcome::VariantEnd { .. }) => unreachable!("Random games are standard chess."),
+        None if game.is_threefold_repetition() => Some(Termination::ThreefoldRepetition),
+        None if game.halfmove_clock() >= 100 => Some(Termination::FiftyMoves),
+        None if game.is_insufficient_material() => Some(Termination::InsufficientMaterial),
+        None => None,
+    }
+}
+
+fn check(game: &mut Game, seed: u64) {
+    if let Err(err) = game.check_invariants() {
+        panic!("{err} {}", describe(game, seed));
+    }
+}
+
+/// Describe the game so that it can be reproduced.
+fn describe(game: &Game, seed: u64) -> String {
+    let moves: Vec<String> = game.history().iter().map(Move::to_string).collect();
+    format!("Seed {seed}, moves: {}", moves.join(" "))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_games_keep_invariants() {
+        for seed in 0..200 {
+            let completed = play_random_game(seed, 50);
+            assert!(completed.game.history().len() <= 50);
+            if completed.termination == Termination::Checkmate {
+                assert!(completed.game.is_game_over());
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let first = play_random_game(7, 60);
+        let second = play_random_game(7, 60);
+        assert_eq!(first.game.history(), second.game.history());
+        assert_eq!(first.termination, second.termination);
+    }
+}
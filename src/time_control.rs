@@ -0,0 +1,361 @@
+//! Time controls in the syntax of the PGN `TimeControl` tag.
+//!
+//! A time control is written as stages separated by `:`. A stage like `40/5400`
+//! gives 5400 seconds for 40 moves, `300` gives 300 seconds for the rest of the
+//! game and `300+3` adds 3 seconds after every move. `-` is a game without a
+//! clock, `?` an unknown time control and `*60` a sandclock of 60 seconds.
+//! Fractions of a second are written with a decimal point, like `0.5`, which is
+//! not part of the PGN standard but keeps every `Duration` when writing and
+//! reading a time control again.
+//!
+//! The crate has no clock of its own. `TimeControl::time_added` tells a clock how
+//! much time to add after each move, including the time of the next stage.
+
+use std::{fmt, num::NonZeroU32, str::FromStr, time::Duration};
+
+/// A time control, see the module documentation.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TimeControl {
+    /// The time control is not known, written as `?`.
+    Unknown,
+    /// The game is played without a clock, written as `-`.
+    Unlimited,
+    /// The stages of the time control in the order they are played. Only the last
+    /// stage may be without a number of moves. If the last stage has a number of
+    /// moves, it is repeated for the rest of the game.
+    Stages(Vec<TimeControlStage>),
+    /// A sandclock, where the time one player uses is added to the clock of the
+    /// other, written like `*60`.
+    Sandclock(Duration),
+}
+
+/// A stage of a time control, for example 40 moves in 90 minutes.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TimeControlStage {
+    /// The number of moves of each player in the stage, or `None` for the rest of
+    /// the game.
+    pub moves: Option<NonZeroU32>,
+    /// The time added to the clock at the start of the stage.
+    pub time: Duration,
+    /// The time added to the clock after every move in the stage.
+    pub increment: Duration,
+}
+
+/// Errors returned when parsing a `TimeControl`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseTimeControlError {
+    /// A stage can not be read. The stage is included.
+    InvalidStage(String),
+    /// A stage follows a stage without a number of moves, which already lasts for
+    /// the rest of the game.
+    StageAfterLast,
+}
+
+impl fmt::Display for ParseTimeControlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidStage(stage) => write!(f, "'{stage}' is not a valid time control stage."),
+            Self::StageAfterLast => write!(f, "Only the last stage of a time control may be without a number of moves."),
+        }
+    }
+}
+
+impl std::error::Error for ParseTimeControlError {}
+
+impl TimeControl {
+
+    /// Create a time control with one stage for the whole game and an increment
+    /// after every move, like `300+3`.
+    pub fn with_increment(time: Duration, increment: Duration) -> TimeControl {
+        TimeControl::Stages(vec![TimeControlStage { moves: None, time, increment }])
+    }
+
+    /// One minute for the game without increment, `60`.
+    pub fn bullet() -> TimeControl {
+        TimeControl::with_increment(Duration::from_secs(60), Duration::ZERO)
+    }
+
+    /// Three minutes for the game and two seconds per move, `180+2`.
+    pub fn blitz() -> TimeControl {
+        TimeControl::with_increment(Duration::from_secs(180), Duration::from_secs(2))
+    }
+
+    /// Fifteen minutes for the game and ten seconds per move, `900+10`.
+    pub fn rapid() -> TimeControl {
+        TimeControl::with_increment(Duration::from_secs(900), Duration::from_secs(10))
+    }
+
+    /// The FIDE classical time control of 90 minutes for the first 40 moves and
+    /// 30 more minutes for the rest of the game, with 30 seconds per move from
+    /// the start, `40/5400+30:1800+30`.
+    pub fn classical() -> TimeControl {
+        TimeControl::Stages(vec![
+            TimeControlStage { moves: NonZeroU32::new(40), time: Duration::from_secs(5400), increment: Duration::from_secs(30) },
+            TimeControlStage { moves: None, time: Duration::from_secs(1800), increment: Duration::from_secs(30) },
+        ])
+    }
+
+    /// Get the stages of the time control. The list is empty unless the time
+    /// control is `Stages`.
+    pub fn stages(&self) -> &[TimeControlStage] {
+        match self {
+            Self::Stages(stages) => stages,
+            _ => &[],
+        }
+    }
+
+    /// Get the time on a clock at the start of the game, or `None` if the game is
+    /// played without a clock or the time control is unknown.
+    pub fn starting_time(&self) -> Option<Duration> {
+        match self {
+            Self::Unknown | Self::Unlimited => None,
+            Self::Stages(stages) => stages.first().map(|stage| stage.time),
+            Self::Sandclock(time) => Some(*time),
+        }
+    }
+
+    /// Get the stage that a move of a player is in, where the move number starts
+    /// at 1 for the first move of each player. `None` is returned if the time
+    /// control has no stages.
+    pub fn stage(&self, move_number: u32) -> Option<&TimeControlStage> {
+        self.stage_index(move_number).map(|(index, _)| &self.stages()[index])
+    }
+
+    /// Get the time to add to the clock of a player after their move with the
+    /// number, see `stage`. This is the increment of the move's stage and, after
+    /// the last move of a stage, the time of the next stage.
+    pub fn time_added(&self, move_number: u32) -> Duration {
+        let stages = self.stages();
+        match self.stage_index(move_number) {
+            Some((index, true)) => {
+                // The last stage is repeated when it has a number of moves.
+                let next = stages.get(index + 1).unwrap_or(&stages[index]);
+                stages[index].increment.saturating_add(next.time)
+            },
+            Some((index, false)) => stages[index].increment,
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Find the index of the stage of a move, and whether it is the last move of
+    /// the stage.
+    fn stage_index(&self, move_number: u32) -> Option<(usize, bool)> {
+        let stages = self.stages();
+        let move_number = u64::from(move_number.max(1));
+        let mut first_move = 1;
+        for (index, stage) in stages.iter().enumerate() {
+            let Some(moves) = stage.moves else {
+                return Some((index, false));
+            };
+            let moves = u64::from(moves.get());
+            if index + 1 == stages.len() {
+                let offset = (move_number - first_move) % moves;
+                return Some((index, offset == moves - 1));
+            }
+            if move_number < first_move + moves {
+                return Some((index, move_number == first_move + moves - 1));
+            }
+            first_move += moves;
+        }
+        None
+    }
+}
+
+/// Time controls are written in the syntax of the PGN `TimeControl` tag, with
+/// times in seconds, see the module documentation.
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "?"),
+            Self::Unlimited => write!(f, "-"),
+            Self::Sandclock(time) => write!(f, "*{}", format_seconds(time)),
+            Self::Stages(stages) => {
+                for (index, stage) in stages.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ":")?;
+                    }
+                    write!(f, "{stage}")?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl fmt::Display for TimeControlStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(moves) = self.moves {
+            write!(f, "{moves}/")?;
+        }
+        write!(f, "{}", format_seconds(&self.time))?;
+        if !self.increment.is_zero() {
+            write!(f, "+{}", format_seconds(&self.increment))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = ParseTimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "?" => return Ok(TimeControl::Unknown),
+            "-" => return Ok(TimeControl::Unlimited),
+            _ => {},
+        }
+        if let Some(time) = s.trim().strip_prefix('*') {
+            let time = parse_seconds(time).ok_or_else(|| ParseTimeControlError::InvalidStage(s.trim().to_string()))?;
+            return Ok(TimeControl::Sandclock(time));
+        }
+
+        let mut stages: Vec<TimeControlStage> = Vec::new();
+        for text in s.trim().split(':') {
+            if stages.last().is_some_and(|stage| stage.moves.is_none()) {
+                return Err(ParseTimeControlError::StageAfterLast);
+            }
+            let stage = parse_stage(text).ok_or_else(|| ParseTimeControlError::InvalidStage(text.to_string()))?;
+            stages.push(stage);
+        }
+        Ok(TimeControl::Stages(stages))
+    }
+}
+
+/// Parse a stage like `40/5400+30`, `300+3` or `60`.
+fn parse_stage(text: &str) -> Option<TimeControlStage> {
+    let (moves, rest) = match text.split_once('/') {
+        Some((moves, rest)) if moves.bytes().all(|byte| byte.is_ascii_digit()) => (Some(moves.parse().ok()?), rest),
+        Some(_) => return None,
+        None => (None, text),
+    };
+    let (time, increment) = match rest.split_once('+') {
+        Some((time, increment)) => (parse_seconds(time)?, parse_seconds(increment)?),
+        None => (parse_seconds(rest)?, Duration::ZERO),
+    };
+    Some(TimeControlStage { moves, time, increment })
+}
+
+/// Parse seconds like `300` or `0.5`, see `format_seconds`.
+fn parse_seconds(text: &str) -> Option<Duration> {
+    let (seconds, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let is_number = |text: &str| !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit());
+    if !is_number(seconds) || (text.contains('.') && !is_number(fraction)) || fraction.len() > 9 {
+        return None;
+    }
+    let nanos = match fraction {
+        "" => 0,
+        fraction => format!("{fraction:0<9}").parse().ok()?,
+    };
+    Some(Duration::new(seconds.parse().ok()?, nanos))
+}
+
+/// Write seconds, with the fraction of a second after a decimal point if there
+/// is one.
+fn format_seconds(time: &Duration) -> String {
+    match time.subsec_nanos() {
+        0 => time.as_secs().to_string(),
+        nanos => format!("{}.{}", time.as_secs(), format!("{nanos:09}").trim_end_matches('0')),
+    }
+}
+
+/// Time controls are serialized as strings in the syntax of the PGN
+/// `TimeControl` tag, like `"40/5400+30:1800+30"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for TimeControl {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TimeControl {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let str = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        str.parse().map_err(|err: ParseTimeControlError| {
+            serde::de::Error::custom(format!("invalid time control \"{str}\": {err}"))
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        for tag in ["40/5400+30:1800+30", "300+3", "60", "-", "?", "*180", "40/7200:20/3600", "40/9000:900+30"] {
+            let time_control: TimeControl = tag.parse().unwrap();
+            assert_eq!(time_control.to_string(), tag);
+        }
+        assert_eq!("40/5400+30:1800+30".parse::<TimeControl>().unwrap(), TimeControl::classical());
+        assert_eq!("180+2".parse::<TimeControl>().unwrap(), TimeControl::blitz());
+        assert_eq!(TimeControl::bullet().to_string(), "60");
+        assert_eq!(TimeControl::rapid().to_string(), "900+10");
+
+        assert_eq!("300:40/7200".parse::<TimeControl>(), Err(ParseTimeControlError::StageAfterLast));
+        for invalid in ["", "5m", "0/300", "+40/300", "40/", "+3", "300+", "*", "-300", "40/5400::1800", "1.", ".5", "0.1234567891"] {
+            assert!(invalid.parse::<TimeControl>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn stages() {
+        let classical = TimeControl::classical();
+        assert_eq!(classical.stage(1).unwrap().moves, NonZeroU32::new(40));
+        assert_eq!(classical.stage(40).unwrap().moves, NonZeroU32::new(40));
+        assert_eq!(classical.stage(41).unwrap().moves, None);
+        assert_eq!(classical.time_added(39), Duration::from_secs(30));
+        assert_eq!(classical.time_added(40), Duration::from_secs(1830));
+        assert_eq!(classical.time_added(41), Duration::from_secs(30));
+
+        // The last stage repeats.
+        let repeating: TimeControl = "40/7200:20/3600".parse().unwrap();
+        assert_eq!(repeating.time_added(40), Duration::from_secs(3600));
+        assert_eq!(repeating.time_added(59), Duration::ZERO);
+        assert_eq!(repeating.time_added(60), Duration::from_secs(3600));
+        assert_eq!(repeating.time_added(80), Duration::from_secs(3600));
+
+        assert_eq!(TimeControl::Unlimited.stage(1), None);
+        assert_eq!(TimeControl::Unlimited.time_added(1), Duration::ZERO);
+        assert_eq!(TimeControl::Unknown.starting_time(), None);
+    }
+
+    #[test]
+    fn fractions_of_a_second() {
+        let time_control = TimeControl::with_increment(Duration::from_millis(60_500), Duration::from_nanos(1));
+        assert_eq!(time_control.to_string(), "60.5+0.000000001");
+        assert_eq!(time_control.to_string().parse::<TimeControl>().unwrap(), time_control);
+
+        let time_control: TimeControl = "*0.25".parse().unwrap();
+        assert_eq!(time_control, TimeControl::Sandclock(Duration::from_millis(250)));
+        assert_eq!(time_control.to_string(), "*0.25");
+    }
+
+    #[test]
+    fn clock_across_a_stage_boundary() {
+        // A clock of one player who spends 100 seconds on every move.
+        let time_control = TimeControl::classical();
+        let mut clock = time_control.starting_time().unwrap();
+        for move_number in 1..=40 {
+            clock -= Duration::from_secs(100);
+            clock += time_control.time_added(move_number);
+        }
+        // 5400 seconds, less 40 moves of 100 seconds, plus 40 increments of 30
+        // seconds and 1800 seconds for the second stage.
+        assert_eq!(clock, Duration::from_secs(4400));
+
+        clock -= Duration::from_secs(100);
+        clock += time_control.time_added(41);
+        assert_eq!(clock, Duration::from_secs(4330));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json_round_trip() {
+        let json = serde_json::to_string(&TimeControl::classical()).unwrap();
+        assert_eq!(json, r#""40/5400+30:1800+30""#);
+        assert_eq!(serde_json::from_str::<TimeControl>(&json).unwrap(), TimeControl::classical());
+        assert!(serde_json::from_str::<TimeControl>(r#""5 minutes""#).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+//! Helpers for the optional instrumentation with the `tracing` crate. Without
+//! the `tracing` feature they expand to nothing.
+
+/// Emit a debug event for a move that was rejected, with the positions and the
+/// reason as fields.
+macro_rules! rejected_move {
+    ($from:expr, $to:expr, $reason:expr) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(from = %$from, to = %$to, reason = %$reason, "Rejected move");
+    };
+}
+
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::{collections::BTreeMap, fmt, sync::{Arc, Mutex}};
+
+    use tracing::{field::{Field, Visit}, span, Event, Metadata, Subscriber};
+
+    use crate::game::Game;
+
+    type Fields = BTreeMap<String, String>;
+
+    /// A subscriber that collects the fields of every event.
+    struct Collector(Arc<Mutex<Vec<Fields>>>);
+
+    struct FieldVisitor(Fields);
+
+    impl Visit for FieldVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl Subscriber for Collector {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &span::Attributes) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &span::Id, _values: &span::Record) {}
+
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+        fn event(&self, event: &Event) {
+            let mut visitor = FieldVisitor(Fields::new());
+            event.record(&mut visitor);
+            self.0.lock().unwrap().push(visitor.0);
+        }
+
+        fn enter(&self, _span: &span::Id) {}
+
+        fn exit(&self, _span: &span::Id) {}
+    }
+
+    #[test]
+    fn rejected_move_event() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Collector(events.clone()), || {
+            let mut game = Game::new();
+            game.move_piece(&"e2".parse().unwrap(), &"e5".parse().unwrap()).unwrap_err();
+            game.move_piece(&"e7".parse().unwrap(), &"e5".parse().unwrap()).unwrap_err();
+        });
+
+        let events = events.lock().unwrap();
+        let rejected: Vec<&Fields> = events.iter()
+            .filter(|fields| fields.get("message").is_some_and(|message| message == "Rejected move"))
+            .collect();
+        assert_eq!(rejected.len(), 2);
+        assert_eq!(rejected[0]["from"], "e2");
+        assert_eq!(rejected[0]["to"], "e5");
+        assert_eq!(rejected[0]["reason"], "That is not a valid move.");
+        assert_eq!(rejected[1]["from"], "e7");
+        assert_eq!(rejected[1]["reason"], "It is not the turn of that piece's team.");
+    }
+}
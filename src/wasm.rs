@@ -0,0 +1,176 @@
+//! JavaScript bindings for WebAssembly builds.
+//!
+//! This module is only available with the `wasm` feature. It exposes the
+//! `WasmGame` class, a wrapper around `Game` that takes and returns squares as
+//! strings in algebraic notation, like `"e4"`.
+//!
+//! Errors are thrown as JavaScript `Error`s with a human-readable message.
+
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::{board::Color, game::{Game, GameState}, pos::BoardPos, piece::PieceType};
+
+/// A chess game with a JavaScript-friendly API.
+#[wasm_bindgen]
+pub struct WasmGame {
+    game: Game,
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+
+    /// Create a new standard game of chess with the default starting position.
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> WasmGame {
+        WasmGame { game: Game::new() }
+    }
+
+    /// Create a game from a FEN string.
+    #[wasm_bindgen(js_name = fromFen)]
+    pub fn from_fen(fen: &str) -> Result<WasmGame, JsError> {
+        Ok(WasmGame { game: Game::from_fen(fen)? })
+    }
+
+    /// Export the current position as a FEN string.
+    pub fn fen(&self) -> String {
+        self.game.to_fen()
+    }
+
+    /// Get the team whose turn it is, `"white"` or `"black"`.
+    #[wasm_bindgen(js_name = currentTurn)]
+    pub fn current_turn(&self) -> String {
+        color_name(&self.game.current_turn()).to_string()
+    }
+
+    /// Get the squares that the piece on `square` can legally move to, sorted
+    /// alphabetically.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&mut self, square: &str) -> Result<Vec<String>, JsError> {
+        let pos: BoardPos = square.parse()?;
+        let mut moves: Vec<String> = self.game.get_legal_moves(&pos)?
+            .iter()
+            .map(|pos| pos.to_string())
+            .collect();
+        moves.sort();
+        Ok(moves)
+    }
+
+    /// Move the piece on `from` to `to`.
+    ///
+    /// If the move is a promotion, `promotion` is the FEN letter of the piece to
+    /// promote to, for example `"q"`. If it is left out, the promotion must be
+    /// completed with `promote` before the next move. The argument is ignored for
+    /// moves that are not promotions.
+    #[wasm_bindgen(js_name = "move")]
+    pub fn move_piece(&mut self, from: &str, to: &str, promotion: Option<String>) -> Result<(), JsError> {
+        let from: BoardPos = from.parse()?;
+        let to: BoardPos = to.parse()?;
+        // Validate the promotion piece before the move is performed.
+        let promotion = promotion.as_deref().map(parse_promotion).transpose()?;
+
+        self.game.move_piece(&from, &to)?;
+
+        if let (GameState::PromotionRequired(_), Some(piece)) = (self.game.get_state(), promotion) {
+            self.game.promote(piece);
+        }
+        Ok(())
+    }
+
+    /// Promote the pawn that is waiting to be promoted. `piece` is the FEN letter
+    /// of the piece to promote to, for example `"q"`.
+    pub fn promote(&mut self, piece: &str) -> Result<(), JsError> {
+        let piece = parse_promotion(piece)?;
+        if !matches!(self.game.get_state(), GameState::PromotionRequired(_)) {
+            return Err(JsError::new("There is no pawn to promote."));
+        }
+        self.game.promote(piece);
+        Ok(())
+    }
+
+    /// Get the state of the game as an object with a `kind` property that is
+    /// `"normal"`, `"check"`, `"checkmate"`, `"stalemate"`,
+    /// `"insufficientMaterial"`, `"promotionRequired"`, `"variantEnd"` or
+    /// `"adjudicated"`.
+    ///
+    /// For `"check"` the `color` property is the team in check, and for
+    /// `"checkmate"`, `"variantEnd"` and `"adjudicated"` it is the team that won,
+    /// missing for a draw. For `"promotionRequired"` the `square` property is the
+    /// square of the pawn to promote.
+    pub fn state(&mut self) -> Result<JsValue, JsError> {
+        let state = Object::new();
+        let (kind, color, square) = match self.game.get_state() {
+            GameState::Normal => ("normal", None, None),
+            GameState::Check(color) => ("check", Some(color), None),
+            // The state holds the checkmated team, while the property is the winner.
+            GameState::Checkmate(color) => ("checkmate", Some(color.opposite()), None),
+            GameState::Stalemate => ("stalemate", None, None),
+            GameState::InsufficientMaterial => ("insufficientMaterial", None, None),
+            GameState::PromotionRequired(pos) => ("promotionRequired", None, Some(pos)),
+            GameState::VariantEnd(winner) => ("variantEnd", winner, None),
+            GameState::Adjudicated(winner) => ("adjudicated", winner, None),
+        };
+        set(&state, "kind", kind)?;
+        if let Some(color) = color {
+            set(&state, "color", color_name(&color))?;
+        }
+        if let Some(square) = square {
+            set(&state, "square", &square.to_string())?;
+        }
+        Ok(state.into())
+    }
+
+    /// Get the board contents as an array of eight ranks, starting with rank 8.
+    ///
+    /// Each rank is an array of eight squares, starting with file a. A square is
+    /// either `null` or the FEN letter of the piece on it, uppercase for white
+    /// pieces and lowercase for black pieces.
+    pub fn board(&self) -> Array {
+        let ranks = Array::new();
+        for row in 0..8 {
+            let squares = Array::new();
+            for col in 0..8 {
+                let pos = BoardPos::from_grid(col, row, &Color::White).expect("The grid is 8 by 8.");
+                let square = match self.game.get_tile(&pos) {
+                    None => JsValue::NULL,
+                    Some(tile) if tile.color() == Color::White => {
+                        JsValue::from(tile.piece().char().to_ascii_uppercase().to_string())
+                    },
+                    Some(tile) => JsValue::from(tile.piece().char().to_string()),
+                };
+                squares.push(&square);
+            }
+            ranks.push(&squares);
+        }
+        ranks
+    }
+}
+
+fn color_name(color: &Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
+
+fn set(object: &Object, key: &str, value: &str) -> Result<(), JsError> {
+    Reflect::set(object, &JsValue::from(key), &JsValue::from(value))
+        .map_err(|_| JsError::new("Failed to set property."))?;
+    Ok(())
+}
+
+/// Parse the FEN letter of a piece that a pawn can be promoted to.
+fn parse_promotion(piece: &str) -> Result<PieceType, JsError> {
+    let mut chars = piece.chars();
+    let piece = match (chars.next(), chars.next()) {
+        (Some(char), None) => PieceType::from_char(char.to_ascii_lowercase()).ok(),
+        _ => None,
+    };
+    match piece {
+        Some(PieceType::King | PieceType::Pawn) | None => {
+            Err(JsError::new("Pawns can only be promoted to a queen (q), rook (r), bishop (b) or knight (n)."))
+        },
+        Some(piece) => Ok(piece),
+    }
+}
@@ -0,0 +1,55 @@
+//! Tests for the JavaScript bindings. Run with `wasm-pack test --node --features wasm`.
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use alvinw_chess::wasm::WasmGame;
+use js_sys::{Array, Reflect};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn state_kind(game: &mut WasmGame) -> String {
+    state_property(game, "kind").unwrap()
+}
+
+fn state_property(game: &mut WasmGame, name: &str) -> Option<String> {
+    let state = game.state().unwrap();
+    Reflect::get(&state, &JsValue::from(name)).unwrap().as_string()
+}
+
+#[wasm_bindgen_test]
+fn fools_mate() {
+    let mut game = WasmGame::new();
+
+    assert_eq!(game.legal_moves("g2").unwrap(), ["g3", "g4"]);
+
+    game.move_piece("f2", "f3", None).unwrap();
+    game.move_piece("e7", "e5", None).unwrap();
+    game.move_piece("g2", "g4", None).unwrap();
+    game.move_piece("d8", "h4", None).unwrap();
+
+    assert_eq!(state_kind(&mut game), "checkmate");
+    // Black won.
+    assert_eq!(state_property(&mut game, "color").as_deref(), Some("black"));
+    assert!(game.fen().starts_with("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq"));
+}
+
+#[wasm_bindgen_test]
+fn promotion_and_board() {
+    let mut game = WasmGame::from_fen("4k3/2P5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+    game.move_piece("c7", "c8", Some("q".to_string())).unwrap();
+    assert_eq!(state_kind(&mut game), "check");
+
+    let board = game.board();
+    let rank8 = Array::from(&board.get(0));
+    assert_eq!(rank8.get(2).as_string().unwrap(), "Q");
+    assert!(rank8.get(0).is_null());
+}
+
+#[wasm_bindgen_test]
+fn errors_are_thrown() {
+    let mut game = WasmGame::new();
+
+    assert!(game.move_piece("e2", "e5", None).is_err());
+    assert!(game.legal_moves("z9").is_err());
+    assert!(WasmGame::from_fen("not a fen").is_err());
+}